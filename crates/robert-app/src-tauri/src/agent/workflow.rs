@@ -4,6 +4,7 @@ use super::config::AgentConfig;
 use super::prompts::{PromptContext, PromptTemplate, PromptType};
 use crate::claude::{ClaudeClient, ClaudeConfig, ClaudeInput, ClaudeResponse};
 use anyhow::{Context, Result};
+use robert_server::client::{ClientError, RobertClient};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 /// A question that needs clarification
@@ -67,11 +68,11 @@ impl WorkflowExecutor {
         agent_config: &AgentConfig,
         _screenshot_path: Option<PathBuf>,
         _html_content: Option<String>,
-        http_client: &reqwest::Client,
+        server_client: &RobertClient,
     ) -> Result<WorkflowResult> {
         match workflow_type {
             WorkflowType::CdpAutomation => {
-                self.execute_cdp_workflow(user_message, http_client).await
+                self.execute_cdp_workflow(user_message, server_client).await
             }
             WorkflowType::ConfigUpdate => {
                 self.execute_config_update_workflow(user_message, agent_config)
@@ -84,64 +85,31 @@ impl WorkflowExecutor {
         }
     }
 
-    /// Execute CDP generation and automation workflow via Standalone Webdriver
+    /// Execute CDP generation and automation workflow via robert-server
     async fn execute_cdp_workflow(
         &self,
         user_message: String,
-        http_client: &reqwest::Client,
+        server_client: &RobertClient,
     ) -> Result<WorkflowResult> {
         log::info!("╔═══════════════════════════════════════════════════════════╗");
         log::info!("║  🤖 CDP AUTOMATION WORKFLOW (DELEGATED TO SERVER)         ║");
         log::info!("╚═══════════════════════════════════════════════════════════╝");
 
-        // Connect to robert-server on port 8443 (default)
-        let url = "http://localhost:8443/inference";
-        let payload = serde_json::json!({
-            "prompt": user_message
-        });
+        log::info!("Sending inference request to {}", server_client.base_url());
 
-        log::info!("Sending inference request to {}", url);
-
-        let response = match http_client.post(url).json(&payload).send().await {
-            Ok(res) => res,
+        let response = match server_client.inference(&user_message).await {
+            Ok(response) => response,
             Err(e) => {
-                log::error!("Failed to connect to robert-server: {}", e);
+                log::error!("robert-server request failed: {}", e);
+                let message = match &e {
+                    ClientError::Http(_) => "Failed to connect to robert-server".to_string(),
+                    ClientError::Api { message, .. } => format!("Server error: {}", message),
+                    ClientError::InvalidResponse(_) => "Invalid response from server".to_string(),
+                };
                 return Ok(WorkflowResult {
                     success: false,
                     workflow_type: WorkflowType::CdpAutomation,
-                    message: "Failed to connect to robert-server".to_string(),
-                    cdp_script: None,
-                    execution_report: None,
-                    error: Some(format!("Connection failed: {}", e)),
-                    clarification: None,
-                    understanding: None,
-                    refined_feedback: None,
-                });
-            }
-        };
-
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Ok(WorkflowResult {
-                success: false,
-                workflow_type: WorkflowType::CdpAutomation,
-                message: format!("Server error: {}", error_text),
-                cdp_script: None,
-                execution_report: None,
-                error: Some(error_text),
-                clarification: None,
-                understanding: None,
-                refined_feedback: None,
-            });
-        }
-
-        let json: serde_json::Value = match response.json().await {
-            Ok(j) => j,
-            Err(e) => {
-                return Ok(WorkflowResult {
-                    success: false,
-                    workflow_type: WorkflowType::CdpAutomation,
-                    message: "Invalid response from server".to_string(),
+                    message,
                     cdp_script: None,
                     execution_report: None,
                     error: Some(e.to_string()),
@@ -152,25 +120,14 @@ impl WorkflowExecutor {
             }
         };
 
-        let status = json
-            .get("status")
-            .and_then(|s| s.as_str())
-            .unwrap_or("error");
-        let message = json
-            .get("message")
-            .and_then(|s| s.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        let success = status == "success";
-        let execution_report = json.get("execution_report").cloned();
+        let success = response.status == "success";
 
         Ok(WorkflowResult {
             success,
             workflow_type: WorkflowType::CdpAutomation,
-            message,
+            message: response.message,
             cdp_script: None, // Server doesn't return raw script currently, or maybe it does in report
-            execution_report,
+            execution_report: response.execution_report,
             error: if success {
                 None
             } else {
@@ -69,7 +69,7 @@ pub async fn process_chat_message(
 
     emit_claude_processing(&app, "Executing workflow...").ok();
 
-    // Execute workflow with http_client
+    // Execute workflow through the robert-server client
     let result = executor
         .execute(
             request.workflow_type,
@@ -77,7 +77,7 @@ pub async fn process_chat_message(
             &agent_config,
             screenshot_path,
             html_content,
-            &state.http_client,
+            state.server_client()?,
         )
         .await;
 
@@ -331,7 +331,7 @@ pub async fn submit_action_feedback(
                 &meta_agent,
                 None,
                 None,
-                &state.http_client,
+                state.server_client()?,
             )
             .await
         {
@@ -59,32 +59,19 @@ pub async fn get_browser_status(state: State<'_, AppState>) -> Result<WebdriverS
     }
 }
 
-/// Send an inference request to the webdriver
+/// Send an inference request to the embedded robert-server
 #[tauri::command]
 pub async fn execute_webdriver_inference(
     prompt: String,
     state: State<'_, AppState>,
 ) -> Result<serde_json::Value, String> {
-    let client = &state.http_client;
-    let url = "http://localhost:8443/inference";
-
-    let payload = serde_json::json!({
-        "prompt": prompt
-    });
-
-    let res = client
-        .post(url)
-        .json(&payload)
-        .send()
+    let response = state
+        .server_client()?
+        .inference(&prompt)
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
 
-    let json: serde_json::Value = res
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    Ok(json)
+    serde_json::to_value(response).map_err(|e| format!("Failed to encode response: {}", e))
 }
 
 // Deprecated / Stubbed commands to prevent frontend breakage temporarily
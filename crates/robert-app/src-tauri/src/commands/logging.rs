@@ -1,4 +1,5 @@
-use crate::logging::{LogEntry, LogLevel};
+use crate::logging::{LogEntry, LogLevel, LogPage, LogQuery};
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,31 +21,84 @@ impl From<LogEntry> for LogEntryDto {
     }
 }
 
+/// Filters and pagination for `get_logs`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilterDto {
+    /// Minimum level ("debug", "info", "warn" or "error")
+    pub level: Option<String>,
+    /// Source ("rust" or "frontend")
+    pub source: Option<String>,
+    /// RFC 3339 start of the time range (inclusive)
+    pub since: Option<String>,
+    /// RFC 3339 end of the time range (exclusive)
+    pub until: Option<String>,
+    /// Number of matching entries to skip, counting from the newest
+    pub offset: Option<usize>,
+    /// Maximum number of entries to return
+    pub limit: Option<usize>,
+}
+
+impl TryFrom<LogFilterDto> for LogQuery {
+    type Error = String;
+
+    fn try_from(filter: LogFilterDto) -> Result<Self, Self::Error> {
+        let parse_time = |time: Option<String>| {
+            time.map(|time| {
+                DateTime::<FixedOffset>::parse_from_rfc3339(&time)
+                    .map_err(|e| format!("Invalid time '{}': {}", time, e))
+            })
+            .transpose()
+        };
+
+        Ok(Self {
+            min_level: filter.level.map(|level| level.parse()).transpose()?,
+            source: filter.source,
+            since: parse_time(filter.since)?,
+            until: parse_time(filter.until)?,
+            offset: filter.offset.unwrap_or(0),
+            limit: filter.limit,
+        })
+    }
+}
+
+/// One page of log entries, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPageDto {
+    pub entries: Vec<LogEntryDto>,
+    pub has_more: bool,
+}
+
+impl From<LogPage> for LogPageDto {
+    fn from(page: LogPage) -> Self {
+        Self {
+            entries: page.entries.into_iter().map(LogEntryDto::from).collect(),
+            has_more: page.has_more,
+        }
+    }
+}
+
 /// Log a message from the frontend
 #[tauri::command]
 pub async fn log_frontend_message(level: String, message: String) -> Result<(), String> {
-    let log_level = match level.to_lowercase().as_str() {
-        "debug" => LogLevel::Debug,
-        "info" => LogLevel::Info,
-        "warn" => LogLevel::Warn,
-        "error" => LogLevel::Error,
-        _ => LogLevel::Info,
-    };
+    let log_level = level.parse().unwrap_or(LogLevel::Info);
 
     crate::logging::log_frontend_message(log_level, message)
 }
 
-/// Get all log entries
+/// Get log entries matching a filter, newest first
+///
+/// Without a filter, all retained entries are returned.
 #[tauri::command]
-pub async fn get_logs() -> Result<Vec<LogEntryDto>, String> {
+pub async fn get_logs(filter: Option<LogFilterDto>) -> Result<LogPageDto, String> {
     let storage = crate::logging::get_storage()
         .ok_or_else(|| "Logging not initialized. Please log in.".to_string())?;
 
-    let logs = storage
-        .read_logs()
+    let query = LogQuery::try_from(filter.unwrap_or_default())?;
+    let page = storage
+        .query_logs(&query)
         .map_err(|e| format!("Failed to read logs: {}", e))?;
 
-    Ok(logs.into_iter().map(LogEntryDto::from).collect())
+    Ok(page.into())
 }
 
 /// Clear all logs
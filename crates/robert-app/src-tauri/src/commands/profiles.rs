@@ -4,19 +4,29 @@
 //! - User creation and login
 //! - User listing
 //! - Profile management
+//! - Password changes
+//! - Account recovery with recovery codes
+//! - Remembered logins and session locking
+//! - Login attempt history
+//! - Encrypted export and import of users
+//! - User deletion
 //! - Session management
 //! - Command management (Phase 3)
 
 use crate::profiles::{
-    auth::{AuthError, AuthService},
+    archive::{self, ArchiveError, ArchiveManifest, ExportOptions, ImportReport},
+    auth::{AuthError, AuthService, UserSession},
     command_md::{CommandExecutor, CommandManager},
     manager::UserManager,
+    storage::DeleteReport,
     storage::{load_user_profile, save_user_profile},
+    throttle::LoginAttempt,
     types::{Command, CommandInfo, UserConfig},
 };
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use tauri::State;
 
 // ============================================================================
@@ -48,6 +58,48 @@ impl<T> ProfileResult<T> {
     }
 }
 
+/// Login attempts of the current user
+#[derive(Debug, Serialize)]
+pub struct LoginHistory {
+    /// Failed and blocked attempts between the previous login and this one
+    pub failed_since_last_login: Vec<LoginAttempt>,
+
+    /// Latest login attempts, oldest first
+    pub attempts: Vec<LoginAttempt>,
+}
+
+/// Start encrypted logging for a session
+fn init_logging(session: &UserSession) {
+    let result = session
+        .get_encryption_key()
+        .map_err(|e| e.to_string())
+        .and_then(|key| crate::logging::init_for_user(&session.username, key));
+
+    if let Err(e) = result {
+        log::warn!("⚠️  Failed to initialize logging: {}", e);
+    }
+}
+
+/// Remember or forget a session's login in the OS key store
+fn set_remembered(state: &AppState, session: &UserSession, remember: bool) {
+    let Some(store) = state.key_store.as_deref() else {
+        if remember {
+            log::warn!("⚠️  Remembered logins are not supported on this platform");
+        }
+        return;
+    };
+
+    let result = if remember {
+        AuthService::remember(session, store, None)
+    } else {
+        AuthService::forget(&session.username, store, None)
+    };
+
+    if let Err(e) = result {
+        log::warn!("⚠️  Failed to update remembered login: {}", e);
+    }
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -71,9 +123,7 @@ pub async fn create_user(
     match AuthService::create_and_login(&username, &password, None) {
         Ok(session) => {
             // Initialize encrypted logging for this user
-            if let Err(e) = crate::logging::init_for_user(&username, &password) {
-                log::warn!("⚠️  Failed to initialize logging: {}", e);
-            }
+            init_logging(&session);
 
             // Store session in app state
             let mut user_session = state.user_session.lock().await;
@@ -94,6 +144,8 @@ pub async fn create_user(
 /// # Parameters
 /// - `username`: The username to login
 /// - `password`: The user's password
+/// - `remember`: Remember the login in the OS key store (`true`), forget a
+///   remembered login (`false`), or leave it as is (omitted)
 ///
 /// # Returns
 /// UserConfig if successful, error message if failed
@@ -102,14 +154,17 @@ pub async fn login_user(
     state: State<'_, AppState>,
     username: String,
     password: String,
+    remember: Option<bool>,
 ) -> Result<ProfileResult<UserConfig>, String> {
     log::info!("Login attempt for user: {}", username);
 
     match AuthService::login(&username, &password, None) {
         Ok(session) => {
             // Initialize encrypted logging for this user
-            if let Err(e) = crate::logging::init_for_user(&username, &password) {
-                log::warn!("⚠️  Failed to initialize logging: {}", e);
+            init_logging(&session);
+
+            if let Some(remember) = remember {
+                set_remembered(&state, &session, remember);
             }
 
             // Store session in app state
@@ -127,6 +182,7 @@ pub async fn login_user(
             log::warn!("❌ User not found: {}", username);
             Ok(ProfileResult::error("User not found".to_string()))
         }
+        Err(e @ AuthError::TooManyAttempts { .. }) => Ok(ProfileResult::error(e.to_string())),
         Err(e) => {
             log::error!("❌ Login failed: {}", e);
             Ok(ProfileResult::error(e.to_string()))
@@ -154,6 +210,312 @@ pub async fn logout_user(state: State<'_, AppState>) -> Result<ProfileResult<()>
     }
 }
 
+/// Login a user through their remembered login
+///
+/// # Parameters
+/// - `username`: The username to login
+///
+/// # Returns
+/// UserConfig if the login was remembered, error message otherwise (the
+/// user then has to enter their password)
+#[tauri::command]
+pub async fn login_remembered(
+    state: State<'_, AppState>,
+    username: String,
+) -> Result<ProfileResult<UserConfig>, String> {
+    let Some(store) = state.key_store.as_deref() else {
+        return Ok(ProfileResult::error(
+            "Remembered logins are not supported on this platform".to_string(),
+        ));
+    };
+
+    match AuthService::login_remembered(&username, store, None) {
+        Ok(Some(session)) => {
+            // Initialize encrypted logging for this user
+            init_logging(&session);
+
+            // Store session in app state
+            let mut user_session = state.user_session.lock().await;
+            *user_session = Some(session.clone());
+
+            log::info!("✅ User '{}' logged in with remembered login", username);
+            Ok(ProfileResult::success(session.config))
+        }
+        Ok(None) => Ok(ProfileResult::error("Login not remembered".to_string())),
+        Err(e) => {
+            log::warn!("⚠️  Remembered login failed: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Forget a user's remembered login
+///
+/// # Parameters
+/// - `username`: The user whose login is forgotten
+#[tauri::command]
+pub async fn forget_login(
+    state: State<'_, AppState>,
+    username: String,
+) -> Result<ProfileResult<()>, String> {
+    let Some(store) = state.key_store.as_deref() else {
+        return Ok(ProfileResult::success(()));
+    };
+
+    match AuthService::forget(&username, store, None) {
+        Ok(()) => Ok(ProfileResult::success(())),
+        Err(e) => {
+            log::error!("❌ Failed to forget login: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Lock the current session
+///
+/// Drops the encryption key from memory and stops encrypted logging until
+/// the session is unlocked with the password.
+#[tauri::command]
+pub async fn lock_session(state: State<'_, AppState>) -> Result<ProfileResult<()>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    session.lock();
+    crate::logging::cleanup();
+
+    log::info!("🔒 Session of user '{}' locked", session.username);
+    Ok(ProfileResult::success(()))
+}
+
+/// Unlock the current session with the user's password
+///
+/// # Parameters
+/// - `password`: The user's password
+#[tauri::command]
+pub async fn unlock_session(
+    state: State<'_, AppState>,
+    password: String,
+) -> Result<ProfileResult<()>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    match session.unlock(&password, None) {
+        Ok(()) => {
+            init_logging(session);
+            Ok(ProfileResult::success(()))
+        }
+        Err(AuthError::InvalidPassword) => {
+            log::warn!("❌ Invalid password for user: {}", session.username);
+            Ok(ProfileResult::error("Invalid password".to_string()))
+        }
+        Err(e @ AuthError::TooManyAttempts { .. }) => Ok(ProfileResult::error(e.to_string())),
+        Err(e) => {
+            log::error!("❌ Failed to unlock session: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Check whether the current session is locked
+#[tauri::command]
+pub async fn is_session_locked(state: State<'_, AppState>) -> Result<ProfileResult<bool>, String> {
+    let user_session = state.user_session.lock().await;
+
+    match user_session.as_ref() {
+        Some(session) => Ok(ProfileResult::success(session.is_locked())),
+        None => Ok(ProfileResult::error("No active session".to_string())),
+    }
+}
+
+/// Change the current user's password
+///
+/// Re-encrypts all of the user's files with a new data encryption key
+/// wrapped by the new password. Encrypted logging is paused while the
+/// files are rewritten and resumes with the new key.
+///
+/// # Parameters
+/// - `current_password`: The user's current password
+/// - `new_password`: New password (minimum 12 characters)
+///
+/// # Returns
+/// Success if changed, error message if failed (the old password keeps working)
+#[tauri::command]
+pub async fn change_password(
+    state: State<'_, AppState>,
+    current_password: String,
+    new_password: String,
+) -> Result<ProfileResult<()>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    // The log file is re-encrypted too, so nothing may write it meanwhile
+    crate::logging::cleanup();
+
+    let result =
+        AuthService::change_password(&session.username, &current_password, &new_password, None);
+
+    if let Ok(ref key) = result {
+        session.set_encryption_key(key.clone());
+    }
+
+    init_logging(session);
+
+    match result {
+        Ok(_) => {
+            log::info!("✅ Password changed for user: {}", session.username);
+            Ok(ProfileResult::success(()))
+        }
+        Err(AuthError::InvalidPassword) => {
+            log::warn!("❌ Invalid current password for user: {}", session.username);
+            Ok(ProfileResult::error("Invalid password".to_string()))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to change password: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Recover an account with a recovery code
+///
+/// Sets a new password using one of the user's recovery codes and logs the
+/// user in. The recovery code cannot be used again.
+///
+/// # Parameters
+/// - `username`: The username to recover
+/// - `recovery_code`: One of the user's unused recovery codes
+/// - `new_password`: New password (minimum 12 characters)
+///
+/// # Returns
+/// UserConfig if successful, error message if failed
+#[tauri::command]
+pub async fn recover_account(
+    state: State<'_, AppState>,
+    username: String,
+    recovery_code: String,
+    new_password: String,
+) -> Result<ProfileResult<UserConfig>, String> {
+    log::info!("Account recovery for user: {}", username);
+
+    match AuthService::recover_account(&username, &recovery_code, &new_password, None) {
+        Ok(session) => {
+            // Initialize encrypted logging for this user
+            init_logging(&session);
+
+            // Store session in app state
+            let mut user_session = state.user_session.lock().await;
+            *user_session = Some(session.clone());
+
+            log::info!("✅ User '{}' recovered and logged in", username);
+            Ok(ProfileResult::success(session.config))
+        }
+        Err(AuthError::InvalidRecoveryCode) => {
+            log::warn!("❌ Invalid recovery code for user: {}", username);
+            Ok(ProfileResult::error("Invalid recovery code".to_string()))
+        }
+        Err(AuthError::UserNotFound(_)) => {
+            log::warn!("❌ User not found: {}", username);
+            Ok(ProfileResult::error("User not found".to_string()))
+        }
+        Err(e) => {
+            log::error!("❌ Account recovery failed: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Get the login attempts of the current user
+///
+/// # Returns
+/// The attempts that failed since the previous login and the full audit
+/// trail, error message if failed
+#[tauri::command]
+pub async fn get_login_history(
+    state: State<'_, AppState>,
+) -> Result<ProfileResult<LoginHistory>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    match AuthService::login_history(&session.username, None) {
+        Ok(attempts) => Ok(ProfileResult::success(LoginHistory {
+            failed_since_last_login: session.failed_attempts.clone(),
+            attempts,
+        })),
+        Err(e) => {
+            log::error!("❌ Failed to load login history: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Get the current user's unused recovery codes
+#[tauri::command]
+pub async fn get_recovery_codes(
+    state: State<'_, AppState>,
+) -> Result<ProfileResult<Vec<String>>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    let Ok(encryption_key) = session.get_encryption_key() else {
+        return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+    };
+
+    match UserManager::recovery_codes(&session.username, &encryption_key, None) {
+        Ok(codes) => Ok(ProfileResult::success(codes)),
+        Err(e) => {
+            log::error!("❌ Failed to load recovery codes: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Replace the current user's recovery codes with a new set
+///
+/// The previous codes stop working.
+#[tauri::command]
+pub async fn regenerate_recovery_codes(
+    state: State<'_, AppState>,
+) -> Result<ProfileResult<Vec<String>>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+
+    let Ok(encryption_key) = session.get_encryption_key() else {
+        return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+    };
+
+    match UserManager::regenerate_recovery_codes(&session.username, &encryption_key, None) {
+        Ok(codes) => {
+            log::info!(
+                "✅ Regenerated recovery codes for user: {}",
+                session.username
+            );
+            Ok(ProfileResult::success(codes))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to regenerate recovery codes: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
 /// Get the current logged-in user's configuration
 ///
 /// Returns None if no user is logged in
@@ -196,7 +558,9 @@ pub async fn get_user_profile(state: State<'_, AppState>) -> Result<ProfileResul
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
 
         match load_user_profile(&session.username, &encryption_key, None) {
             Ok(content) => {
@@ -229,7 +593,9 @@ pub async fn update_user_profile(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
 
         match save_user_profile(&session.username, &content, &encryption_key, None) {
             Ok(_) => {
@@ -246,6 +612,168 @@ pub async fn update_user_profile(
     }
 }
 
+/// Delete a user and all of their data
+///
+/// The user's files are overwritten before removal (best effort) and their
+/// browser profiles are removed. Deleting the logged-in user ends the
+/// session and stops encrypted logging.
+///
+/// # Parameters
+/// - `username`: User to delete
+/// - `password`: The user's password, as confirmation
+///
+/// # Returns
+/// Report of what was removed, error message if failed
+#[tauri::command]
+pub async fn delete_user(
+    state: State<'_, AppState>,
+    username: String,
+    password: String,
+) -> Result<ProfileResult<DeleteReport>, String> {
+    log::info!("🗑️  Deleting user: {}", username);
+
+    let mut user_session = state.user_session.lock().await;
+    let is_active = user_session
+        .as_ref()
+        .is_some_and(|session| session.username == username);
+
+    // The logger writes to the user directory
+    if is_active {
+        crate::logging::cleanup();
+    }
+
+    match AuthService::delete_user(&username, &password, None) {
+        Ok(report) => {
+            if is_active {
+                *user_session = None;
+            }
+            if let Some(store) = state.key_store.as_deref() {
+                if let Err(e) = store.delete_secret(&username) {
+                    log::warn!("⚠️  Failed to remove remembered login: {}", e);
+                }
+            }
+
+            log::info!(
+                "✅ User '{}' deleted ({} files removed, {} overwritten)",
+                username,
+                report.removed_files,
+                report.wiped_files
+            );
+            Ok(ProfileResult::success(report))
+        }
+        Err(e) => {
+            if let Some(session) = user_session.as_ref().filter(|_| is_active) {
+                init_logging(session);
+            }
+
+            match e {
+                AuthError::InvalidPassword => {
+                    log::warn!("❌ Invalid password for user: {}", username);
+                    Ok(ProfileResult::error("Invalid password".to_string()))
+                }
+                AuthError::UserNotFound(_) => {
+                    log::warn!("❌ User not found: {}", username);
+                    Ok(ProfileResult::error("User not found".to_string()))
+                }
+                e => {
+                    log::error!("❌ Failed to delete user: {}", e);
+                    Ok(ProfileResult::error(e.to_string()))
+                }
+            }
+        }
+    }
+}
+
+/// Export the current user to an encrypted archive
+///
+/// # Parameters
+/// - `dest`: Path of the archive file to write
+/// - `passphrase`: Passphrase protecting the archive (minimum 12 characters)
+/// - `include_browser_profiles`: Also export browser profiles (can be large)
+/// - `include_logs`: Also export the debug logs
+///
+/// # Returns
+/// Manifest of the exported files, error message if failed
+#[tauri::command]
+pub async fn export_user(
+    state: State<'_, AppState>,
+    dest: PathBuf,
+    passphrase: String,
+    include_browser_profiles: Option<bool>,
+    include_logs: Option<bool>,
+) -> Result<ProfileResult<ArchiveManifest>, String> {
+    let user_session = state.user_session.lock().await;
+
+    let Some(session) = user_session.as_ref() else {
+        return Ok(ProfileResult::error("No active session".to_string()));
+    };
+    let Ok(encryption_key) = session.get_encryption_key() else {
+        return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+    };
+
+    let options = ExportOptions {
+        include_browser_profiles: include_browser_profiles.unwrap_or(false),
+        include_logs: include_logs.unwrap_or(false),
+    };
+
+    match archive::export_user(
+        &session.username,
+        &encryption_key,
+        &dest,
+        &passphrase,
+        &options,
+        None,
+    ) {
+        Ok(manifest) => Ok(ProfileResult::success(manifest)),
+        Err(e) => {
+            log::error!("❌ Failed to export user: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
+/// Import a user from an encrypted archive
+///
+/// The imported user gets a new password and new recovery codes. If the
+/// archived username is taken, the user is imported as `name-2`, `name-3`, ...
+///
+/// # Parameters
+/// - `archive_path`: Path of the archive file
+/// - `passphrase`: Passphrase the archive was exported with
+/// - `password`: Password for the imported user (minimum 12 characters)
+/// - `username`: Import under this username instead of the archived one
+///
+/// # Returns
+/// Import report with the final username, error message if failed
+#[tauri::command]
+pub async fn import_user(
+    archive_path: PathBuf,
+    passphrase: String,
+    password: String,
+    username: Option<String>,
+) -> Result<ProfileResult<ImportReport>, String> {
+    match archive::import_user(
+        &archive_path,
+        &passphrase,
+        &password,
+        username.as_deref(),
+        None,
+    ) {
+        Ok(report) => Ok(ProfileResult::success(report)),
+        Err(ArchiveError::InvalidPassphrase) => {
+            log::warn!(
+                "❌ Invalid passphrase for archive: {}",
+                archive_path.display()
+            );
+            Ok(ProfileResult::error("Invalid passphrase".to_string()))
+        }
+        Err(e) => {
+            log::error!("❌ Failed to import user: {}", e);
+            Ok(ProfileResult::error(e.to_string()))
+        }
+    }
+}
+
 /// Check if any users exist in the system
 ///
 /// Useful for determining if this is first launch
@@ -279,7 +807,9 @@ pub async fn save_command(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let manager = CommandManager::new(session.username.clone(), encryption_key);
 
         match manager.save_command(&command) {
@@ -312,7 +842,9 @@ pub async fn get_command(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let manager = CommandManager::new(session.username.clone(), encryption_key);
 
         match manager.load_command(&name) {
@@ -341,7 +873,9 @@ pub async fn list_commands(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let manager = CommandManager::new(session.username.clone(), encryption_key);
 
         match manager.list_commands() {
@@ -374,7 +908,9 @@ pub async fn delete_command(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let manager = CommandManager::new(session.username.clone(), encryption_key);
 
         match manager.delete_command(&name) {
@@ -409,7 +945,9 @@ pub async fn build_command_prompt(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let executor = CommandExecutor::new(session.username.clone(), encryption_key.clone());
 
         // Load user profile if exists
@@ -447,7 +985,9 @@ pub async fn get_static_cdp(
     let user_session = state.user_session.lock().await;
 
     if let Some(session) = user_session.as_ref() {
-        let encryption_key = session.get_encryption_key();
+        let Ok(encryption_key) = session.get_encryption_key() else {
+            return Ok(ProfileResult::error(AuthError::SessionLocked.to_string()));
+        };
         let executor = CommandExecutor::new(session.username.clone(), encryption_key);
 
         match executor.get_static_cdp_script(&name, params) {
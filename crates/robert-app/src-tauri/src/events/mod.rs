@@ -74,6 +74,15 @@ impl DebugEvent {
     }
 }
 
+/// Event emitted when the user session locks after inactivity
+pub const SESSION_LOCKED_EVENT: &str = "session-locked";
+
+/// Tell the frontend that the session of `username` was locked
+pub fn emit_session_locked(app: &AppHandle, username: impl Into<String>) -> Result<(), String> {
+    app.emit(SESSION_LOCKED_EVENT, username.into())
+        .map_err(|e| format!("Failed to emit event: {}", e))
+}
+
 /// Helper functions to emit specific events
 pub fn emit_info(app: &AppHandle, message: impl Into<String>) -> Result<(), String> {
     DebugEvent::Info {
@@ -11,10 +11,14 @@ mod logging;
 pub mod profiles;
 mod state;
 
+use robert_server::client::RetryPolicy;
 use state::AppState;
 
 use tauri::Manager;
 
+/// How often the active session is checked for auto-lock
+const AUTO_LOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize custom logger that writes to both console and encrypted log file
@@ -35,13 +39,55 @@ pub fn run() {
         .setup(|app| {
             let state = app.state::<AppState>();
             let webdriver_mode = state.webdriver_mode.clone();
+            let server_client = state.server_client().cloned();
+            let user_session = state.user_session.clone();
+            let app_handle = app.handle().clone();
+
+            // Lock the user session after inactivity
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(AUTO_LOCK_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+
+                    let session = user_session.lock().await;
+                    let Some(session) = session.as_ref() else {
+                        continue;
+                    };
+                    if logging::stop_if_locked(session) {
+                        if let Err(e) = events::emit_session_locked(&app_handle, &session.username)
+                        {
+                            log::warn!("⚠️  {}", e);
+                        }
+                    }
+                }
+            });
 
             // Spawn the embedded robert-server
             tauri::async_runtime::spawn(async move {
+                let server_client = match server_client {
+                    Ok(client) => client,
+                    Err(e) => {
+                        log::error!("❌ Embedded robert-server not started: {}", e);
+                        return;
+                    }
+                };
+
                 log::info!("🚀 Starting embedded robert-server...");
 
-                // load dev defaults for now
-                let config = robert_server::Config::dev_default();
+                // load dev defaults for now, listening on a private Unix
+                // socket where available instead of TCP localhost
+                #[allow(unused_mut)]
+                let mut config = robert_server::Config::dev_default();
+                #[cfg(unix)]
+                {
+                    match state::server_socket_path() {
+                        Ok(path) => config.server.unix_socket = Some(path.display().to_string()),
+                        Err(e) => {
+                            log::error!("❌ Embedded robert-server not started: {}", e);
+                            return;
+                        }
+                    }
+                }
 
                 // Spawn server in a separate task
                 tauri::async_runtime::spawn(async move {
@@ -51,26 +97,18 @@ pub fn run() {
                 });
 
                 // Wait for server to be healthy
-                let client = reqwest::Client::new();
-                let health_url = "http://localhost:8443/api/v1/health";
+                let client = server_client.with_retry_policy(RetryPolicy::none());
                 let mut retries = 0;
                 let max_retries = 30; // 30 attempts * 500ms = 15 seconds
 
                 while retries < max_retries {
-                    match client.get(health_url).send().await {
-                        Ok(res) => {
-                            if res.status().is_success() {
-                                log::info!(
-                                    "✅ Embedded robert-server is healthy and reachable at {}",
-                                    health_url
-                                );
-                                *webdriver_mode.lock().await = true; // Still using this flag to indicate "backend ready"
-                                break;
-                            }
-                        }
-                        Err(_) => {
-                            // Server starting up...
-                        }
+                    if client.health().await.is_ok() {
+                        log::info!(
+                            "✅ Embedded robert-server is healthy and reachable at {}",
+                            client.base_url()
+                        );
+                        *webdriver_mode.lock().await = true; // Still using this flag to indicate "backend ready"
+                        break;
                     }
                     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
                     retries += 1;
@@ -110,11 +148,24 @@ pub fn run() {
             commands::create_user,
             commands::login_user,
             commands::logout_user,
+            commands::login_remembered,
+            commands::forget_login,
+            commands::lock_session,
+            commands::unlock_session,
+            commands::is_session_locked,
             commands::get_current_user,
+            commands::change_password,
+            commands::recover_account,
+            commands::get_login_history,
+            commands::get_recovery_codes,
+            commands::regenerate_recovery_codes,
             commands::list_users,
             commands::get_user_profile,
             commands::update_user_profile,
             commands::has_users,
+            commands::delete_user,
+            commands::export_user,
+            commands::import_user,
             // Browser session management commands (Phase 2)
             commands::browser::launch_browser_session,
             commands::browser::close_browser_session,
@@ -5,14 +5,15 @@ pub mod logger;
 /// All logs are encrypted using the same encryption system as other user files.
 ///
 /// Features:
-/// - Encrypted log file storage
-/// - Log rotation (max 10MB per file, keeps last 3 files)
+/// - Append-only encrypted log file storage (one encrypted record per entry)
+/// - Log rotation by size and age (max 10MB or 7 days per file, keeps last 3 files)
+/// - Queries filtered by level, source and time range, with pagination
 /// - Captures both Rust and frontend logs
 /// - Thread-safe logging
 mod storage;
 
 pub use logger::log_frontend_message;
-pub use storage::{LogEntry, LogLevel, LogStorage};
+pub use storage::{LogEntry, LogLevel, LogPage, LogQuery, LogStorage};
 
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
@@ -21,8 +22,13 @@ use std::sync::Mutex;
 static LOG_STORAGE: Lazy<Mutex<Option<LogStorage>>> = Lazy::new(|| Mutex::new(None));
 
 /// Initialize the logging system for a specific user
-pub fn init_for_user(username: &str, password: &str) -> Result<(), String> {
-    let storage = LogStorage::new(username, password)
+///
+/// `encryption_key` is the data encryption key of the user's session.
+pub fn init_for_user(
+    username: &str,
+    encryption_key: crate::profiles::crypto::EncryptionKey,
+) -> Result<(), String> {
+    let storage = LogStorage::new(username, encryption_key, None)
         .map_err(|e| format!("Failed to initialize log storage: {}", e))?;
 
     *LOG_STORAGE.lock().unwrap() = Some(storage);
@@ -37,7 +43,66 @@ pub fn cleanup() {
     log::info!("Cleaned up encrypted logging");
 }
 
+/// Stop logging once the user's session is locked
+///
+/// Checks whether the session is locked rather than only whether this call
+/// locked it, since a session also locks itself when it is used after the
+/// auto-lock timeout.
+///
+/// # Returns
+/// - `true` if logging was stopped by this call
+pub fn stop_if_locked(session: &crate::profiles::auth::UserSession) -> bool {
+    session.lock_if_idle();
+    if !session.is_locked() || get_storage().is_none() {
+        return false;
+    }
+
+    cleanup();
+    true
+}
+
 /// Get reference to log storage
 pub(crate) fn get_storage() -> Option<LogStorage> {
     LOG_STORAGE.lock().unwrap().clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::get_logs;
+    use crate::profiles::auth::UserSession;
+    use crate::profiles::crypto::generate_key;
+    use crate::profiles::storage::get_user_dir;
+    use crate::profiles::types::UserConfig;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_logging_stops_after_auto_lock() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(get_user_dir("alice", Some(temp_dir.path())).unwrap()).unwrap();
+
+        let key = generate_key();
+        let storage = LogStorage::new("alice", key.clone(), Some(temp_dir.path())).unwrap();
+        *LOG_STORAGE.lock().unwrap() = Some(storage.clone());
+
+        let session = UserSession::new("alice".to_string(), UserConfig::default(), key)
+            .with_auto_lock(Some(Duration::from_secs(3600)));
+        assert!(!stop_if_locked(&session));
+
+        log_frontend_message(LogLevel::Info, "before lock".to_string()).unwrap();
+        assert_eq!(get_logs(None).await.unwrap().entries.len(), 1);
+
+        // Using the idle session locks it before the timer sees it
+        let session = session.with_auto_lock(Some(Duration::ZERO));
+        assert!(session.get_encryption_key().is_err());
+        assert!(stop_if_locked(&session));
+        assert!(!stop_if_locked(&session));
+
+        log_frontend_message(LogLevel::Info, "after lock".to_string()).unwrap();
+        assert!(get_logs(None).await.is_err());
+
+        let page = storage.query_logs(&LogQuery::default()).unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].message, "before lock");
+    }
+}
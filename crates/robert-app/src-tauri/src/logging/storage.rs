@@ -1,17 +1,28 @@
-use crate::profiles::crypto::{decrypt_file, derive_key, encrypt_file};
+use crate::profiles::crypto::{
+    decrypt_file, decrypt_stream, encrypt_record, is_record_log, is_stream_format,
+    record_log_length, record_log_prefix, EncryptionKey, EnvelopeHeader, FileKind, RecordReader,
+};
 use crate::profiles::storage::get_user_dir;
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 /// Maximum log file size before rotation (10MB)
 const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
 
+/// Maximum age of the oldest entry in the current log file before rotation (7 days)
+const MAX_LOG_AGE_DAYS: i64 = 7;
+
 /// Number of rotated log files to keep
 const MAX_LOG_FILES: usize = 3;
 
+/// Serializes appends and rotation across `LogStorage` clones
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
 /// Log file name
 const LOG_FILE_NAME: &str = "debug.log";
 
@@ -32,7 +43,8 @@ pub enum LogError {
 
 pub type Result<T> = std::result::Result<T, LogError>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Log level, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
     Debug,
     Info,
@@ -51,6 +63,20 @@ impl From<log::Level> for LogLevel {
     }
 }
 
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> std::result::Result<Self, Self::Err> {
+        match level.to_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(format!("Unknown log level: {}", level)),
+        }
+    }
+}
+
 impl std::fmt::Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -79,78 +105,229 @@ impl LogEntry {
             message,
         }
     }
+
+    /// Parse the entry timestamp
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok()
+    }
+}
+
+/// Filters and pagination for reading logs
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Only entries at this level or more severe
+    pub min_level: Option<LogLevel>,
+
+    /// Only entries from this source ("rust" or "frontend")
+    pub source: Option<String>,
+
+    /// Only entries at or after this time
+    pub since: Option<DateTime<FixedOffset>>,
+
+    /// Only entries before this time
+    pub until: Option<DateTime<FixedOffset>>,
+
+    /// Number of matching entries to skip, counting from the newest
+    pub offset: usize,
+
+    /// Maximum number of entries to return (all if None)
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    /// Check whether an entry passes the level, source and time filters
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let time = entry.time();
+
+        self.min_level.is_none_or(|level| entry.level >= level)
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| entry.source == *source)
+            && self
+                .since
+                .is_none_or(|since| time.is_some_and(|time| time >= since))
+            && self
+                .until
+                .is_none_or(|until| time.is_some_and(|time| time < until))
+    }
+}
+
+/// One page of log entries, newest first
+#[derive(Debug, Clone)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+
+    /// Whether more matching entries follow this page
+    pub has_more: bool,
 }
 
+/// Encrypted, append-only log storage for one user
+///
+/// The log file is a record log: every entry is encrypted on its own and
+/// appended, so writing an entry never reads or rewrites the rest of the
+/// file. The current file is rotated to `debug.log.1` before it would
+/// exceed `MAX_LOG_SIZE` or once its oldest entry is older than
+/// `MAX_LOG_AGE_DAYS`.
 #[derive(Clone)]
 pub struct LogStorage {
     username: String,
-    encryption_key: crate::profiles::crypto::EncryptionKey,
+    encryption_key: EncryptionKey,
     log_file_path: PathBuf,
+
+    /// Time of the oldest entry in the current log file, shared by clones
+    ///
+    /// Read when the storage is created and kept up to date by appends and
+    /// rotation, so appending never reads the log back.
+    oldest_entry: Arc<Mutex<Option<DateTime<FixedOffset>>>>,
 }
 
 impl LogStorage {
     /// Create a new log storage for a user
-    pub fn new(username: &str, password: &str) -> Result<Self> {
-        // Load salt for user
-        let salt = crate::profiles::storage::load_salt(username, None)?;
-
-        // Derive encryption key from password
-        let (encryption_key, _) = derive_key(password, Some(&salt))?;
-
+    ///
+    /// `encryption_key` is the user's data encryption key from their session.
+    /// A partial entry left by an interrupted write is dropped so new entries
+    /// stay readable, and a log in an older format is rotated away.
+    ///
+    /// # Parameters
+    /// - `base_dir`: Optional base directory (defaults to home directory)
+    pub fn new(
+        username: &str,
+        encryption_key: EncryptionKey,
+        base_dir: Option<&Path>,
+    ) -> Result<Self> {
         // Get log file path
-        let user_dir = get_user_dir(username, None)?;
+        let user_dir = get_user_dir(username, base_dir)?;
         let log_file_path = user_dir.join(LOG_FILE_NAME);
 
-        Ok(Self {
+        let storage = Self {
             username: username.to_string(),
             encryption_key,
             log_file_path,
-        })
+            oldest_entry: Arc::new(Mutex::new(None)),
+        };
+        storage.repair()?;
+
+        if storage.is_legacy_format()? {
+            storage.rotate()?;
+        }
+        *storage
+            .oldest_entry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = storage.oldest_entry_time()?;
+
+        Ok(storage)
     }
 
     /// Append a log entry to the encrypted log file
     pub fn append_log(&self, entry: LogEntry) -> Result<()> {
-        // Load existing logs
-        let mut logs = self.read_logs()?;
-
-        // Append new entry
-        logs.push(entry);
-
-        // Check if rotation is needed
-        self.rotate_if_needed()?;
-
-        // Write logs back
-        self.write_logs(&logs)?;
+        let time = entry.time();
+        let header = EnvelopeHeader::for_file(FileKind::Log, &self.username);
+        let record = encrypt_record(&serde_json::to_vec(&entry)?, &self.encryption_key, &header)?;
+
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut oldest_entry = self.oldest_entry.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Rotate before the file would grow past the limit
+        self.rotate_if_needed(record.len() as u64, &mut oldest_entry)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_file_path)?;
+
+        // A new file starts with the record log prefix
+        if file.metadata()?.len() == 0 {
+            let mut data = record_log_prefix();
+            data.extend_from_slice(&record);
+            file.write_all(&data)?;
+            *oldest_entry = time;
+        } else {
+            file.write_all(&record)?;
+            if oldest_entry.is_none() {
+                *oldest_entry = time;
+            }
+        }
 
         Ok(())
     }
 
-    /// Read all log entries from the encrypted log file
-    pub fn read_logs(&self) -> Result<Vec<LogEntry>> {
-        if !self.log_file_path.exists() {
-            return Ok(Vec::new());
+    /// Read log entries matching `query`, newest first
+    ///
+    /// Files are read from the newest (`debug.log`) to the oldest rotated
+    /// one, and reading stops as soon as the page is full or entries are
+    /// older than `query.since`.
+    pub fn query_logs(&self, query: &LogQuery) -> Result<LogPage> {
+        let wanted = query.limit.map(|limit| query.offset + limit);
+        let mut matched = Vec::new();
+        let mut has_more = false;
+
+        'files: for path in self.log_files() {
+            for entry in self.read_file(&path)?.into_iter().rev() {
+                // Entries are in time order, so everything after is older still
+                let before_range = query
+                    .since
+                    .is_some_and(|since| entry.time().is_some_and(|time| time < since));
+                if before_range {
+                    break 'files;
+                }
+
+                if !query.matches(&entry) {
+                    continue;
+                }
+
+                if wanted.is_some_and(|wanted| matched.len() == wanted) {
+                    has_more = true;
+                    break 'files;
+                }
+                matched.push(entry);
+            }
         }
 
+        Ok(LogPage {
+            entries: matched.into_iter().skip(query.offset).collect(),
+            has_more,
+        })
+    }
+
+    /// Read all log entries of one file, oldest first
+    fn read_file(&self, path: &Path) -> Result<Vec<LogEntry>> {
         // Read encrypted file
-        let encrypted = fs::read(&self.log_file_path)?;
+        let encrypted = fs::read(path)?;
 
         if encrypted.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Decrypt
-        let decrypted = decrypt_file(&encrypted, &self.encryption_key)?;
-
-        // Parse JSON (each line is a JSON object)
-        let content = String::from_utf8_lossy(&decrypted);
-        let mut logs = Vec::new();
-
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            match serde_json::from_str::<LogEntry>(line) {
+        let lines: Vec<Vec<u8>> = if is_record_log(&encrypted) {
+            RecordReader::new(encrypted.as_slice(), &self.encryption_key)?
+                .filter_map(|record| match record {
+                    Ok((plaintext, _)) => Some(plaintext),
+                    Err(e) => {
+                        // Skip the damaged entry but keep the rest
+                        eprintln!("Failed to decrypt log entry: {}", e);
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            // Logs written before the record format hold JSON lines in a
+            // single stream or envelope
+            let decrypted = if is_stream_format(&encrypted) {
+                decrypt_stream(&encrypted, &self.encryption_key)?.0
+            } else {
+                decrypt_file(&encrypted, &self.encryption_key)?
+            };
+            decrypted
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.trim_ascii().is_empty())
+                .map(<[u8]>::to_vec)
+                .collect()
+        };
+
+        let mut logs = Vec::with_capacity(lines.len());
+        for line in lines {
+            match serde_json::from_slice::<LogEntry>(&line) {
                 Ok(entry) => logs.push(entry),
                 Err(e) => {
                     // Log parsing error but continue
@@ -162,37 +339,108 @@ impl LogStorage {
         Ok(logs)
     }
 
-    /// Write logs to encrypted file
-    fn write_logs(&self, logs: &[LogEntry]) -> Result<()> {
-        // Serialize logs (one JSON per line)
-        let mut content = String::new();
-        for log in logs {
-            content.push_str(&serde_json::to_string(log)?);
-            content.push('\n');
+    /// Log files from the newest to the oldest
+    fn log_files(&self) -> Vec<PathBuf> {
+        std::iter::once(self.log_file_path.clone())
+            .chain(
+                (1..=MAX_LOG_FILES)
+                    .map(|i| self.log_file_path.with_extension(format!("log.{}", i))),
+            )
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Drop a partial record left at the end of the log by an interrupted write
+    fn repair(&self) -> Result<()> {
+        if !self.log_file_path.exists() {
+            return Ok(());
         }
 
-        // Encrypt
-        let encrypted = encrypt_file(content.as_bytes(), &self.encryption_key)?;
+        let encrypted = fs::read(&self.log_file_path)?;
+        if !is_record_log(&encrypted) {
+            return Ok(());
+        }
 
-        // Write to file
-        fs::write(&self.log_file_path, encrypted)?;
+        let length = record_log_length(&encrypted);
+        if length < encrypted.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(&self.log_file_path)?
+                .set_len(length as u64)?;
+        }
 
         Ok(())
     }
 
-    /// Rotate log files if current file exceeds max size
-    fn rotate_if_needed(&self) -> Result<()> {
-        if !self.log_file_path.exists() {
+    /// Whether the current log file holds entries in an older format
+    fn is_legacy_format(&self) -> Result<bool> {
+        let mut file = match File::open(&self.log_file_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if file.metadata()?.len() == 0 {
+            return Ok(false);
+        }
+
+        let mut magic = [0u8; 4];
+        Ok(file.read_exact(&mut magic).is_err() || !is_record_log(&magic))
+    }
+
+    /// Time of the oldest entry in the current log file, read from the file
+    fn oldest_entry_time(&self) -> Result<Option<DateTime<FixedOffset>>> {
+        let file = match File::open(&self.log_file_path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let Ok(mut records) = RecordReader::new(file, &self.encryption_key) else {
+            return Ok(None);
+        };
+        let first = records.next();
+
+        Ok(match first {
+            Some(Ok((plaintext, _))) => serde_json::from_slice::<LogEntry>(&plaintext)
+                .ok()
+                .and_then(|entry| entry.time()),
+            _ => None,
+        })
+    }
+
+    /// Rotate log files if the current file is too large or too old
+    ///
+    /// `incoming` is the size of the record about to be appended and
+    /// `oldest_entry` the cached time of the file's oldest entry, which is
+    /// reset when the file is rotated.
+    fn rotate_if_needed(
+        &self,
+        incoming: u64,
+        oldest_entry: &mut Option<DateTime<FixedOffset>>,
+    ) -> Result<()> {
+        let Ok(metadata) = fs::metadata(&self.log_file_path) else {
+            return Ok(());
+        };
+        if metadata.len() == 0 {
             return Ok(());
         }
 
-        let metadata = fs::metadata(&self.log_file_path)?;
-        if metadata.len() < MAX_LOG_SIZE {
+        let should_rotate = metadata.len() + incoming > MAX_LOG_SIZE
+            || oldest_entry.is_some_and(|time| {
+                chrono::Local::now().fixed_offset() - time
+                    > chrono::Duration::days(MAX_LOG_AGE_DAYS)
+            });
+        if !should_rotate {
             return Ok(());
         }
 
-        log::info!("Rotating log file for user: {}", self.username);
+        self.rotate()?;
+        *oldest_entry = None;
+
+        Ok(())
+    }
 
+    /// Move the current log file to `debug.log.1`, shifting older ones
+    fn rotate(&self) -> Result<()> {
         // Shift existing rotated files
         for i in (1..MAX_LOG_FILES).rev() {
             let old_path = self.log_file_path.with_extension(format!("log.{}", i));
@@ -218,6 +466,9 @@ impl LogStorage {
 
     /// Clear all logs for this user
     pub fn clear_logs(&self) -> Result<()> {
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        *self.oldest_entry.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
         // Remove main log file
         if self.log_file_path.exists() {
             fs::remove_file(&self.log_file_path)?;
@@ -253,3 +504,227 @@ impl LogStorage {
         Ok(total_size)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::crypto::generate_key;
+    use tempfile::TempDir;
+
+    /// Create a log storage for "alice" in a temporary base directory
+    fn create_storage(temp_dir: &TempDir, key: &EncryptionKey) -> LogStorage {
+        fs::create_dir_all(get_user_dir("alice", Some(temp_dir.path())).unwrap()).unwrap();
+        LogStorage::new("alice", key.clone(), Some(temp_dir.path())).unwrap()
+    }
+
+    fn entry_at(level: LogLevel, source: &str, message: &str, age: chrono::Duration) -> LogEntry {
+        LogEntry {
+            timestamp: (chrono::Local::now() - age).to_rfc3339(),
+            level,
+            source: source.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn messages(page: &LogPage) -> Vec<&str> {
+        page.entries
+            .iter()
+            .map(|entry| entry.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_query_filters_and_pagination() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = create_storage(&temp_dir, &generate_key());
+
+        let minutes = chrono::Duration::minutes;
+        for (i, (level, source)) in [
+            (LogLevel::Debug, "rust"),
+            (LogLevel::Info, "frontend"),
+            (LogLevel::Warn, "rust"),
+            (LogLevel::Error, "frontend"),
+            (LogLevel::Info, "rust"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let entry = entry_at(
+                level,
+                source,
+                &format!("m{}", i),
+                minutes(50 - 10 * i as i64),
+            );
+            storage.append_log(entry).unwrap();
+        }
+
+        // Newest first, everything
+        let page = storage.query_logs(&LogQuery::default()).unwrap();
+        assert_eq!(messages(&page), vec!["m4", "m3", "m2", "m1", "m0"]);
+        assert!(!page.has_more);
+
+        let page = storage
+            .query_logs(&LogQuery {
+                min_level: Some(LogLevel::Warn),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(messages(&page), vec!["m3", "m2"]);
+
+        let page = storage
+            .query_logs(&LogQuery {
+                source: Some("rust".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(messages(&page), vec!["m4", "m2", "m0"]);
+
+        let now = chrono::Local::now().fixed_offset();
+        let page = storage
+            .query_logs(&LogQuery {
+                since: Some(now - minutes(35)),
+                until: Some(now - minutes(15)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(messages(&page), vec!["m3", "m2"]);
+
+        // Pages of two
+        let page_query = |offset| LogQuery {
+            offset,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = storage.query_logs(&page_query(0)).unwrap();
+        assert_eq!(messages(&page), vec!["m4", "m3"]);
+        assert!(page.has_more);
+
+        let page = storage.query_logs(&page_query(2)).unwrap();
+        assert_eq!(messages(&page), vec!["m2", "m1"]);
+        assert!(page.has_more);
+
+        let page = storage.query_logs(&page_query(4)).unwrap();
+        assert_eq!(messages(&page), vec!["m0"]);
+        assert!(!page.has_more);
+
+        // A page ending exactly at the last entry has nothing more
+        let page = storage
+            .query_logs(&LogQuery {
+                offset: 3,
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(messages(&page), vec!["m1", "m0"]);
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn test_rotates_old_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let key = generate_key();
+        let storage = create_storage(&temp_dir, &key);
+        let rotated = storage.log_file_path.with_extension("log.1");
+
+        let old = chrono::Duration::days(MAX_LOG_AGE_DAYS + 1);
+        storage
+            .append_log(entry_at(LogLevel::Info, "rust", "old", old))
+            .unwrap();
+        assert!(!rotated.exists());
+
+        // The next entry starts a new file
+        storage
+            .append_log(LogEntry::new(LogLevel::Info, "rust".into(), "new".into()))
+            .unwrap();
+        assert!(rotated.exists());
+        assert_eq!(
+            messages(&storage.query_logs(&LogQuery::default()).unwrap()),
+            vec!["new", "old"]
+        );
+        assert_eq!(storage.read_file(&storage.log_file_path).unwrap().len(), 1);
+
+        // The age of an existing log is read when it is opened again
+        storage.clear_logs().unwrap();
+        storage
+            .append_log(entry_at(LogLevel::Info, "rust", "old", old))
+            .unwrap();
+        let reopened = create_storage(&temp_dir, &key);
+        reopened
+            .append_log(LogEntry::new(LogLevel::Info, "rust".into(), "new".into()))
+            .unwrap();
+        assert_eq!(reopened.read_file(&rotated).unwrap().len(), 1);
+        assert_eq!(
+            reopened.read_file(&reopened.log_file_path).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_repair_after_truncated_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let key = generate_key();
+        let storage = create_storage(&temp_dir, &key);
+
+        for message in ["one", "two"] {
+            storage
+                .append_log(LogEntry::new(LogLevel::Info, "rust".into(), message.into()))
+                .unwrap();
+        }
+        let intact_len = fs::metadata(&storage.log_file_path).unwrap().len();
+
+        // An interrupted write leaves part of a record behind
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&storage.log_file_path)
+            .unwrap();
+        file.write_all(&[0xff; 7]).unwrap();
+        drop(file);
+
+        let storage = create_storage(&temp_dir, &key);
+        assert_eq!(
+            fs::metadata(&storage.log_file_path).unwrap().len(),
+            intact_len
+        );
+
+        storage
+            .append_log(LogEntry::new(LogLevel::Info, "rust".into(), "three".into()))
+            .unwrap();
+        assert_eq!(
+            messages(&storage.query_logs(&LogQuery::default()).unwrap()),
+            vec!["three", "two", "one"]
+        );
+    }
+
+    #[test]
+    fn test_corrupt_record_is_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        let key = generate_key();
+        let storage = create_storage(&temp_dir, &key);
+
+        let mut record_ends = Vec::new();
+        for message in ["one", "two", "three"] {
+            storage
+                .append_log(LogEntry::new(LogLevel::Info, "rust".into(), message.into()))
+                .unwrap();
+            record_ends.push(fs::metadata(&storage.log_file_path).unwrap().len() as usize);
+        }
+
+        // Flip the last byte of the second record
+        let mut data = fs::read(&storage.log_file_path).unwrap();
+        data[record_ends[1] - 1] ^= 0xff;
+        fs::write(&storage.log_file_path, &data).unwrap();
+
+        let storage = create_storage(&temp_dir, &key);
+        assert_eq!(
+            fs::metadata(&storage.log_file_path).unwrap().len(),
+            data.len() as u64
+        );
+        storage
+            .append_log(LogEntry::new(LogLevel::Info, "rust".into(), "four".into()))
+            .unwrap();
+        assert_eq!(
+            messages(&storage.query_logs(&LogQuery::default()).unwrap()),
+            vec!["four", "three", "one"]
+        );
+    }
+}
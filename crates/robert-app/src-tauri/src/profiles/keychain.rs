@@ -0,0 +1,66 @@
+//! OS key store for remembered logins
+//!
+//! On Linux, remembered logins are kept in the Secret Service (GNOME Keyring,
+//! KWallet) under the `robert` service. Other platforms have no key store yet,
+//! so "remember me" is unavailable there.
+
+use crate::profiles::keystore::KeyStore;
+use std::sync::Arc;
+
+/// Get the key store of this platform
+///
+/// # Returns
+/// - `Some(Arc<dyn KeyStore>)`: The OS key store
+/// - `None`: If the platform has no supported key store
+pub fn os_key_store() -> Option<Arc<dyn KeyStore>> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(Arc::new(secret_service::SecretServiceKeyStore))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod secret_service {
+    use crate::profiles::keystore::{KeyStore, KeyStoreError, Result, KEYSTORE_SERVICE};
+    use keyring::Entry;
+
+    /// Key store backed by the freedesktop Secret Service API
+    pub struct SecretServiceKeyStore;
+
+    fn entry(account: &str) -> Result<Entry> {
+        Entry::new(KEYSTORE_SERVICE, account).map_err(map_error)
+    }
+
+    fn map_error(e: keyring::Error) -> KeyStoreError {
+        match e {
+            keyring::Error::NoStorageAccess(e) => KeyStoreError::Unavailable(e.to_string()),
+            e => KeyStoreError::Backend(e.to_string()),
+        }
+    }
+
+    impl KeyStore for SecretServiceKeyStore {
+        fn set_secret(&self, account: &str, secret: &[u8]) -> Result<()> {
+            entry(account)?.set_secret(secret).map_err(map_error)
+        }
+
+        fn get_secret(&self, account: &str) -> Result<Option<Vec<u8>>> {
+            match entry(account)?.get_secret() {
+                Ok(secret) => Ok(Some(secret)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(map_error(e)),
+            }
+        }
+
+        fn delete_secret(&self, account: &str) -> Result<()> {
+            match entry(account)?.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(map_error(e)),
+            }
+        }
+    }
+}
@@ -1,8 +1,45 @@
 use crate::developer_mode::DevTestServer;
 use crate::profiles::auth::UserSession;
+use crate::profiles::keychain::os_key_store;
+use crate::profiles::keystore::KeyStore;
+use robert_server::client::RobertClient;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Address of the embedded robert-server on platforms without Unix sockets
+#[cfg(not(unix))]
+pub const ROBERT_SERVER_URL: &str = "http://localhost:8443";
+
+/// Unix domain socket the embedded robert-server listens on
+///
+/// Only the current user can connect to it, so other local processes
+/// cannot reach the server's unauthenticated routes. The socket lives in
+/// the user's own data directory; a shared location such as the temp
+/// directory could be prepared by another user.
+///
+/// # Errors
+/// If the user has no local data directory (e.g. `HOME` is unset)
+#[cfg(unix)]
+pub fn server_socket_path() -> Result<std::path::PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("robert").join("server.sock"))
+        .ok_or_else(|| "No local data directory for the robert-server socket".to_string())
+}
+
+/// Client for the embedded robert-server
+fn embedded_server_client() -> Result<RobertClient, String> {
+    #[cfg(unix)]
+    {
+        RobertClient::unix_socket(server_socket_path()?)
+            .map_err(|e| format!("Failed to create robert-server client: {}", e))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Ok(RobertClient::new(ROBERT_SERVER_URL))
+    }
+}
+
 /// Application state that holds the developer mode resources and user session
 pub struct AppState {
     pub dev_server: Arc<Mutex<Option<DevTestServer>>>,
@@ -11,8 +48,14 @@ pub struct AppState {
     /// Active user session (username, config, and encryption key)
     /// None if no user is logged in
     pub user_session: Arc<Mutex<Option<UserSession>>>,
+    /// OS key store for remembered logins
+    /// None if the platform has no supported key store
+    pub key_store: Option<Arc<dyn KeyStore>>,
     /// HTTP Client for communicating with standalone webdriver
     pub http_client: reqwest::Client,
+    /// Client for the embedded robert-server
+    /// Err with the reason if the server cannot be reached on this system
+    server_client: Result<RobertClient, String>,
     /// Webdriver mode enabled (detected at startup)
     pub webdriver_mode: Arc<Mutex<bool>>,
 }
@@ -26,12 +69,24 @@ impl AppState {
             dev_server: Arc::new(Mutex::new(None)),
             session_id: Arc::new(Mutex::new(session_id)),
             user_session: Arc::new(Mutex::new(None)),
+            key_store: os_key_store(),
             http_client: reqwest::Client::new(),
+            server_client: embedded_server_client(),
             webdriver_mode: Arc::new(Mutex::new(false)),
         }
     }
 }
 
+impl AppState {
+    /// Client for the embedded robert-server
+    ///
+    /// # Errors
+    /// Why the embedded server is unavailable, to show to the user
+    pub fn server_client(&self) -> Result<&RobertClient, String> {
+        self.server_client.as_ref().map_err(Clone::clone)
+    }
+}
+
 impl Default for AppState {
     fn default() -> Self {
         Self::new()
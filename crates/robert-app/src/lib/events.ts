@@ -1,14 +1,21 @@
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type { DebugEventType } from './types';
 import { addDebugLog } from './stores';
+import { isLocked } from './userStore';
 
 let unlistenFn: UnlistenFn | null = null;
+let unlistenLockFn: UnlistenFn | null = null;
 
 export async function setupEventListeners(): Promise<void> {
   // Listen to debug events from backend
   unlistenFn = await listen<DebugEventType>('debug-event', (event) => {
     addDebugLog(event.payload);
   });
+
+  // The backend locks the session after inactivity
+  unlistenLockFn = await listen<string>('session-locked', () => {
+    isLocked.set(true);
+  });
 }
 
 export function cleanupEventListeners(): void {
@@ -16,4 +23,8 @@ export function cleanupEventListeners(): void {
     unlistenFn();
     unlistenFn = null;
   }
+  if (unlistenLockFn) {
+    unlistenLockFn();
+    unlistenLockFn = null;
+  }
 }
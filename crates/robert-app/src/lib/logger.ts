@@ -79,13 +79,35 @@ export function initializeConsoleLogger() {
   console.log('[Logger] Console logging initialized');
 }
 
-/** Get all logs from encrypted log file */
-export async function getLogs(): Promise<LogEntry[]> {
+/** Filters and pagination for reading logs */
+export interface LogFilter {
+  /** Minimum level ('debug', 'info', 'warn' or 'error') */
+  level?: string;
+  /** 'rust' or 'frontend' */
+  source?: string;
+  /** RFC 3339 start of the time range (inclusive) */
+  since?: string;
+  /** RFC 3339 end of the time range (exclusive) */
+  until?: string;
+  /** Number of matching entries to skip, counting from the newest */
+  offset?: number;
+  /** Maximum number of entries to return */
+  limit?: number;
+}
+
+/** One page of log entries, newest first */
+export interface LogPage {
+  entries: LogEntry[];
+  has_more: boolean;
+}
+
+/** Get logs from the encrypted log files, newest first */
+export async function getLogs(filter?: LogFilter): Promise<LogPage> {
   try {
-    return await invoke<LogEntry[]>('get_logs');
+    return await invoke<LogPage>('get_logs', { filter });
   } catch (error) {
     console.error('[Logger] Failed to get logs:', error);
-    return [];
+    return { entries: [], has_more: false };
   }
 }
 
@@ -165,6 +165,7 @@ export interface UserPreferences {
   default_timeout_ms: number;
   inference_mode: 'local' | 'cloud';
   language: string; // ISO 639-1 code (e.g., "en")
+  auto_lock_minutes: number | null; // Lock the session after inactivity (null: never)
 }
 
 /**
@@ -186,6 +187,65 @@ export interface PasswordValidation {
   suggestions: string[];
 }
 
+/**
+ * Login attempt from the audit trail
+ */
+export interface LoginAttempt {
+  at: string; // ISO 8601 timestamp
+  method: 'password' | 'remembered' | 'unlock' | 'password_change' | 'recovery' | 'deletion';
+  outcome: 'success' | 'failure' | 'blocked'; // blocked: refused during backoff or lockout
+}
+
+/**
+ * Login attempts of the current user
+ */
+export interface LoginHistory {
+  failed_since_last_login: LoginAttempt[]; // Between the previous login and this one
+  attempts: LoginAttempt[]; // Latest attempts, oldest first
+}
+
+/**
+ * What was removed when a user was deleted
+ */
+export interface DeleteReport {
+  username: string;
+  browser_profiles: string[]; // Browser profiles removed with the user
+  wiped_files: number; // Files overwritten before removal
+  wipe_failures: string[]; // Files that could not be overwritten (removed anyway)
+  removed_files: number;
+  removed_bytes: number;
+}
+
+/**
+ * File contained in an exported user archive
+ */
+export interface ArchiveEntry {
+  path: string; // Relative to the user directory
+  size: number;
+  sha256: string; // Lowercase hex
+}
+
+/**
+ * Manifest of an exported user archive
+ */
+export interface ArchiveManifest {
+  format_version: number;
+  username: string;
+  exported_at: string; // ISO 8601 timestamp
+  entries: ArchiveEntry[];
+}
+
+/**
+ * Result of importing a user archive
+ */
+export interface ImportReport {
+  username: string; // Username the archive was imported as
+  renamed_from: string | null; // Original username if renamed to avoid a collision
+  commands: number;
+  log_files: number;
+  browser_profile_files: number;
+}
+
 // ============================================================================
 // Command System Types (Phase 3 - Markdown-based)
 // ============================================================================
@@ -10,6 +10,7 @@
  * - Calls Tauri commands for all backend operations
  * - Automatically checks for users on app start
  * - Persists current user info in memory (cleared on logout)
+ * - Tracks whether the session is locked (after inactivity)
  *
  * Security Notes:
  * - Never stores passwords in frontend
@@ -17,15 +18,41 @@
  * - User session cleared on logout or app close
  */
 
-import { writable, derived } from 'svelte/store';
+import { writable, derived, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
-import type { UserConfig, ProfileResult, PasswordValidation } from './types';
+import type {
+  UserConfig,
+  ProfileResult,
+  PasswordValidation,
+  ArchiveManifest,
+  ImportReport,
+  LoginAttempt,
+  LoginHistory,
+  DeleteReport,
+} from './types';
 
 /**
  * Current user configuration (null if not logged in)
  */
 export const currentUser = writable<UserConfig | null>(null);
 
+/**
+ * Whether the current session is locked and needs the password again
+ */
+export const isLocked = writable<boolean>(false);
+
+/**
+ * Failed login attempts between the previous login and the current one
+ * Shown to the user until dismissed
+ */
+export const failedLoginAttempts = writable<LoginAttempt[]>([]);
+
+/**
+ * Whether the user logged out since the app started
+ * A remembered login is then only used when a profile is picked again
+ */
+let loggedOut = false;
+
 /**
  * Loading state for async operations
  */
@@ -67,6 +94,7 @@ export const userPreferences = derived(
       default_timeout_ms: 5000,
       inference_mode: 'local' as const,
       language: 'en',
+      auto_lock_minutes: 15,
     }
 );
 
@@ -192,9 +220,14 @@ export async function createUser(username: string, password: string): Promise<bo
  *
  * @param username - Username to log in
  * @param password - User's password
+ * @param remember - Remember the login in the OS keychain (unchecking forgets it)
  * @returns Promise<boolean> - True if login successful
  */
-export async function loginUser(username: string, password: string): Promise<boolean> {
+export async function loginUser(
+  username: string,
+  password: string,
+  remember = false
+): Promise<boolean> {
   try {
     isLoading.set(true);
     userError.set(null);
@@ -202,11 +235,14 @@ export async function loginUser(username: string, password: string): Promise<boo
     const result = await invoke<ProfileResult<UserConfig>>('login_user', {
       username,
       password,
+      remember,
     });
 
     if (result.success && result.data) {
       // Login successful, store user in state
       currentUser.set(result.data);
+      void loadLoginHistory();
+      isLocked.set(false);
       return true;
     } else {
       const error = result.error || 'Incorrect password';
@@ -222,6 +258,105 @@ export async function loginUser(username: string, password: string): Promise<boo
   }
 }
 
+/**
+ * Log in a user through their remembered login (no password)
+ * Fails silently if the login is not remembered
+ *
+ * @param username - Username to log in
+ * @returns Promise<boolean> - True if login successful
+ */
+export async function loginRemembered(username: string): Promise<boolean> {
+  try {
+    const result = await invoke<ProfileResult<UserConfig>>('login_remembered', {
+      username,
+    });
+
+    if (result.success && result.data) {
+      currentUser.set(result.data);
+      void loadLoginHistory();
+      isLocked.set(false);
+      return true;
+    }
+    return false;
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Whether the login screen may sign in a remembered user on its own
+ * (not after the user logged out)
+ */
+export function canAutoLogin(): boolean {
+  return !loggedOut;
+}
+
+/**
+ * Forget a user's remembered login
+ *
+ * @param username - User whose login is forgotten
+ */
+export async function forgetLogin(username: string): Promise<void> {
+  try {
+    const result = await invoke<ProfileResult<void>>('forget_login', { username });
+
+    if (!result.success) {
+      userError.set(result.error || 'Failed to forget login');
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to forget login: ${errorMessage}`);
+  }
+}
+
+/**
+ * Lock the current session
+ * Drops the encryption key from memory until the password is entered again
+ */
+export async function lockSession(): Promise<void> {
+  try {
+    const result = await invoke<ProfileResult<void>>('lock_session');
+
+    if (result.success) {
+      isLocked.set(true);
+    } else {
+      userError.set(result.error || 'Failed to lock session');
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to lock session: ${errorMessage}`);
+  }
+}
+
+/**
+ * Unlock the current session with the user's password
+ *
+ * @param password - User's password
+ * @returns Promise<boolean> - True if the session was unlocked
+ */
+export async function unlockSession(password: string): Promise<boolean> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const result = await invoke<ProfileResult<void>>('unlock_session', { password });
+
+    if (result.success) {
+      isLocked.set(false);
+      return true;
+    } else {
+      userError.set(result.error || 'Incorrect password');
+      return false;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Unlock failed: ${errorMessage}`);
+    return false;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
 /**
  * Log out the current user
  * Clears user session and sensitive data from memory
@@ -236,6 +371,9 @@ export async function logoutUser(): Promise<void> {
     if (result.success) {
       // Clear user from state
       currentUser.set(null);
+      isLocked.set(false);
+      failedLoginAttempts.set([]);
+      loggedOut = true;
     } else {
       const error = result.error || 'Failed to logout';
       userError.set(error);
@@ -248,6 +386,178 @@ export async function logoutUser(): Promise<void> {
   }
 }
 
+/**
+ * Change the current user's password
+ * Re-encrypts the user's files with a new key; the old password keeps
+ * working if the change fails
+ *
+ * @param currentPassword - The user's current password
+ * @param newPassword - New password (minimum 12 characters)
+ * @returns Promise<boolean> - True if the password was changed
+ */
+export async function changePassword(
+  currentPassword: string,
+  newPassword: string
+): Promise<boolean> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const passwordValidation = validatePassword(newPassword);
+    if (!passwordValidation.valid) {
+      userError.set(passwordValidation.errors.join(', '));
+      return false;
+    }
+
+    const result = await invoke<ProfileResult<void>>('change_password', {
+      currentPassword,
+      newPassword,
+    });
+
+    if (result.success) {
+      return true;
+    } else {
+      const error = result.error || 'Failed to change password';
+      userError.set(error);
+      return false;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Password change failed: ${errorMessage}`);
+    return false;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/**
+ * Recover an account with a recovery code
+ * Sets a new password and logs the user in; the code cannot be used again
+ *
+ * @param username - Username to recover
+ * @param recoveryCode - One of the user's unused recovery codes
+ * @param newPassword - New password (minimum 12 characters)
+ * @returns Promise<boolean> - True if recovery successful
+ */
+export async function recoverAccount(
+  username: string,
+  recoveryCode: string,
+  newPassword: string
+): Promise<boolean> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const passwordValidation = validatePassword(newPassword);
+    if (!passwordValidation.valid) {
+      userError.set(passwordValidation.errors.join(', '));
+      return false;
+    }
+
+    const result = await invoke<ProfileResult<UserConfig>>('recover_account', {
+      username,
+      recoveryCode,
+      newPassword,
+    });
+
+    if (result.success && result.data) {
+      currentUser.set(result.data);
+      void loadLoginHistory();
+      return true;
+    } else {
+      const error = result.error || 'Invalid recovery code';
+      userError.set(error);
+      return false;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Account recovery failed: ${errorMessage}`);
+    return false;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/**
+ * Get the login attempts of the current user
+ *
+ * @returns Promise<LoginHistory | null> - Login history or null on error
+ */
+export async function getLoginHistory(): Promise<LoginHistory | null> {
+  try {
+    const result = await invoke<ProfileResult<LoginHistory>>('get_login_history');
+
+    if (result.success && result.data) {
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to load login history');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to load login history: ${errorMessage}`);
+    return null;
+  }
+}
+
+/**
+ * Load the failed attempts since the previous login into failedLoginAttempts
+ */
+async function loadLoginHistory(): Promise<void> {
+  const history = await getLoginHistory();
+  failedLoginAttempts.set(history ? history.failed_since_last_login : []);
+}
+
+/**
+ * Get the current user's unused recovery codes
+ *
+ * @returns Promise<string[] | null> - Recovery codes or null on error
+ */
+export async function getRecoveryCodes(): Promise<string[] | null> {
+  try {
+    const result = await invoke<ProfileResult<string[]>>('get_recovery_codes');
+
+    if (result.success && result.data) {
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to load recovery codes');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to load recovery codes: ${errorMessage}`);
+    return null;
+  }
+}
+
+/**
+ * Replace the current user's recovery codes with a new set
+ * The previous codes stop working
+ *
+ * @returns Promise<string[] | null> - New recovery codes or null on error
+ */
+export async function regenerateRecoveryCodes(): Promise<string[] | null> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const result = await invoke<ProfileResult<string[]>>('regenerate_recovery_codes');
+
+    if (result.success && result.data) {
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to regenerate recovery codes');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to regenerate recovery codes: ${errorMessage}`);
+    return null;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
 /**
  * Get current user config
  * Fetches latest user data from backend
@@ -307,6 +617,142 @@ export async function updateUserProfile(content: string): Promise<boolean> {
   }
 }
 
+/**
+ * Delete a user and all of their data
+ * Deleting the logged-in user also logs them out
+ *
+ * @param username - User to delete
+ * @param password - The user's password, as confirmation
+ * @returns Promise<DeleteReport | null> - What was removed or null on error
+ */
+export async function deleteUser(
+  username: string,
+  password: string
+): Promise<DeleteReport | null> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const result = await invoke<ProfileResult<DeleteReport>>('delete_user', {
+      username,
+      password,
+    });
+
+    if (result.success && result.data) {
+      if (get(currentUser)?.username === username) {
+        currentUser.set(null);
+        isLocked.set(false);
+        failedLoginAttempts.set([]);
+      }
+      await checkHasUsers();
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to delete user');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Failed to delete user: ${errorMessage}`);
+    return null;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/**
+ * Export the current user to an encrypted archive
+ *
+ * @param dest - Path of the archive file to write
+ * @param passphrase - Passphrase protecting the archive (minimum 12 characters)
+ * @param options - Also export browser profiles and/or debug logs
+ * @returns Promise<ArchiveManifest | null> - Manifest of the exported files or null on error
+ */
+export async function exportUser(
+  dest: string,
+  passphrase: string,
+  options: { includeBrowserProfiles?: boolean; includeLogs?: boolean } = {}
+): Promise<ArchiveManifest | null> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const passphraseValidation = validatePassword(passphrase);
+    if (!passphraseValidation.valid) {
+      userError.set(passphraseValidation.errors.join(', '));
+      return null;
+    }
+
+    const result = await invoke<ProfileResult<ArchiveManifest>>('export_user', {
+      dest,
+      passphrase,
+      includeBrowserProfiles: options.includeBrowserProfiles ?? false,
+      includeLogs: options.includeLogs ?? false,
+    });
+
+    if (result.success && result.data) {
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to export user');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Export failed: ${errorMessage}`);
+    return null;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/**
+ * Import a user from an encrypted archive
+ * The imported user gets a new password; if the archived username is taken
+ * the user is imported under a free name (see `renamed_from` in the report)
+ *
+ * @param archivePath - Path of the archive file
+ * @param passphrase - Passphrase the archive was exported with
+ * @param password - Password for the imported user (minimum 12 characters)
+ * @param username - Import under this username instead of the archived one
+ * @returns Promise<ImportReport | null> - Import report or null on error
+ */
+export async function importUser(
+  archivePath: string,
+  passphrase: string,
+  password: string,
+  username?: string
+): Promise<ImportReport | null> {
+  try {
+    isLoading.set(true);
+    userError.set(null);
+
+    const passwordValidation = validatePassword(password);
+    if (!passwordValidation.valid) {
+      userError.set(passwordValidation.errors.join(', '));
+      return null;
+    }
+
+    const result = await invoke<ProfileResult<ImportReport>>('import_user', {
+      archivePath,
+      passphrase,
+      password,
+      username: username ?? null,
+    });
+
+    if (result.success && result.data) {
+      return result.data;
+    } else {
+      userError.set(result.error || 'Failed to import user');
+      return null;
+    }
+  } catch (error) {
+    const errorMessage = error instanceof Error ? error.message : String(error);
+    userError.set(`Import failed: ${errorMessage}`);
+    return null;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
 /**
  * Validate username format
  * Rules:
@@ -431,7 +877,11 @@ export async function initializeUserStore(): Promise<void> {
       await listUsers();
 
       // Try to restore session (will fail if no active session)
-      await getCurrentUser();
+      const user = await getCurrentUser();
+      if (user) {
+        const locked = await invoke<ProfileResult<boolean>>('is_session_locked');
+        isLocked.set(locked.success && locked.data === true);
+      }
     }
   } catch (error) {
     console.error('Failed to initialize user store:', error);
@@ -0,0 +1,186 @@
+//! Batch endpoints
+//!
+//! Submits, inspects and cancels batches of execute requests (see
+//! [`crate::batch`]). Results are downloaded as JSON Lines, one
+//! [`BatchItemResult`](crate::models::BatchItemResult) per item.
+
+use crate::batch::BatchManager;
+use crate::claude::Executor;
+use crate::config::Config;
+use crate::conversation::ConversationManager;
+use crate::models::BatchRequest;
+use crate::session::SessionManager;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::{http::StatusCode, reply, Reply};
+
+/// Content type of the results download
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// POST /api/v1/batches handler
+///
+/// Validates every request of the batch and starts running them in the
+/// background.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `batch` - Requests or template to run
+/// * `batches` - Shared batch manager
+/// * `executor` - Claude executor (real or mock)
+/// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
+/// * `config` - Server configuration for validation limits
+///
+/// # Returns
+/// 201 with the status of the new batch
+///
+/// # Errors
+/// Rejects with RobertError::InvalidRequest for invalid batches and
+/// RobertError::ServerBusy (503) when too many batches are running
+///
+/// # Example Response
+/// ```json
+/// {
+///   "batch_id": "uuid",
+///   "status": "running",
+///   "created_at": "2025-10-17T10:30:00Z",
+///   "max_parallel": 4,
+///   "progress": {"total": 2, "queued": 2, "running": 0, "completed": 0, "failed": 0, "cancelled": 0},
+///   "items": [
+///     {"index": 0, "session_id": "uuid", "status": "queued"},
+///     {"index": 1, "session_id": "uuid", "status": "queued"}
+///   ]
+/// }
+/// ```
+pub async fn create_batch_handler(
+    token: String,
+    batch: BatchRequest,
+    batches: Arc<BatchManager>,
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: Arc<Config>,
+) -> Result<impl Reply, warp::Rejection> {
+    let status = batches
+        .submit(
+            &token,
+            batch,
+            executor,
+            session_manager,
+            conversations,
+            config,
+        )
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::with_status(
+        reply::json(&status),
+        StatusCode::CREATED,
+    ))
+}
+
+/// GET /api/v1/batches/:id handler
+///
+/// Returns the progress of a batch and the session status of every item
+/// that has started.
+///
+/// # Arguments
+/// * `batch_id` - UUID of the batch
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `batches` - Shared batch manager
+/// * `session_manager` - Session tracking
+///
+/// # Returns
+/// JSON batch status
+///
+/// # Errors
+/// Rejects with RobertError::BatchNotFound (404) if the batch does not
+/// exist or belongs to another key
+pub async fn get_batch_handler(
+    batch_id: Uuid,
+    token: String,
+    batches: Arc<BatchManager>,
+    session_manager: Arc<SessionManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let status = batches
+        .status(batch_id, &token, &session_manager)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&status))
+}
+
+/// GET /api/v1/batches/:id/results handler
+///
+/// Returns one JSON line per item, in submission order. Items that are
+/// still queued or running are included with their current state.
+///
+/// # Arguments
+/// * `batch_id` - UUID of the batch
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `batches` - Shared batch manager
+///
+/// # Returns
+/// `application/x-ndjson` body
+///
+/// # Errors
+/// Rejects with RobertError::BatchNotFound (404) if the batch does not
+/// exist or belongs to another key
+///
+/// # Example Response
+/// ```text
+/// {"index":0,"session_id":"uuid","status":"completed","content":"...","usage":{...}}
+/// {"index":1,"session_id":"uuid","status":"failed","content":"","error":"..."}
+/// ```
+pub async fn batch_results_handler(
+    batch_id: Uuid,
+    token: String,
+    batches: Arc<BatchManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let results = batches
+        .results(batch_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    let mut body = String::new();
+    for result in &results {
+        body.push_str(&serde_json::to_string(result).expect("results serialize"));
+        body.push('\n');
+    }
+
+    Ok(reply::with_header(
+        body,
+        "content-type",
+        NDJSON_CONTENT_TYPE,
+    ))
+}
+
+/// DELETE /api/v1/batches/:id handler
+///
+/// Cancels a batch: queued items are dropped and running sessions are
+/// cancelled.
+///
+/// # Arguments
+/// * `batch_id` - UUID of the batch
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `batches` - Shared batch manager
+///
+/// # Returns
+/// JSON batch status after cancellation
+///
+/// # Errors
+/// Rejects with RobertError::BatchNotFound (404) if the batch does not
+/// exist or belongs to another key, and RobertError::InvalidRequest (400)
+/// if it has already finished
+pub async fn cancel_batch_handler(
+    batch_id: Uuid,
+    token: String,
+    batches: Arc<BatchManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let status = batches
+        .cancel(batch_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&status))
+}
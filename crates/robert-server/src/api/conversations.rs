@@ -0,0 +1,185 @@
+//! Conversation endpoints
+//!
+//! Creates, reads and deletes multi-turn conversations. Turns are added by
+//! execute requests that set `options.conversation_id` (see
+//! [`crate::conversation`]).
+
+use crate::conversation::ConversationManager;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::{http::StatusCode, reply, Reply};
+
+/// POST /api/v1/conversations handler
+///
+/// Creates an empty conversation owned by the caller's key.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `manager` - Shared conversation manager
+///
+/// # Returns
+/// 201 with the new conversation
+///
+/// # Errors
+/// Rejects with RobertError::Internal when the conversation store is full
+///
+/// # Example Response
+/// ```json
+/// {
+///   "conversation_id": "uuid",
+///   "created_at": "2025-10-17T10:30:00Z",
+///   "updated_at": "2025-10-17T10:30:00Z",
+///   "turns": []
+/// }
+/// ```
+pub async fn create_conversation_handler(
+    token: String,
+    manager: Arc<ConversationManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let conversation = manager.create(&token).await.map_err(warp::reject::custom)?;
+
+    Ok(reply::with_status(
+        reply::json(&conversation),
+        StatusCode::CREATED,
+    ))
+}
+
+/// GET /api/v1/conversations/:id handler
+///
+/// Returns a conversation with its transcript.
+///
+/// # Arguments
+/// * `conversation_id` - UUID of the conversation
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `manager` - Shared conversation manager
+///
+/// # Returns
+/// JSON conversation
+///
+/// # Errors
+/// Rejects with RobertError::ConversationNotFound (404) if the
+/// conversation does not exist or belongs to another key
+///
+/// # Example Response
+/// ```json
+/// {
+///   "conversation_id": "uuid",
+///   "created_at": "2025-10-17T10:30:00Z",
+///   "updated_at": "2025-10-17T10:31:12Z",
+///   "claude_session_id": "4f1c...",
+///   "turns": [
+///     {
+///       "session_id": "uuid",
+///       "prompt": "What is on this page?",
+///       "response": "A login form.",
+///       "status": "completed",
+///       "started_at": "2025-10-17T10:30:05Z",
+///       "completed_at": "2025-10-17T10:31:12Z"
+///     }
+///   ]
+/// }
+/// ```
+pub async fn get_conversation_handler(
+    conversation_id: Uuid,
+    token: String,
+    manager: Arc<ConversationManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let conversation = manager
+        .get(conversation_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&conversation))
+}
+
+/// DELETE /api/v1/conversations/:id handler
+///
+/// Deletes a conversation and its transcript.
+///
+/// # Arguments
+/// * `conversation_id` - UUID of the conversation
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `manager` - Shared conversation manager
+///
+/// # Returns
+/// 204 No Content
+///
+/// # Errors
+/// Rejects with RobertError::ConversationNotFound (404) if the
+/// conversation does not exist or belongs to another key
+pub async fn delete_conversation_handler(
+    conversation_id: Uuid,
+    token: String,
+    manager: Arc<ConversationManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    manager
+        .delete(conversation_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RobertError;
+
+    fn is_not_found(rejection: &warp::Rejection) -> bool {
+        matches!(
+            rejection.find::<RobertError>(),
+            Some(RobertError::ConversationNotFound(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn test_conversation_handlers() {
+        let manager = Arc::new(ConversationManager::new(10));
+
+        let response = create_conversation_handler("key".to_string(), manager.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id: Uuid = created["conversation_id"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(created["turns"].as_array().unwrap().len(), 0);
+
+        let response = get_conversation_handler(id, "key".to_string(), manager.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Other keys cannot see or delete it
+        let rejection = get_conversation_handler(id, "other".to_string(), manager.clone())
+            .await
+            .err()
+            .unwrap();
+        assert!(is_not_found(&rejection));
+        let rejection = delete_conversation_handler(id, "other".to_string(), manager.clone())
+            .await
+            .err()
+            .unwrap();
+        assert!(is_not_found(&rejection));
+
+        let response = delete_conversation_handler(id, "key".to_string(), manager.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let rejection = delete_conversation_handler(id, "key".to_string(), manager)
+            .await
+            .err()
+            .unwrap();
+        assert!(is_not_found(&rejection));
+    }
+}
@@ -1,54 +1,95 @@
 //! Execute endpoint for running Claude CLI requests
 //!
-//! Handles POST /api/v1/execute with streaming SSE responses.
+//! Handles POST /api/v1/execute with streaming SSE responses. Requests
+//! with an `Idempotency-Key` header or a `callback_url` are run detached
+//! from the connection, so that retries can attach to them (see
+//! [`crate::idempotency`]) and callbacks are sent even if the client goes
+//! away (see [`crate::webhook`]).
 
-use crate::claude::Executor;
+use crate::claude::{permissions, Executor};
 use crate::config::Config;
+use crate::conversation::ConversationManager;
 use crate::error::RobertError;
+use crate::idempotency::{Claim, EventLog};
 use crate::models::{ClaudeEvent, RobertRequest};
 use crate::session::SessionManager;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, Instrument};
+use uuid::Uuid;
+use warp::http::HeaderValue;
 use warp::Reply;
 
+/// Header that marks a reply as attached to an earlier request's run
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+
 /// Execute endpoint handler
 ///
 /// Processes a Robert request and returns a stream of Claude events via SSE.
+/// Requested tool permissions are checked against the server policy and
+/// the caller's usage budget first. Usage reported on completion is
+/// charged to the session and the caller's key. Requests that reference a
+/// conversation continue it and are recorded as its next turn.
+///
+/// Requests with an `Idempotency-Key` or a `callback_url` run
+/// independently of the connection. Repeating the key within `limits.idempotency_window_seconds` attaches to
+/// the same run: all of its events are replayed from the start and the
+/// reply carries `Idempotent-Replayed: true`.
 ///
 /// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `idempotency_key` - Value of the `Idempotency-Key` header, if any
 /// * `request` - Validated Robert request
 /// * `executor` - Claude executor (real or mock)
 /// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
 /// * `config` - Server configuration for validation limits
 ///
 /// # Returns
 /// Server-Sent Events stream of Claude events
+///
+/// # Errors
+/// Rejects with the errors of [`start_session`], and with
+/// RobertError::InvalidRequest for malformed idempotency keys or keys
+/// already used for another session
 pub async fn execute_handler(
-    request: RobertRequest,
+    token: String,
+    idempotency_key: Option<String>,
+    mut request: RobertRequest,
     executor: Arc<dyn Executor>,
     session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
     config: Arc<Config>,
-) -> Result<impl Reply, warp::Rejection> {
-    let session_id = request.session_id;
-
-    // Validate request against configured limits
-    if let Err(e) = request.validate(
-        config.limits.max_screenshot_count,
-        config.limits.max_prompt_length,
-        50000, // max intent length - could be configurable
-    ) {
-        return Err(warp::reject::custom(crate::auth::AuthRejection(
-            RobertError::InvalidRequest(e),
-        )));
+) -> Result<warp::reply::Response, warp::Rejection> {
+    if idempotency_key.is_some() || request.options.callback_url.is_some() {
+        return execute_detached(
+            token,
+            idempotency_key,
+            request,
+            executor,
+            session_manager,
+            conversations,
+            config,
+        )
+        .await
+        .map_err(warp::reject::custom);
     }
 
-    // Register session
-    if let Err(e) = session_manager
-        .register(session_id, config.claude.max_concurrent_sessions)
-        .await
+    let session_id = request.session_id;
+
+    if let Err(e) = start_session(
+        &token,
+        &mut request,
+        executor.as_ref(),
+        &session_manager,
+        &conversations,
+        &config,
+    )
+    .await
     {
-        return Err(warp::reject::custom(crate::auth::AuthRejection(e)));
+        return Err(warp::reject::custom(e));
     }
 
     // Execute request and get event stream
@@ -60,41 +101,317 @@ pub async fn execute_handler(
         while let Some(result) = event_stream.next().await {
             match result {
                 Ok(event) => {
-                    // Check if this is a terminal event
-                    let is_complete = matches!(event, ClaudeEvent::Complete { .. });
-                    let is_error = matches!(event, ClaudeEvent::Error { .. });
-
-                    // Update session status
-                    if is_complete {
-                        let _ = session_manager_clone.complete(session_id).await;
-                    } else if is_error {
-                        if let ClaudeEvent::Error { message, .. } = &event {
-                            let _ = session_manager_clone.fail(session_id, message.clone()).await;
-                        }
-                    }
+                    track_event(&session_manager_clone, &conversations, session_id, &event).await;
 
                     // Convert event to SSE format
-                    let sse_data = event.to_sse();
-                    yield Ok::<_, Infallible>(warp::sse::Event::default().data(sse_data));
+                    yield Ok::<_, Infallible>(event.to_sse_event());
                 }
                 Err(e) => {
-                    // Convert error to SSE error event
-                    let error_event = ClaudeEvent::Error {
-                        code: e.error_code(),
-                        message: e.to_string(),
-                    };
-                    let sse_data = error_event.to_sse();
-                    yield Ok::<_, Infallible>(warp::sse::Event::default().data(sse_data));
-
-                    // Mark session as failed
-                    let _ = session_manager_clone.fail(session_id, e.to_string()).await;
+                    // Mark session as failed and report the error
+                    let error_event =
+                        track_failure(&session_manager_clone, &conversations, session_id, &e).await;
+                    yield Ok::<_, Infallible>(error_event.to_sse_event());
                     break;
                 }
             }
         }
     };
 
-    Ok(warp::sse::reply(warp::sse::keep_alive().stream(sse_stream)))
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(sse_stream)).into_response())
+}
+
+/// Runs a request independently of the connection
+///
+/// The session runs on a background task that records its events; the
+/// reply streams them from the session's event log. With an idempotency
+/// key, the first request starts the session and later requests with the
+/// key stream the same log.
+async fn execute_detached(
+    token: String,
+    idempotency_key: Option<String>,
+    mut request: RobertRequest,
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: Arc<Config>,
+) -> Result<warp::reply::Response, RobertError> {
+    let session_id = request.session_id;
+    let claim = match &idempotency_key {
+        Some(key) => {
+            let window = Duration::from_secs(config.limits.idempotency_window_seconds);
+            session_manager
+                .idempotency()
+                .claim(&token, key, session_id, window)
+                .await?
+        }
+        None => Claim::New(Arc::new(EventLog::new())),
+    };
+
+    let (log, replayed) = match claim {
+        Claim::Existing(log) => {
+            info!("Attaching to session {} (Idempotency-Key)", session_id);
+            (log, true)
+        }
+        Claim::New(log) => {
+            if let Err(e) = start_session(
+                &token,
+                &mut request,
+                executor.as_ref(),
+                &session_manager,
+                &conversations,
+                &config,
+            )
+            .await
+            {
+                if let Some(key) = &idempotency_key {
+                    session_manager.idempotency().release(&token, key, &e).await;
+                }
+                return Err(e);
+            }
+
+            let event_stream = executor.execute(request).await;
+            tokio::spawn(
+                record_run(
+                    event_stream,
+                    log.clone(),
+                    session_manager,
+                    conversations,
+                    session_id,
+                )
+                .instrument(tracing::Span::current()),
+            );
+            (log, false)
+        }
+    };
+
+    let sse_stream = log
+        .subscribe()
+        .map(|event| Ok::<_, Infallible>(event.to_sse_event()));
+    let mut response = warp::sse::reply(warp::sse::keep_alive().stream(sse_stream)).into_response();
+    if replayed {
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+    Ok(response)
+}
+
+/// Drives a detached run to completion and records its events
+///
+/// Runs whether or not any client is still attached.
+async fn record_run(
+    mut event_stream: Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin>,
+    log: Arc<EventLog>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    session_id: Uuid,
+) {
+    while let Some(result) = event_stream.next().await {
+        match result {
+            Ok(event) => {
+                track_event(&session_manager, &conversations, session_id, &event).await;
+                log.push(event);
+            }
+            Err(e) => {
+                log.push(track_failure(&session_manager, &conversations, session_id, &e).await);
+                break;
+            }
+        }
+    }
+
+    // Report runs that stopped without a complete event
+    session_manager.webhooks().finish(session_id).await;
+    log.finish();
+}
+
+/// Validates a request and registers its session
+///
+/// Shared by the SSE and WebSocket transports. Checks the request against
+/// the configured limits, restricts its tool permissions to the server
+/// policy, checks the caller's budget and registers the session. When the
+/// request references a conversation, it is prepared to continue it (see
+/// [`ConversationManager::begin_turn`]). When it names a callback URL,
+/// its summary is collected for the webhook.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `request` - Request to validate; its options are narrowed in place
+/// * `executor` - Executor that will run the request
+/// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
+/// * `config` - Server configuration
+///
+/// # Returns
+/// Ok(()) once the session is registered
+///
+/// # Errors
+/// Returns RobertError::InvalidRequest for invalid requests, policy
+/// violations or callback URLs outside the allow-list,
+/// RobertError::BudgetExceeded when the caller's budget is
+/// used up, RobertError::Internal when too many sessions are running, and
+/// RobertError::ConversationNotFound for unknown conversations
+pub(crate) async fn start_session(
+    token: &str,
+    request: &mut RobertRequest,
+    executor: &dyn Executor,
+    session_manager: &SessionManager,
+    conversations: &ConversationManager,
+    config: &Config,
+) -> Result<(), RobertError> {
+    check_request(request, config)?;
+
+    // Refuse new work once the key's budget is used up
+    session_manager
+        .check_budget(token, &config.budgets.limits_for(token))
+        .await?;
+
+    // Register session
+    session_manager
+        .register_with_owner(
+            request.session_id,
+            config.claude.max_concurrent_sessions,
+            Some(token.to_string()),
+        )
+        .await?;
+
+    // Continue the referenced conversation, if any
+    if let Err(e) = conversations
+        .begin_turn(
+            request,
+            token,
+            executor.supports_resume(),
+            config.limits.max_prompt_length,
+        )
+        .await
+    {
+        let _ = session_manager
+            .fail(request.session_id, e.to_string())
+            .await;
+        return Err(e);
+    }
+
+    // Collect the summary for the callback, if any
+    if let Some(url) = &request.options.callback_url {
+        session_manager
+            .webhooks()
+            .register(request.session_id, token, url, &config.webhooks)
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Checks a request against the configured limits and policies
+///
+/// Restricts the request's tool permissions to the server policy and
+/// checks its callback URL against the allow-list.
+///
+/// # Arguments
+/// * `request` - Request to check; its options are narrowed in place
+/// * `config` - Server configuration
+///
+/// # Errors
+/// Returns RobertError::InvalidRequest for invalid requests, policy
+/// violations or callback URLs outside the allow-list
+pub(crate) fn check_request(
+    request: &mut RobertRequest,
+    config: &Config,
+) -> Result<(), RobertError> {
+    // Validate request against configured limits
+    request
+        .validate(
+            config.limits.max_screenshot_count,
+            config.limits.max_prompt_length,
+            50000, // max intent length - could be configurable
+        )
+        .map_err(RobertError::InvalidRequest)?;
+
+    // Restrict tool permissions to the server policy
+    permissions::apply_policy(&config.claude.permissions, &mut request.options)?;
+
+    // Callbacks may only go to allow-listed URLs
+    if let Some(url) = &request.options.callback_url {
+        if !config.webhooks.allows(url) {
+            return Err(RobertError::InvalidRequest(format!(
+                "callback_url is not allowed: {}",
+                url
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates session state for an event emitted by a session
+///
+/// Records reported usage, marks the session completed or failed on
+/// terminal events, adds the event to the session's conversation turn and
+/// to its webhook summary.
+///
+/// # Arguments
+/// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
+/// * `session_id` - Session that emitted the event
+/// * `event` - The event
+pub(crate) async fn track_event(
+    session_manager: &SessionManager,
+    conversations: &ConversationManager,
+    session_id: Uuid,
+    event: &ClaudeEvent,
+) {
+    conversations.record_event(session_id, event).await;
+    session_manager
+        .webhooks()
+        .record_event(session_id, event)
+        .await;
+
+    match event {
+        ClaudeEvent::Complete { usage, .. } => {
+            if let Some(usage) = usage {
+                let _ = session_manager.record_usage(session_id, usage).await;
+            }
+            let _ = session_manager.complete(session_id).await;
+        }
+        ClaudeEvent::Error { message, .. } => {
+            let _ = session_manager.fail(session_id, message.clone()).await;
+        }
+        _ => {}
+    }
+}
+
+/// Updates session state for a session whose event stream failed
+///
+/// Marks the session failed, records the error in its conversation turn
+/// and sends its webhook summary.
+///
+/// # Arguments
+/// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
+/// * `session_id` - Session whose stream failed
+/// * `error` - The stream error
+///
+/// # Returns
+/// Error event to report to the client
+pub(crate) async fn track_failure(
+    session_manager: &SessionManager,
+    conversations: &ConversationManager,
+    session_id: Uuid,
+    error: &RobertError,
+) -> ClaudeEvent {
+    let event = ClaudeEvent::Error {
+        code: error.error_code(),
+        message: error.to_string(),
+    };
+
+    let _ = session_manager.fail(session_id, error.to_string()).await;
+    conversations.record_event(session_id, &event).await;
+    session_manager
+        .webhooks()
+        .record_event(session_id, &event)
+        .await;
+    session_manager.webhooks().finish(session_id).await;
+
+    event
 }
 
 #[cfg(test)]
@@ -144,7 +461,16 @@ mod tests {
         let request = create_test_request();
         let session_id = request.session_id;
 
-        let result = execute_handler(request, executor, session_manager.clone(), config).await;
+        let result = execute_handler(
+            String::new(),
+            None,
+            request,
+            executor,
+            session_manager.clone(),
+            Arc::new(ConversationManager::new(100)),
+            config,
+        )
+        .await;
         assert!(result.is_ok());
 
         // Give time for async processing
@@ -165,7 +491,16 @@ mod tests {
         // Make prompt too long
         request.prompt = "a".repeat(100000);
 
-        let result = execute_handler(request, executor, session_manager, config).await;
+        let result = execute_handler(
+            String::new(),
+            None,
+            request,
+            executor,
+            session_manager,
+            Arc::new(ConversationManager::new(100)),
+            config,
+        )
+        .await;
         assert!(result.is_err());
     }
 
@@ -186,7 +521,310 @@ mod tests {
 
         // Next request should fail
         let request = create_test_request();
-        let result = execute_handler(request, executor, session_manager, config).await;
+        let result = execute_handler(
+            String::new(),
+            None,
+            request,
+            executor,
+            session_manager,
+            Arc::new(ConversationManager::new(100)),
+            config,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_handler_rejects_disallowed_tool() {
+        let mut config = Config::dev_default();
+        config.claude.permissions.disallowed_tools = vec!["Bash".to_string()];
+        let config = Arc::new(config);
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_delay(10));
+        let session_manager = Arc::new(SessionManager::new(100));
+
+        let mut request = create_test_request();
+        request.options.allowed_tools = vec!["Bash(ls:*)".to_string()];
+        let session_id = request.session_id;
+
+        let result = execute_handler(
+            String::new(),
+            None,
+            request,
+            executor,
+            session_manager.clone(),
+            Arc::new(ConversationManager::new(100)),
+            config,
+        )
+        .await;
         assert!(result.is_err());
+
+        // Rejected requests never register a session
+        assert!(session_manager.get_status(session_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_handler_records_usage_and_enforces_budget() {
+        use futures::StreamExt;
+
+        let mut config = Config::dev_default();
+        config.budgets.default.daily_tokens = Some(1000);
+        let config = Arc::new(config);
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_delay(1));
+        let session_manager = Arc::new(SessionManager::new(100));
+        let token = "key-1".to_string();
+
+        let request = create_test_request();
+        let session_id = request.session_id;
+        let reply = execute_handler(
+            token.clone(),
+            None,
+            request,
+            executor.clone(),
+            session_manager.clone(),
+            Arc::new(ConversationManager::new(100)),
+            config.clone(),
+        )
+        .await
+        .unwrap();
+
+        // Drain the SSE body so the session runs to completion
+        let mut body = reply.into_response().into_body();
+        while body.next().await.is_some() {}
+
+        let status = session_manager.get_status(session_id).await.unwrap();
+        assert_eq!(status.usage.unwrap().total_tokens(), 1550);
+
+        // The mock run used more than the daily budget
+        let result = execute_handler(
+            token.clone(),
+            None,
+            create_test_request(),
+            executor.clone(),
+            session_manager.clone(),
+            Arc::new(ConversationManager::new(100)),
+            config.clone(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        // Other keys are unaffected
+        let result = execute_handler(
+            "key-2".to_string(),
+            None,
+            create_test_request(),
+            executor,
+            session_manager,
+            Arc::new(ConversationManager::new(100)),
+            config,
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_execute_handler_continues_conversation() {
+        use futures::StreamExt;
+
+        let config = Arc::new(Config::dev_default());
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_delay(1));
+        let session_manager = Arc::new(SessionManager::new(100));
+        let conversations = Arc::new(ConversationManager::new(100));
+        let conversation_id = conversations.create("key-1").await.unwrap().conversation_id;
+
+        for prompt in ["first question", "follow-up"] {
+            let mut request = create_test_request();
+            request.prompt = prompt.to_string();
+            request.options.conversation_id = Some(conversation_id);
+
+            let reply = execute_handler(
+                "key-1".to_string(),
+                None,
+                request,
+                executor.clone(),
+                session_manager.clone(),
+                conversations.clone(),
+                config.clone(),
+            )
+            .await
+            .unwrap();
+
+            let mut body = reply.into_response().into_body();
+            while body.next().await.is_some() {}
+        }
+
+        let transcript = conversations.get(conversation_id, "key-1").await.unwrap();
+        assert_eq!(transcript.turns.len(), 2);
+        assert_eq!(transcript.turns[1].prompt, "follow-up");
+        for turn in &transcript.turns {
+            assert_eq!(turn.status, crate::models::SessionState::Completed);
+            assert!(!turn.response.is_empty());
+        }
+
+        // Conversations of other keys are rejected before a session starts
+        let mut request = create_test_request();
+        request.options.conversation_id = Some(conversation_id);
+        let session_id = request.session_id;
+        let result = execute_handler(
+            "key-2".to_string(),
+            None,
+            request,
+            executor,
+            session_manager.clone(),
+            conversations,
+            config,
+        )
+        .await;
+        assert!(result.is_err());
+        let status = session_manager.get_status(session_id).await.unwrap();
+        assert_eq!(status.status, crate::models::SessionState::Failed);
+    }
+
+    /// Executor that counts the runs it starts
+    struct CountingExecutor {
+        inner: MockClaudeExecutor,
+        runs: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Executor for CountingExecutor {
+        async fn execute(
+            &self,
+            request: RobertRequest,
+        ) -> Box<
+            dyn futures::Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static,
+        > {
+            self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.execute(request).await
+        }
+    }
+
+    async fn body_text(response: warp::reply::Response) -> String {
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_handler_idempotency_key_attaches_to_run() {
+        let config = Arc::new(Config::dev_default());
+        let executor = Arc::new(CountingExecutor {
+            inner: MockClaudeExecutor::with_delay(5),
+            runs: Default::default(),
+        });
+        let session_manager = Arc::new(SessionManager::new(100));
+        let conversations = Arc::new(ConversationManager::new(100));
+        let request = create_test_request();
+        let session_id = request.session_id;
+
+        let send = |key: &str, request: RobertRequest| {
+            execute_handler(
+                "key-1".to_string(),
+                Some(key.to_string()),
+                request,
+                executor.clone(),
+                session_manager.clone(),
+                conversations.clone(),
+                config.clone(),
+            )
+        };
+
+        // The first client goes away right after the run starts
+        let first = send("retry-1", request.clone()).await.unwrap();
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+        drop(first);
+
+        // The retry replays the run from the start
+        let retry = send("retry-1", request.clone()).await.unwrap();
+        assert_eq!(retry.headers()[REPLAYED_HEADER], "true");
+        let text = body_text(retry).await;
+        assert!(text.contains("\"type\":\"content\""), "{}", text);
+        assert!(text.contains("\"type\":\"complete\""), "{}", text);
+
+        assert_eq!(executor.runs.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let status = session_manager.get_status(session_id).await.unwrap();
+        assert_eq!(status.status, crate::models::SessionState::Completed);
+
+        // The key cannot start a different session
+        let result = send("retry-1", create_test_request()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_handler_sends_callback() {
+        use crate::models::{DeliveryStatus, WebhookPayload};
+        use warp::Filter;
+
+        let received = Arc::new(std::sync::Mutex::new(Vec::<WebhookPayload>::new()));
+        let log = received.clone();
+        let receiver = warp::post()
+            .and(warp::path!("hooks"))
+            .and(warp::body::json())
+            .map(move |payload: WebhookPayload| {
+                log.lock().unwrap().push(payload);
+                warp::reply()
+            });
+        let (addr, server) = warp::serve(receiver).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let mut config = Config::dev_default();
+        config.webhooks.allowed_urls = vec![format!("http://{}/hooks", addr)];
+        config.webhooks.secret = Some("secret".to_string());
+        let config = Arc::new(config);
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_delay(1));
+        let session_manager = Arc::new(SessionManager::new(100));
+        let conversations = Arc::new(ConversationManager::new(100));
+
+        // URLs outside the allow-list are rejected
+        let mut request = create_test_request();
+        request.options.callback_url = Some("http://attacker.example/hooks".to_string());
+        let result = execute_handler(
+            "key-1".to_string(),
+            None,
+            request,
+            executor.clone(),
+            session_manager.clone(),
+            conversations.clone(),
+            config.clone(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        // The callback is sent even though the client never reads the stream
+        let mut request = create_test_request();
+        request.options.callback_url = Some(format!("http://{}/hooks", addr));
+        let session_id = request.session_id;
+        let reply = execute_handler(
+            "key-1".to_string(),
+            None,
+            request,
+            executor,
+            session_manager.clone(),
+            conversations,
+            config,
+        )
+        .await
+        .unwrap();
+        drop(reply);
+
+        let mut delivered = false;
+        for _ in 0..200 {
+            let deliveries = session_manager.webhooks().deliveries("key-1").await;
+            if deliveries
+                .first()
+                .is_some_and(|d| d.status == DeliveryStatus::Delivered)
+            {
+                delivered = true;
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+        assert!(delivered, "Callback was not delivered");
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].session_id, session_id);
+        assert_eq!(received[0].status, crate::models::SessionState::Completed);
+        assert!(!received[0].content.is_empty());
     }
 }
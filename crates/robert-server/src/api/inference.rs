@@ -4,31 +4,46 @@
 //! Returns JSON response.
 //! This acts as a simplified adapter to the main execution engine.
 
-use crate::claude::Executor;
+use crate::claude::{permissions, Executor};
 use crate::config::Config;
+use crate::error::RobertError;
 use crate::models::{
     ClaudeEvent, DomState, RequestContext, RequestOptions, RobertRequest, Screenshot,
     ScreenshotMetadata, Viewport,
 };
 use futures::StreamExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 use warp::Reply;
 
-#[derive(Debug, Deserialize)]
+/// Request body for POST /inference
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InferenceRequest {
-    prompt: String,
+    /// Prompt to run
+    pub prompt: String,
 }
 
-#[derive(Debug, Serialize)]
+/// Response body for POST /inference
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InferenceResponse {
-    status: String,
-    message: String,
-    execution_report: Option<serde_json::Value>,
+    /// Always "success"; failed runs are returned as error responses
+    pub status: String,
+
+    /// Collected output
+    pub message: String,
+
+    /// Optional structured report of the execution
+    pub execution_report: Option<serde_json::Value>,
 }
 
 /// Handler for the /inference endpoint
+///
+/// Invalid prompts are rejected with RobertError::InvalidRequest (400).
+/// A run that reports an error or does not complete is rejected with
+/// RobertError::ExecutionError (500); executor errors such as timeouts are
+/// passed through.
 pub async fn inference_handler(
     request: InferenceRequest,
     executor: Arc<dyn Executor>,
@@ -50,7 +65,7 @@ pub async fn inference_handler(
         },
     };
 
-    let robert_request = RobertRequest {
+    let mut robert_request = RobertRequest {
         session_id,
         prompt: request.prompt,
         context: RequestContext {
@@ -70,57 +85,107 @@ pub async fn inference_handler(
         config.limits.max_prompt_length,
         50000,
     ) {
-        return Ok(warp::reply::json(&InferenceResponse {
-            status: "error".to_string(),
-            message: format!("Invalid request: {}", e),
-            execution_report: None,
-        }));
+        return Err(warp::reject::custom(RobertError::InvalidRequest(e)));
     }
 
+    // Apply the server's default tool permissions
+    permissions::apply_policy(&config.claude.permissions, &mut robert_request.options)
+        .map_err(warp::reject::custom)?;
+
     // Execute directly and collect results (non-streaming for this endpoint)
     let mut event_stream = executor.execute(robert_request).await;
     let mut final_message = String::new();
-    let mut status = "success".to_string();
+    let mut completed = false;
 
     while let Some(result) = event_stream.next().await {
         match result {
-            Ok(event) => match event {
-                ClaudeEvent::Complete { .. } => {
-                    // Done
+            Ok(ClaudeEvent::Complete { status, .. }) => {
+                if status != "success" {
+                    return Err(warp::reject::custom(RobertError::ExecutionError(format!(
+                        "Execution finished with status {}",
+                        status
+                    ))));
                 }
-                ClaudeEvent::Error { message, .. } => {
-                    status = "error".to_string();
-                    if final_message.is_empty() {
-                        final_message = message;
-                    } else {
-                        final_message = format!("{}\nError: {}", final_message, message);
-                    }
-                }
-                ClaudeEvent::Content { text } => {
-                    // Accumulate text content
-                    final_message.push_str(&text);
-                }
-                _ => {}
-            },
-            Err(e) => {
-                status = "error".to_string();
-                final_message = e.to_string();
+                completed = true;
             }
+            Ok(ClaudeEvent::Error { code, message }) => {
+                return Err(warp::reject::custom(RobertError::ExecutionError(format!(
+                    "{}: {}",
+                    code, message
+                ))));
+            }
+            Ok(ClaudeEvent::Content { text }) => {
+                // Accumulate text content
+                final_message.push_str(&text);
+            }
+            Ok(_) => {}
+            Err(e) => return Err(warp::reject::custom(e)),
         }
     }
 
+    if !completed {
+        return Err(warp::reject::custom(RobertError::ExecutionError(
+            "Execution ended without completing".to_string(),
+        )));
+    }
+
     if final_message.is_empty() {
-        if status == "success" {
-            final_message = "Command executed successfully.".to_string();
-        } else {
-            final_message = "Unknown error occurred.".to_string();
-        }
+        final_message = "Command executed successfully.".to_string();
     }
 
     // Return simple JSON response
     Ok(warp::reply::json(&InferenceResponse {
-        status,
+        status: "success".to_string(),
         message: final_message,
         execution_report: None, // TODO: Extract report from events if available
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::MockClaudeExecutor;
+    use warp::http::StatusCode;
+
+    fn request() -> InferenceRequest {
+        InferenceRequest {
+            prompt: "hello".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_collects_output() {
+        let reply = inference_handler(
+            request(),
+            Arc::new(MockClaudeExecutor::with_delay(1)),
+            Arc::new(Config::dev_default()),
+        )
+        .await
+        .unwrap();
+
+        let response = reply.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body: InferenceResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.status, "success");
+        assert!(body.message.starts_with("Mock:"), "{}", body.message);
+    }
+
+    #[tokio::test]
+    async fn test_inference_failure_is_an_error() {
+        let rejection = inference_handler(
+            request(),
+            Arc::new(MockClaudeExecutor::with_failure()),
+            Arc::new(Config::dev_default()),
+        )
+        .await
+        .err()
+        .expect("failed run must be rejected");
+
+        let error = rejection.find::<RobertError>().unwrap();
+        assert!(matches!(error, RobertError::ExecutionError(_)));
+        assert!(error.to_string().contains("Simulated failure"), "{}", error);
+    }
+}
@@ -0,0 +1,233 @@
+//! Scheduled job endpoints
+//!
+//! Creates, lists, updates, pauses, resumes and deletes jobs that run an
+//! execute request on a cron schedule (see [`crate::scheduler`]).
+
+use crate::models::JobDefinition;
+use crate::scheduler::Scheduler;
+use std::sync::Arc;
+use uuid::Uuid;
+use warp::{http::StatusCode, reply, Reply};
+
+/// POST /api/v1/jobs handler
+///
+/// Creates a job owned by the caller's key.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `definition` - Schedule and request of the job
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// 201 with the new job
+///
+/// # Errors
+/// Rejects with RobertError::InvalidRequest for invalid schedules and
+/// requests, and RobertError::Internal if the job cannot be saved
+///
+/// # Example Response
+/// ```json
+/// {
+///   "job_id": "uuid",
+///   "name": "Competitor pricing",
+///   "schedule": "0 9 * * *",
+///   "request": { ... },
+///   "missed_runs": "skip",
+///   "state": "active",
+///   "created_at": "2025-10-17T08:30:00+00:00",
+///   "updated_at": "2025-10-17T08:30:00+00:00",
+///   "next_run_at": "2025-10-17T09:00:00+00:00",
+///   "runs": []
+/// }
+/// ```
+pub async fn create_job_handler(
+    token: String,
+    definition: JobDefinition,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    let job = scheduler
+        .create(&token, definition)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::with_status(reply::json(&job), StatusCode::CREATED))
+}
+
+/// GET /api/v1/jobs handler
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// JSON array of the caller's jobs, oldest first
+pub async fn list_jobs_handler(
+    token: String,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    Ok(reply::json(&scheduler.list(&token).await))
+}
+
+/// GET /api/v1/jobs/:id handler
+///
+/// Returns a job with its run history. Each run links to the session
+/// that executed it.
+///
+/// # Arguments
+/// * `job_id` - UUID of the job
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// JSON job
+///
+/// # Errors
+/// Rejects with RobertError::JobNotFound (404) if the job does not exist
+/// or belongs to another key
+///
+/// # Example Response
+/// ```json
+/// {
+///   "job_id": "uuid",
+///   "state": "active",
+///   "next_run_at": "2025-10-18T09:00:00+00:00",
+///   "runs": [
+///     {
+///       "scheduled_for": "2025-10-17T09:00:00+00:00",
+///       "started_at": "2025-10-17T09:00:00.412+00:00",
+///       "session_id": "uuid",
+///       "status": "completed",
+///       "completed_at": "2025-10-17T09:01:10.003+00:00"
+///     }
+///   ],
+///   ...
+/// }
+/// ```
+pub async fn get_job_handler(
+    job_id: Uuid,
+    token: String,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    let job = scheduler
+        .get(job_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&job))
+}
+
+/// PUT /api/v1/jobs/:id handler
+///
+/// Replaces the definition of a job; its run history is kept.
+///
+/// # Arguments
+/// * `job_id` - UUID of the job
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `definition` - New schedule and request
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// JSON job after the update
+///
+/// # Errors
+/// Rejects with RobertError::JobNotFound (404) if the job does not exist
+/// or belongs to another key, and RobertError::InvalidRequest (400) for
+/// invalid schedules and requests
+pub async fn update_job_handler(
+    job_id: Uuid,
+    token: String,
+    definition: JobDefinition,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    let job = scheduler
+        .update(job_id, &token, definition)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&job))
+}
+
+/// POST /api/v1/jobs/:id/pause handler
+///
+/// Stops a job from running until it is resumed. Pausing a paused job
+/// has no effect.
+///
+/// # Arguments
+/// * `job_id` - UUID of the job
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// JSON job after the change
+///
+/// # Errors
+/// Rejects with RobertError::JobNotFound (404) if the job does not exist
+/// or belongs to another key
+pub async fn pause_job_handler(
+    job_id: Uuid,
+    token: String,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    let job = scheduler
+        .set_paused(job_id, &token, true)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&job))
+}
+
+/// POST /api/v1/jobs/:id/resume handler
+///
+/// Lets a paused job run again from its next occurrence.
+///
+/// # Arguments
+/// * `job_id` - UUID of the job
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// JSON job after the change
+///
+/// # Errors
+/// Rejects with RobertError::JobNotFound (404) if the job does not exist
+/// or belongs to another key
+pub async fn resume_job_handler(
+    job_id: Uuid,
+    token: String,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    let job = scheduler
+        .set_paused(job_id, &token, false)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&job))
+}
+
+/// DELETE /api/v1/jobs/:id handler
+///
+/// Deletes a job and its run history. A run that is going keeps running.
+///
+/// # Arguments
+/// * `job_id` - UUID of the job
+/// * `token` - Bearer token of the caller (empty when auth is disabled)
+/// * `scheduler` - Shared scheduler
+///
+/// # Returns
+/// 204 No Content
+///
+/// # Errors
+/// Rejects with RobertError::JobNotFound (404) if the job does not exist
+/// or belongs to another key
+pub async fn delete_job_handler(
+    job_id: Uuid,
+    token: String,
+    scheduler: Arc<Scheduler>,
+) -> Result<impl Reply, warp::Rejection> {
+    scheduler
+        .delete(job_id, &token)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -2,12 +2,35 @@
 //!
 //! This module contains all HTTP endpoint handlers and route definitions.
 
+pub mod batches;
+pub mod conversations;
 pub mod execute;
 pub mod health;
 pub mod inference;
+pub mod jobs;
+pub mod openapi;
+pub mod recover;
 pub mod sessions;
+pub mod usage;
+pub mod webhooks;
+pub mod ws;
 
+pub use batches::{
+    batch_results_handler, cancel_batch_handler, create_batch_handler, get_batch_handler,
+};
+pub use conversations::{
+    create_conversation_handler, delete_conversation_handler, get_conversation_handler,
+};
 pub use execute::execute_handler;
 pub use health::health_handler;
 pub use inference::inference_handler;
+pub use jobs::{
+    create_job_handler, delete_job_handler, get_job_handler, list_jobs_handler, pause_job_handler,
+    resume_job_handler, update_job_handler,
+};
+pub use openapi::openapi_handler;
+pub use recover::handle_rejection;
 pub use sessions::{delete_session_handler, get_session_handler};
+pub use usage::usage_handler;
+pub use webhooks::webhook_deliveries_handler;
+pub use ws::ws_handler;
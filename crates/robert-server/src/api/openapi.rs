@@ -0,0 +1,1023 @@
+//! OpenAPI specification endpoint
+//!
+//! Serves an OpenAPI 3.1 document for the HTTP API at
+//! GET /api/v1/openapi.json. Component schemas are generated from the
+//! model types with `schemars`, so the document follows the structs the
+//! handlers actually serialize.
+
+use crate::api::inference::{InferenceRequest, InferenceResponse};
+use crate::error::ErrorResponse;
+use crate::models::{
+    BatchItemResult, BatchRequest, BatchStatus, ClaudeEvent, Conversation, HealthResponse, Job,
+    JobDefinition, RobertRequest, SessionStatus, UsageReport, WebhookDelivery, WebhookPayload,
+    WsClientFrame, WsServerMessage,
+};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use warp::{reply, Reply};
+
+/// Location of component schemas within the document
+const SCHEMA_PATH: &str = "#/components/schemas/";
+
+/// GET /api/v1/openapi.json handler
+///
+/// Returns the OpenAPI document. Does not require authentication.
+///
+/// # Returns
+/// JSON OpenAPI 3.1 document
+pub async fn openapi_handler() -> Result<impl Reply, warp::Rejection> {
+    Ok(reply::json(openapi_document()))
+}
+
+/// Returns the OpenAPI document for the API
+///
+/// Built on first use and cached afterwards.
+///
+/// # Returns
+/// OpenAPI 3.1 document as JSON
+pub fn openapi_document() -> &'static Value {
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    DOCUMENT.get_or_init(build_document)
+}
+
+/// Builds the OpenAPI document
+fn build_document() -> Value {
+    let mut gen = SchemaSettings::draft2019_09()
+        .with(|settings| settings.definitions_path = SCHEMA_PATH.to_string())
+        .into_generator();
+
+    let request = schema_ref::<RobertRequest>(&mut gen);
+    let event = schema_ref::<ClaudeEvent>(&mut gen);
+    let health = schema_ref::<HealthResponse>(&mut gen);
+    let session = schema_ref::<SessionStatus>(&mut gen);
+    let conversation = schema_ref::<Conversation>(&mut gen);
+    let batch_request = schema_ref::<BatchRequest>(&mut gen);
+    let batch = schema_ref::<BatchStatus>(&mut gen);
+    let batch_result = schema_ref::<BatchItemResult>(&mut gen);
+    let job_definition = schema_ref::<JobDefinition>(&mut gen);
+    let job = schema_ref::<Job>(&mut gen);
+    let jobs = schema_ref::<Vec<Job>>(&mut gen);
+    let usage = schema_ref::<UsageReport>(&mut gen);
+    let deliveries = schema_ref::<Vec<WebhookDelivery>>(&mut gen);
+    let webhook = schema_ref::<WebhookPayload>(&mut gen);
+    let inference_request = schema_ref::<InferenceRequest>(&mut gen);
+    let inference_response = schema_ref::<InferenceResponse>(&mut gen);
+    schema_ref::<ErrorResponse>(&mut gen);
+    schema_ref::<WsClientFrame>(&mut gen);
+    schema_ref::<WsServerMessage>(&mut gen);
+
+    let session_id = path_parameter("session_id", "Session UUID");
+    let conversation_id = path_parameter("conversation_id", "Conversation UUID");
+    let batch_id = path_parameter("batch_id", "Batch UUID");
+    let job_id = path_parameter("job_id", "Job UUID");
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Robert Server API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Remote execution server for the Robert desktop application. Every response carries an `X-Request-Id` header, echoing the client's when it sent one; error bodies repeat it as `request_id`."
+        },
+        "security": [{ "bearerAuth": [] }],
+        "paths": {
+            "/api/v1/health": {
+                "get": {
+                    "summary": "Server health",
+                    "security": [],
+                    "responses": {
+                        "200": json_response("Server status", &health)
+                    }
+                }
+            },
+            "/api/v1/openapi.json": {
+                "get": {
+                    "summary": "This document",
+                    "security": [],
+                    "responses": {
+                        "200": json_response("OpenAPI document", &json!({ "type": "object" }))
+                    }
+                }
+            },
+            "/api/v1/execute": {
+                "post": {
+                    "summary": "Execute a request and stream claude's events",
+                    "parameters": [{
+                        "name": "Idempotency-Key",
+                        "in": "header",
+                        "required": false,
+                        "description": "Repeating a key attaches to the run it started, replaying its events from the start",
+                        "schema": { "type": "string", "minLength": 1, "maxLength": 255 }
+                    }],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": request } }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Server-Sent Events; each `data` field holds one event",
+                            "content": { "text/event-stream": { "schema": event } }
+                        },
+                        "400": error_response("Invalid request or tool permission policy violation"),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown conversation"),
+                        "413": error_response("Request body too large"),
+                        "429": error_response("Rate limited or budget exceeded"),
+                        "500": error_response("Too many concurrent sessions")
+                    }
+                }
+            },
+            "/api/v1/sessions/{session_id}": {
+                "parameters": [session_id],
+                "get": {
+                    "summary": "Session status",
+                    "responses": {
+                        "200": json_response("Session status", &session),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown session")
+                    }
+                },
+                "delete": {
+                    "summary": "Cancel a running session",
+                    "responses": {
+                        "200": json_response("Session status after cancellation", &session),
+                        "400": error_response("Session is not running"),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown session")
+                    }
+                }
+            },
+            "/api/v1/conversations": {
+                "post": {
+                    "summary": "Create a conversation",
+                    "responses": {
+                        "201": json_response("The new conversation", &conversation),
+                        "401": error_response("Missing or invalid token"),
+                        "500": error_response("Too many conversations")
+                    }
+                }
+            },
+            "/api/v1/conversations/{conversation_id}": {
+                "parameters": [conversation_id],
+                "get": {
+                    "summary": "Conversation transcript",
+                    "responses": {
+                        "200": json_response("Conversation with its turns", &conversation),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown conversation")
+                    }
+                },
+                "delete": {
+                    "summary": "Delete a conversation",
+                    "responses": {
+                        "204": { "description": "Conversation deleted" },
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown conversation")
+                    }
+                }
+            },
+            "/api/v1/batches": {
+                "post": {
+                    "summary": "Run many requests in the background",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": batch_request } }
+                    },
+                    "responses": {
+                        "201": json_response("The new batch, every item queued", &batch),
+                        "400": error_response("Invalid batch or item"),
+                        "401": error_response("Missing or invalid token"),
+                        "413": error_response("Request body too large"),
+                        "503": error_response("Too many batches in progress; retry after `Retry-After` seconds")
+                    }
+                }
+            },
+            "/api/v1/batches/{batch_id}": {
+                "parameters": [batch_id.clone()],
+                "get": {
+                    "summary": "Batch progress and per-item session status",
+                    "responses": {
+                        "200": json_response("Batch status", &batch),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown batch")
+                    }
+                },
+                "delete": {
+                    "summary": "Cancel a batch",
+                    "responses": {
+                        "200": json_response("Batch status after cancellation", &batch),
+                        "400": error_response("Batch has already finished"),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown batch")
+                    }
+                }
+            },
+            "/api/v1/batches/{batch_id}/results": {
+                "parameters": [batch_id],
+                "get": {
+                    "summary": "Item results as JSON Lines, in submission order",
+                    "responses": {
+                        "200": {
+                            "description": "One result per line",
+                            "content": { "application/x-ndjson": { "schema": batch_result } }
+                        },
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown batch")
+                    }
+                }
+            },
+            "/api/v1/jobs": {
+                "get": {
+                    "summary": "The caller's scheduled jobs",
+                    "responses": {
+                        "200": json_response("Jobs, oldest first", &jobs),
+                        "401": error_response("Missing or invalid token")
+                    }
+                },
+                "post": {
+                    "summary": "Schedule a request",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": job_definition } }
+                    },
+                    "responses": {
+                        "201": json_response("The new job", &job),
+                        "400": error_response("Invalid schedule or request"),
+                        "401": error_response("Missing or invalid token"),
+                        "413": error_response("Request body too large"),
+                        "500": error_response("Job could not be saved")
+                    }
+                }
+            },
+            "/api/v1/jobs/{job_id}": {
+                "parameters": [job_id.clone()],
+                "get": {
+                    "summary": "Job with its run history",
+                    "responses": {
+                        "200": json_response("Job", &job),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown job")
+                    }
+                },
+                "put": {
+                    "summary": "Replace a job's definition",
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": job_definition } }
+                    },
+                    "responses": {
+                        "200": json_response("Job after the update", &job),
+                        "400": error_response("Invalid schedule or request"),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown job"),
+                        "413": error_response("Request body too large"),
+                        "500": error_response("Job could not be saved")
+                    }
+                },
+                "delete": {
+                    "summary": "Delete a job",
+                    "responses": {
+                        "204": { "description": "Job deleted" },
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown job"),
+                        "500": error_response("Job could not be saved")
+                    }
+                }
+            },
+            "/api/v1/jobs/{job_id}/pause": {
+                "parameters": [job_id.clone()],
+                "post": {
+                    "summary": "Pause a job",
+                    "responses": {
+                        "200": json_response("Job after the change", &job),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown job"),
+                        "500": error_response("Job could not be saved")
+                    }
+                }
+            },
+            "/api/v1/jobs/{job_id}/resume": {
+                "parameters": [job_id],
+                "post": {
+                    "summary": "Resume a paused job from its next occurrence",
+                    "responses": {
+                        "200": json_response("Job after the change", &job),
+                        "401": error_response("Missing or invalid token"),
+                        "404": error_response("Unknown job"),
+                        "500": error_response("Job could not be saved")
+                    }
+                }
+            },
+            "/api/v1/usage": {
+                "get": {
+                    "summary": "Usage and budget of the caller's key",
+                    "parameters": [{
+                        "name": "days",
+                        "in": "query",
+                        "required": false,
+                        "description": "Days to break down (default 30, at most 92)",
+                        "schema": { "type": "integer", "minimum": 0 }
+                    }],
+                    "responses": {
+                        "200": json_response("Usage report", &usage),
+                        "401": error_response("Missing or invalid token")
+                    }
+                }
+            },
+            "/api/v1/webhooks/deliveries": {
+                "get": {
+                    "summary": "Webhook deliveries of the caller's key, most recent first",
+                    "responses": {
+                        "200": json_response("Delivery log", &deliveries),
+                        "401": error_response("Missing or invalid token")
+                    }
+                }
+            },
+            "/api/v1/ws": {
+                "get": {
+                    "summary": "WebSocket transport for interactive sessions",
+                    "description": "Text frames carry JSON messages: `WsClientFrame` from the client, `WsServerMessage` from the server. Each client message is authenticated with its own token.",
+                    "security": [],
+                    "responses": {
+                        "101": { "description": "Switching to the WebSocket protocol" }
+                    }
+                }
+            },
+            "/inference": {
+                "post": {
+                    "summary": "Run a prompt and wait for the result",
+                    "security": [],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": inference_request } }
+                    },
+                    "responses": {
+                        "200": json_response("Collected result", &inference_response),
+                        "400": error_response("Invalid request or tool permission policy violation"),
+                        "413": error_response("Request body too large"),
+                        "500": error_response("Execution failed or did not complete"),
+                        "504": error_response("Execution timed out")
+                    }
+                }
+            }
+        },
+        "webhooks": {
+            "sessionCompleted": {
+                "post": {
+                    "summary": "Summary sent to a request's callback_url when its session ends",
+                    "description": "`X-Robert-Signature` is `sha256=` followed by the hex HMAC-SHA256 of `<X-Robert-Timestamp>.<body>`, keyed with the server's webhook secret. Deliveries are retried until the receiver answers 2xx.",
+                    "security": [],
+                    "parameters": [
+                        webhook_header("X-Robert-Delivery", "Delivery UUID, the same for every attempt"),
+                        webhook_header("X-Robert-Timestamp", "Unix time of the attempt in seconds"),
+                        webhook_header("X-Robert-Signature", "HMAC-SHA256 signature")
+                    ],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": webhook } }
+                    },
+                    "responses": {
+                        "200": { "description": "Delivery accepted" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" }
+            },
+            "schemas": gen.take_definitions()
+        }
+    })
+}
+
+/// Registers a type's schema and returns a reference to it
+fn schema_ref<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default()
+}
+
+/// JSON response object with the given schema
+fn json_response(description: &str, schema: &Value) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema } }
+    })
+}
+
+/// Error response object carrying an [`ErrorResponse`]
+fn error_response(description: &str) -> Value {
+    json_response(
+        description,
+        &json!({ "$ref": format!("{}ErrorResponse", SCHEMA_PATH) }),
+    )
+}
+
+/// Required string header sent with webhook deliveries
+fn webhook_header(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "header",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" }
+    })
+}
+
+/// Required UUID path parameter
+fn path_parameter(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string", "format": "uuid" }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::health::HealthState;
+    use crate::auth::AuthState;
+    use crate::batch::BatchManager;
+    use crate::claude::{Executor, MockClaudeExecutor};
+    use crate::config::{Config, MockScenario};
+    use crate::conversation::ConversationManager;
+    use crate::models::{
+        DomState, RequestContext, RequestOptions, Screenshot, ScreenshotMetadata, Viewport,
+        WsClientMessage,
+    };
+    use crate::scheduler::{Scheduler, SystemClock};
+    use crate::session::SessionManager;
+    use arc_swap::ArcSwap;
+    use jsonschema::{Draft, JSONSchema};
+    use std::sync::Arc;
+    use uuid::Uuid;
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    const TOKEN: &str = "contract-token";
+
+    fn create_test_request() -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: "dGVzdA==".to_string(),
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 100,
+                            height: 100,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "intent".to_string(),
+            },
+            prompt: "prompt".to_string(),
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns the violations of a schema from the document
+    fn violations(schema: &Value, instance: &Value) -> Vec<String> {
+        let root = json!({
+            "allOf": [schema],
+            "components": openapi_document()["components"]
+        });
+        let compiled = JSONSchema::options()
+            .with_draft(Draft::Draft201909)
+            .compile(&root)
+            .expect("document schemas compile");
+
+        let errors = match compiled.validate(instance) {
+            Ok(()) => return vec![],
+            Err(errors) => errors,
+        };
+        errors
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect()
+    }
+
+    /// Asserts that an instance matches a schema from the document
+    fn validate(schema: &Value, instance: &Value) {
+        let errors = violations(schema, instance);
+        assert!(
+            errors.is_empty(),
+            "{} does not match {}: {:?}",
+            instance,
+            schema,
+            errors
+        );
+    }
+
+    /// Sends a request and checks the response against the document
+    ///
+    /// The status must be documented for the operation and the body must
+    /// match the documented schema. Returns the decoded JSON body (the
+    /// last event for event streams, an array of lines for JSON Lines).
+    async fn check<F>(
+        routes: &F,
+        method: &str,
+        template: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value)
+    where
+        F: Filter + 'static,
+        F::Extract: Reply + Send,
+    {
+        let mut builder = warp::test::request()
+            .method(method)
+            .path(path)
+            .header("authorization", format!("Bearer {}", TOKEN));
+        if let Some(body) = body {
+            builder = builder.json(&body);
+        }
+        let response = builder.reply(routes).await;
+        let status = response.status();
+
+        let documented = &openapi_document()["paths"][template][method.to_lowercase()]["responses"]
+            [status.as_str()];
+        assert!(
+            !documented.is_null(),
+            "{} {} returned undocumented status {}",
+            method,
+            template,
+            status
+        );
+
+        let content = &documented["content"];
+        let text = String::from_utf8_lossy(response.body()).to_string();
+        if let Some(schema) = content["application/json"].get("schema") {
+            let body: Value = serde_json::from_str(&text).unwrap_or_else(|e| {
+                panic!("{} {}: invalid JSON body {:?}: {}", method, path, text, e)
+            });
+            validate(schema, &body);
+            (status, body)
+        } else if let Some(schema) = content["text/event-stream"].get("schema") {
+            let mut last = Value::Null;
+            for data in text.lines().filter_map(|line| line.strip_prefix("data:")) {
+                last = serde_json::from_str(data.trim()).expect("JSON event");
+                validate(schema, &last);
+            }
+            (status, last)
+        } else if let Some(schema) = content["application/x-ndjson"].get("schema") {
+            let lines: Vec<Value> = text
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("JSON line"))
+                .collect();
+            for line in &lines {
+                validate(schema, line);
+            }
+            (status, Value::Array(lines))
+        } else {
+            assert!(text.is_empty(), "Undocumented body: {}", text);
+            (status, Value::Null)
+        }
+    }
+
+    #[test]
+    fn test_document_lists_model_schemas() {
+        let schemas = &openapi_document()["components"]["schemas"];
+        for name in [
+            "RobertRequest",
+            "ClaudeEvent",
+            "SessionStatus",
+            "ErrorResponse",
+            "HealthResponse",
+            "WebhookPayload",
+            "BatchStatus",
+        ] {
+            assert!(schemas.get(name).is_some(), "Missing schema {}", name);
+        }
+
+        // Server-only options are not part of the contract
+        let options = &schemas["RequestOptions"]["properties"];
+        assert!(options.get("conversation_id").is_some());
+        assert!(options.get("resume_session").is_none());
+
+        let event = json!({ "$ref": "#/components/schemas/ClaudeEvent" });
+        validate(&event, &json!({ "type": "content", "text": "hi" }));
+        assert!(!violations(&event, &json!({ "type": "content" })).is_empty());
+        assert!(!violations(&event, &json!({ "type": "unknown" })).is_empty());
+    }
+
+    /// Server routes backed by the mock executor
+    ///
+    /// The mock follows `[claude.mock]` of `config`, with a 1 ms delay.
+    fn test_routes(
+        config: Config,
+        session_manager: Arc<SessionManager>,
+        batches: Arc<BatchManager>,
+    ) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone
+    {
+        let mut config = config;
+        config.claude.mock.event_delay_ms = 1;
+        let auth_state = Arc::new(AuthState::new(vec![TOKEN.to_string()], true, 1000));
+        let health_state = Arc::new(HealthState::new(config.claude.binary_path.clone()));
+        let config = Arc::new(ArcSwap::from_pointee(config));
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_config(config.clone()));
+        let conversations = Arc::new(ConversationManager::new(100));
+        let scheduler = Scheduler::new(
+            executor.clone(),
+            session_manager.clone(),
+            conversations.clone(),
+            config.clone(),
+            Arc::new(SystemClock),
+        )
+        .unwrap();
+
+        crate::server::build_routes(
+            config,
+            executor,
+            session_manager,
+            conversations,
+            batches,
+            Arc::new(scheduler),
+            auth_state,
+            health_state,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_handlers_match_document() {
+        let session_manager = Arc::new(SessionManager::new(100));
+        let routes = test_routes(
+            Config::dev_default(),
+            session_manager.clone(),
+            Arc::new(BatchManager::new(10)),
+        );
+
+        check(&routes, "GET", "/api/v1/health", "/api/v1/health", None).await;
+        check(
+            &routes,
+            "GET",
+            "/api/v1/openapi.json",
+            "/api/v1/openapi.json",
+            None,
+        )
+        .await;
+
+        let (_, conversation) = check(
+            &routes,
+            "POST",
+            "/api/v1/conversations",
+            "/api/v1/conversations",
+            None,
+        )
+        .await;
+        let conversation_id = conversation["conversation_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let mut request = create_test_request();
+        request.options.conversation_id = Some(conversation_id.parse().unwrap());
+        let (status, last) = check(
+            &routes,
+            "POST",
+            "/api/v1/execute",
+            "/api/v1/execute",
+            Some(serde_json::to_value(&request).unwrap()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(last["type"], "complete");
+
+        let session_path = format!("/api/v1/sessions/{}", request.session_id);
+        check(
+            &routes,
+            "GET",
+            "/api/v1/sessions/{session_id}",
+            &session_path,
+            None,
+        )
+        .await;
+
+        let running = Uuid::new_v4();
+        session_manager.register(running, 10).await.unwrap();
+        let (status, _) = check(
+            &routes,
+            "DELETE",
+            "/api/v1/sessions/{session_id}",
+            &format!("/api/v1/sessions/{}", running),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let conversation_path = format!("/api/v1/conversations/{}", conversation_id);
+        let (_, transcript) = check(
+            &routes,
+            "GET",
+            "/api/v1/conversations/{conversation_id}",
+            &conversation_path,
+            None,
+        )
+        .await;
+        assert_eq!(transcript["turns"].as_array().unwrap().len(), 1);
+
+        check(
+            &routes,
+            "GET",
+            "/api/v1/usage",
+            "/api/v1/usage?days=7",
+            None,
+        )
+        .await;
+        check(
+            &routes,
+            "GET",
+            "/api/v1/webhooks/deliveries",
+            "/api/v1/webhooks/deliveries",
+            None,
+        )
+        .await;
+
+        let batch = json!({
+            "requests": [create_test_request(), create_test_request()],
+            "max_parallel": 1
+        });
+        let (status, created) = check(
+            &routes,
+            "POST",
+            "/api/v1/batches",
+            "/api/v1/batches",
+            Some(batch),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let batch_path = format!("/api/v1/batches/{}", created["batch_id"].as_str().unwrap());
+        let mut batch_status = Value::Null;
+        for _ in 0..100 {
+            (_, batch_status) = check(
+                &routes,
+                "GET",
+                "/api/v1/batches/{batch_id}",
+                &batch_path,
+                None,
+            )
+            .await;
+            if batch_status["status"] == "completed" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(batch_status["progress"]["completed"], 2);
+        let (_, results) = check(
+            &routes,
+            "GET",
+            "/api/v1/batches/{batch_id}/results",
+            &format!("{}/results", batch_path),
+            None,
+        )
+        .await;
+        assert_eq!(results.as_array().unwrap().len(), 2);
+        let (status, _) = check(
+            &routes,
+            "DELETE",
+            "/api/v1/batches/{batch_id}",
+            &batch_path,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = check(
+            &routes,
+            "DELETE",
+            "/api/v1/conversations/{conversation_id}",
+            &conversation_path,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = check(
+            &routes,
+            "GET",
+            "/api/v1/conversations/{conversation_id}",
+            &conversation_path,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let definition = json!({
+            "name": "pricing",
+            "schedule": "0 9 * * *",
+            "request": create_test_request()
+        });
+        let (status, job) = check(
+            &routes,
+            "POST",
+            "/api/v1/jobs",
+            "/api/v1/jobs",
+            Some(definition.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let job_path = format!("/api/v1/jobs/{}", job["job_id"].as_str().unwrap());
+        let (_, jobs) = check(&routes, "GET", "/api/v1/jobs", "/api/v1/jobs", None).await;
+        assert_eq!(jobs.as_array().unwrap().len(), 1);
+        check(&routes, "GET", "/api/v1/jobs/{job_id}", &job_path, None).await;
+        let (_, job) = check(
+            &routes,
+            "PUT",
+            "/api/v1/jobs/{job_id}",
+            &job_path,
+            Some(definition),
+        )
+        .await;
+        assert_eq!(job["state"], "active");
+        let (_, job) = check(
+            &routes,
+            "POST",
+            "/api/v1/jobs/{job_id}/pause",
+            &format!("{}/pause", job_path),
+            None,
+        )
+        .await;
+        assert_eq!(job["state"], "paused");
+        let (_, job) = check(
+            &routes,
+            "POST",
+            "/api/v1/jobs/{job_id}/resume",
+            &format!("{}/resume", job_path),
+            None,
+        )
+        .await;
+        assert_eq!(job["state"], "active");
+        let (status, _) = check(&routes, "DELETE", "/api/v1/jobs/{job_id}", &job_path, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = check(&routes, "GET", "/api/v1/jobs/{job_id}", &job_path, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, inference) = check(
+            &routes,
+            "POST",
+            "/inference",
+            "/inference",
+            Some(json!({ "prompt": "hello" })),
+        )
+        .await;
+        assert_eq!(inference["status"], "success");
+
+        // WebSocket messages follow the documented message schemas
+        let mut client = warp::test::ws()
+            .path("/api/v1/ws")
+            .handshake(routes)
+            .await
+            .unwrap();
+        let frame = WsClientFrame {
+            token: Some(TOKEN.to_string()),
+            message: WsClientMessage::Execute {
+                request: create_test_request(),
+            },
+        };
+        let frame = serde_json::to_value(&frame).unwrap();
+        validate(
+            &json!({ "$ref": "#/components/schemas/WsClientFrame" }),
+            &frame,
+        );
+        client.send_text(frame.to_string()).await;
+
+        let server_schema = json!({ "$ref": "#/components/schemas/WsServerMessage" });
+        loop {
+            let message = client.recv().await.unwrap();
+            let message: Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+            validate(&server_schema, &message);
+            if message["event"]["type"] == "complete" {
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_match_document() {
+        let mut config = Config::dev_default();
+        config.limits.max_request_size_mb = 1;
+        config.claude.mock.scenario = MockScenario::Failure;
+        // No room for batches
+        let routes = test_routes(
+            config,
+            Arc::new(SessionManager::new(100)),
+            Arc::new(BatchManager::new(0)),
+        );
+
+        let (status, body) = check(
+            &routes,
+            "GET",
+            "/api/v1/sessions/{session_id}",
+            &format!("/api/v1/sessions/{}", Uuid::new_v4()),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "SESSION_NOT_FOUND");
+        assert!(body["request_id"].is_string());
+
+        let (status, body) = check(
+            &routes,
+            "POST",
+            "/api/v1/execute",
+            "/api/v1/execute",
+            Some(json!({ "prompt": "missing everything else" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_REQUEST");
+
+        let mut request = serde_json::to_value(create_test_request()).unwrap();
+        request["prompt"] = json!("x".repeat(2 * 1024 * 1024));
+        let (status, body) = check(
+            &routes,
+            "POST",
+            "/api/v1/execute",
+            "/api/v1/execute",
+            Some(request),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+
+        let (status, body) = check(
+            &routes,
+            "POST",
+            "/inference",
+            "/inference",
+            Some(json!({ "prompt": "fail" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "EXECUTION_ERROR");
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/v1/batches")
+            .header("authorization", format!("Bearer {}", TOKEN))
+            .json(&json!({ "requests": [create_test_request()] }))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "30");
+        let (status, body) = check(
+            &routes,
+            "POST",
+            "/api/v1/batches",
+            "/api/v1/batches",
+            Some(json!({ "requests": [create_test_request()] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "SERVER_BUSY");
+
+        // Unauthenticated, unknown and wrongly used endpoints
+        let response = warp::test::request()
+            .method("GET")
+            .path("/api/v1/usage")
+            .header("x-request-id", "client-42")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-request-id"], "client-42");
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "AUTH_FAILED");
+        assert_eq!(body["request_id"], "client-42");
+
+        let response = warp::test::request()
+            .path("/api/v1/nothing-here")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "NOT_FOUND");
+
+        let response = warp::test::request()
+            .method("PUT")
+            .path("/api/v1/health")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "METHOD_NOT_ALLOWED");
+
+        // Successful responses carry a generated id when the client sent
+        // none (or an unusable one)
+        let response = warp::test::request()
+            .path("/api/v1/health")
+            .header("x-request-id", "not\tprintable")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert!(Uuid::parse_str(id).is_ok(), "{}", id);
+    }
+}
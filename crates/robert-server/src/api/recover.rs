@@ -0,0 +1,184 @@
+//! Rejection recovery
+//!
+//! Turns every warp rejection into an HTTP response with the matching
+//! status code and an [`ErrorResponse`](crate::error::ErrorResponse) body.
+//! Handlers and filters reject with a [`RobertError`]; rejections raised
+//! by warp itself (unknown path, wrong method, malformed body, ...) are
+//! mapped to the closest RobertError first.
+
+use crate::api::sessions::error_to_response;
+use crate::error::RobertError;
+use std::convert::Infallible;
+use tracing::error;
+use warp::http::header::{HeaderValue, RETRY_AFTER};
+use warp::reject::{self, Rejection};
+use warp::{reply, Reply};
+
+/// Maps a rejection to the error reported to the client
+///
+/// # Arguments
+/// * `rejection` - Rejection produced by the routes
+///
+/// # Returns
+/// The RobertError carried by the rejection, or the closest match for
+/// warp's own rejections. Unknown rejections are logged and reported
+/// without their details.
+pub fn rejection_to_error(rejection: &Rejection) -> RobertError {
+    if let Some(error) = rejection.find::<RobertError>() {
+        return error.clone();
+    }
+
+    if let Some(e) = rejection.find::<reject::MethodNotAllowed>() {
+        RobertError::MethodNotAllowed(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::PayloadTooLarge>() {
+        RobertError::PayloadTooLarge(e.to_string())
+    } else if let Some(e) = rejection.find::<warp::body::BodyDeserializeError>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::UnsupportedMediaType>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::LengthRequired>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::InvalidQuery>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::MissingHeader>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if let Some(e) = rejection.find::<reject::InvalidHeader>() {
+        RobertError::InvalidRequest(e.to_string())
+    } else if rejection.is_not_found() {
+        RobertError::NotFound("No such endpoint".to_string())
+    } else {
+        // The details stay in the server log
+        error!("Unhandled rejection: {:?}", rejection);
+        RobertError::Internal("Unhandled rejection".to_string())
+    }
+}
+
+/// Builds the HTTP response for an error
+///
+/// Rate limited and busy responses also carry a `Retry-After` header.
+///
+/// # Arguments
+/// * `error` - Error to report
+/// * `request_id` - Id of the failed request, if known
+///
+/// # Returns
+/// Response with the error's status code and ErrorResponse body
+pub fn error_reply(error: RobertError, request_id: Option<String>) -> reply::Response {
+    let (status, mut body) = error_to_response(error, None);
+    body.request_id = request_id;
+
+    let mut response = reply::with_status(reply::json(&body), status).into_response();
+    if let Some(seconds) = body.retry_after_seconds {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(seconds));
+    }
+    response
+}
+
+/// Recover handler for filters used on their own
+///
+/// The server's routes use the same mapping and add the request id; this
+/// handler is for composing individual filters (e.g. in tests).
+///
+/// # Arguments
+/// * `rejection` - Rejection to convert
+///
+/// # Returns
+/// Error response; never fails
+pub async fn handle_rejection(rejection: Rejection) -> Result<reply::Response, Infallible> {
+    Ok(error_reply(rejection_to_error(&rejection), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::StatusCode;
+    use warp::Filter;
+
+    async fn body_json(response: reply::Response) -> serde_json::Value {
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_robert_error_keeps_status_and_code() {
+        let response = handle_rejection(reject::custom(RobertError::SessionNotFound(
+            "abc".to_string(),
+        )))
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_json(response).await["code"], "SESSION_NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_sets_retry_after() {
+        let response = error_reply(
+            RobertError::RateLimited("slow down".to_string()),
+            Some("req-1".to_string()),
+        );
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "60");
+        assert_eq!(body_json(response).await["request_id"], "req-1");
+    }
+
+    #[tokio::test]
+    async fn test_unhandled_rejection_hides_details() {
+        #[derive(Debug)]
+        struct Secret;
+        impl reject::Reject for Secret {}
+
+        let response = handle_rejection(reject::custom(Secret)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let json = body_json(response).await;
+        assert_eq!(json["message"], "Internal error: Unhandled rejection");
+        assert!(!json.to_string().contains("Secret"));
+    }
+
+    #[tokio::test]
+    async fn test_warp_rejections() {
+        let route = warp::path!("items")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(|_: serde_json::Value| "ok")
+            .recover(handle_rejection);
+
+        let cases = [
+            ("GET", "/missing", "", StatusCode::NOT_FOUND, "NOT_FOUND"),
+            (
+                "GET",
+                "/items",
+                "",
+                StatusCode::METHOD_NOT_ALLOWED,
+                "METHOD_NOT_ALLOWED",
+            ),
+            (
+                "POST",
+                "/items",
+                "{not json",
+                StatusCode::BAD_REQUEST,
+                "INVALID_REQUEST",
+            ),
+        ];
+
+        for (method, path, body, status, code) in cases {
+            let response = warp::test::request()
+                .method(method)
+                .path(path)
+                .header("content-type", "application/json")
+                .body(body)
+                .reply(&route)
+                .await;
+
+            assert_eq!(response.status(), status, "{} {}", method, path);
+            let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(json["code"], code, "{} {}", method, path);
+        }
+    }
+}
@@ -17,7 +17,10 @@ use warp::{http::StatusCode, reply, Reply};
 /// * `manager` - Shared session manager
 ///
 /// # Returns
-/// JSON response with session status or 404 if not found
+/// JSON response with session status
+///
+/// # Errors
+/// Rejects with RobertError::SessionNotFound (404) for unknown sessions
 ///
 /// # Example Response
 /// ```json
@@ -33,13 +36,12 @@ pub async fn get_session_handler(
     session_id: Uuid,
     manager: Arc<SessionManager>,
 ) -> Result<impl Reply, warp::Rejection> {
-    match manager.get_status(session_id).await {
-        Ok(status) => Ok(reply::json(&status)),
-        Err(e) => {
-            let error_response = e.to_error_response(Some(session_id.to_string()));
-            Ok(reply::json(&error_response))
-        }
-    }
+    let status = manager
+        .get_status(session_id)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&status))
 }
 
 /// DELETE /api/v1/sessions/:id handler
@@ -51,7 +53,11 @@ pub async fn get_session_handler(
 /// * `manager` - Shared session manager
 ///
 /// # Returns
-/// JSON response with updated session status or error
+/// JSON response with updated session status
+///
+/// # Errors
+/// Rejects with RobertError::SessionNotFound (404) for unknown sessions
+/// and RobertError::InvalidRequest (400) for sessions that are not running
 ///
 /// # Example Response
 /// ```json
@@ -64,28 +70,25 @@ pub async fn delete_session_handler(
     session_id: Uuid,
     manager: Arc<SessionManager>,
 ) -> Result<impl Reply, warp::Rejection> {
-    match manager.cancel(session_id).await {
-        Ok(_) => {
-            // Fetch updated status
-            match manager.get_status(session_id).await {
-                Ok(status) => Ok(reply::json(&status)),
-                Err(e) => {
-                    let error_response = e.to_error_response(Some(session_id.to_string()));
-                    Ok(reply::json(&error_response))
-                }
-            }
-        }
-        Err(e) => {
-            let error_response = e.to_error_response(Some(session_id.to_string()));
-            Ok(reply::json(&error_response))
-        }
-    }
+    manager
+        .cancel(session_id)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    // Fetch updated status
+    let status = manager
+        .get_status(session_id)
+        .await
+        .map_err(warp::reject::custom)?;
+
+    Ok(reply::json(&status))
 }
 
 /// Converts RobertError to HTTP response
 ///
 /// Helper function to create appropriate HTTP status code and error response
-/// for different error types.
+/// for different error types. Used by the rejection recovery handler (see
+/// [`crate::api::recover`]).
 ///
 /// # Arguments
 /// * `error` - RobertError to convert
@@ -126,8 +129,14 @@ mod tests {
         let session_id = Uuid::new_v4();
 
         // Query nonexistent session
-        let result = get_session_handler(session_id, manager).await;
-        assert!(result.is_ok()); // Handler doesn't reject, returns error JSON
+        let rejection = get_session_handler(session_id, manager)
+            .await
+            .err()
+            .expect("unknown session is rejected");
+        assert!(matches!(
+            rejection.find::<RobertError>(),
+            Some(RobertError::SessionNotFound(_))
+        ));
     }
 
     #[tokio::test]
@@ -157,8 +166,14 @@ mod tests {
         manager.complete(session_id).await.unwrap();
 
         // Try to cancel it
-        let result = delete_session_handler(session_id, manager).await;
-        assert!(result.is_ok()); // Returns error JSON, not rejection
+        let rejection = delete_session_handler(session_id, manager)
+            .await
+            .err()
+            .expect("finished session is rejected");
+        assert_eq!(
+            rejection.find::<RobertError>().map(|e| e.status_code()),
+            Some(StatusCode::BAD_REQUEST)
+        );
     }
 
     #[tokio::test]
@@ -167,8 +182,14 @@ mod tests {
         let session_id = Uuid::new_v4();
 
         // Try to cancel nonexistent session
-        let result = delete_session_handler(session_id, manager).await;
-        assert!(result.is_ok()); // Returns error JSON
+        let rejection = delete_session_handler(session_id, manager)
+            .await
+            .err()
+            .expect("unknown session is rejected");
+        assert!(matches!(
+            rejection.find::<RobertError>(),
+            Some(RobertError::SessionNotFound(_))
+        ));
     }
 
     #[test]
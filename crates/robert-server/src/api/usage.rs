@@ -0,0 +1,116 @@
+//! Usage reporting endpoint
+//!
+//! Reports token usage and cost for the calling API key together with the
+//! budget limits that apply to it.
+
+use crate::config::Config;
+use crate::session::SessionManager;
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::{reply, Reply};
+
+/// Default number of days in the per-day breakdown
+const DEFAULT_DAYS: u32 = 30;
+
+/// Maximum number of days in the per-day breakdown (usage retention)
+const MAX_DAYS: u32 = 92;
+
+/// Query parameters for GET /api/v1/usage
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageQuery {
+    /// Number of days (including today) to break down
+    #[serde(default = "default_days")]
+    pub days: u32,
+}
+
+fn default_days() -> u32 {
+    DEFAULT_DAYS
+}
+
+/// GET /api/v1/usage handler
+///
+/// Returns the caller's usage for today and this month (UTC), a per-day
+/// and per-model breakdown, and the key's budget limits.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller
+/// * `query` - Query parameters (`days`, default 30, at most 92)
+/// * `manager` - Shared session manager holding the usage ledger
+/// * `config` - Server configuration (for budget limits)
+///
+/// # Returns
+/// JSON usage report
+///
+/// # Example Response
+/// ```json
+/// {
+///   "today": { "input_tokens": 1200, "output_tokens": 350, "cost_usd": 0.01, ... },
+///   "this_month": { ... },
+///   "budget": { "daily_tokens": 100000 },
+///   "by_day": [{ "date": "2025-10-17", "usage": { ... }, "by_model": { ... } }],
+///   "by_model": { "claude-sonnet-4-5": { ... } }
+/// }
+/// ```
+pub async fn usage_handler(
+    token: String,
+    query: UsageQuery,
+    manager: Arc<SessionManager>,
+    config: Arc<Config>,
+) -> Result<impl Reply, warp::Rejection> {
+    let limits = config.budgets.limits_for(&token);
+    let days = query.days.clamp(1, MAX_DAYS);
+    let report = manager.usage_report(&token, &limits, days).await;
+
+    Ok(reply::json(&report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Usage;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_usage_handler_reports_caller_usage() {
+        let mut config = Config::dev_default();
+        config.budgets.default.daily_tokens = Some(5000);
+        let manager = Arc::new(SessionManager::new(100));
+
+        let session_id = Uuid::new_v4();
+        manager
+            .register_with_owner(session_id, 10, Some("key-1".to_string()))
+            .await
+            .unwrap();
+        manager
+            .record_usage(
+                session_id,
+                &Usage {
+                    input_tokens: 100,
+                    output_tokens: 20,
+                    model: Some("mock".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let reply = usage_handler(
+            "key-1".to_string(),
+            UsageQuery { days: 1000 },
+            manager,
+            Arc::new(config),
+        )
+        .await
+        .unwrap();
+
+        let body = warp::hyper::body::to_bytes(reply.into_response().into_body())
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["today"]["input_tokens"], 100);
+        assert_eq!(json["budget"]["daily_tokens"], 5000);
+        assert_eq!(json["by_model"]["mock"]["output_tokens"], 20);
+        assert_eq!(json["by_day"].as_array().unwrap().len(), 1);
+    }
+}
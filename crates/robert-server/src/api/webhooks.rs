@@ -0,0 +1,43 @@
+//! Webhook delivery log endpoint
+//!
+//! Lists the webhook deliveries of the calling API key (see
+//! [`crate::webhook`]).
+
+use crate::session::SessionManager;
+use std::sync::Arc;
+use warp::{reply, Reply};
+
+/// GET /api/v1/webhooks/deliveries handler
+///
+/// Returns the caller's recent webhook deliveries, most recent first.
+///
+/// # Arguments
+/// * `token` - Bearer token of the caller
+/// * `manager` - Shared session manager holding the webhook dispatcher
+///
+/// # Returns
+/// JSON array of deliveries
+///
+/// # Example Response
+/// ```json
+/// [
+///   {
+///     "delivery_id": "uuid",
+///     "session_id": "uuid",
+///     "url": "https://hooks.example.com/robert",
+///     "status": "delivered",
+///     "attempts": 2,
+///     "last_status_code": 200,
+///     "created_at": "2025-10-17T10:31:12Z",
+///     "finished_at": "2025-10-17T10:31:14Z"
+///   }
+/// ]
+/// ```
+pub async fn webhook_deliveries_handler(
+    token: String,
+    manager: Arc<SessionManager>,
+) -> Result<impl Reply, warp::Rejection> {
+    let deliveries = manager.webhooks().deliveries(&token).await;
+
+    Ok(reply::json(&deliveries))
+}
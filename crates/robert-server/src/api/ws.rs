@@ -0,0 +1,694 @@
+//! WebSocket transport for interactive sessions
+//!
+//! Handles GET /api/v1/ws. Unlike the SSE execute endpoint, a WebSocket
+//! connection carries traffic in both directions, so clients can answer
+//! clarification questions, send new context frames and cancel sessions
+//! while they run. Several sessions can share one connection.
+//!
+//! Messages are JSON text frames ([`WsClientFrame`] in,
+//! [`WsServerMessage`] out). Every client message carries its token and
+//! is authenticated and rate limited on its own; messages about a
+//! session are only accepted from the token that started it.
+//!
+//! The server pings the client every heartbeat interval and closes the
+//! connection when nothing (not even a pong) arrived for two intervals.
+//! Sessions still running when the connection closes are cancelled.
+
+use crate::api::execute::{start_session, track_event, track_failure};
+use crate::auth::AuthState;
+use crate::claude::Executor;
+use crate::conversation::ConversationManager;
+use crate::error::RobertError;
+use crate::models::{ClaudeEvent, SessionInput, WsClientFrame, WsClientMessage, WsServerMessage};
+use crate::reload::SharedConfig;
+use crate::session::SessionManager;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::Reply;
+
+/// Interval between heartbeat pings
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of unconsumed inputs buffered per session
+const INPUT_BUFFER: usize = 16;
+
+/// GET /api/v1/ws handler
+///
+/// Upgrades the connection to a WebSocket. Authentication happens per
+/// message, so the upgrade itself needs no Authorization header (browser
+/// WebSocket APIs cannot set one).
+///
+/// # Arguments
+/// * `ws` - WebSocket upgrade request
+/// * `auth_state` - Authentication state for per-message validation
+/// * `executor` - Claude executor (real or mock)
+/// * `session_manager` - Session tracking
+/// * `conversations` - Conversation store
+/// * `config` - Live configuration, read per message
+///
+/// # Returns
+/// Upgrade response
+pub async fn ws_handler(
+    ws: Ws,
+    auth_state: Arc<AuthState>,
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: SharedConfig,
+) -> Result<impl Reply, warp::Rejection> {
+    Ok(ws.on_upgrade(move |socket| {
+        serve_socket(
+            socket,
+            Connection::new(auth_state, executor, session_manager, conversations, config),
+            HEARTBEAT_INTERVAL,
+        )
+    }))
+}
+
+/// Session started over a connection
+struct ActiveSession {
+    /// Token that started the session
+    owner: String,
+
+    /// Inputs forwarded to the executor
+    input: mpsc::Sender<SessionInput>,
+
+    /// Task streaming the session's events to the client
+    task: JoinHandle<()>,
+}
+
+/// State of one WebSocket connection
+struct Connection {
+    auth_state: Arc<AuthState>,
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: SharedConfig,
+
+    /// Running sessions started over this connection
+    sessions: HashMap<Uuid, ActiveSession>,
+}
+
+impl Connection {
+    fn new(
+        auth_state: Arc<AuthState>,
+        executor: Arc<dyn Executor>,
+        session_manager: Arc<SessionManager>,
+        conversations: Arc<ConversationManager>,
+        config: SharedConfig,
+    ) -> Self {
+        Self {
+            auth_state,
+            executor,
+            session_manager,
+            conversations,
+            config,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Handles one text frame from the client
+    async fn handle_text(
+        &mut self,
+        text: &str,
+        outgoing: &mpsc::UnboundedSender<Message>,
+        finished: &mpsc::UnboundedSender<Uuid>,
+    ) {
+        let frame: WsClientFrame = match serde_json::from_str(text) {
+            Ok(frame) => frame,
+            Err(e) => {
+                send_error(
+                    outgoing,
+                    None,
+                    RobertError::InvalidRequest(format!("Malformed message: {}", e)),
+                );
+                return;
+            }
+        };
+
+        let session_id = match &frame.message {
+            WsClientMessage::Execute { request } => request.session_id,
+            WsClientMessage::Answer { session_id, .. }
+            | WsClientMessage::Context { session_id, .. }
+            | WsClientMessage::Cancel { session_id } => *session_id,
+        };
+
+        let token = match self.auth_state.authenticate(frame.token.as_deref()).await {
+            Ok(token) => token,
+            Err(e) => {
+                send_error(outgoing, Some(session_id), e);
+                return;
+            }
+        };
+
+        let result = match frame.message {
+            WsClientMessage::Execute { request } => {
+                self.start(token, request, outgoing, finished).await
+            }
+            WsClientMessage::Answer { session_id, text } => {
+                self.forward(&token, session_id, SessionInput::Answer { text })
+                    .await
+            }
+            WsClientMessage::Context {
+                session_id,
+                context,
+            } => {
+                let config = self.config.load();
+                match context.validate(config.limits.max_screenshot_count, 50000) {
+                    Ok(()) => {
+                        self.forward(&token, session_id, SessionInput::Context { context })
+                            .await
+                    }
+                    Err(e) => Err(RobertError::InvalidRequest(e)),
+                }
+            }
+            WsClientMessage::Cancel { session_id } => {
+                self.cancel(&token, session_id, outgoing).await
+            }
+        };
+
+        if let Err(e) = result {
+            send_error(outgoing, Some(session_id), e);
+        }
+    }
+
+    /// Starts a session and streams its events to the client
+    async fn start(
+        &mut self,
+        token: String,
+        mut request: crate::models::RobertRequest,
+        outgoing: &mpsc::UnboundedSender<Message>,
+        finished: &mpsc::UnboundedSender<Uuid>,
+    ) -> Result<(), RobertError> {
+        let session_id = request.session_id;
+        if self.sessions.contains_key(&session_id) {
+            return Err(RobertError::InvalidRequest(format!(
+                "Session {} is already running",
+                session_id
+            )));
+        }
+
+        let config = self.config.load_full();
+        start_session(
+            &token,
+            &mut request,
+            self.executor.as_ref(),
+            &self.session_manager,
+            &self.conversations,
+            &config,
+        )
+        .await?;
+
+        send(outgoing, &WsServerMessage::Accepted { session_id });
+
+        let (input, input_rx) = mpsc::channel(INPUT_BUFFER);
+        let mut events = self.executor.execute_interactive(request, input_rx).await;
+        let session_manager = self.session_manager.clone();
+        let conversations = self.conversations.clone();
+        let outgoing = outgoing.clone();
+        let finished = finished.clone();
+
+        let task = tokio::spawn(async move {
+            while let Some(result) = events.next().await {
+                let event = match result {
+                    Ok(event) => {
+                        track_event(&session_manager, &conversations, session_id, &event).await;
+                        event
+                    }
+                    Err(e) => {
+                        let event =
+                            track_failure(&session_manager, &conversations, session_id, &e).await;
+                        send(&outgoing, &WsServerMessage::Event { session_id, event });
+                        break;
+                    }
+                };
+
+                send(&outgoing, &WsServerMessage::Event { session_id, event });
+            }
+
+            let _ = finished.send(session_id);
+        });
+
+        self.sessions.insert(
+            session_id,
+            ActiveSession {
+                owner: token,
+                input,
+                task,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Looks up a running session started by `token`
+    fn owned(&self, token: &str, session_id: Uuid) -> Result<&ActiveSession, RobertError> {
+        self.sessions
+            .get(&session_id)
+            .filter(|session| session.owner == token)
+            .ok_or_else(|| RobertError::SessionNotFound(session_id.to_string()))
+    }
+
+    /// Sends client input to a running session
+    async fn forward(
+        &self,
+        token: &str,
+        session_id: Uuid,
+        input: SessionInput,
+    ) -> Result<(), RobertError> {
+        self.owned(token, session_id)?
+            .input
+            .send(input)
+            .await
+            .map_err(|_| {
+                RobertError::InvalidRequest(format!("Session {} does not accept input", session_id))
+            })
+    }
+
+    /// Cancels a running session
+    async fn cancel(
+        &mut self,
+        token: &str,
+        session_id: Uuid,
+        outgoing: &mpsc::UnboundedSender<Message>,
+    ) -> Result<(), RobertError> {
+        self.owned(token, session_id)?;
+        if let Some(session) = self.sessions.remove(&session_id) {
+            // Dropping the event stream stops the claude-cli process
+            session.task.abort();
+        }
+
+        self.session_manager.cancel(session_id).await?;
+        let event = ClaudeEvent::Complete {
+            session_id,
+            status: "cancelled".to_string(),
+            usage: None,
+        };
+        self.conversations.record_event(session_id, &event).await;
+        self.session_manager
+            .webhooks()
+            .record_event(session_id, &event)
+            .await;
+        send(outgoing, &WsServerMessage::Event { session_id, event });
+
+        Ok(())
+    }
+
+    /// Stops all sessions of a closed connection
+    async fn shutdown(&mut self) {
+        for (session_id, session) in self.sessions.drain() {
+            session.task.abort();
+            let _ = self.session_manager.cancel(session_id).await;
+            let event = ClaudeEvent::Complete {
+                session_id,
+                status: "cancelled".to_string(),
+                usage: None,
+            };
+            self.conversations.record_event(session_id, &event).await;
+            self.session_manager
+                .webhooks()
+                .record_event(session_id, &event)
+                .await;
+        }
+    }
+}
+
+/// Runs a WebSocket connection until it closes
+async fn serve_socket(socket: WebSocket, mut connection: Connection, heartbeat: Duration) {
+    let (mut sink, mut incoming) = socket.split();
+
+    // All writes go through one task so sessions can send concurrently
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Message>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing_rx.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let (finished, mut finished_rx) = mpsc::unbounded_channel::<Uuid>();
+    let mut ticker = tokio::time::interval(heartbeat);
+    ticker.tick().await;
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            message = incoming.next() => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => {
+                        debug!("WebSocket error: {}", e);
+                        break;
+                    }
+                    None => break,
+                };
+
+                last_seen = Instant::now();
+                if message.is_close() {
+                    break;
+                }
+                if let Ok(text) = message.to_str() {
+                    connection.handle_text(text, &outgoing, &finished).await;
+                } else if message.is_binary() {
+                    send_error(
+                        &outgoing,
+                        None,
+                        RobertError::InvalidRequest("Binary messages are not supported".to_string()),
+                    );
+                }
+            }
+            Some(session_id) = finished_rx.recv() => {
+                connection.sessions.remove(&session_id);
+            }
+            _ = ticker.tick() => {
+                if last_seen.elapsed() > heartbeat * 2 {
+                    warn!("WebSocket client missed heartbeats, closing connection");
+                    break;
+                }
+                let _ = outgoing.send(Message::ping(Vec::new()));
+            }
+        }
+    }
+
+    connection.shutdown().await;
+    drop(outgoing);
+    let _ = writer.await;
+}
+
+/// Queues a message for the client
+fn send(outgoing: &mpsc::UnboundedSender<Message>, message: &WsServerMessage) {
+    match serde_json::to_string(message) {
+        Ok(text) => {
+            let _ = outgoing.send(Message::text(text));
+        }
+        Err(e) => warn!("Failed to encode WebSocket message: {}", e),
+    }
+}
+
+/// Queues an error message for the client
+fn send_error(
+    outgoing: &mpsc::UnboundedSender<Message>,
+    session_id: Option<Uuid>,
+    error: RobertError,
+) {
+    send(
+        outgoing,
+        &WsServerMessage::Error {
+            session_id,
+            code: error.error_code(),
+            message: error.to_string(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::MockClaudeExecutor;
+    use crate::config::Config;
+    use crate::models::{
+        DomState, RequestContext, RequestOptions, RobertRequest, Screenshot, ScreenshotMetadata,
+        SessionState, Viewport,
+    };
+    use arc_swap::ArcSwap;
+    use warp::test::WsClient;
+    use warp::Filter;
+
+    const TOKEN: &str = "ws-token";
+    const OTHER_TOKEN: &str = "other-token";
+
+    fn create_test_request() -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: "dGVzdA==".to_string(),
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 100,
+                            height: 100,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "intent".to_string(),
+            },
+            prompt: "prompt".to_string(),
+            options: RequestOptions::default(),
+        }
+    }
+
+    async fn connect(
+        executor: MockClaudeExecutor,
+        heartbeat: Duration,
+    ) -> (WsClient, Arc<SessionManager>) {
+        let auth_state = Arc::new(AuthState::new(
+            vec![TOKEN.to_string(), OTHER_TOKEN.to_string()],
+            true,
+            1000,
+        ));
+        let session_manager = Arc::new(SessionManager::new(100));
+        let executor: Arc<dyn Executor> = Arc::new(executor);
+        let config: SharedConfig = Arc::new(ArcSwap::from_pointee(Config::dev_default()));
+
+        let manager = session_manager.clone();
+        let route = warp::ws().map(move |ws: Ws| {
+            let connection = Connection::new(
+                auth_state.clone(),
+                executor.clone(),
+                manager.clone(),
+                Arc::new(ConversationManager::new(100)),
+                config.clone(),
+            );
+            ws.on_upgrade(move |socket| serve_socket(socket, connection, heartbeat))
+        });
+
+        let client = warp::test::ws().handshake(route).await.unwrap();
+        (client, session_manager)
+    }
+
+    async fn send_frame(client: &mut WsClient, token: Option<&str>, message: WsClientMessage) {
+        let frame = WsClientFrame {
+            token: token.map(str::to_string),
+            message,
+        };
+        client
+            .send_text(serde_json::to_string(&frame).unwrap())
+            .await;
+    }
+
+    /// Receives the next JSON message, skipping heartbeat pings
+    async fn recv(client: &mut WsClient) -> WsServerMessage {
+        loop {
+            let message = client.recv().await.unwrap();
+            if let Ok(text) = message.to_str() {
+                return serde_json::from_str(text).unwrap();
+            }
+        }
+    }
+
+    /// Receives events of a session until it completes
+    async fn recv_until_complete(client: &mut WsClient) -> Vec<ClaudeEvent> {
+        let mut events = Vec::new();
+        loop {
+            if let WsServerMessage::Event { event, .. } = recv(client).await {
+                let complete = matches!(event, ClaudeEvent::Complete { .. });
+                events.push(event);
+                if complete {
+                    return events;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_streams_events() {
+        let (mut client, manager) =
+            connect(MockClaudeExecutor::with_delay(1), HEARTBEAT_INTERVAL).await;
+        let request = create_test_request();
+        let session_id = request.session_id;
+
+        send_frame(
+            &mut client,
+            Some(TOKEN),
+            WsClientMessage::Execute { request },
+        )
+        .await;
+
+        assert_eq!(
+            recv(&mut client).await,
+            WsServerMessage::Accepted { session_id }
+        );
+        let events = recv_until_complete(&mut client).await;
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::ToolUse { .. })));
+
+        let status = manager.get_status(session_id).await.unwrap();
+        assert_eq!(status.status, SessionState::Completed);
+        assert!(status.usage.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_every_message_is_authenticated() {
+        let (mut client, manager) =
+            connect(MockClaudeExecutor::with_delay(1), HEARTBEAT_INTERVAL).await;
+
+        for token in [None, Some("wrong-token")] {
+            send_frame(
+                &mut client,
+                token,
+                WsClientMessage::Execute {
+                    request: create_test_request(),
+                },
+            )
+            .await;
+
+            match recv(&mut client).await {
+                WsServerMessage::Error { code, .. } => assert_eq!(code, "AUTH_FAILED"),
+                other => panic!("Expected auth error, got {:?}", other),
+            }
+        }
+        assert_eq!(manager.total_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_message() {
+        let (mut client, _) = connect(MockClaudeExecutor::with_delay(1), HEARTBEAT_INTERVAL).await;
+
+        client.send_text(r#"{"type":"launch"}"#).await;
+        match recv(&mut client).await {
+            WsServerMessage::Error { code, .. } => assert_eq!(code, "INVALID_REQUEST"),
+            other => panic!("Expected error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clarification_answered_over_socket() {
+        let (mut client, _) = connect(
+            MockClaudeExecutor::with_clarification(1),
+            HEARTBEAT_INTERVAL,
+        )
+        .await;
+        let request = create_test_request();
+        let session_id = request.session_id;
+
+        send_frame(
+            &mut client,
+            Some(TOKEN),
+            WsClientMessage::Execute { request },
+        )
+        .await;
+
+        loop {
+            match recv(&mut client).await {
+                WsServerMessage::Event {
+                    event: ClaudeEvent::Clarification { question, .. },
+                    ..
+                } => {
+                    assert!(question.contains("account"));
+                    break;
+                }
+                WsServerMessage::Error { message, .. } => panic!("Unexpected error: {}", message),
+                _ => continue,
+            }
+        }
+
+        // Another key cannot answer
+        send_frame(
+            &mut client,
+            Some(OTHER_TOKEN),
+            WsClientMessage::Answer {
+                session_id,
+                text: "Personal".to_string(),
+            },
+        )
+        .await;
+        match recv(&mut client).await {
+            WsServerMessage::Error { code, .. } => assert_eq!(code, "SESSION_NOT_FOUND"),
+            other => panic!("Expected error, got {:?}", other),
+        }
+
+        send_frame(
+            &mut client,
+            Some(TOKEN),
+            WsClientMessage::Answer {
+                session_id,
+                text: "Work".to_string(),
+            },
+        )
+        .await;
+
+        let events = recv_until_complete(&mut client).await;
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Content { text } if text.contains("Work"))));
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { status, .. }) if status == "success"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_session() {
+        let (mut client, manager) = connect(
+            MockClaudeExecutor::with_clarification(1),
+            HEARTBEAT_INTERVAL,
+        )
+        .await;
+        let request = create_test_request();
+        let session_id = request.session_id;
+
+        send_frame(
+            &mut client,
+            Some(TOKEN),
+            WsClientMessage::Execute { request },
+        )
+        .await;
+        assert_eq!(
+            recv(&mut client).await,
+            WsServerMessage::Accepted { session_id }
+        );
+
+        send_frame(
+            &mut client,
+            Some(TOKEN),
+            WsClientMessage::Cancel { session_id },
+        )
+        .await;
+
+        let events = recv_until_complete(&mut client).await;
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { status, .. }) if status == "cancelled"
+        ));
+        assert_eq!(
+            manager.get_status(session_id).await.unwrap().status,
+            SessionState::Cancelled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_ping() {
+        let (mut client, _) =
+            connect(MockClaudeExecutor::with_delay(1), Duration::from_millis(20)).await;
+
+        let message = client.recv().await.unwrap();
+        assert!(message.is_ping());
+    }
+}
@@ -4,16 +4,18 @@
 //! for API endpoints. Supports development mode with relaxed requirements.
 
 use crate::error::RobertError;
+use arc_swap::ArcSwap;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use warp::{reject, Filter, Rejection};
 
-/// Authentication state
+/// Authentication policy
 ///
-/// Tracks valid tokens and per-token request counts for rate limiting.
-#[derive(Clone)]
-pub struct AuthState {
+/// The reloadable part of the authentication state: accepted tokens,
+/// whether auth is enforced and the per-token rate limit.
+#[derive(Debug, Clone)]
+struct AuthPolicy {
     /// List of valid bearer tokens
     valid_tokens: Vec<String>,
 
@@ -22,6 +24,17 @@ pub struct AuthState {
 
     /// Rate limit (requests per minute)
     rate_limit: u32,
+}
+
+/// Authentication state
+///
+/// Tracks valid tokens and per-token request counts for rate limiting.
+/// The token list and limits can be swapped at runtime with
+/// [`AuthState::update`] without losing rate limit history.
+#[derive(Clone)]
+pub struct AuthState {
+    /// Current authentication policy
+    policy: Arc<ArcSwap<AuthPolicy>>,
 
     /// Map of token to request timestamps (for rate limiting)
     request_history: Arc<Mutex<HashMap<String, Vec<std::time::Instant>>>>,
@@ -39,11 +52,35 @@ impl AuthState {
     /// New AuthState instance
     pub fn new(valid_tokens: Vec<String>, require_auth: bool, rate_limit: u32) -> Self {
         Self {
+            policy: Arc::new(ArcSwap::from_pointee(AuthPolicy {
+                valid_tokens,
+                require_auth,
+                rate_limit,
+            })),
+            request_history: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Atomically replaces the authentication policy
+    ///
+    /// Requests already past authentication are unaffected; subsequent
+    /// requests are checked against the new tokens and rate limit.
+    ///
+    /// # Arguments
+    /// * `valid_tokens` - New list of accepted bearer tokens
+    /// * `require_auth` - Whether to enforce authentication
+    /// * `rate_limit` - Maximum requests per minute per token
+    pub fn update(&self, valid_tokens: Vec<String>, require_auth: bool, rate_limit: u32) {
+        self.policy.store(Arc::new(AuthPolicy {
             valid_tokens,
             require_auth,
             rate_limit,
-            request_history: Arc::new(Mutex::new(HashMap::new())),
-        }
+        }));
+    }
+
+    /// Returns whether authentication is currently enforced
+    pub fn require_auth(&self) -> bool {
+        self.policy.load().require_auth
     }
 
     /// Validates bearer token
@@ -57,17 +94,51 @@ impl AuthState {
     /// # Returns
     /// Ok(token) if valid, Err if invalid or missing
     pub fn validate_token(&self, token: &str) -> Result<String, RobertError> {
-        if !self.require_auth {
+        let policy = self.policy.load();
+
+        if !policy.require_auth {
             return Ok(token.to_string());
         }
 
-        if self.valid_tokens.contains(&token.to_string()) {
+        if policy.valid_tokens.iter().any(|t| t == token) {
             Ok(token.to_string())
         } else {
             Err(RobertError::AuthFailed("Invalid token".to_string()))
         }
     }
 
+    /// Authenticates a request
+    ///
+    /// Validates the token and records the request against the token's
+    /// rate limit. A missing token is accepted (as the empty token) only
+    /// when authentication is not required.
+    ///
+    /// # Arguments
+    /// * `token` - Bearer token supplied with the request, if any
+    ///
+    /// # Returns
+    /// The validated token
+    ///
+    /// # Errors
+    /// Returns RobertError::AuthFailed for missing or invalid tokens and
+    /// RobertError::RateLimited when the rate limit is exceeded
+    pub async fn authenticate(&self, token: Option<&str>) -> Result<String, RobertError> {
+        let token = match token {
+            Some(token) => token.to_string(),
+            None if !self.require_auth() => String::new(),
+            None => {
+                return Err(RobertError::AuthFailed(
+                    "Missing Authorization header".to_string(),
+                ))
+            }
+        };
+
+        self.validate_token(&token)?;
+        self.check_rate_limit(&token).await?;
+
+        Ok(token)
+    }
+
     /// Checks rate limit for a token
     ///
     /// Examines request history for the token and determines if the
@@ -79,6 +150,7 @@ impl AuthState {
     /// # Returns
     /// Ok(()) if within limit, Err if rate limit exceeded
     pub async fn check_rate_limit(&self, token: &str) -> Result<(), RobertError> {
+        let rate_limit = self.policy.load().rate_limit;
         let mut history = self.request_history.lock().await;
         let now = std::time::Instant::now();
         let one_minute_ago = now - std::time::Duration::from_secs(60);
@@ -90,10 +162,10 @@ impl AuthState {
         requests.retain(|&timestamp| timestamp > one_minute_ago);
 
         // Check if limit exceeded
-        if requests.len() >= self.rate_limit as usize {
+        if requests.len() >= rate_limit as usize {
             return Err(RobertError::RateLimited(format!(
                 "Rate limit of {} requests per minute exceeded",
-                self.rate_limit
+                rate_limit
             )));
         }
 
@@ -138,12 +210,6 @@ impl AuthState {
     }
 }
 
-/// Custom rejection for authentication failures
-#[derive(Debug)]
-pub struct AuthRejection(pub RobertError);
-
-impl reject::Reject for AuthRejection {}
-
 /// Extracts bearer token from Authorization header
 ///
 /// Parses "Bearer <token>" format and returns the token portion.
@@ -157,9 +223,9 @@ fn extract_bearer_token(auth_header: String) -> Result<String, Rejection> {
     if let Some(token) = auth_header.strip_prefix("Bearer ") {
         Ok(token.to_string())
     } else {
-        Err(reject::custom(AuthRejection(RobertError::AuthFailed(
+        Err(reject::custom(RobertError::AuthFailed(
             "Invalid Authorization header format".to_string(),
-        ))))
+        )))
     }
 }
 
@@ -180,29 +246,12 @@ pub fn with_auth(
         move |auth_header: Option<String>| {
             let auth_state = auth_state.clone();
             async move {
-                // If auth not required and no header, use empty token
-                let token = if let Some(header) = auth_header {
-                    extract_bearer_token(header)?
-                } else if !auth_state.require_auth {
-                    String::new()
-                } else {
-                    return Err(reject::custom(AuthRejection(RobertError::AuthFailed(
-                        "Missing Authorization header".to_string(),
-                    ))));
-                };
-
-                // Validate token
-                auth_state
-                    .validate_token(&token)
-                    .map_err(|e| reject::custom(AuthRejection(e)))?;
+                let token = auth_header.map(extract_bearer_token).transpose()?;
 
-                // Check rate limit
                 auth_state
-                    .check_rate_limit(&token)
+                    .authenticate(token.as_deref())
                     .await
-                    .map_err(|e| reject::custom(AuthRejection(e)))?;
-
-                Ok::<String, Rejection>(token)
+                    .map_err(reject::custom)
             }
         },
     )
@@ -242,6 +291,25 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_authenticate() {
+        let auth_state = create_test_auth_state();
+
+        assert_eq!(
+            auth_state
+                .authenticate(Some("valid-token-1"))
+                .await
+                .unwrap(),
+            "valid-token-1"
+        );
+        assert!(auth_state.authenticate(Some("nope")).await.is_err());
+        assert!(auth_state.authenticate(None).await.is_err());
+        assert_eq!(auth_state.get_request_count("valid-token-1").await, 1);
+
+        let open = AuthState::new(vec![], false, 5);
+        assert_eq!(open.authenticate(None).await.unwrap(), "");
+    }
+
     #[tokio::test]
     async fn test_rate_limit_within_limit() {
         let auth_state = create_test_auth_state();
@@ -356,4 +424,30 @@ mod tests {
         // Note: We can't easily test time-based cleanup without mocking time
         // In a real scenario, requests older than 1 minute would be removed
     }
+
+    #[tokio::test]
+    async fn test_update_swaps_tokens_and_limit() {
+        let auth_state = create_test_auth_state();
+        assert!(auth_state.validate_token("valid-token-1").is_ok());
+        assert!(auth_state.validate_token("new-token").is_err());
+
+        auth_state.update(vec!["new-token".to_string()], true, 1);
+
+        assert!(auth_state.validate_token("valid-token-1").is_err());
+        assert!(auth_state.validate_token("new-token").is_ok());
+
+        auth_state.check_rate_limit("new-token").await.unwrap();
+        assert!(auth_state.check_rate_limit("new-token").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_preserves_rate_limit_history() {
+        let auth_state = create_test_auth_state();
+        let token = "valid-token-1";
+
+        auth_state.check_rate_limit(token).await.unwrap();
+        auth_state.update(vec![token.to_string()], true, 10);
+
+        assert_eq!(auth_state.get_request_count(token).await, 1);
+    }
 }
@@ -0,0 +1,987 @@
+//! Batch execution
+//!
+//! A batch runs many execute requests in the background, at most
+//! `max_parallel` of them at a time and never more than the server's
+//! `max_concurrent_sessions` overall. Each item runs in its own session,
+//! so its progress can also be followed through the session endpoints.
+//!
+//! Batches are kept in memory and are only visible to the API key that
+//! created them.
+
+use crate::api::execute::{check_request, start_session, track_event, track_failure};
+use crate::claude::Executor;
+use crate::config::Config;
+use crate::conversation::ConversationManager;
+use crate::error::RobertError;
+use crate::models::{
+    BatchItemResult, BatchItemState, BatchItemStatus, BatchProgress, BatchRequest, BatchState,
+    BatchStatus, ClaudeEvent, RobertRequest, Usage,
+};
+use crate::session::SessionManager;
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex, Semaphore};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+/// Items running at once when the request does not say
+const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// How often a queued item checks for a free session slot
+const CAPACITY_POLL: Duration = Duration::from_millis(200);
+
+/// A batch and the API key that owns it
+#[derive(Debug)]
+struct BatchEntry {
+    /// API key that created the batch (empty when auth is disabled)
+    owner: String,
+
+    /// When the batch was created
+    created_at: String,
+
+    /// When the last item finished (if finished)
+    completed_at: Option<String>,
+
+    /// Maximum number of items running at once
+    max_parallel: usize,
+
+    /// Whether the batch was cancelled
+    cancelled: bool,
+
+    /// Tells the runner to stop
+    cancel: watch::Sender<bool>,
+
+    /// Items in submission order
+    items: Vec<ItemEntry>,
+}
+
+/// Progress and result of a single item
+#[derive(Debug)]
+struct ItemEntry {
+    /// Session that runs the item
+    session_id: Uuid,
+
+    /// State of the item
+    status: BatchItemState,
+
+    /// Content produced so far
+    content: String,
+
+    /// Tokens and cost consumed
+    usage: Option<Usage>,
+
+    /// Error message (if failed)
+    error: Option<String>,
+}
+
+/// Everything an item needs to run
+#[derive(Clone)]
+struct RunContext {
+    /// API key that created the batch
+    owner: String,
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: Arc<Config>,
+}
+
+/// Thread-safe batch store and runner
+#[derive(Debug, Clone)]
+pub struct BatchManager {
+    /// Batches by id
+    state: Arc<Mutex<HashMap<Uuid, BatchEntry>>>,
+
+    /// Maximum number of batches kept; the oldest finished batch is
+    /// dropped to make room
+    max_batches: usize,
+}
+
+impl BatchManager {
+    /// Creates a new BatchManager
+    ///
+    /// # Arguments
+    /// * `max_batches` - Maximum number of batches kept in memory
+    ///
+    /// # Returns
+    /// New BatchManager instance
+    pub fn new(max_batches: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(HashMap::new())),
+            max_batches,
+        }
+    }
+
+    /// Validates a batch and starts running it in the background
+    ///
+    /// Template batches are expanded first. Every item is checked like a
+    /// single execute request before any of them runs; budgets are
+    /// checked as each item starts.
+    ///
+    /// # Arguments
+    /// * `owner` - API key of the caller
+    /// * `batch` - Requests or template to run
+    /// * `executor` - Executor that runs the items
+    /// * `session_manager` - Session tracking
+    /// * `conversations` - Conversation store
+    /// * `config` - Server configuration
+    ///
+    /// # Returns
+    /// Status of the new batch, with every item queued
+    ///
+    /// # Errors
+    /// Returns RobertError::InvalidRequest for empty or oversized batches,
+    /// unresolved template placeholders, duplicate session ids and items
+    /// that fail validation, and RobertError::ServerBusy when too many
+    /// batches are still running
+    pub async fn submit(
+        &self,
+        owner: &str,
+        batch: BatchRequest,
+        executor: Arc<dyn Executor>,
+        session_manager: Arc<SessionManager>,
+        conversations: Arc<ConversationManager>,
+        config: Arc<Config>,
+    ) -> Result<BatchStatus, RobertError> {
+        let max_parallel = match batch.max_parallel {
+            Some(0) => {
+                return Err(RobertError::InvalidRequest(
+                    "max_parallel must be greater than 0".to_string(),
+                ))
+            }
+            Some(n) => n,
+            None => DEFAULT_MAX_PARALLEL,
+        }
+        .min(config.claude.max_concurrent_sessions);
+
+        let mut requests = expand(batch)?;
+        if requests.len() > config.limits.max_batch_size {
+            return Err(RobertError::InvalidRequest(format!(
+                "Too many requests in batch: {} (max: {})",
+                requests.len(),
+                config.limits.max_batch_size
+            )));
+        }
+
+        let mut session_ids = HashSet::new();
+        for (index, request) in requests.iter_mut().enumerate() {
+            if request.options.conversation_id.is_some() {
+                return Err(RobertError::InvalidRequest(format!(
+                    "Item {}: batch items cannot continue conversations",
+                    index
+                )));
+            }
+            if !session_ids.insert(request.session_id) {
+                return Err(RobertError::InvalidRequest(format!(
+                    "Item {}: duplicate session_id {}",
+                    index, request.session_id
+                )));
+            }
+            check_request(request, &config).map_err(|e| match e {
+                RobertError::InvalidRequest(message) => {
+                    RobertError::InvalidRequest(format!("Item {}: {}", index, message))
+                }
+                other => other,
+            })?;
+        }
+
+        let batch_id = Uuid::new_v4();
+        let (cancel, cancelled) = watch::channel(false);
+        let entry = BatchEntry {
+            owner: owner.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            completed_at: None,
+            max_parallel,
+            cancelled: false,
+            cancel,
+            items: requests
+                .iter()
+                .map(|request| ItemEntry {
+                    session_id: request.session_id,
+                    status: BatchItemState::Queued,
+                    content: String::new(),
+                    usage: None,
+                    error: None,
+                })
+                .collect(),
+        };
+        let status = status_of(batch_id, &entry);
+
+        {
+            let mut state = self.state.lock().await;
+            if state.len() >= self.max_batches && !evict_finished(&mut state) {
+                return Err(RobertError::ServerBusy(format!(
+                    "Too many batches in progress (max: {})",
+                    self.max_batches
+                )));
+            }
+            state.insert(batch_id, entry);
+        }
+
+        let context = RunContext {
+            owner: owner.to_string(),
+            executor,
+            session_manager,
+            conversations,
+            config,
+        };
+        tokio::spawn(
+            self.clone()
+                .run(batch_id, requests, max_parallel, context, cancelled),
+        );
+
+        Ok(status)
+    }
+
+    /// Returns the progress of a batch and the status of its sessions
+    ///
+    /// # Arguments
+    /// * `batch_id` - Batch to look up
+    /// * `owner` - API key of the caller
+    /// * `session_manager` - Session tracking, for the per-item status
+    ///
+    /// # Errors
+    /// Returns RobertError::BatchNotFound if the batch does not exist or
+    /// belongs to another key
+    pub async fn status(
+        &self,
+        batch_id: Uuid,
+        owner: &str,
+        session_manager: &SessionManager,
+    ) -> Result<BatchStatus, RobertError> {
+        let mut status = {
+            let state = self.state.lock().await;
+            status_of(batch_id, lookup(&state, batch_id, owner)?)
+        };
+
+        for item in &mut status.items {
+            if item.status != BatchItemState::Queued {
+                item.session = session_manager.get_status(item.session_id).await.ok();
+            }
+        }
+        Ok(status)
+    }
+
+    /// Returns the result of every item, in submission order
+    ///
+    /// Items that have not finished yet are included with their current
+    /// state and the content produced so far.
+    ///
+    /// # Arguments
+    /// * `batch_id` - Batch to look up
+    /// * `owner` - API key of the caller
+    ///
+    /// # Errors
+    /// Returns RobertError::BatchNotFound if the batch does not exist or
+    /// belongs to another key
+    pub async fn results(
+        &self,
+        batch_id: Uuid,
+        owner: &str,
+    ) -> Result<Vec<BatchItemResult>, RobertError> {
+        let state = self.state.lock().await;
+        let entry = lookup(&state, batch_id, owner)?;
+
+        Ok(entry
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| BatchItemResult {
+                index,
+                session_id: item.session_id,
+                status: item.status.clone(),
+                content: item.content.clone(),
+                usage: item.usage.clone(),
+                error: item.error.clone(),
+            })
+            .collect())
+    }
+
+    /// Cancels a batch
+    ///
+    /// Queued items are cancelled right away; running items are stopped
+    /// and their sessions cancelled shortly after.
+    ///
+    /// # Arguments
+    /// * `batch_id` - Batch to cancel
+    /// * `owner` - API key of the caller
+    ///
+    /// # Returns
+    /// Status of the batch after cancellation
+    ///
+    /// # Errors
+    /// Returns RobertError::BatchNotFound if the batch does not exist or
+    /// belongs to another key, and RobertError::InvalidRequest if it has
+    /// already finished
+    pub async fn cancel(&self, batch_id: Uuid, owner: &str) -> Result<BatchStatus, RobertError> {
+        let mut state = self.state.lock().await;
+        lookup(&state, batch_id, owner)?;
+
+        let entry = state.get_mut(&batch_id).expect("batch looked up above");
+        if entry.completed_at.is_some() || entry.cancelled {
+            return Err(RobertError::InvalidRequest(format!(
+                "Batch {} has already finished",
+                batch_id
+            )));
+        }
+
+        entry.cancelled = true;
+        for item in &mut entry.items {
+            if item.status == BatchItemState::Queued {
+                item.status = BatchItemState::Cancelled;
+            }
+        }
+        entry.cancel.send_replace(true);
+
+        Ok(status_of(batch_id, entry))
+    }
+
+    /// Runs the items of a batch, at most `max_parallel` at a time
+    async fn run(
+        self,
+        batch_id: Uuid,
+        requests: Vec<RobertRequest>,
+        max_parallel: usize,
+        context: RunContext,
+        cancelled: watch::Receiver<bool>,
+    ) {
+        let slots = Arc::new(Semaphore::new(max_parallel));
+        let mut running = JoinSet::new();
+
+        for (index, request) in requests.into_iter().enumerate() {
+            let mut cancel = cancelled.clone();
+            let slot = tokio::select! {
+                slot = slots.clone().acquire_owned() => slot.expect("semaphore is never closed"),
+                _ = wait_cancelled(&mut cancel) => break,
+            };
+
+            // Leave room for requests outside the batch
+            if !wait_for_capacity(&context, &mut cancel).await {
+                break;
+            }
+            if !self.start_item(batch_id, index).await {
+                continue;
+            }
+
+            let manager = self.clone();
+            let context = context.clone();
+            running.spawn(async move {
+                manager
+                    .run_item(batch_id, index, request, context, cancel)
+                    .await;
+                drop(slot);
+            });
+        }
+
+        while running.join_next().await.is_some() {}
+
+        let mut state = self.state.lock().await;
+        if let Some(entry) = state.get_mut(&batch_id) {
+            entry.completed_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+    }
+
+    /// Runs one item until it finishes or the batch is cancelled
+    async fn run_item(
+        &self,
+        batch_id: Uuid,
+        index: usize,
+        mut request: RobertRequest,
+        context: RunContext,
+        mut cancel: watch::Receiver<bool>,
+    ) {
+        let session_id = request.session_id;
+        let RunContext {
+            owner,
+            executor,
+            session_manager,
+            conversations,
+            config,
+        } = context;
+
+        if let Err(e) = start_session(
+            &owner,
+            &mut request,
+            executor.as_ref(),
+            &session_manager,
+            &conversations,
+            &config,
+        )
+        .await
+        {
+            self.record(
+                batch_id,
+                index,
+                &ClaudeEvent::Error {
+                    code: e.error_code(),
+                    message: e.to_string(),
+                },
+            )
+            .await;
+            return;
+        }
+
+        let mut events = executor.execute(request).await;
+        let mut cancelled = false;
+        loop {
+            tokio::select! {
+                next = events.next() => match next {
+                    Some(Ok(event)) => {
+                        track_event(&session_manager, &conversations, session_id, &event).await;
+                        self.record(batch_id, index, &event).await;
+                    }
+                    Some(Err(e)) => {
+                        let event =
+                            track_failure(&session_manager, &conversations, session_id, &e).await;
+                        self.record(batch_id, index, &event).await;
+                        break;
+                    }
+                    None => break,
+                },
+                _ = wait_cancelled(&mut cancel) => {
+                    cancelled = true;
+                    break;
+                }
+            }
+        }
+
+        // Dropping the event stream stops the claude-cli process
+        drop(events);
+
+        if cancelled {
+            let _ = session_manager.cancel(session_id).await;
+            let event = ClaudeEvent::Complete {
+                session_id,
+                status: "cancelled".to_string(),
+                usage: None,
+            };
+            session_manager
+                .webhooks()
+                .record_event(session_id, &event)
+                .await;
+            self.record(batch_id, index, &event).await;
+        }
+
+        // Report runs that stopped without a complete event
+        session_manager.webhooks().finish(session_id).await;
+        self.record(
+            batch_id,
+            index,
+            &ClaudeEvent::Error {
+                code: "EXECUTION_ERROR".to_string(),
+                message: "Session ended without a result".to_string(),
+            },
+        )
+        .await;
+    }
+
+    /// Marks a queued item running
+    ///
+    /// # Returns
+    /// false if the item was cancelled while it waited
+    async fn start_item(&self, batch_id: Uuid, index: usize) -> bool {
+        let mut state = self.state.lock().await;
+        match state
+            .get_mut(&batch_id)
+            .and_then(|entry| entry.items.get_mut(index))
+        {
+            Some(item) if item.status == BatchItemState::Queued => {
+                item.status = BatchItemState::Running;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates an item with one of its session's events
+    ///
+    /// Content is collected and the first terminal event decides the
+    /// item's final state; later terminal events are ignored.
+    async fn record(&self, batch_id: Uuid, index: usize, event: &ClaudeEvent) {
+        let mut state = self.state.lock().await;
+        let Some(item) = state
+            .get_mut(&batch_id)
+            .and_then(|entry| entry.items.get_mut(index))
+        else {
+            return;
+        };
+
+        match event {
+            ClaudeEvent::Content { text } => item.content.push_str(text),
+            ClaudeEvent::Complete { status, usage, .. } => {
+                if usage.is_some() {
+                    item.usage = usage.clone();
+                }
+                if item.status == BatchItemState::Running {
+                    item.status = match status.as_str() {
+                        "cancelled" => BatchItemState::Cancelled,
+                        "failed" => BatchItemState::Failed,
+                        _ => BatchItemState::Completed,
+                    };
+                }
+            }
+            ClaudeEvent::Error { message, .. } if item.status == BatchItemState::Running => {
+                item.status = BatchItemState::Failed;
+                item.error = Some(message.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the requests of a batch, expanding its template
+///
+/// # Errors
+/// Returns RobertError::InvalidRequest unless exactly one of `requests`
+/// and `template` is given and it yields at least one request, or when a
+/// template placeholder has no value
+fn expand(batch: BatchRequest) -> Result<Vec<RobertRequest>, RobertError> {
+    let requests = match (batch.requests, batch.template) {
+        (Some(requests), None) => requests,
+        (None, Some(template)) => template
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let mut request = template.request.clone();
+                request.session_id = Uuid::new_v4();
+                request.prompt = fill(&template.request.prompt, row)
+                    .map_err(|e| RobertError::InvalidRequest(format!("Item {}: {}", index, e)))?;
+                request.context.user_intent = fill(&template.request.context.user_intent, row)
+                    .map_err(|e| RobertError::InvalidRequest(format!("Item {}: {}", index, e)))?;
+                Ok(request)
+            })
+            .collect::<Result<Vec<_>, RobertError>>()?,
+        _ => {
+            return Err(RobertError::InvalidRequest(
+                "Exactly one of requests and template is required".to_string(),
+            ))
+        }
+    };
+
+    if requests.is_empty() {
+        return Err(RobertError::InvalidRequest("Batch is empty".to_string()));
+    }
+    Ok(requests)
+}
+
+/// Replaces `{{name}}` placeholders with the row's values
+///
+/// # Errors
+/// Returns a message naming the first placeholder without a value
+fn fill(text: &str, row: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        let value = row
+            .get(name)
+            .ok_or_else(|| format!("No value for placeholder {{{{{}}}}}", name))?;
+
+        filled.push_str(&rest[..start]);
+        filled.push_str(value);
+        rest = &rest[start + 2 + end + 2..];
+    }
+
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// Looks up a batch owned by `owner`
+fn lookup<'a>(
+    state: &'a HashMap<Uuid, BatchEntry>,
+    batch_id: Uuid,
+    owner: &str,
+) -> Result<&'a BatchEntry, RobertError> {
+    state
+        .get(&batch_id)
+        .filter(|entry| entry.owner == owner)
+        .ok_or_else(|| RobertError::BatchNotFound(batch_id.to_string()))
+}
+
+/// Builds the status of a batch, without session details
+fn status_of(batch_id: Uuid, entry: &BatchEntry) -> BatchStatus {
+    let mut progress = BatchProgress {
+        total: entry.items.len(),
+        ..Default::default()
+    };
+    for item in &entry.items {
+        match item.status {
+            BatchItemState::Queued => progress.queued += 1,
+            BatchItemState::Running => progress.running += 1,
+            BatchItemState::Completed => progress.completed += 1,
+            BatchItemState::Failed => progress.failed += 1,
+            BatchItemState::Cancelled => progress.cancelled += 1,
+        }
+    }
+
+    let status = if entry.cancelled {
+        BatchState::Cancelled
+    } else if entry.completed_at.is_some() {
+        BatchState::Completed
+    } else {
+        BatchState::Running
+    };
+
+    BatchStatus {
+        batch_id,
+        status,
+        created_at: entry.created_at.clone(),
+        completed_at: entry.completed_at.clone(),
+        max_parallel: entry.max_parallel,
+        progress,
+        items: entry
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| BatchItemStatus {
+                index,
+                session_id: item.session_id,
+                status: item.status.clone(),
+                session: None,
+            })
+            .collect(),
+    }
+}
+
+/// Drops the oldest finished batch
+///
+/// # Returns
+/// true if a batch was dropped
+fn evict_finished(state: &mut HashMap<Uuid, BatchEntry>) -> bool {
+    let oldest = state
+        .iter()
+        .filter(|(_, entry)| entry.completed_at.is_some())
+        .min_by(|(_, a), (_, b)| a.created_at.cmp(&b.created_at))
+        .map(|(id, _)| *id);
+
+    match oldest {
+        Some(id) => state.remove(&id).is_some(),
+        None => false,
+    }
+}
+
+/// Waits until the batch is cancelled
+///
+/// Also returns if the batch was dropped from the store.
+async fn wait_cancelled(cancel: &mut watch::Receiver<bool>) {
+    let _ = cancel.wait_for(|cancelled| *cancelled).await;
+}
+
+/// Waits until the server can take another session
+///
+/// # Returns
+/// false if the batch was cancelled while waiting
+async fn wait_for_capacity(context: &RunContext, cancel: &mut watch::Receiver<bool>) -> bool {
+    while context.session_manager.running_count().await
+        >= context.config.claude.max_concurrent_sessions
+    {
+        tokio::select! {
+            _ = tokio::time::sleep(CAPACITY_POLL) => {}
+            _ = wait_cancelled(cancel) => return false,
+        }
+    }
+    !*cancel.borrow()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::MockClaudeExecutor;
+    use crate::models::{
+        BatchTemplate, DomState, RequestContext, RequestOptions, Screenshot, ScreenshotMetadata,
+        SessionState, Viewport,
+    };
+
+    fn create_test_request(prompt: &str) -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: {
+                        use base64::{engine::general_purpose, Engine as _};
+                        general_purpose::STANDARD.encode(b"test image")
+                    },
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 1920,
+                            height: 1080,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "test tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "test intent".to_string(),
+            },
+            prompt: prompt.to_string(),
+            options: RequestOptions::default(),
+        }
+    }
+
+    fn batch_of(requests: Vec<RobertRequest>, max_parallel: Option<usize>) -> BatchRequest {
+        BatchRequest {
+            requests: Some(requests),
+            template: None,
+            max_parallel,
+        }
+    }
+
+    struct Fixture {
+        batches: BatchManager,
+        executor: Arc<dyn Executor>,
+        session_manager: Arc<SessionManager>,
+        conversations: Arc<ConversationManager>,
+        config: Arc<Config>,
+    }
+
+    impl Fixture {
+        fn new(executor: MockClaudeExecutor) -> Self {
+            Self {
+                batches: BatchManager::new(10),
+                executor: Arc::new(executor),
+                session_manager: Arc::new(SessionManager::new(100)),
+                conversations: Arc::new(ConversationManager::new(10)),
+                config: Arc::new(Config::dev_default()),
+            }
+        }
+
+        async fn submit(&self, batch: BatchRequest) -> Result<BatchStatus, RobertError> {
+            self.batches
+                .submit(
+                    "key",
+                    batch,
+                    self.executor.clone(),
+                    self.session_manager.clone(),
+                    self.conversations.clone(),
+                    self.config.clone(),
+                )
+                .await
+        }
+
+        async fn wait_until_finished(&self, batch_id: Uuid) -> BatchStatus {
+            for _ in 0..100 {
+                let status = self
+                    .batches
+                    .status(batch_id, "key", &self.session_manager)
+                    .await
+                    .unwrap();
+                if status.completed_at.is_some() {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            panic!("batch did not finish");
+        }
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        let row = BTreeMap::from([
+            ("city".to_string(), "Oslo".to_string()),
+            ("day".to_string(), "Monday".to_string()),
+        ]);
+
+        assert_eq!(
+            fill("Weather in {{city}} on {{ day }}", &row).unwrap(),
+            "Weather in Oslo on Monday"
+        );
+        assert_eq!(fill("No placeholders", &row).unwrap(), "No placeholders");
+        assert_eq!(fill("Unclosed {{city", &row).unwrap(), "Unclosed {{city");
+        assert_eq!(
+            fill("{{country}}", &row).unwrap_err(),
+            "No value for placeholder {{country}}"
+        );
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let template = create_test_request("Summarize {{page}}");
+        let batch = BatchRequest {
+            requests: None,
+            template: Some(BatchTemplate {
+                request: template.clone(),
+                parameters: vec![
+                    BTreeMap::from([("page".to_string(), "home".to_string())]),
+                    BTreeMap::from([("page".to_string(), "pricing".to_string())]),
+                ],
+            }),
+            max_parallel: None,
+        };
+
+        let requests = expand(batch).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].prompt, "Summarize home");
+        assert_eq!(requests[1].prompt, "Summarize pricing");
+        assert_ne!(requests[0].session_id, template.session_id);
+        assert_ne!(requests[0].session_id, requests[1].session_id);
+
+        // Missing parameters are rejected
+        let batch = BatchRequest {
+            requests: None,
+            template: Some(BatchTemplate {
+                request: template.clone(),
+                parameters: vec![BTreeMap::new()],
+            }),
+            max_parallel: None,
+        };
+        assert!(matches!(expand(batch), Err(RobertError::InvalidRequest(_))));
+
+        // Exactly one of requests and template
+        let batch = BatchRequest {
+            requests: Some(vec![template.clone()]),
+            template: Some(BatchTemplate {
+                request: template,
+                parameters: vec![],
+            }),
+            max_parallel: None,
+        };
+        assert!(matches!(expand(batch), Err(RobertError::InvalidRequest(_))));
+        assert!(matches!(
+            expand(batch_of(vec![], None)),
+            Err(RobertError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejects_invalid_batches() {
+        let fixture = Fixture::new(MockClaudeExecutor::with_delay(1));
+
+        // Duplicate session ids
+        let request = create_test_request("test");
+        let result = fixture
+            .submit(batch_of(vec![request.clone(), request], None))
+            .await;
+        assert!(matches!(result, Err(RobertError::InvalidRequest(_))));
+
+        // Invalid items name their position
+        let mut invalid = create_test_request("test");
+        invalid.context.screenshots.clear();
+        let result = fixture
+            .submit(batch_of(vec![create_test_request("test"), invalid], None))
+            .await;
+        match result {
+            Err(RobertError::InvalidRequest(message)) => assert!(message.starts_with("Item 1:")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Zero parallelism
+        let result = fixture
+            .submit(batch_of(vec![create_test_request("test")], Some(0)))
+            .await;
+        assert!(matches!(result, Err(RobertError::InvalidRequest(_))));
+
+        // Nothing ran
+        assert_eq!(fixture.session_manager.total_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_to_completion() {
+        let fixture = Fixture::new(MockClaudeExecutor::with_delay(5));
+        let requests: Vec<_> = (0..5)
+            .map(|i| create_test_request(&format!("prompt {}", i)))
+            .collect();
+
+        let created = fixture.submit(batch_of(requests, Some(2))).await.unwrap();
+        assert_eq!(created.status, BatchState::Running);
+        assert_eq!(created.max_parallel, 2);
+        assert_eq!(created.progress.total, 5);
+
+        let status = fixture.wait_until_finished(created.batch_id).await;
+        assert_eq!(status.status, BatchState::Completed);
+        assert_eq!(status.progress.completed, 5);
+        for item in &status.items {
+            assert_eq!(item.status, BatchItemState::Completed);
+            assert_eq!(
+                item.session.as_ref().unwrap().status,
+                SessionState::Completed
+            );
+        }
+
+        let results = fixture
+            .batches
+            .results(created.batch_id, "key")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results[0].content.starts_with("Mock:"));
+        assert_eq!(results[0].usage.as_ref().unwrap().input_tokens, 1200);
+
+        // Other keys cannot see the batch
+        assert!(matches!(
+            fixture.batches.results(created.batch_id, "other").await,
+            Err(RobertError::BatchNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_items_are_reported() {
+        let fixture = Fixture::new(MockClaudeExecutor::with_failure());
+        let created = fixture
+            .submit(batch_of(vec![create_test_request("test")], None))
+            .await
+            .unwrap();
+
+        let status = fixture.wait_until_finished(created.batch_id).await;
+        assert_eq!(status.status, BatchState::Completed);
+        assert_eq!(status.progress.failed, 1);
+
+        let results = fixture
+            .batches
+            .results(created.batch_id, "key")
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0].error.as_deref(),
+            Some("Simulated failure for testing")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_batch() {
+        let fixture = Fixture::new(MockClaudeExecutor::with_delay(5000));
+        let requests: Vec<_> = (0..3).map(|_| create_test_request("test")).collect();
+        let created = fixture.submit(batch_of(requests, Some(1))).await.unwrap();
+
+        // Wait for the first item to start
+        for _ in 0..50 {
+            if fixture.session_manager.running_count().await == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let cancelled = fixture
+            .batches
+            .cancel(created.batch_id, "key")
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, BatchState::Cancelled);
+        assert_eq!(cancelled.items[1].status, BatchItemState::Cancelled);
+        assert_eq!(cancelled.items[2].status, BatchItemState::Cancelled);
+
+        let status = fixture.wait_until_finished(created.batch_id).await;
+        assert_eq!(status.status, BatchState::Cancelled);
+        assert_eq!(status.progress.cancelled, 3);
+        assert_eq!(
+            status.items[0].session.as_ref().unwrap().status,
+            SessionState::Cancelled
+        );
+        assert_eq!(fixture.session_manager.total_count().await, 1);
+
+        // A finished batch cannot be cancelled again
+        assert!(matches!(
+            fixture.batches.cancel(created.batch_id, "key").await,
+            Err(RobertError::InvalidRequest(_))
+        ));
+    }
+}
@@ -1,16 +1,109 @@
 //! Real Claude CLI executor
 //!
 //! Spawns headless claude-cli processes and streams stdout/stderr events.
-//! Handles timeouts, process cleanup, and error recovery.
+//! Handles timeouts, process cleanup, and error recovery. When a sandbox is
+//! configured, each process runs in its own workspace (see [`super::sandbox`]).
 
+use crate::claude::permissions::permission_args;
+use crate::claude::sandbox::{Sandbox, SessionWorkspace};
 use crate::claude::Executor;
+use crate::config::ClaudeConfig;
 use crate::error::RobertError;
-use crate::models::{ClaudeEvent, RobertRequest};
+use crate::models::{ClaudeEvent, RobertRequest, SessionInput, Usage};
 use async_stream::stream;
 use futures::Stream;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
 use tokio::time::{timeout, Duration};
+use tracing::warn;
+
+/// Number of trailing stderr lines included in process failure messages
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Final `result` line printed by claude-cli in JSON output modes
+#[derive(Debug, Deserialize)]
+struct CliResult {
+    /// Line type; only "result" lines are of interest
+    #[serde(rename = "type")]
+    kind: String,
+
+    /// Final response text
+    #[serde(default)]
+    result: Option<String>,
+
+    /// Whether the run ended in an error
+    #[serde(default)]
+    is_error: bool,
+
+    /// Total cost of the run in US dollars
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+
+    /// Token counts
+    #[serde(default)]
+    usage: Option<Usage>,
+
+    /// Per-model usage, keyed by model name
+    #[serde(default, rename = "modelUsage")]
+    model_usage: HashMap<String, serde_json::Value>,
+
+    /// Id of the claude session, for resuming it later
+    #[serde(default)]
+    session_id: Option<String>,
+}
+
+/// `system` line printed by claude-cli when a session starts
+#[derive(Debug, Deserialize)]
+struct CliInit {
+    /// Line type; only "system" lines are of interest
+    #[serde(rename = "type")]
+    kind: String,
+
+    /// Line subtype; only "init" lines are of interest
+    #[serde(default)]
+    subtype: String,
+
+    /// Id of the claude session
+    session_id: String,
+}
+
+impl CliInit {
+    /// Parses a claude-cli init line and returns its session id
+    fn session_id(line: &str) -> Option<String> {
+        serde_json::from_str::<Self>(line)
+            .ok()
+            .filter(|init| init.kind == "system" && init.subtype == "init")
+            .map(|init| init.session_id)
+    }
+}
+
+impl CliResult {
+    /// Parses a claude-cli result line
+    fn parse(line: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(line)
+            .ok()
+            .filter(|result| result.kind == "result")
+    }
+
+    /// Returns the usage of the run, attributed to `model` if given
+    fn to_usage(&self, model: Option<&str>) -> Usage {
+        let model = model.map(str::to_string).or_else(|| {
+            // Only attribute to a single model when claude-cli reports exactly one
+            (self.model_usage.len() == 1)
+                .then(|| self.model_usage.keys().next().cloned())
+                .flatten()
+        });
+
+        Usage {
+            cost_usd: self.total_cost_usd.unwrap_or_default(),
+            model,
+            ..self.usage.clone().unwrap_or_default()
+        }
+    }
+}
 
 /// Real Claude CLI executor
 ///
@@ -23,6 +116,9 @@ pub struct ClaudeExecutor {
     /// Default timeout for executions
     #[allow(dead_code)]
     default_timeout: Duration,
+
+    /// Sandbox applied to spawned processes (None runs them unsandboxed)
+    sandbox: Option<Sandbox>,
 }
 
 impl ClaudeExecutor {
@@ -38,36 +134,96 @@ impl ClaudeExecutor {
         Self {
             binary_path,
             default_timeout: Duration::from_secs(timeout_seconds),
+            sandbox: None,
         }
     }
 
-    /// Spawns a claude-cli process
+    /// Creates an executor from the `[claude]` configuration section
     ///
-    /// Launches claude in headless mode with streaming enabled.
+    /// Applies the configured sandbox unless `sandbox.enabled` is false.
     ///
     /// # Arguments
-    /// * `request` - Request data to send to claude
+    /// * `config` - Claude configuration
     ///
     /// # Returns
-    /// Spawned child process handle
+    /// New ClaudeExecutor instance
+    pub fn from_config(config: &ClaudeConfig) -> Self {
+        Self {
+            sandbox: config
+                .sandbox
+                .enabled
+                .then(|| Sandbox::new(config.sandbox.clone())),
+            ..Self::new(config.binary_path.clone(), config.default_timeout_seconds)
+        }
+    }
+
+    /// Prepares the claude-cli command for a session
+    ///
+    /// Creates the session workspace when sandboxed. The returned workspace
+    /// must be kept alive until the process has exited.
+    ///
+    /// # Arguments
+    /// * `request` - Request being executed
+    ///
+    /// # Returns
+    /// Command to spawn and the session workspace, if any
     ///
     /// # Errors
-    /// Returns RobertError if process fails to spawn
-    #[allow(dead_code)]
-    async fn spawn_process(&self, _request: &RobertRequest) -> Result<Child, RobertError> {
-        Command::new(&self.binary_path)
+    /// Returns RobertError if the workspace cannot be created
+    fn build_command(
+        &self,
+        request: &RobertRequest,
+    ) -> Result<(Command, Option<SessionWorkspace>), RobertError> {
+        let (mut command, workspace) = match &self.sandbox {
+            Some(sandbox) => {
+                let workspace = sandbox.create_workspace(request.session_id)?;
+                (
+                    sandbox.command(&self.binary_path, &workspace),
+                    Some(workspace),
+                )
+            }
+            None => (Command::new(&self.binary_path), None),
+        };
+
+        command
             .arg("--headless")
             .arg("--stream")
+            .args(model_args(&request.options.model))
+            .args(resume_args(&request.options.resume_session))
+            .args(permission_args(&request.options))
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                RobertError::ClaudeUnavailable(format!(
-                    "Failed to spawn claude-cli: {} (binary: {})",
-                    e, self.binary_path
-                ))
-            })
+            .kill_on_drop(true);
+
+        Ok((command, workspace))
+    }
+
+    /// Spawns a claude-cli process
+    ///
+    /// Launches claude in headless mode with streaming enabled.
+    ///
+    /// # Arguments
+    /// * `request` - Request data to send to claude
+    ///
+    /// # Returns
+    /// Spawned child process handle and its session workspace, if any
+    ///
+    /// # Errors
+    /// Returns RobertError if process fails to spawn
+    fn spawn_process(
+        &self,
+        request: &RobertRequest,
+    ) -> Result<(Child, Option<SessionWorkspace>), RobertError> {
+        let (mut command, workspace) = self.build_command(request)?;
+        let child = command.spawn().map_err(|e| {
+            RobertError::ClaudeUnavailable(format!(
+                "Failed to spawn claude-cli: {} (binary: {})",
+                e, self.binary_path
+            ))
+        })?;
+
+        Ok((child, workspace))
     }
 
     /// Parses a line of stdout/stderr into a ClaudeEvent
@@ -98,28 +254,53 @@ impl Executor for ClaudeExecutor {
     async fn execute(
         &self,
         request: RobertRequest,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        self.run(request, None)
+    }
+
+    async fn execute_interactive(
+        &self,
+        request: RobertRequest,
+        input: mpsc::Receiver<SessionInput>,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        self.run(request, Some(input))
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+}
+
+impl ClaudeExecutor {
+    /// Runs claude-cli and streams its events
+    ///
+    /// Without `input`, stdin is closed right away. With `input`, stdin
+    /// stays open and every input is written to it as one JSON line;
+    /// since the process may then legitimately wait for the user, output
+    /// gaps are bounded by the request timeout instead of the usual
+    /// few seconds.
+    fn run(
+        &self,
+        request: RobertRequest,
+        input: Option<mpsc::Receiver<SessionInput>>,
     ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
         let session_id = request.session_id;
-        let binary_path = self.binary_path.clone();
+        let line_timeout = match input {
+            Some(_) => Duration::from_secs(request.options.timeout_seconds),
+            None => Duration::from_secs(5),
+        };
+        let model = request.options.model.clone();
 
         // Spawn process before creating stream
-        let child_result = Command::new(&binary_path)
-            .arg("--headless")
-            .arg("--stream")
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn();
+        let spawn_result = self.spawn_process(&request);
 
         let stream = stream! {
-            // Check if spawn succeeded
-            let mut child = match child_result {
-                Ok(child) => child,
+            // Check if spawn succeeded. The workspace lives as long as the
+            // stream and is removed when the session ends.
+            let (mut child, _workspace) = match spawn_result {
+                Ok(spawned) => spawned,
                 Err(e) => {
-                    yield Err(RobertError::ClaudeUnavailable(format!(
-                        "Failed to spawn claude-cli: {} (binary: {})",
-                        e, binary_path
-                    )));
+                    yield Err(e);
                     return;
                 }
             };
@@ -135,29 +316,61 @@ impl Executor for ClaudeExecutor {
                 }
             };
 
+            // Drain stderr in the background so the pipe never fills up;
+            // its tail explains process failures (e.g. API overload errors)
+            let stderr_tail = child.stderr.take().map(|stderr| tokio::spawn(read_tail(stderr)));
+
             // Create buffered reader for stdout
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
 
-            // Write request to stdin (TODO: implement proper JSON serialization)
-            if let Some(stdin) = child.stdin.take() {
-                // For now, just close stdin - full implementation would write request data
-                drop(stdin);
-            }
+            // Forward session input to stdin; otherwise close it right away
+            let stdin = child.stdin.take();
+            let _input_forwarder = match (stdin, input) {
+                (Some(stdin), Some(input)) => Some(AbortOnDrop(tokio::spawn(forward_input(stdin, input)))),
+                _ => None,
+            };
+
+            // Usage reported by the final result line, if any
+            let mut usage = None;
+
+            // Whether the claude session id has been reported
+            let mut session_reported = false;
 
             // Stream output lines with timeout
             loop {
-                let line_result = timeout(Duration::from_secs(5), lines.next_line()).await;
+                let line_result = timeout(line_timeout, lines.next_line()).await;
 
                 match line_result {
                     Ok(Ok(Some(line))) => {
                         // Parse output line inline instead of using self
-                        let event = if let Ok(event) = serde_json::from_str::<ClaudeEvent>(&line) {
-                            event
+                        if let Ok(event) = serde_json::from_str::<ClaudeEvent>(&line) {
+                            yield Ok(event);
+                        } else if let Some(claude_session_id) = CliInit::session_id(&line) {
+                            if !session_reported {
+                                session_reported = true;
+                                yield Ok(ClaudeEvent::SessionStarted { claude_session_id });
+                            }
+                        } else if let Some(result) = CliResult::parse(&line) {
+                            usage = Some(result.to_usage(model.as_deref()));
+                            if let Some(claude_session_id) = result.session_id.clone() {
+                                if !session_reported {
+                                    session_reported = true;
+                                    yield Ok(ClaudeEvent::SessionStarted { claude_session_id });
+                                }
+                            }
+                            let text = result.result.unwrap_or_default();
+                            if result.is_error {
+                                yield Ok(ClaudeEvent::Error {
+                                    code: "CLAUDE_ERROR".to_string(),
+                                    message: text,
+                                });
+                            } else if !text.is_empty() {
+                                yield Ok(ClaudeEvent::Content { text });
+                            }
                         } else {
-                            ClaudeEvent::Content { text: line }
-                        };
-                        yield Ok(event);
+                            yield Ok(ClaudeEvent::Content { text: line });
+                        }
                     }
                     Ok(Ok(None)) => {
                         // EOF reached
@@ -184,18 +397,29 @@ impl Executor for ClaudeExecutor {
             match timeout(Duration::from_secs(5), child.wait()).await {
                 Ok(Ok(status)) => {
                     if !status.success() {
+                        let stderr = match stderr_tail {
+                            Some(handle) => handle.await.unwrap_or_default(),
+                            None => String::new(),
+                        };
+                        let message = if stderr.is_empty() {
+                            format!("Claude process exited with status: {}", status)
+                        } else {
+                            format!("Claude process exited with status: {}: {}", status, stderr)
+                        };
                         yield Ok(ClaudeEvent::Error {
                             code: "PROCESS_FAILED".to_string(),
-                            message: format!("Claude process exited with status: {}", status),
+                            message,
                         });
                         yield Ok(ClaudeEvent::Complete {
                             session_id,
                             status: "failed".to_string(),
+                            usage,
                         });
                     } else {
                         yield Ok(ClaudeEvent::Complete {
                             session_id,
                             status: "success".to_string(),
+                            usage,
                         });
                     }
                 }
@@ -219,6 +443,70 @@ impl Executor for ClaudeExecutor {
     }
 }
 
+/// Writes session inputs to claude-cli's stdin as JSON lines
+///
+/// Returns when the client stops sending input or the process closes
+/// its stdin.
+async fn forward_input(mut stdin: ChildStdin, mut input: mpsc::Receiver<SessionInput>) {
+    while let Some(item) = input.recv().await {
+        let mut line = match serde_json::to_vec(&item) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to encode session input: {}", e);
+                continue;
+            }
+        };
+        line.push(b'\n');
+
+        if stdin.write_all(&line).await.is_err() || stdin.flush().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Aborts a spawned task when dropped
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Builds the `--model` flag for a requested model
+///
+/// The model is joined to the flag so it can never be taken for a flag of
+/// its own.
+fn model_args(model: &Option<String>) -> Vec<String> {
+    match model {
+        Some(model) => vec![format!("--model={}", model)],
+        None => vec![],
+    }
+}
+
+/// Builds the `--resume` flag for a claude session to continue
+fn resume_args(claude_session_id: &Option<String>) -> Vec<String> {
+    match claude_session_id {
+        Some(id) => vec!["--resume".to_string(), id.clone()],
+        None => vec![],
+    }
+}
+
+/// Reads a stream to the end and returns its last lines
+async fn read_tail<R: AsyncRead + Unpin>(reader: R) -> String {
+    let mut lines = BufReader::new(reader).lines();
+    let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+
+    Vec::from(tail).join("\n").trim().to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,7 +584,7 @@ mod tests {
         let executor = ClaudeExecutor::new("/nonexistent/binary".to_string(), 300);
         let request = create_test_request();
 
-        let result = executor.spawn_process(&request).await;
+        let result = executor.spawn_process(&request);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -317,6 +605,247 @@ mod tests {
         assert!(first.is_some());
     }
 
+    #[tokio::test]
+    async fn test_execute_sandboxed_cleans_up_workspace() {
+        let root = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::Config::dev_default().claude;
+        config.binary_path = "echo".to_string();
+        config.sandbox.workspace_root = Some(root.path().to_string_lossy().to_string());
+
+        let executor = ClaudeExecutor::from_config(&config);
+        let mut stream = executor.execute(create_test_request()).await;
+
+        // The workspace exists while the session is running
+        let first = stream.next().await;
+        assert!(matches!(first, Some(Ok(ClaudeEvent::Content { .. }))));
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 1);
+
+        while stream.next().await.is_some() {}
+        drop(stream);
+
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_permission_flags() {
+        let executor = ClaudeExecutor::new("echo".to_string(), 30);
+        let mut request = create_test_request();
+        request.options.allowed_tools = vec!["Read".to_string()];
+
+        let mut stream = executor.execute(request).await;
+
+        match stream.next().await {
+            Some(Ok(ClaudeEvent::Content { text })) => {
+                assert_eq!(text, "--headless --stream --allowed-tools=Read");
+            }
+            other => panic!("Expected echoed arguments, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_model_flag() {
+        let executor = ClaudeExecutor::new("echo".to_string(), 30);
+        let mut request = create_test_request();
+        request.options.model = Some("claude-haiku-4-5".to_string());
+
+        let mut stream = executor.execute(request).await;
+
+        match stream.next().await {
+            Some(Ok(ClaudeEvent::Content { text })) => {
+                assert_eq!(text, "--headless --stream --model=claude-haiku-4-5");
+            }
+            other => panic!("Expected echoed arguments, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_resume_flag() {
+        let executor = ClaudeExecutor::new("echo".to_string(), 30);
+        let mut request = create_test_request();
+        request.options.resume_session = Some("claude-session-1".to_string());
+
+        let mut stream = executor.execute(request).await;
+
+        match stream.next().await {
+            Some(Ok(ClaudeEvent::Content { text })) => {
+                assert_eq!(text, "--headless --stream --resume claude-session-1");
+            }
+            other => panic!("Expected echoed arguments, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reports_claude_session_id() {
+        let root = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::Config::dev_default().claude;
+        config.sandbox.workspace_root = Some(root.path().to_string_lossy().to_string());
+        config.sandbox.wrapper = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"echo '{"type":"system","subtype":"init","session_id":"abc-123"}'; echo '{"type":"result","result":"Done","session_id":"abc-123"}'"#
+                .to_string(),
+        ];
+
+        let executor = ClaudeExecutor::from_config(&config);
+        let events: Vec<_> = executor
+            .execute(create_test_request())
+            .await
+            .collect()
+            .await;
+
+        // Reported once, although both lines carry the id
+        let reported: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Ok(ClaudeEvent::SessionStarted { claude_session_id }) => {
+                    Some(claude_session_id.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reported, vec!["abc-123"]);
+        assert!(matches!(
+            &events[1],
+            Ok(ClaudeEvent::Content { text }) if text == "Done"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reports_stderr_on_failure() {
+        // The wrapper runs a script in place of claude-cli (which becomes $0)
+        let root = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::Config::dev_default().claude;
+        config.sandbox.workspace_root = Some(root.path().to_string_lossy().to_string());
+        config.sandbox.wrapper = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo 'API Error: 529 overloaded_error' >&2; exit 1".to_string(),
+        ];
+
+        let executor = ClaudeExecutor::from_config(&config);
+        let events: Vec<_> = executor
+            .execute(create_test_request())
+            .await
+            .collect()
+            .await;
+
+        let message = events
+            .iter()
+            .find_map(|e| match e {
+                Ok(ClaudeEvent::Error { message, .. }) => Some(message.clone()),
+                _ => None,
+            })
+            .expect("Expected an Error event");
+        assert!(message.contains("overloaded_error"), "{}", message);
+    }
+
+    #[test]
+    fn test_cli_result_usage() {
+        let line = r#"{"type":"result","subtype":"success","is_error":false,"result":"Done","total_cost_usd":0.0123,"usage":{"input_tokens":120,"output_tokens":45,"cache_read_input_tokens":300},"modelUsage":{"claude-sonnet-4-5":{"inputTokens":120}}}"#;
+        let result = CliResult::parse(line).expect("result line");
+
+        let usage = result.to_usage(None);
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.output_tokens, 45);
+        assert_eq!(usage.cache_read_input_tokens, 300);
+        assert_eq!(usage.cost_usd, 0.0123);
+        assert_eq!(usage.model.as_deref(), Some("claude-sonnet-4-5"));
+
+        // The requested model takes precedence
+        let usage = result.to_usage(Some("claude-haiku-4-5"));
+        assert_eq!(usage.model.as_deref(), Some("claude-haiku-4-5"));
+
+        assert!(CliResult::parse(r#"{"type":"assistant"}"#).is_none());
+        assert!(CliResult::parse("plain text").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reports_usage_on_complete() {
+        let root = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::Config::dev_default().claude;
+        config.sandbox.workspace_root = Some(root.path().to_string_lossy().to_string());
+        config.sandbox.wrapper = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"echo '{"type":"result","result":"Done","total_cost_usd":0.5,"usage":{"input_tokens":7,"output_tokens":3}}'"#
+                .to_string(),
+        ];
+
+        let executor = ClaudeExecutor::from_config(&config);
+        let events: Vec<_> = executor
+            .execute(create_test_request())
+            .await
+            .collect()
+            .await;
+
+        assert!(matches!(
+            &events[0],
+            Ok(ClaudeEvent::Content { text }) if text == "Done"
+        ));
+        match events.last() {
+            Some(Ok(ClaudeEvent::Complete {
+                usage: Some(usage), ..
+            })) => {
+                assert_eq!(usage.total_tokens(), 10);
+                assert_eq!(usage.cost_usd, 0.5);
+            }
+            other => panic!("Expected Complete with usage, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_interactive_forwards_input() {
+        // Emit a clarification, then echo the answer line read from stdin
+        let root = tempfile::TempDir::new().unwrap();
+        let mut config = crate::config::Config::dev_default().claude;
+        config.sandbox.workspace_root = Some(root.path().to_string_lossy().to_string());
+        config.sandbox.wrapper = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"echo '{"type":"clarification","question":"Which one?","options":["A","B"]}'; read answer; echo "$answer""#
+                .to_string(),
+        ];
+
+        let executor = ClaudeExecutor::from_config(&config);
+        let (input, receiver) = mpsc::channel(1);
+        let mut stream = executor
+            .execute_interactive(create_test_request(), receiver)
+            .await;
+
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(ClaudeEvent::Clarification { question, .. })) if question == "Which one?"
+        ));
+
+        input
+            .send(SessionInput::Answer {
+                text: "B".to_string(),
+            })
+            .await
+            .unwrap();
+
+        match stream.next().await {
+            Some(Ok(ClaudeEvent::Content { text })) => {
+                let echoed: SessionInput = serde_json::from_str(&text).unwrap();
+                assert_eq!(
+                    echoed,
+                    SessionInput::Answer {
+                        text: "B".to_string()
+                    }
+                );
+            }
+            other => panic!("Expected echoed input, got {:?}", other),
+        }
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(ClaudeEvent::Complete { status, .. })) if status == "success"
+        ));
+    }
+
     // Note: Full integration tests with real claude-cli would require
     // the binary to be installed and properly configured
 }
@@ -0,0 +1,544 @@
+//! Model selection and fallback
+//!
+//! Wraps another executor, resolves the requested model through the
+//! configured aliases and retries with the next model in the fallback
+//! chain when an attempt fails because the model is overloaded or rate
+//! limited. A fallback is only attempted while nothing but progress has
+//! been streamed to the client, so output is never duplicated. Usage
+//! reported by failed attempts is added to the final completion.
+
+use crate::claude::Executor;
+use crate::error::RobertError;
+use crate::models::{ClaudeEvent, RobertRequest, SessionInput, Usage};
+use crate::reload::SharedConfig;
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Executor that applies model selection and the fallback chain
+pub struct FallbackExecutor {
+    /// Executor performing each attempt
+    inner: Arc<dyn Executor>,
+
+    /// Live configuration (reads `[claude.models]` per request)
+    config: SharedConfig,
+}
+
+impl FallbackExecutor {
+    /// Wraps an executor with model selection and fallback
+    ///
+    /// # Arguments
+    /// * `inner` - Executor used for each attempt
+    /// * `config` - Shared, reloadable server configuration
+    ///
+    /// # Returns
+    /// New FallbackExecutor instance
+    pub fn new(inner: Arc<dyn Executor>, config: SharedConfig) -> Self {
+        Self { inner, config }
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for FallbackExecutor {
+    async fn execute(
+        &self,
+        request: RobertRequest,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        let chain = self
+            .config
+            .load()
+            .claude
+            .models
+            .chain(request.options.model.as_deref());
+
+        // No model configured or requested: let claude-cli choose
+        if chain.is_empty() {
+            return self.inner.execute(request).await;
+        }
+
+        let inner = self.inner.clone();
+        let stream = stream! {
+            // Usage of failed attempts, reported with the final completion
+            let mut failed_usage: Option<Usage> = None;
+
+            for (attempt, model) in chain.iter().enumerate() {
+                let next_model = chain.get(attempt + 1);
+                let mut request = request.clone();
+                request.options.model = Some(model.clone());
+
+                let mut events = inner.execute(request).await;
+                let mut output_sent = false;
+                let mut unavailable = None;
+
+                while let Some(item) = events.next().await {
+                    // Drain the failed attempt for the usage it reports
+                    if unavailable.is_some() {
+                        if let Ok(ClaudeEvent::Complete { usage: Some(usage), .. }) = &item {
+                            add_usage(&mut failed_usage, usage);
+                        }
+                        continue;
+                    }
+
+                    if next_model.is_some() && !output_sent {
+                        if let Some(reason) = overload_reason(&item) {
+                            unavailable = Some(reason);
+                            continue;
+                        }
+                    }
+
+                    if matches!(
+                        item,
+                        Ok(ClaudeEvent::Content { .. })
+                            | Ok(ClaudeEvent::ToolUse { .. })
+                            | Ok(ClaudeEvent::Clarification { .. })
+                    ) {
+                        output_sent = true;
+                    }
+
+                    let item = match item {
+                        Ok(ClaudeEvent::Complete { session_id, status, usage }) => {
+                            let mut total = failed_usage.take();
+                            if let Some(usage) = &usage {
+                                add_usage(&mut total, usage);
+                            }
+                            Ok(ClaudeEvent::Complete { session_id, status, usage: total })
+                        }
+                        other => other,
+                    };
+
+                    yield item;
+                }
+
+                match (unavailable, next_model) {
+                    (Some(reason), Some(next)) => {
+                        warn!("Model {} unavailable ({}), falling back to {}", model, reason, next);
+                        yield Ok(ClaudeEvent::Progress {
+                            message: format!(
+                                "Model {} is unavailable ({}); falling back to {}",
+                                model, reason, next
+                            ),
+                            percent: 0,
+                        });
+                    }
+                    _ => return,
+                }
+            }
+        };
+
+        Box::new(Box::pin(stream))
+    }
+
+    /// Runs an interactive session on the first model of the chain
+    ///
+    /// Client input cannot be replayed to a second attempt, so
+    /// interactive sessions resolve aliases and the default model but do
+    /// not fall back.
+    async fn execute_interactive(
+        &self,
+        mut request: RobertRequest,
+        input: mpsc::Receiver<SessionInput>,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        let chain = self
+            .config
+            .load()
+            .claude
+            .models
+            .chain(request.options.model.as_deref());
+
+        if let Some(model) = chain.into_iter().next() {
+            request.options.model = Some(model);
+        }
+
+        self.inner.execute_interactive(request, input).await
+    }
+
+    fn supports_resume(&self) -> bool {
+        self.inner.supports_resume()
+    }
+}
+
+/// Adds `usage` to an optional running total
+fn add_usage(total: &mut Option<Usage>, usage: &Usage) {
+    match total {
+        Some(total) => total.add(usage),
+        None => *total = Some(usage.clone()),
+    }
+}
+
+/// Returns a short reason if the item reports an overloaded or rate limited model
+///
+/// Only the API error reported by claude-cli is considered: its HTTP status
+/// (529, 429) or its error type (`overloaded_error`, `rate_limit_error`).
+/// Free text that merely mentions these numbers or words does not count.
+fn overload_reason(item: &Result<ClaudeEvent, RobertError>) -> Option<&'static str> {
+    let message = match item {
+        Ok(ClaudeEvent::Error { message, .. }) => message,
+        Err(RobertError::RateLimited(_)) => return Some("rate limited"),
+        _ => return None,
+    };
+
+    let error = ApiError::parse(message)?;
+    match (error.status, error.kind.as_deref()) {
+        (529, _) | (_, Some("overloaded_error")) => Some("overloaded"),
+        (429, _) | (_, Some("rate_limit_error")) => Some("rate limited"),
+        _ => None,
+    }
+}
+
+/// Anthropic API error as reported by claude-cli
+///
+/// claude-cli reports API failures as `API Error: <status> <body>`, where
+/// the body is usually the JSON error returned by the API
+/// (`{"type":"error","error":{"type":"overloaded_error",...}}`).
+#[derive(Debug, PartialEq)]
+struct ApiError {
+    /// HTTP status of the API response
+    status: u16,
+
+    /// `error.type` of the JSON body, if present
+    kind: Option<String>,
+}
+
+impl ApiError {
+    /// Marker preceding the status in claude-cli's error output
+    const MARKER: &'static str = "API Error: ";
+
+    /// Extracts the API error from an error message, if it reports one
+    fn parse(message: &str) -> Option<Self> {
+        let start = message.find(Self::MARKER)? + Self::MARKER.len();
+        let rest = &message[start..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let status = rest[..end].parse().ok()?;
+
+        // The body may be followed by more output, so only read one value
+        let kind = serde_json::Deserializer::from_str(rest[end..].trim_start())
+            .into_iter::<serde_json::Value>()
+            .next()
+            .and_then(Result::ok)
+            .and_then(|body| body["error"]["type"].as_str().map(str::to_string));
+
+        Some(Self { status, kind })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ModelConfig};
+    use crate::models::{
+        DomState, RequestContext, RequestOptions, Screenshot, ScreenshotMetadata, Viewport,
+    };
+    use arc_swap::ArcSwap;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// Executor that fails with `failure` for the listed models
+    struct ScriptedExecutor {
+        failing: Vec<&'static str>,
+        failure: (&'static str, &'static str),
+        content_before_failure: bool,
+        attempts: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Executor for ScriptedExecutor {
+        async fn execute(
+            &self,
+            request: RobertRequest,
+        ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static>
+        {
+            let model = request.options.model.clone();
+            self.attempts.lock().unwrap().push(model.clone());
+
+            let fails = model.as_deref().is_some_and(|m| self.failing.contains(&m));
+            let mut events = Vec::new();
+            if self.content_before_failure || !fails {
+                events.push(ClaudeEvent::Content {
+                    text: format!("answer from {:?}", model),
+                });
+            }
+            if fails {
+                events.push(ClaudeEvent::Error {
+                    code: self.failure.0.to_string(),
+                    message: self.failure.1.to_string(),
+                });
+            }
+            events.push(ClaudeEvent::Complete {
+                session_id: request.session_id,
+                status: if fails { "failed" } else { "success" }.to_string(),
+                usage: Some(Usage {
+                    input_tokens: 100,
+                    cost_usd: 0.01,
+                    model,
+                    ..Default::default()
+                }),
+            });
+
+            Box::new(futures::stream::iter(events.into_iter().map(Ok)))
+        }
+    }
+
+    fn scripted(failing: Vec<&'static str>) -> (ScriptedExecutor, Arc<Mutex<Vec<Option<String>>>>) {
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        (
+            ScriptedExecutor {
+                failing,
+                failure: ("PROCESS_FAILED", "API Error: 529 overloaded_error"),
+                content_before_failure: false,
+                attempts: attempts.clone(),
+            },
+            attempts,
+        )
+    }
+
+    fn shared_config(models: ModelConfig) -> SharedConfig {
+        let mut config = Config::dev_default();
+        config.claude.models = models;
+        Arc::new(ArcSwap::from_pointee(config))
+    }
+
+    fn models() -> ModelConfig {
+        ModelConfig {
+            default: Some("smart".to_string()),
+            aliases: HashMap::from([
+                ("fast".to_string(), "model-fast".to_string()),
+                ("smart".to_string(), "model-smart".to_string()),
+            ]),
+            fallback: vec!["fast".to_string()],
+        }
+    }
+
+    fn create_test_request(model: Option<&str>) -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: "dGVzdA==".to_string(),
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 100,
+                            height: 100,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "intent".to_string(),
+            },
+            prompt: "prompt".to_string(),
+            options: RequestOptions {
+                model: model.map(str::to_string),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn run(executor: &FallbackExecutor, model: Option<&str>) -> Vec<ClaudeEvent> {
+        executor
+            .execute(create_test_request(model))
+            .await
+            .map(|e| e.unwrap())
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_resolves_alias() {
+        let (inner, attempts) = scripted(vec![]);
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        run(&executor, Some("fast")).await;
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            vec![Some("model-fast".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_model_configured_passes_through() {
+        let (inner, attempts) = scripted(vec![]);
+        let executor =
+            FallbackExecutor::new(Arc::new(inner), shared_config(ModelConfig::default()));
+
+        run(&executor, None).await;
+        assert_eq!(*attempts.lock().unwrap(), vec![None]);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_overload() {
+        let (inner, attempts) = scripted(vec!["model-smart"]);
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        let events = run(&executor, None).await;
+
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            vec![
+                Some("model-smart".to_string()),
+                Some("model-fast".to_string())
+            ]
+        );
+        match &events[0] {
+            ClaudeEvent::Progress { message, .. } => {
+                assert!(message.contains("model-smart"));
+                assert!(message.contains("falling back to model-fast"));
+            }
+            other => panic!("Expected Progress, got {:?}", other),
+        }
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Error { .. })));
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { status, .. }) if status == "success"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_usage_is_counted() {
+        let (inner, _attempts) = scripted(vec!["model-smart"]);
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        let events = run(&executor, None).await;
+
+        let completions: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ClaudeEvent::Complete { usage, .. } => Some(usage.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(completions.len(), 1);
+
+        let usage = completions[0].clone().unwrap();
+        assert_eq!(usage.input_tokens, 200);
+        assert!((usage.cost_usd - 0.02).abs() < 1e-9);
+        // Usage spans two models
+        assert_eq!(usage.model, None);
+    }
+
+    #[tokio::test]
+    async fn test_last_model_failure_is_reported() {
+        let (inner, attempts) = scripted(vec!["model-smart", "model-fast"]);
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        let events = run(&executor, None).await;
+
+        assert_eq!(attempts.lock().unwrap().len(), 2);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Error { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_do_not_fall_back() {
+        let (mut inner, attempts) = scripted(vec!["model-smart"]);
+        inner.failure = ("PROCESS_FAILED", "invalid prompt");
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        let events = run(&executor, None).await;
+
+        assert_eq!(attempts.lock().unwrap().len(), 1);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Error { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_after_output() {
+        let (mut inner, attempts) = scripted(vec!["model-smart"]);
+        inner.content_before_failure = true;
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+
+        run(&executor, None).await;
+        assert_eq!(attempts.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_uses_first_model_without_fallback() {
+        let (inner, attempts) = scripted(vec!["model-smart"]);
+        let executor = FallbackExecutor::new(Arc::new(inner), shared_config(models()));
+        let (_input, receiver) = mpsc::channel(1);
+
+        let events: Vec<ClaudeEvent> = executor
+            .execute_interactive(create_test_request(None), receiver)
+            .await
+            .map(|e| e.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            vec![Some("model-smart".to_string())]
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Error { .. })));
+    }
+
+    #[test]
+    fn test_overload_reason() {
+        let error = |message: &str| {
+            Ok(ClaudeEvent::Error {
+                code: "PROCESS_FAILED".to_string(),
+                message: message.to_string(),
+            })
+        };
+
+        assert_eq!(
+            overload_reason(&error("API Error: 529 Overloaded")),
+            Some("overloaded")
+        );
+        assert_eq!(
+            overload_reason(&error(
+                r#"Claude process exited with status: 1: API Error: 429 {"type":"error","error":{"type":"rate_limit_error","message":"slow down"}}"#
+            )),
+            Some("rate limited")
+        );
+        assert_eq!(
+            overload_reason(&error(
+                r#"API Error: 500 {"type":"error","error":{"type":"overloaded_error"}} (retrying)"#
+            )),
+            Some("overloaded")
+        );
+        assert_eq!(overload_reason(&error("syntax error")), None);
+
+        // Numbers and words outside a reported API error do not count
+        assert_eq!(
+            overload_reason(&error("Order 529 failed: server overloaded")),
+            None
+        );
+        assert_eq!(
+            overload_reason(&error("rate_limit_error: too many requests")),
+            None
+        );
+        assert_eq!(
+            overload_reason(&error(r#"API Error: 500 {"type":"error"}"#)),
+            None
+        );
+        assert_eq!(
+            overload_reason(&Err(RobertError::ExecutionError(
+                "API Error: 529 Overloaded".to_string()
+            ))),
+            None
+        );
+        assert_eq!(
+            overload_reason(&Err(RobertError::RateLimited("slow down".to_string()))),
+            Some("rate limited")
+        );
+        assert_eq!(
+            overload_reason(&Ok(ClaudeEvent::Content {
+                text: "overloaded".to_string()
+            })),
+            None
+        );
+    }
+}
@@ -5,10 +5,13 @@
 //! development and automated testing.
 
 use crate::claude::Executor;
+use crate::config::MockScenario;
 use crate::error::RobertError;
-use crate::models::{ClaudeEvent, RobertRequest};
+use crate::models::{ClaudeEvent, RobertRequest, SessionInput, Usage};
+use crate::reload::SharedConfig;
 use async_stream::stream;
 use futures::Stream;
+use tokio::sync::mpsc;
 
 /// Mock executor that returns predefined responses
 ///
@@ -19,8 +22,11 @@ pub struct MockClaudeExecutor {
     /// Delay between events in milliseconds
     event_delay_ms: u64,
 
-    /// Whether to simulate an error
-    should_fail: bool,
+    /// Scenario to play back
+    scenario: MockScenario,
+
+    /// Live configuration; when set, overrides the fixed settings above
+    config: Option<SharedConfig>,
 }
 
 impl MockClaudeExecutor {
@@ -31,7 +37,8 @@ impl MockClaudeExecutor {
     pub fn new() -> Self {
         Self {
             event_delay_ms: 100,
-            should_fail: false,
+            scenario: MockScenario::Success,
+            config: None,
         }
     }
 
@@ -45,7 +52,8 @@ impl MockClaudeExecutor {
     pub fn with_delay(delay_ms: u64) -> Self {
         Self {
             event_delay_ms: delay_ms,
-            should_fail: false,
+            scenario: MockScenario::Success,
+            config: None,
         }
     }
 
@@ -56,7 +64,52 @@ impl MockClaudeExecutor {
     pub fn with_failure() -> Self {
         Self {
             event_delay_ms: 100,
-            should_fail: true,
+            scenario: MockScenario::Failure,
+            config: None,
+        }
+    }
+
+    /// Creates a mock executor that asks a clarification question
+    ///
+    /// # Arguments
+    /// * `delay_ms` - Milliseconds to wait between events
+    ///
+    /// # Returns
+    /// MockClaudeExecutor that waits for an answer before completing
+    pub fn with_clarification(delay_ms: u64) -> Self {
+        Self {
+            event_delay_ms: delay_ms,
+            scenario: MockScenario::Clarification,
+            config: None,
+        }
+    }
+
+    /// Creates a mock executor driven by the live server configuration
+    ///
+    /// The scenario and event delay are read from `[claude.mock]` at the
+    /// start of every execution, so a configuration reload takes effect
+    /// for the next request.
+    ///
+    /// # Arguments
+    /// * `config` - Shared, reloadable server configuration
+    ///
+    /// # Returns
+    /// MockClaudeExecutor that follows the configured scenario
+    pub fn with_config(config: SharedConfig) -> Self {
+        Self {
+            config: Some(config),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the delay and scenario for the next execution
+    fn current_settings(&self) -> (u64, MockScenario) {
+        match &self.config {
+            Some(config) => {
+                let mock = &config.load().claude.mock;
+                (mock.event_delay_ms, mock.scenario)
+            }
+            None => (self.event_delay_ms, self.scenario),
         }
     }
 }
@@ -73,15 +126,46 @@ impl Executor for MockClaudeExecutor {
         &self,
         request: RobertRequest,
     ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
-        let delay_ms = self.event_delay_ms;
-        let should_fail = self.should_fail;
+        self.run(request, None)
+    }
+
+    async fn execute_interactive(
+        &self,
+        request: RobertRequest,
+        input: mpsc::Receiver<SessionInput>,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        self.run(request, Some(input))
+    }
+}
+
+impl MockClaudeExecutor {
+    /// Plays back the current scenario, reading answers from `input`
+    fn run(
+        &self,
+        request: RobertRequest,
+        mut input: Option<mpsc::Receiver<SessionInput>>,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        let (delay_ms, scenario) = self.current_settings();
         let session_id = request.session_id;
+        let usage = Usage {
+            input_tokens: 1200,
+            output_tokens: 350,
+            cost_usd: 0.0,
+            model: Some(
+                request
+                    .options
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| "mock".to_string()),
+            ),
+            ..Default::default()
+        };
 
         let stream = stream! {
             // Simulate processing delay
             tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
 
-            if should_fail {
+            if scenario == MockScenario::Failure {
                 // Emit error event
                 yield Ok(ClaudeEvent::Error {
                     code: "MOCK_ERROR".to_string(),
@@ -90,6 +174,7 @@ impl Executor for MockClaudeExecutor {
                 yield Ok(ClaudeEvent::Complete {
                     session_id,
                     status: "failed".to_string(),
+                    usage: None,
                 });
                 return;
             }
@@ -101,6 +186,54 @@ impl Executor for MockClaudeExecutor {
 
             tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
 
+            if scenario == MockScenario::Clarification {
+                yield Ok(ClaudeEvent::Clarification {
+                    question: "Which account should I sign in with?".to_string(),
+                    options: vec!["Personal".to_string(), "Work".to_string()],
+                    context: Some("Two saved accounts match this site".to_string()),
+                });
+
+                // Wait for the answer; context frames are acknowledged
+                let mut answer = None;
+                if let Some(input) = input.as_mut() {
+                    while let Some(item) = input.recv().await {
+                        match item {
+                            SessionInput::Answer { text } => {
+                                answer = Some(text);
+                                break;
+                            }
+                            SessionInput::Context { context } => {
+                                yield Ok(ClaudeEvent::Progress {
+                                    message: format!(
+                                        "Received context with {} screenshot(s)",
+                                        context.screenshots.len()
+                                    ),
+                                    percent: 25,
+                                });
+                            }
+                        }
+                    }
+                }
+
+                match answer {
+                    Some(answer) => yield Ok(ClaudeEvent::Content {
+                        text: format!("Mock: Continuing with {}", answer),
+                    }),
+                    None => {
+                        yield Ok(ClaudeEvent::Error {
+                            code: "NO_ANSWER".to_string(),
+                            message: "Clarification was not answered".to_string(),
+                        });
+                        yield Ok(ClaudeEvent::Complete {
+                            session_id,
+                            status: "failed".to_string(),
+                            usage: None,
+                        });
+                        return;
+                    }
+                }
+            }
+
             // Emit progress event
             yield Ok(ClaudeEvent::Progress {
                 message: "Processing request".to_string(),
@@ -131,6 +264,7 @@ impl Executor for MockClaudeExecutor {
             yield Ok(ClaudeEvent::Complete {
                 session_id,
                 status: "success".to_string(),
+                usage: Some(usage),
             });
         };
 
@@ -202,10 +336,12 @@ mod tests {
         if let ClaudeEvent::Complete {
             session_id: sid,
             status,
+            usage,
         } = &events[events.len() - 1]
         {
             assert_eq!(*sid, session_id);
             assert_eq!(status, "success");
+            assert_eq!(usage.as_ref().unwrap().model.as_deref(), Some("mock"));
         } else {
             panic!("Last event should be Complete");
         }
@@ -301,14 +437,14 @@ mod tests {
     fn test_mock_executor_new() {
         let executor = MockClaudeExecutor::new();
         assert_eq!(executor.event_delay_ms, 100);
-        assert!(!executor.should_fail);
+        assert_eq!(executor.scenario, MockScenario::Success);
     }
 
     #[test]
     fn test_mock_executor_default() {
         let executor = MockClaudeExecutor::default();
         assert_eq!(executor.event_delay_ms, 100);
-        assert!(!executor.should_fail);
+        assert_eq!(executor.scenario, MockScenario::Success);
     }
 
     #[tokio::test]
@@ -332,4 +468,103 @@ mod tests {
 
         panic!("Should have received Complete event");
     }
+
+    #[tokio::test]
+    async fn test_mock_executor_follows_reloaded_scenario() {
+        use crate::config::Config;
+        use arc_swap::ArcSwap;
+        use std::sync::Arc;
+
+        let mut config = Config::dev_default();
+        config.claude.mock.event_delay_ms = 1;
+        let shared: SharedConfig = Arc::new(ArcSwap::from_pointee(config.clone()));
+        let executor = MockClaudeExecutor::with_config(shared.clone());
+
+        let events: Vec<_> = executor
+            .execute(create_test_request())
+            .await
+            .collect()
+            .await;
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, Ok(ClaudeEvent::Error { .. }))));
+
+        config.claude.mock.scenario = MockScenario::Failure;
+        shared.store(Arc::new(config));
+
+        let events: Vec<_> = executor
+            .execute(create_test_request())
+            .await
+            .collect()
+            .await;
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Ok(ClaudeEvent::Error { .. }))));
+    }
+
+    #[tokio::test]
+    async fn test_mock_executor_clarification_waits_for_answer() {
+        let executor = MockClaudeExecutor::with_clarification(1);
+        let (input, receiver) = mpsc::channel(4);
+
+        let mut stream = executor
+            .execute_interactive(create_test_request(), receiver)
+            .await;
+
+        loop {
+            match stream.next().await {
+                Some(Ok(ClaudeEvent::Clarification { options, .. })) => {
+                    assert_eq!(options, vec!["Personal", "Work"]);
+                    break;
+                }
+                Some(Ok(_)) => continue,
+                other => panic!("Expected Clarification, got {:?}", other),
+            }
+        }
+
+        input
+            .send(SessionInput::Context {
+                context: create_test_request().context,
+            })
+            .await
+            .unwrap();
+        input
+            .send(SessionInput::Answer {
+                text: "Work".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let events: Vec<ClaudeEvent> = stream.map(|e| e.unwrap()).collect().await;
+        assert!(
+            matches!(&events[0], ClaudeEvent::Progress { message, .. } if message.contains("1 screenshot"))
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Content { text } if text.contains("Work"))));
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { status, .. }) if status == "success"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_executor_clarification_without_input_fails() {
+        let executor = MockClaudeExecutor::with_clarification(1);
+
+        let events: Vec<ClaudeEvent> = executor
+            .execute(create_test_request())
+            .await
+            .map(|e| e.unwrap())
+            .collect()
+            .await;
+
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Error { code, .. } if code == "NO_ANSWER")));
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { status, .. }) if status == "failed"
+        ));
+    }
 }
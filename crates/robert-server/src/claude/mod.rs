@@ -3,14 +3,20 @@
 //! Provides interfaces for executing claude-cli processes and streaming results.
 
 pub mod executor;
+pub mod fallback;
 pub mod mock;
+pub mod permissions;
+pub mod sandbox;
 
 pub use executor::ClaudeExecutor;
+pub use fallback::FallbackExecutor;
 pub use mock::MockClaudeExecutor;
+pub use sandbox::{Sandbox, SessionWorkspace};
 
 use crate::error::RobertError;
-use crate::models::{ClaudeEvent, RobertRequest};
+use crate::models::{ClaudeEvent, RobertRequest, SessionInput};
 use futures::Stream;
+use tokio::sync::mpsc;
 
 /// Trait for Claude CLI executors
 ///
@@ -39,4 +45,35 @@ pub trait Executor: Send + Sync {
         &self,
         request: RobertRequest,
     ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static>;
+
+    /// Executes a Robert request that accepts input while running
+    ///
+    /// Used by the WebSocket transport: answers to clarification
+    /// questions and new context frames arrive on `input` until the
+    /// client disconnects. The default implementation ignores the input
+    /// and behaves like [`Executor::execute`].
+    ///
+    /// # Arguments
+    /// * `request` - The validated Robert request to execute
+    /// * `input` - Inputs sent by the client during the session
+    ///
+    /// # Returns
+    /// Async stream of ClaudeEvent instances
+    async fn execute_interactive(
+        &self,
+        request: RobertRequest,
+        input: mpsc::Receiver<SessionInput>,
+    ) -> Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin + 'static> {
+        drop(input);
+        self.execute(request).await
+    }
+
+    /// Whether the executor can resume a claude-cli session
+    ///
+    /// Executors that can honour `options.resume_session` continue
+    /// conversations in claude's own session. For all others, the server
+    /// replays the conversation transcript in the prompt instead.
+    fn supports_resume(&self) -> bool {
+        false
+    }
 }
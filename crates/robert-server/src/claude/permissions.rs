@@ -0,0 +1,361 @@
+//! Tool permissions for claude-cli
+//!
+//! Checks the permissions requested in [`RequestOptions`] against the
+//! server's [`ToolPolicy`] and turns the result into claude-cli flags.
+//! Requests can only narrow what the policy allows; anything beyond it is
+//! rejected rather than silently dropped, so clients learn why a tool is
+//! unavailable.
+
+use crate::config::ToolPolicy;
+use crate::error::RobertError;
+use crate::models::RequestOptions;
+use std::path::{Component, Path};
+
+/// Applies the server policy to a request's permission options
+///
+/// On success `options` holds the effective permissions: the requested
+/// allow-list (or the policy's when none was requested) and the union of
+/// requested and policy deny-lists.
+///
+/// # Arguments
+/// * `policy` - Server tool policy
+/// * `options` - Request options to check and update
+///
+/// # Returns
+/// Ok(()) if the request stays within the policy
+///
+/// # Errors
+/// Returns RobertError::InvalidRequest if the request has a malformed tool
+/// spec, asks for a denied or unlisted tool, a directory outside the
+/// allowed ones, or skip_permissions when the policy forbids it
+pub fn apply_policy(policy: &ToolPolicy, options: &mut RequestOptions) -> Result<(), RobertError> {
+    for tool in options
+        .allowed_tools
+        .iter()
+        .chain(&options.disallowed_tools)
+    {
+        if !is_tool_spec(tool) {
+            return Err(RobertError::InvalidRequest(format!(
+                "Invalid tool '{}': expected Name or Name(pattern)",
+                tool
+            )));
+        }
+    }
+
+    for tool in &options.allowed_tools {
+        if policy
+            .disallowed_tools
+            .iter()
+            .any(|denied| covers(denied, tool))
+        {
+            return Err(RobertError::InvalidRequest(format!(
+                "Tool '{}' is disallowed by server policy",
+                tool
+            )));
+        }
+
+        if !policy.allowed_tools.is_empty()
+            && !policy
+                .allowed_tools
+                .iter()
+                .any(|allowed| covers(allowed, tool))
+        {
+            return Err(RobertError::InvalidRequest(format!(
+                "Tool '{}' is not in the server's allowed tools",
+                tool
+            )));
+        }
+    }
+
+    for dir in &options.allowed_dirs {
+        if !dir_permitted(policy, dir) {
+            return Err(RobertError::InvalidRequest(format!(
+                "Directory '{}' is not permitted by server policy",
+                dir.display()
+            )));
+        }
+    }
+
+    if options.skip_permissions && !policy.allow_skip_permissions {
+        return Err(RobertError::InvalidRequest(
+            "skip_permissions is not permitted by server policy".to_string(),
+        ));
+    }
+
+    if options.allowed_tools.is_empty() {
+        options.allowed_tools = policy.allowed_tools.clone();
+    }
+
+    for tool in &policy.disallowed_tools {
+        if !options.disallowed_tools.contains(tool) {
+            options.disallowed_tools.push(tool.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds claude-cli flags for the permissions in `options`
+///
+/// Tool specs are passed as `--flag=value`, one flag per tool, so a value
+/// can never be taken for a flag of its own.
+///
+/// # Arguments
+/// * `options` - Request options, normally already passed through [`apply_policy`]
+///
+/// # Returns
+/// Command line arguments to append to the claude-cli invocation
+pub fn permission_args(options: &RequestOptions) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if options.skip_permissions {
+        args.push("--dangerously-skip-permissions".to_string());
+    }
+
+    if !options.allowed_dirs.is_empty() {
+        args.push("--add-dir".to_string());
+        args.extend(
+            options
+                .allowed_dirs
+                .iter()
+                .map(|dir| dir.to_string_lossy().to_string()),
+        );
+    }
+
+    args.extend(
+        options
+            .allowed_tools
+            .iter()
+            .map(|tool| format!("--allowed-tools={}", tool)),
+    );
+    args.extend(
+        options
+            .disallowed_tools
+            .iter()
+            .map(|tool| format!("--disallowed-tools={}", tool)),
+    );
+
+    args
+}
+
+/// Returns true if `tool` is a tool spec: `Name` or `Name(pattern)`
+///
+/// Names start with a letter and hold letters, digits, `_` and `-` (MCP
+/// tools are named like `mcp__server__tool`). Patterns are non-empty and
+/// free of control characters.
+fn is_tool_spec(tool: &str) -> bool {
+    let (name, pattern) = match tool.split_once('(') {
+        Some((name, rest)) => match rest.strip_suffix(')') {
+            Some(pattern) => (name, Some(pattern)),
+            None => return false,
+        },
+        None => (tool, None),
+    };
+
+    let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let valid_pattern =
+        pattern.is_none_or(|pattern| !pattern.is_empty() && !pattern.chars().any(char::is_control));
+
+    valid_name && valid_pattern
+}
+
+/// Returns true if the policy entry `rule` covers the tool spec `tool`
+///
+/// A bare tool name (`Bash`) covers every pattern of that tool
+/// (`Bash(git:*)`); a pattern only covers itself.
+fn covers(rule: &str, tool: &str) -> bool {
+    rule == tool || (!rule.contains('(') && tool.split('(').next() == Some(rule))
+}
+
+/// Returns true if `dir` lies within one of the policy's allowed directories
+fn dir_permitted(policy: &ToolPolicy, dir: &Path) -> bool {
+    if !dir.is_absolute() || dir.components().any(|c| c == Component::ParentDir) {
+        return false;
+    }
+
+    policy
+        .allowed_dirs
+        .iter()
+        .any(|allowed| dir.starts_with(allowed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn policy() -> ToolPolicy {
+        ToolPolicy {
+            allowed_tools: vec!["Read".to_string(), "Bash".to_string()],
+            disallowed_tools: vec!["Bash(rm:*)".to_string()],
+            allowed_dirs: vec!["/srv/data".to_string()],
+            allow_skip_permissions: false,
+        }
+    }
+
+    #[test]
+    fn test_apply_policy_defaults_to_policy_lists() {
+        let mut options = RequestOptions::default();
+        apply_policy(&policy(), &mut options).unwrap();
+
+        assert_eq!(options.allowed_tools, vec!["Read", "Bash"]);
+        assert_eq!(options.disallowed_tools, vec!["Bash(rm:*)"]);
+    }
+
+    #[test]
+    fn test_apply_policy_narrows_to_request() {
+        let mut options = RequestOptions {
+            allowed_tools: vec!["Bash(git:*)".to_string()],
+            disallowed_tools: vec!["Read".to_string()],
+            ..Default::default()
+        };
+        apply_policy(&policy(), &mut options).unwrap();
+
+        assert_eq!(options.allowed_tools, vec!["Bash(git:*)"]);
+        assert_eq!(options.disallowed_tools, vec!["Read", "Bash(rm:*)"]);
+    }
+
+    #[test]
+    fn test_apply_policy_rejects_unlisted_tool() {
+        let mut options = RequestOptions {
+            allowed_tools: vec!["Write".to_string()],
+            ..Default::default()
+        };
+        let err = apply_policy(&policy(), &mut options).unwrap_err();
+        assert!(matches!(err, RobertError::InvalidRequest(_)));
+        assert!(err.to_string().contains("Write"));
+    }
+
+    #[test]
+    fn test_apply_policy_rejects_denied_tool() {
+        let mut options = RequestOptions {
+            allowed_tools: vec!["Bash(rm:*)".to_string()],
+            ..Default::default()
+        };
+        assert!(apply_policy(&policy(), &mut options).is_err());
+    }
+
+    #[test]
+    fn test_apply_policy_empty_allow_list_permits_any_undenied_tool() {
+        let policy = ToolPolicy {
+            disallowed_tools: vec!["WebFetch".to_string()],
+            ..Default::default()
+        };
+
+        let mut options = RequestOptions {
+            allowed_tools: vec!["Write".to_string()],
+            ..Default::default()
+        };
+        assert!(apply_policy(&policy, &mut options).is_ok());
+
+        let mut options = RequestOptions {
+            allowed_tools: vec!["WebFetch".to_string()],
+            ..Default::default()
+        };
+        assert!(apply_policy(&policy, &mut options).is_err());
+    }
+
+    #[test]
+    fn test_apply_policy_dirs() {
+        let mut options = RequestOptions {
+            allowed_dirs: vec![PathBuf::from("/srv/data/project")],
+            ..Default::default()
+        };
+        assert!(apply_policy(&policy(), &mut options).is_ok());
+
+        for dir in ["/etc", "/srv/data/../../etc", "relative", "/srv/database"] {
+            let mut options = RequestOptions {
+                allowed_dirs: vec![PathBuf::from(dir)],
+                ..Default::default()
+            };
+            assert!(
+                apply_policy(&policy(), &mut options).is_err(),
+                "{} should be rejected",
+                dir
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_policy_skip_permissions() {
+        let mut options = RequestOptions {
+            skip_permissions: true,
+            ..Default::default()
+        };
+        assert!(apply_policy(&policy(), &mut options).is_err());
+
+        let permissive = ToolPolicy {
+            allow_skip_permissions: true,
+            ..policy()
+        };
+        assert!(apply_policy(&permissive, &mut options).is_ok());
+    }
+
+    #[test]
+    fn test_permission_args() {
+        let options = RequestOptions {
+            allowed_tools: vec!["Read".to_string(), "Bash(git:*)".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string()],
+            allowed_dirs: vec![PathBuf::from("/srv/data")],
+            skip_permissions: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            permission_args(&options),
+            vec![
+                "--dangerously-skip-permissions",
+                "--add-dir",
+                "/srv/data",
+                "--allowed-tools=Read",
+                "--allowed-tools=Bash(git:*)",
+                "--disallowed-tools=WebFetch",
+            ]
+        );
+        assert!(permission_args(&RequestOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_apply_policy_rejects_flag_injection() {
+        // The default policy allows any tool that is not denied
+        for tool in [
+            "--dangerously-skip-permissions",
+            "-p",
+            "Bash --dangerously-skip-permissions",
+            "Bash(",
+            "Bash()",
+            "(git:*)",
+            "Bash(git:*)x",
+            "Bash(git\n:*)",
+            "",
+        ] {
+            let mut options = RequestOptions {
+                allowed_tools: vec![tool.to_string()],
+                ..Default::default()
+            };
+            assert!(
+                apply_policy(&ToolPolicy::default(), &mut options).is_err(),
+                "{:?} should be rejected",
+                tool
+            );
+
+            let mut options = RequestOptions {
+                disallowed_tools: vec![tool.to_string()],
+                ..Default::default()
+            };
+            assert!(apply_policy(&ToolPolicy::default(), &mut options).is_err());
+        }
+
+        let mut options = RequestOptions {
+            allowed_tools: vec![
+                "mcp__browser__navigate".to_string(),
+                "Bash(git diff:*)".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert!(apply_policy(&ToolPolicy::default(), &mut options).is_ok());
+    }
+}
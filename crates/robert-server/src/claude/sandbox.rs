@@ -0,0 +1,262 @@
+//! Process sandboxing for claude-cli
+//!
+//! Gives every session its own temporary working directory, removes
+//! everything but an allow-list of variables from the environment and
+//! applies rlimits to the child process. Namespaces, seccomp and similar
+//! isolation are delegated to an optional wrapper command (e.g. `bwrap`),
+//! which keeps this module free of platform-specific setup.
+
+use crate::config::SandboxConfig;
+use crate::error::RobertError;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// Temporary working directory for a single session
+///
+/// The directory and everything in it are removed when this value is
+/// dropped, i.e. when the session's output stream ends or is abandoned.
+#[derive(Debug)]
+pub struct SessionWorkspace {
+    dir: TempDir,
+}
+
+impl SessionWorkspace {
+    /// Returns the workspace directory
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Builds sandboxed commands from a [`SandboxConfig`]
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    config: SandboxConfig,
+}
+
+impl Sandbox {
+    /// Creates a sandbox with the given settings
+    ///
+    /// # Arguments
+    /// * `config` - Sandbox configuration
+    ///
+    /// # Returns
+    /// New Sandbox instance
+    pub fn new(config: SandboxConfig) -> Self {
+        Self { config }
+    }
+
+    /// Creates a fresh workspace for a session
+    ///
+    /// # Arguments
+    /// * `session_id` - Session the workspace belongs to (used in the directory name)
+    ///
+    /// # Returns
+    /// Workspace that is deleted on drop
+    ///
+    /// # Errors
+    /// Returns RobertError::ExecutionError if the directory cannot be created
+    pub fn create_workspace(&self, session_id: Uuid) -> Result<SessionWorkspace, RobertError> {
+        let root = self
+            .config
+            .workspace_root
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+
+        std::fs::create_dir_all(&root).map_err(|e| {
+            RobertError::ExecutionError(format!(
+                "Failed to create workspace root {}: {}",
+                root.display(),
+                e
+            ))
+        })?;
+
+        let dir = tempfile::Builder::new()
+            .prefix(&format!("robert-session-{}-", session_id))
+            .tempdir_in(&root)
+            .map_err(|e| {
+                RobertError::ExecutionError(format!("Failed to create session workspace: {}", e))
+            })?;
+
+        Ok(SessionWorkspace { dir })
+    }
+
+    /// Builds a command that runs `program` inside the sandbox
+    ///
+    /// The command runs in `workspace`, sees only allow-listed environment
+    /// variables (plus `TMPDIR` pointing into the workspace), is subject to
+    /// the configured rlimits and is prefixed with the wrapper command if
+    /// one is configured.
+    ///
+    /// # Arguments
+    /// * `program` - Program to execute (e.g. the claude-cli binary)
+    /// * `workspace` - Session workspace to use as working directory
+    ///
+    /// # Returns
+    /// Command ready for arguments and stdio configuration
+    pub fn command(&self, program: &str, workspace: &SessionWorkspace) -> Command {
+        let mut command = match self.config.wrapper.split_first() {
+            Some((wrapper, wrapper_args)) => {
+                let mut command = Command::new(wrapper);
+                command.args(wrapper_args).arg(program);
+                command
+            }
+            None => Command::new(program),
+        };
+
+        command.current_dir(workspace.path());
+
+        command.env_clear();
+        for name in &self.config.env_allowlist {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        command.env("TMPDIR", workspace.path());
+
+        #[cfg(unix)]
+        self.apply_resource_limits(&mut command);
+
+        command
+    }
+
+    /// Installs rlimits on the child process
+    #[cfg(unix)]
+    fn apply_resource_limits(&self, command: &mut Command) {
+        let cpu = self.config.max_cpu_seconds;
+        let memory = self
+            .config
+            .max_memory_mb
+            .map(|mb| mb.saturating_mul(1024 * 1024));
+        let files = self.config.max_open_files;
+
+        if cpu.is_none() && memory.is_none() && files.is_none() {
+            return;
+        }
+
+        macro_rules! set_limit {
+            ($resource:expr, $value:expr) => {
+                if let Some(value) = $value {
+                    let limit = libc::rlimit {
+                        rlim_cur: value as libc::rlim_t,
+                        rlim_max: value as libc::rlim_t,
+                    };
+                    if libc::setrlimit($resource, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+            };
+        }
+
+        // SAFETY: the closure runs in the forked child before exec and only
+        // calls setrlimit, which is async-signal-safe.
+        unsafe {
+            command.pre_exec(move || {
+                set_limit!(libc::RLIMIT_CPU, cpu);
+                set_limit!(libc::RLIMIT_AS, memory);
+                set_limit!(libc::RLIMIT_NOFILE, files);
+                Ok(())
+            });
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn test_config(root: &Path) -> SandboxConfig {
+        SandboxConfig {
+            workspace_root: Some(root.to_string_lossy().to_string()),
+            env_allowlist: vec!["PATH".to_string()],
+            ..SandboxConfig::default()
+        }
+    }
+
+    async fn run(sandbox: &Sandbox, workspace: &SessionWorkspace, script: &str) -> String {
+        let output = sandbox
+            .command("sh", workspace)
+            .arg("-c")
+            .arg(script)
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn test_workspace_removed_on_drop() {
+        let root = TempDir::new().unwrap();
+        let sandbox = Sandbox::new(test_config(root.path()));
+
+        let session_id = Uuid::new_v4();
+        let workspace = sandbox.create_workspace(session_id).unwrap();
+        let path = workspace.path().to_path_buf();
+
+        assert!(path.is_dir());
+        assert!(path.starts_with(root.path()));
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .contains(&session_id.to_string()));
+
+        std::fs::write(path.join("scratch.txt"), b"data").unwrap();
+        drop(workspace);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_command_runs_in_workspace() {
+        let root = TempDir::new().unwrap();
+        let sandbox = Sandbox::new(test_config(root.path()));
+        let workspace = sandbox.create_workspace(Uuid::new_v4()).unwrap();
+
+        let cwd = run(&sandbox, &workspace, "pwd -P").await;
+        assert_eq!(PathBuf::from(cwd), workspace.path().canonicalize().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_command_scrubs_environment() {
+        let root = TempDir::new().unwrap();
+        let sandbox = Sandbox::new(test_config(root.path()));
+        let workspace = sandbox.create_workspace(Uuid::new_v4()).unwrap();
+
+        let env = run(&sandbox, &workspace, "env").await;
+        let mut names: Vec<&str> = env
+            .lines()
+            .filter_map(|line| line.split('=').next())
+            .filter(|name| !["PWD", "SHLVL", "_"].contains(name))
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["PATH", "TMPDIR"]);
+    }
+
+    #[tokio::test]
+    async fn test_command_applies_open_file_limit() {
+        let root = TempDir::new().unwrap();
+        let sandbox = Sandbox::new(SandboxConfig {
+            max_open_files: Some(64),
+            ..test_config(root.path())
+        });
+        let workspace = sandbox.create_workspace(Uuid::new_v4()).unwrap();
+
+        assert_eq!(run(&sandbox, &workspace, "ulimit -n").await, "64");
+    }
+
+    #[tokio::test]
+    async fn test_command_uses_wrapper() {
+        let root = TempDir::new().unwrap();
+        let sandbox = Sandbox::new(SandboxConfig {
+            wrapper: vec!["env".to_string(), "WRAPPED=yes".to_string()],
+            ..test_config(root.path())
+        });
+        let workspace = sandbox.create_workspace(Uuid::new_v4()).unwrap();
+
+        assert_eq!(run(&sandbox, &workspace, "echo $WRAPPED").await, "yes");
+    }
+}
@@ -0,0 +1,1203 @@
+//! Typed client for the robert-server API
+//!
+//! Wraps the HTTP API in [`RobertClient`] so callers work with the same
+//! request and event types the server uses instead of hand-built JSON.
+//! Execution output is decoded from Server-Sent Events into a stream of
+//! [`ClaudeEvent`]s.
+//!
+//! Requests that fail before the server produced a response (connection
+//! errors, timeouts) or that are rejected as temporarily unavailable
+//! (429 rate limits, 502/503/504) are retried with exponential backoff.
+//! POSTs without an `Idempotency-Key` (inference, batches, jobs,
+//! conversations) are not retried after a timeout or a 502/504, since the
+//! server may already be running them. Budget exhaustion and other client
+//! errors are returned immediately.
+//!
+//! A server listening on a Unix domain socket (`server.unix_socket`) is
+//! reached with [`RobertClient::unix_socket`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use robert_server::client::RobertClient;
+//! # async fn run(request: robert_server::RobertRequest) -> Result<(), robert_server::client::ClientError> {
+//! let client = RobertClient::new("http://localhost:8443").with_token("dev-token-12345");
+//!
+//! let mut events = client.execute(&request).await?;
+//! while let Some(event) = events.next().await {
+//!     println!("{:?}", event?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::inference::{InferenceRequest, InferenceResponse};
+use crate::error::ErrorResponse;
+use crate::models::{
+    BatchItemResult, BatchRequest, BatchStatus, ClaudeEvent, Conversation, HealthResponse, Job,
+    JobDefinition, RobertRequest, SessionStatus, UsageReport, WebhookDelivery,
+};
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Stream of events from an execution
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<ClaudeEvent, ClientError>> + Send>>;
+
+/// Errors returned by [`RobertClient`]
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The request could not be sent or the response could not be read
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with an error
+    #[error("Server returned {status} ({code}): {message}")]
+    Api {
+        /// HTTP status code
+        status: u16,
+        /// Error code from the server (e.g. `BUDGET_EXCEEDED`)
+        code: String,
+        /// Human-readable error message
+        message: String,
+    },
+
+    /// The server's response could not be understood
+    #[error("Invalid response from server: {0}")]
+    InvalidResponse(String),
+}
+
+impl ClientError {
+    /// Returns the server's error code, if the server reported one
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the request may succeed when repeated
+    ///
+    /// A request that is not idempotent is only repeated if the server
+    /// cannot have acted on it: it never connected, or the server refused
+    /// it as rate limited or busy. After a timeout or a gateway error it
+    /// may already be running.
+    fn is_retryable(&self, idempotent: bool) -> bool {
+        match self {
+            ClientError::Http(e) => e.is_connect() || (idempotent && e.is_timeout()),
+            ClientError::Api { status, code, .. } => match status {
+                429 => code != "BUDGET_EXCEEDED",
+                503 => true,
+                502 | 504 => idempotent,
+                _ => false,
+            },
+            ClientError::InvalidResponse(_) => false,
+        }
+    }
+}
+
+/// Retry behaviour for failed requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubled for every further retry
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the delay before retry number `attempt` (starting at 0)
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Client for a robert-server instance
+#[derive(Debug, Clone)]
+pub struct RobertClient {
+    /// Server base URL without trailing slash (e.g. `http://localhost:8443`)
+    base_url: String,
+
+    /// Bearer token sent with every request
+    token: Option<String>,
+
+    /// Retry behaviour
+    retry: RetryPolicy,
+
+    /// Underlying HTTP client
+    http: reqwest::Client,
+}
+
+impl RobertClient {
+    /// Creates a client for the server at `base_url`
+    ///
+    /// # Arguments
+    /// * `base_url` - Server URL, e.g. `http://localhost:8443`
+    ///
+    /// # Returns
+    /// New RobertClient without authentication and with the default retry policy
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            retry: RetryPolicy::default(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates a client for a server listening on a Unix domain socket
+    ///
+    /// # Arguments
+    /// * `path` - Socket path, the server's `server.unix_socket`
+    ///
+    /// # Returns
+    /// New RobertClient without authentication and with the default retry policy
+    ///
+    /// # Errors
+    /// Returns ClientError::Http if the HTTP client cannot be built
+    #[cfg(unix)]
+    pub fn unix_socket(path: impl AsRef<std::path::Path>) -> Result<Self, ClientError> {
+        let http = reqwest::Client::builder()
+            .unix_socket(path.as_ref().to_path_buf())
+            .build()?;
+
+        // The host is only used for the Host header; every connection goes
+        // to the socket
+        Ok(Self::new("http://localhost").with_http_client(http))
+    }
+
+    /// Sets the bearer token sent with every request
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sets the retry policy
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Uses an existing HTTP client (e.g. one with timeouts or TLS settings)
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Returns the server base URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// GET /api/v1/health
+    ///
+    /// # Returns
+    /// Server health information
+    ///
+    /// # Errors
+    /// Returns ClientError if the server is unreachable or unhealthy
+    pub async fn health(&self) -> Result<HealthResponse, ClientError> {
+        let response = self
+            .send(|| self.http.get(self.url("/api/v1/health")))
+            .await?;
+        decode(response).await
+    }
+
+    /// POST /api/v1/execute
+    ///
+    /// Starts an execution and returns its events as they arrive. The
+    /// stream ends after the `complete` event. Only establishing the
+    /// stream is retried; a connection lost mid-stream ends it with an
+    /// error.
+    ///
+    /// The request's session id is sent as its `Idempotency-Key`, so a
+    /// retry (or a later call with the same request) attaches to the run
+    /// that is already going instead of starting another one.
+    ///
+    /// # Arguments
+    /// * `request` - Request to execute
+    ///
+    /// # Returns
+    /// Stream of events from the execution
+    ///
+    /// # Errors
+    /// Returns ClientError if the server rejects the request (invalid
+    /// request, budget exhausted, too many sessions, ...)
+    pub async fn execute(&self, request: &RobertRequest) -> Result<EventStream, ClientError> {
+        let response = self
+            .send(|| {
+                self.http
+                    .post(self.url("/api/v1/execute"))
+                    .header("idempotency-key", request.session_id.to_string())
+                    .json(request)
+            })
+            .await?;
+
+        let mut body = response.bytes_stream();
+        let events = stream! {
+            let mut decoder = SseDecoder::default();
+
+            while let Some(chunk) = body.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(ClientError::Http(e));
+                        return;
+                    }
+                };
+
+                for data in decoder.push(&chunk) {
+                    match serde_json::from_str::<ClaudeEvent>(&data) {
+                        Ok(event) => {
+                            let complete = matches!(event, ClaudeEvent::Complete { .. });
+                            yield Ok(event);
+                            if complete {
+                                return;
+                            }
+                        }
+                        Err(e) => yield Err(ClientError::InvalidResponse(format!(
+                            "Malformed event {:?}: {}", data, e
+                        ))),
+                    }
+                }
+            }
+
+            yield Err(ClientError::InvalidResponse(
+                "Event stream ended before completion".to_string(),
+            ));
+        };
+
+        Ok(Box::pin(events))
+    }
+
+    /// GET /api/v1/sessions/:id
+    ///
+    /// # Arguments
+    /// * `session_id` - Session to query
+    ///
+    /// # Returns
+    /// Current session status
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `SESSION_NOT_FOUND` for unknown sessions
+    pub async fn get_session(&self, session_id: Uuid) -> Result<SessionStatus, ClientError> {
+        let path = format!("/api/v1/sessions/{}", session_id);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// DELETE /api/v1/sessions/:id
+    ///
+    /// # Arguments
+    /// * `session_id` - Running session to cancel
+    ///
+    /// # Returns
+    /// Session status after cancellation
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the session is unknown or not running
+    pub async fn cancel_session(&self, session_id: Uuid) -> Result<SessionStatus, ClientError> {
+        let path = format!("/api/v1/sessions/{}", session_id);
+        let response = self.send(|| self.http.delete(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/usage
+    ///
+    /// # Arguments
+    /// * `days` - Number of days to break down
+    ///
+    /// # Returns
+    /// Usage report for the client's token
+    ///
+    /// # Errors
+    /// Returns ClientError if the request fails
+    pub async fn usage(&self, days: u32) -> Result<UsageReport, ClientError> {
+        let path = format!("/api/v1/usage?days={}", days);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/webhooks/deliveries
+    ///
+    /// # Returns
+    /// Webhook deliveries for the client's token, most recent first
+    ///
+    /// # Errors
+    /// Returns ClientError if the request fails
+    pub async fn webhook_deliveries(&self) -> Result<Vec<WebhookDelivery>, ClientError> {
+        let response = self
+            .send(|| self.http.get(self.url("/api/v1/webhooks/deliveries")))
+            .await?;
+        decode(response).await
+    }
+
+    /// POST /api/v1/conversations
+    ///
+    /// Set the returned id as `options.conversation_id` on execute
+    /// requests to continue the conversation.
+    ///
+    /// # Returns
+    /// The new, empty conversation
+    ///
+    /// # Errors
+    /// Returns ClientError if the request fails
+    pub async fn create_conversation(&self) -> Result<Conversation, ClientError> {
+        let response = self
+            .send(|| self.http.post(self.url("/api/v1/conversations")))
+            .await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/conversations/:id
+    ///
+    /// # Arguments
+    /// * `conversation_id` - Conversation to fetch
+    ///
+    /// # Returns
+    /// The conversation with its transcript
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the conversation is unknown
+    pub async fn get_conversation(
+        &self,
+        conversation_id: Uuid,
+    ) -> Result<Conversation, ClientError> {
+        let path = format!("/api/v1/conversations/{}", conversation_id);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// DELETE /api/v1/conversations/:id
+    ///
+    /// # Arguments
+    /// * `conversation_id` - Conversation to delete
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the conversation is unknown
+    pub async fn delete_conversation(&self, conversation_id: Uuid) -> Result<(), ClientError> {
+        let path = format!("/api/v1/conversations/{}", conversation_id);
+        self.send(|| self.http.delete(self.url(&path))).await?;
+        Ok(())
+    }
+
+    /// POST /api/v1/batches
+    ///
+    /// # Arguments
+    /// * `batch` - Requests or template to run
+    ///
+    /// # Returns
+    /// Status of the new batch, with every item queued
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `INVALID_REQUEST` if the batch or
+    /// one of its items is invalid
+    pub async fn create_batch(&self, batch: &BatchRequest) -> Result<BatchStatus, ClientError> {
+        let response = self
+            .send(|| self.http.post(self.url("/api/v1/batches")).json(batch))
+            .await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/batches/:id
+    ///
+    /// # Arguments
+    /// * `batch_id` - Batch to query
+    ///
+    /// # Returns
+    /// Batch progress and the session status of every started item
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `BATCH_NOT_FOUND` for unknown batches
+    pub async fn get_batch(&self, batch_id: Uuid) -> Result<BatchStatus, ClientError> {
+        let path = format!("/api/v1/batches/{}", batch_id);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/batches/:id/results
+    ///
+    /// # Arguments
+    /// * `batch_id` - Batch to fetch results for
+    ///
+    /// # Returns
+    /// Result of every item, in submission order
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `BATCH_NOT_FOUND` for unknown
+    /// batches, and ClientError::InvalidResponse for malformed lines
+    pub async fn batch_results(&self, batch_id: Uuid) -> Result<Vec<BatchItemResult>, ClientError> {
+        let path = format!("/api/v1/batches/{}/results", batch_id);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        let body = response.text().await?;
+
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// DELETE /api/v1/batches/:id
+    ///
+    /// # Arguments
+    /// * `batch_id` - Running batch to cancel
+    ///
+    /// # Returns
+    /// Batch status after cancellation
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the batch is unknown or already finished
+    pub async fn cancel_batch(&self, batch_id: Uuid) -> Result<BatchStatus, ClientError> {
+        let path = format!("/api/v1/batches/{}", batch_id);
+        let response = self.send(|| self.http.delete(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// POST /api/v1/jobs
+    ///
+    /// # Arguments
+    /// * `definition` - Schedule and request of the job
+    ///
+    /// # Returns
+    /// The new job
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `INVALID_REQUEST` for invalid
+    /// schedules and requests
+    pub async fn create_job(&self, definition: &JobDefinition) -> Result<Job, ClientError> {
+        let response = self
+            .send(|| self.http.post(self.url("/api/v1/jobs")).json(definition))
+            .await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/jobs
+    ///
+    /// # Returns
+    /// Jobs of the client's token, oldest first
+    ///
+    /// # Errors
+    /// Returns ClientError if the request fails
+    pub async fn list_jobs(&self) -> Result<Vec<Job>, ClientError> {
+        let response = self
+            .send(|| self.http.get(self.url("/api/v1/jobs")))
+            .await?;
+        decode(response).await
+    }
+
+    /// GET /api/v1/jobs/:id
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to fetch
+    ///
+    /// # Returns
+    /// The job with its run history
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `JOB_NOT_FOUND` for unknown jobs
+    pub async fn get_job(&self, job_id: Uuid) -> Result<Job, ClientError> {
+        let path = format!("/api/v1/jobs/{}", job_id);
+        let response = self.send(|| self.http.get(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// PUT /api/v1/jobs/:id
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to update
+    /// * `definition` - New schedule and request
+    ///
+    /// # Returns
+    /// The updated job
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the job is unknown or the definition
+    /// is invalid
+    pub async fn update_job(
+        &self,
+        job_id: Uuid,
+        definition: &JobDefinition,
+    ) -> Result<Job, ClientError> {
+        let path = format!("/api/v1/jobs/{}", job_id);
+        let response = self
+            .send(|| self.http.put(self.url(&path)).json(definition))
+            .await?;
+        decode(response).await
+    }
+
+    /// POST /api/v1/jobs/:id/pause
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to pause
+    ///
+    /// # Returns
+    /// The paused job
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `JOB_NOT_FOUND` for unknown jobs
+    pub async fn pause_job(&self, job_id: Uuid) -> Result<Job, ClientError> {
+        let path = format!("/api/v1/jobs/{}/pause", job_id);
+        let response = self.send(|| self.http.post(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// POST /api/v1/jobs/:id/resume
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to resume
+    ///
+    /// # Returns
+    /// The resumed job
+    ///
+    /// # Errors
+    /// Returns ClientError::Api with code `JOB_NOT_FOUND` for unknown jobs
+    pub async fn resume_job(&self, job_id: Uuid) -> Result<Job, ClientError> {
+        let path = format!("/api/v1/jobs/{}/resume", job_id);
+        let response = self.send(|| self.http.post(self.url(&path))).await?;
+        decode(response).await
+    }
+
+    /// DELETE /api/v1/jobs/:id
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to delete
+    ///
+    /// # Errors
+    /// Returns ClientError::Api if the job is unknown
+    pub async fn delete_job(&self, job_id: Uuid) -> Result<(), ClientError> {
+        let path = format!("/api/v1/jobs/{}", job_id);
+        self.send(|| self.http.delete(self.url(&path))).await?;
+        Ok(())
+    }
+
+    /// POST /inference
+    ///
+    /// Runs a prompt without browser context and waits for the result.
+    ///
+    /// # Arguments
+    /// * `prompt` - Prompt to run
+    ///
+    /// # Returns
+    /// Collected result; check `status` for success
+    ///
+    /// # Errors
+    /// Returns ClientError if the request fails
+    pub async fn inference(&self, prompt: &str) -> Result<InferenceResponse, ClientError> {
+        let request = InferenceRequest {
+            prompt: prompt.to_string(),
+        };
+        let response = self
+            .send(|| self.http.post(self.url("/inference")).json(&request))
+            .await?;
+        decode(response).await
+    }
+
+    /// Builds the full URL for an API path
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Sends a request, retrying according to the retry policy
+    ///
+    /// `build` is called for every attempt. Requests with an idempotent
+    /// method or an `Idempotency-Key` header are retried on timeouts and
+    /// gateway errors too; others only when the server cannot have acted
+    /// on them. Non-success responses are turned into ClientError::Api.
+    async fn send<F>(&self, build: F) -> Result<reqwest::Response, ClientError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let mut builder = build();
+            if let Some(token) = &self.token {
+                builder = builder.bearer_auth(token);
+            }
+            let request = builder.build()?;
+            let idempotent = request.method().is_idempotent()
+                || request.headers().contains_key("idempotency-key");
+
+            let error = match self.http.execute(request).await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => api_error(response).await,
+                Err(e) => ClientError::Http(e),
+            };
+
+            if attempt >= self.retry.max_retries || !error.is_retryable(idempotent) {
+                return Err(error);
+            }
+
+            let delay = self.retry.backoff(attempt);
+            warn!(
+                "robert-server request failed ({}), retrying in {:?}",
+                error, delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Converts a non-success response into ClientError::Api
+async fn api_error(response: reqwest::Response) -> ClientError {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+
+    match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(error) => ClientError::Api {
+            status,
+            code: error.code,
+            message: error.message,
+        },
+        Err(_) => ClientError::Api {
+            status,
+            code: "HTTP_ERROR".to_string(),
+            message: body,
+        },
+    }
+}
+
+/// Decodes a successful JSON response
+async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ClientError> {
+    let body = response.bytes().await?;
+
+    serde_json::from_slice::<T>(&body).map_err(|e| ClientError::InvalidResponse(e.to_string()))
+}
+
+/// Incremental decoder for Server-Sent Events
+///
+/// Collects the `data:` lines of each event; other fields and comments
+/// (such as keep-alives) are ignored.
+#[derive(Debug, Default)]
+struct SseDecoder {
+    /// Bytes of the current, incomplete line
+    line: Vec<u8>,
+
+    /// Data lines of the current event
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Feeds a chunk of the body and returns the data of completed events
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+
+            let line = String::from_utf8_lossy(&self.line).into_owned();
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            self.line.clear();
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthState;
+    use crate::claude::{Executor, MockClaudeExecutor};
+    use crate::config::Config;
+    use crate::models::{
+        DomState, RequestContext, RequestOptions, Screenshot, ScreenshotMetadata, SessionState,
+        Viewport,
+    };
+    use crate::session::SessionManager;
+    use arc_swap::ArcSwap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use warp::Filter;
+
+    const TOKEN: &str = "client-test-token";
+
+    fn create_test_request() -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: "dGVzdA==".to_string(),
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 100,
+                            height: 100,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "intent".to_string(),
+            },
+            prompt: "prompt".to_string(),
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Starts the full API on an ephemeral port with the mock executor
+    fn spawn_server(config: Config) -> (String, Arc<SessionManager>) {
+        let auth_state = Arc::new(AuthState::new(vec![TOKEN.to_string()], true, 1000));
+        let session_manager = Arc::new(SessionManager::new(100));
+        let executor: Arc<dyn Executor> = Arc::new(MockClaudeExecutor::with_delay(1));
+        let health_state = Arc::new(crate::api::health::HealthState::new(
+            config.claude.binary_path.clone(),
+        ));
+
+        let config = Arc::new(ArcSwap::from_pointee(config));
+        let conversations = Arc::new(crate::conversation::ConversationManager::new(100));
+        let scheduler = crate::scheduler::Scheduler::new(
+            executor.clone(),
+            session_manager.clone(),
+            conversations.clone(),
+            config.clone(),
+            Arc::new(crate::scheduler::SystemClock),
+        )
+        .unwrap();
+
+        let routes = crate::server::build_routes(
+            config,
+            executor,
+            session_manager.clone(),
+            conversations,
+            Arc::new(crate::batch::BatchManager::new(10)),
+            Arc::new(scheduler),
+            auth_state,
+            health_state,
+        );
+        let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        (format!("http://{}", addr), session_manager)
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_sse_decoder_split_chunks() {
+        let mut decoder = SseDecoder::default();
+
+        assert!(decoder.push(b": keep-alive\n\nda").is_empty());
+        assert!(decoder.push(b"ta: {\"a\":1}\r\n").is_empty());
+        assert_eq!(decoder.push(b"\r\n"), vec!["{\"a\":1}".to_string()]);
+
+        let events = decoder.push(b"event: x\ndata:one\ndata: two\n\ndata: three\n\n");
+        assert_eq!(events, vec!["one\ntwo".to_string(), "three".to_string()]);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_error_retryable() {
+        let api = |status, code: &str| ClientError::Api {
+            status,
+            code: code.to_string(),
+            message: String::new(),
+        };
+        assert!(api(503, "HTTP_ERROR").is_retryable(true));
+        assert!(api(429, "RATE_LIMITED").is_retryable(true));
+        assert!(!api(429, "BUDGET_EXCEEDED").is_retryable(true));
+        assert!(!api(400, "INVALID_REQUEST").is_retryable(true));
+
+        // The server may already be running a request it did not answer
+        assert!(api(504, "HTTP_ERROR").is_retryable(true));
+        assert!(!api(504, "HTTP_ERROR").is_retryable(false));
+        assert!(!api(502, "HTTP_ERROR").is_retryable(false));
+        assert!(api(503, "SERVER_BUSY").is_retryable(false));
+        assert!(api(429, "RATE_LIMITED").is_retryable(false));
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(format!("{}/", url));
+
+        let health = client.health().await.unwrap();
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_streams_events() {
+        let (url, session_manager) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_token(TOKEN);
+
+        let request = create_test_request();
+        let events: Vec<ClaudeEvent> = client
+            .execute(&request)
+            .await
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect()
+            .await;
+
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, ClaudeEvent::Content { .. })));
+        assert!(matches!(
+            events.last(),
+            Some(ClaudeEvent::Complete { session_id, .. }) if *session_id == request.session_id
+        ));
+
+        let status = client.get_session(request.session_id).await.unwrap();
+        assert_eq!(status.status, SessionState::Completed);
+        assert_eq!(session_manager.total_count().await, 1);
+
+        let usage = client.usage(7).await.unwrap();
+        assert!(usage.today.total_tokens() > 0);
+
+        // No callback was requested
+        assert!(client.webhook_deliveries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_requires_token() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_retry_policy(RetryPolicy::none());
+
+        assert!(client.execute(&create_test_request()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_budget_exceeded_is_not_retried() {
+        let mut config = Config::dev_default();
+        config.budgets.default.daily_tokens = Some(0);
+        let (url, _) = spawn_server(config);
+        let client = RobertClient::new(url)
+            .with_token(TOKEN)
+            .with_retry_policy(fast_retry());
+
+        let err = match client.execute(&create_test_request()).await {
+            Err(e) => e,
+            Ok(_) => panic!("Expected budget error"),
+        };
+        assert!(!err.is_retryable(true), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_session_errors() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_token(TOKEN);
+
+        let err = client.get_session(Uuid::new_v4()).await.unwrap_err();
+        assert_eq!(err.code(), Some("SESSION_NOT_FOUND"));
+        assert!(matches!(err, ClientError::Api { status: 404, .. }));
+
+        let err = client.cancel_session(Uuid::new_v4()).await.unwrap_err();
+        assert_eq!(err.code(), Some("SESSION_NOT_FOUND"));
+        assert!(matches!(err, ClientError::Api { status: 404, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_round_trip() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_token(TOKEN);
+
+        let conversation = client.create_conversation().await.unwrap();
+        let conversation_id = conversation.conversation_id;
+
+        for _ in 0..2 {
+            let mut request = create_test_request();
+            request.options.conversation_id = Some(conversation_id);
+            let events: Vec<_> = client.execute(&request).await.unwrap().collect().await;
+            assert!(events.iter().all(|e| e.is_ok()));
+        }
+
+        let transcript = client.get_conversation(conversation_id).await.unwrap();
+        assert_eq!(transcript.turns.len(), 2);
+        assert!(transcript
+            .turns
+            .iter()
+            .all(|turn| turn.status == SessionState::Completed));
+
+        client.delete_conversation(conversation_id).await.unwrap();
+        let err = client.get_conversation(conversation_id).await.unwrap_err();
+        assert_eq!(err.code(), Some("CONVERSATION_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_round_trip() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_token(TOKEN);
+
+        let batch = BatchRequest {
+            requests: Some(vec![create_test_request(), create_test_request()]),
+            template: None,
+            max_parallel: Some(2),
+        };
+        let created = client.create_batch(&batch).await.unwrap();
+        assert_eq!(created.progress.total, 2);
+
+        let mut status = created;
+        for _ in 0..100 {
+            status = client.get_batch(status.batch_id).await.unwrap();
+            if status.completed_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status.progress.completed, 2);
+
+        let results = client.batch_results(status.batch_id).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].index, 1);
+        assert!(!results[0].content.is_empty());
+
+        let err = client.cancel_batch(status.batch_id).await.unwrap_err();
+        assert_eq!(err.code(), Some("INVALID_REQUEST"));
+        let err = client.get_batch(Uuid::new_v4()).await.unwrap_err();
+        assert_eq!(err.code(), Some("BATCH_NOT_FOUND"));
+    }
+
+    #[tokio::test]
+    async fn test_job_round_trip() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url).with_token(TOKEN);
+
+        let mut definition = JobDefinition {
+            name: "pricing".to_string(),
+            schedule: "0 9 * * *".to_string(),
+            request: create_test_request(),
+            missed_runs: Default::default(),
+            paused: false,
+        };
+        let job = client.create_job(&definition).await.unwrap();
+        assert!(job.next_run_at.is_some());
+        assert_eq!(client.list_jobs().await.unwrap().len(), 1);
+
+        definition.schedule = "30 18 * * 1-5".to_string();
+        let updated = client.update_job(job.job_id, &definition).await.unwrap();
+        assert_eq!(updated.schedule, "30 18 * * 1-5");
+
+        let paused = client.pause_job(job.job_id).await.unwrap();
+        assert_eq!(paused.next_run_at, None);
+        let resumed = client.resume_job(job.job_id).await.unwrap();
+        assert!(resumed.next_run_at.is_some());
+
+        client.delete_job(job.job_id).await.unwrap();
+        let err = client.get_job(job.job_id).await.unwrap_err();
+        assert_eq!(err.code(), Some("JOB_NOT_FOUND"));
+
+        definition.schedule = "daily".to_string();
+        let err = client.create_job(&definition).await.unwrap_err();
+        assert_eq!(err.code(), Some("INVALID_REQUEST"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("robert.sock");
+        let mut config = Config::dev_default();
+        config.server.unix_socket = Some(path.display().to_string());
+        config.claude.mock_mode = true;
+        tokio::spawn(crate::server::run(config));
+
+        let client = RobertClient::unix_socket(&path)
+            .unwrap()
+            .with_retry_policy(fast_retry());
+        let mut health = client.health().await;
+        for _ in 0..50 {
+            if health.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            health = client.health().await;
+        }
+        assert_eq!(health.unwrap().status, "healthy");
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let mode = std::fs::metadata(path.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_unix_socket_refuses_other_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+        let path = dir.path().join("robert.sock");
+        std::fs::write(&path, "data").unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        assert!(crate::server::bind_unix_socket(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+
+        std::fs::remove_file(&path).unwrap();
+        drop(crate::server::bind_unix_socket(&path).unwrap());
+        // A stale socket from an earlier run is replaced
+        assert!(crate::server::bind_unix_socket(&path).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_unix_socket_requires_private_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("shared");
+        std::fs::create_dir(&shared).unwrap();
+        std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let path = shared.join("robert.sock");
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        let err = crate::server::bind_unix_socket(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(!path.exists());
+
+        // An existing private directory is used as is
+        std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(crate::server::bind_unix_socket(&path).is_ok());
+
+        // A directory owned by another user is refused (only root can set
+        // one up)
+        if unsafe { libc::geteuid() } == 0 {
+            let foreign = dir.path().join("foreign");
+            std::fs::create_dir(&foreign).unwrap();
+            std::os::unix::fs::chown(&foreign, Some(65534), None).unwrap();
+            let err = crate::server::bind_unix_socket(&foreign.join("robert.sock")).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference() {
+        let (url, _) = spawn_server(Config::dev_default());
+        let client = RobertClient::new(url);
+
+        let response = client.inference("hello").await.unwrap();
+        assert_eq!(response.status, "success");
+        assert!(!response.message.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retries_unavailable_server() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let route = warp::path!("api" / "v1" / "health").map(move || {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({"error": "starting"})),
+                    warp::http::StatusCode::SERVICE_UNAVAILABLE,
+                )
+            } else {
+                warp::reply::with_status(
+                    warp::reply::json(&HealthResponse {
+                        status: "healthy".to_string(),
+                        version: "test".to_string(),
+                        claude_cli_available: false,
+                        uptime_seconds: 0,
+                    }),
+                    warp::http::StatusCode::OK,
+                )
+            }
+        });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let client = RobertClient::new(format!("http://{}", addr)).with_retry_policy(fast_retry());
+        assert_eq!(client.health().await.unwrap().version, "test");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        hits.store(0, Ordering::SeqCst);
+        let client =
+            RobertClient::new(format!("http://{}", addr)).with_retry_policy(RetryPolicy::none());
+        let err = client.health().await.unwrap_err();
+        assert!(matches!(err, ClientError::Api { status: 503, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_request_is_not_repeated_after_gateway_error() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let route = warp::path!("inference").map(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"error": "upstream timed out"})),
+                warp::http::StatusCode::GATEWAY_TIMEOUT,
+            )
+        });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let client = RobertClient::new(format!("http://{}", addr)).with_retry_policy(fast_retry());
+        let err = client.inference("hello").await.unwrap_err();
+        assert!(matches!(err, ClientError::Api { status: 504, .. }));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
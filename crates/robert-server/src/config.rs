@@ -5,7 +5,9 @@
 //! for all optional settings.
 
 use crate::error::RobertError;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Server configuration
@@ -36,6 +38,13 @@ pub struct ServerConfig {
     /// Path to TLS private key file (required if enable_tls = true)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tls_key: Option<String>,
+
+    /// Unix domain socket to listen on instead of `host`/`port`
+    ///
+    /// The socket is created with mode 0600 so only the owning user can
+    /// connect. Unix platforms only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<String>,
 }
 
 fn default_host() -> String {
@@ -100,6 +109,22 @@ pub struct ClaudeConfig {
     /// Maximum concurrent sessions
     #[serde(default = "default_max_concurrent")]
     pub max_concurrent_sessions: usize,
+
+    /// Behaviour of the mock executor (only used when mock_mode = true)
+    #[serde(default)]
+    pub mock: MockConfig,
+
+    /// Isolation applied to spawned claude-cli processes
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+
+    /// Tool permissions requests are allowed to ask for
+    #[serde(default)]
+    pub permissions: ToolPolicy,
+
+    /// Model defaults, aliases and fallback chain
+    #[serde(default)]
+    pub models: ModelConfig,
 }
 
 fn default_binary_path() -> String {
@@ -114,6 +139,207 @@ fn default_max_concurrent() -> usize {
     20
 }
 
+/// Mock executor configuration
+///
+/// Selects the scenario the mock executor plays back. Can be changed
+/// at runtime through a configuration reload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MockConfig {
+    /// Scenario to simulate
+    #[serde(default)]
+    pub scenario: MockScenario,
+
+    /// Delay between simulated events in milliseconds
+    #[serde(default = "default_mock_event_delay_ms")]
+    pub event_delay_ms: u64,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            scenario: MockScenario::default(),
+            event_delay_ms: default_mock_event_delay_ms(),
+        }
+    }
+}
+
+fn default_mock_event_delay_ms() -> u64 {
+    100
+}
+
+/// Sandbox configuration for claude-cli processes
+///
+/// Each session runs in its own temporary workspace with a scrubbed
+/// environment and optional resource limits. Stronger isolation
+/// (namespaces, seccomp) is available through a wrapper command such as
+/// `bwrap` or `firejail`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SandboxConfig {
+    /// Enable per-session workspaces, environment scrubbing and limits
+    #[serde(default = "default_sandbox_enabled")]
+    pub enabled: bool,
+
+    /// Directory in which session workspaces are created (default: system temp dir)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_root: Option<String>,
+
+    /// Environment variables passed through to claude-cli; all others are removed
+    #[serde(default = "default_env_allowlist")]
+    pub env_allowlist: Vec<String>,
+
+    /// Maximum CPU time in seconds (RLIMIT_CPU)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_seconds: Option<u64>,
+
+    /// Maximum address space in megabytes (RLIMIT_AS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+
+    /// Maximum number of open file descriptors (RLIMIT_NOFILE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_open_files: Option<u64>,
+
+    /// Command prepended to the claude-cli invocation, e.g. `["bwrap", "--unshare-net", ...]`
+    #[serde(default)]
+    pub wrapper: Vec<String>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_sandbox_enabled(),
+            workspace_root: None,
+            env_allowlist: default_env_allowlist(),
+            max_cpu_seconds: None,
+            max_memory_mb: None,
+            max_open_files: None,
+            wrapper: vec![],
+        }
+    }
+}
+
+fn default_sandbox_enabled() -> bool {
+    true
+}
+
+fn default_env_allowlist() -> Vec<String> {
+    [
+        "PATH",
+        "HOME",
+        "USER",
+        "LANG",
+        "LC_ALL",
+        "TERM",
+        "ANTHROPIC_API_KEY",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Tool permission policy
+///
+/// Bounds the tool permissions a request may pass to claude-cli. Tool
+/// names may carry a pattern, e.g. `Bash(git:*)`; a bare name such as
+/// `Bash` covers all patterns of that tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolPolicy {
+    /// Tools requests may enable; empty allows any tool not denied.
+    /// Used as the allow-list when a request does not specify one.
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+
+    /// Tools that are always denied
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+
+    /// Directories (and their subdirectories) requests may grant access to
+    #[serde(default)]
+    pub allowed_dirs: Vec<String>,
+
+    /// Whether requests may set skip_permissions
+    #[serde(default)]
+    pub allow_skip_permissions: bool,
+}
+
+/// Model selection configuration
+///
+/// Maps aliases to concrete model names and defines the models to fall
+/// back to when the preferred one is overloaded or rate limited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelConfig {
+    /// Model used when a request does not name one (claude-cli default if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+
+    /// Alias to model name mapping, e.g. `fast = "claude-haiku-4-5"`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+
+    /// Models (or aliases) tried in order after the preferred model fails
+    #[serde(default)]
+    pub fallback: Vec<String>,
+}
+
+impl ModelConfig {
+    /// Resolves an alias to a concrete model name
+    ///
+    /// Names that are not aliases are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `name` - Model name or alias
+    ///
+    /// # Returns
+    /// Concrete model name
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// Returns the models to try for a request, in order
+    ///
+    /// Starts with the requested model (or the default) and continues with
+    /// the fallback chain, skipping duplicates. Empty when neither a
+    /// requested nor a default model is set, meaning claude-cli picks.
+    ///
+    /// # Arguments
+    /// * `requested` - Model or alias named in the request
+    ///
+    /// # Returns
+    /// Concrete model names in the order they should be attempted
+    pub fn chain(&self, requested: Option<&str>) -> Vec<String> {
+        let Some(preferred) = requested.or(self.default.as_deref()) else {
+            return vec![];
+        };
+
+        let mut chain: Vec<String> = Vec::new();
+        for name in std::iter::once(preferred).chain(self.fallback.iter().map(String::as_str)) {
+            let model = self.resolve(name);
+            if !chain.iter().any(|m| m == model) {
+                chain.push(model.to_string());
+            }
+        }
+        chain
+    }
+}
+
+/// Scenarios supported by the mock executor
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MockScenario {
+    /// Emit content, progress and tool use events, then complete successfully
+    #[default]
+    Success,
+
+    /// Emit an error event and complete with a failed status
+    Failure,
+
+    /// Ask a clarification question and continue with the answer
+    ///
+    /// Needs an interactive (WebSocket) session; elsewhere the question
+    /// cannot be answered and the session fails.
+    Clarification,
+}
+
 /// Request size and content limits
 ///
 /// Enforces maximum sizes to prevent resource exhaustion.
@@ -130,6 +356,14 @@ pub struct LimitsConfig {
     /// Maximum prompt length in characters
     #[serde(default = "default_max_prompt_length")]
     pub max_prompt_length: usize,
+
+    /// How long an execute request's Idempotency-Key is remembered
+    #[serde(default = "default_idempotency_window")]
+    pub idempotency_window_seconds: u64,
+
+    /// Maximum number of items in a batch
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
 }
 
 fn default_max_request_mb() -> usize {
@@ -144,6 +378,14 @@ fn default_max_prompt_length() -> usize {
     50000
 }
 
+fn default_idempotency_window() -> u64 {
+    3600
+}
+
+fn default_max_batch_size() -> usize {
+    100
+}
+
 /// Logging configuration
 ///
 /// Controls log level, format, and data sanitization.
@@ -170,6 +412,203 @@ fn default_sanitize() -> bool {
     true
 }
 
+/// Usage budgets per API key
+///
+/// Limits apply per UTC day and month. The top-level limits are the
+/// default for every key; `[budgets.keys."<token>"]` overrides them for a
+/// single key, field by field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BudgetConfig {
+    /// Default limits for all keys
+    #[serde(flatten)]
+    pub default: BudgetLimits,
+
+    /// Per-key overrides, keyed by bearer token
+    #[serde(default)]
+    pub keys: HashMap<String, BudgetLimits>,
+}
+
+impl BudgetConfig {
+    /// Returns the limits that apply to a key
+    ///
+    /// # Arguments
+    /// * `token` - Bearer token identifying the key
+    ///
+    /// # Returns
+    /// Default limits with any per-key overrides applied
+    pub fn limits_for(&self, token: &str) -> BudgetLimits {
+        let Some(overrides) = self.keys.get(token) else {
+            return self.default.clone();
+        };
+
+        BudgetLimits {
+            daily_tokens: overrides.daily_tokens.or(self.default.daily_tokens),
+            monthly_tokens: overrides.monthly_tokens.or(self.default.monthly_tokens),
+            daily_cost_usd: overrides.daily_cost_usd.or(self.default.daily_cost_usd),
+            monthly_cost_usd: overrides.monthly_cost_usd.or(self.default.monthly_cost_usd),
+        }
+    }
+}
+
+/// Token and cost limits for a key (unset means unlimited)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BudgetLimits {
+    /// Maximum tokens per UTC day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_tokens: Option<u64>,
+
+    /// Maximum tokens per UTC month
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_tokens: Option<u64>,
+
+    /// Maximum cost in US dollars per UTC day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_cost_usd: Option<f64>,
+
+    /// Maximum cost in US dollars per UTC month
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_cost_usd: Option<f64>,
+}
+
+/// Webhook callbacks on session completion
+///
+/// Requests may name a `callback_url` that receives a signed summary when
+/// the session ends. Only URLs matching `allowed_urls` are accepted: same
+/// scheme, host and port as an entry, with a path under the entry's path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    /// Callback URLs (or URL prefixes) requests may use
+    #[serde(default)]
+    pub allowed_urls: Vec<String>,
+
+    /// Secret for the HMAC-SHA256 signature of each delivery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Delivery attempts before a callback is given up
+    #[serde(default = "default_webhook_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry in milliseconds; doubles per retry
+    #[serde(default = "default_webhook_retry_delay")]
+    pub retry_delay_ms: u64,
+
+    /// Timeout of a single delivery attempt
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout_seconds: u64,
+}
+
+fn default_webhook_attempts() -> u32 {
+    5
+}
+
+fn default_webhook_retry_delay() -> u64 {
+    1000
+}
+
+fn default_webhook_timeout() -> u64 {
+    10
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            allowed_urls: Vec::new(),
+            secret: None,
+            max_attempts: default_webhook_attempts(),
+            retry_delay_ms: default_webhook_retry_delay(),
+            timeout_seconds: default_webhook_timeout(),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Returns true if a callback URL matches the allow-list
+    ///
+    /// # Arguments
+    /// * `url` - Callback URL requested by a client
+    pub fn allows(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+
+        self.allowed_urls.iter().any(|allowed| {
+            let Ok(allowed) = reqwest::Url::parse(allowed) else {
+                return false;
+            };
+            let prefix = allowed.path().trim_end_matches('/');
+            let path = url.path();
+
+            url.scheme() == allowed.scheme()
+                && url.host_str() == allowed.host_str()
+                && url.port_or_known_default() == allowed.port_or_known_default()
+                && (path == prefix || path.starts_with(&format!("{}/", prefix)))
+        })
+    }
+}
+
+/// Scheduled jobs
+///
+/// Job definitions and their run history are written to `jobs_file` so
+/// they survive restarts. Without a file, jobs are kept in memory only.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SchedulerConfig {
+    /// File the jobs are persisted to (JSON)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs_file: Option<String>,
+
+    /// Runs kept in each job's history
+    #[serde(default = "default_job_history")]
+    pub max_history: usize,
+
+    /// How often due jobs are checked for, in seconds
+    #[serde(default = "default_scheduler_tick")]
+    pub tick_seconds: u64,
+}
+
+fn default_job_history() -> usize {
+    50
+}
+
+fn default_scheduler_tick() -> u64 {
+    1
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            jobs_file: None,
+            max_history: default_job_history(),
+            tick_seconds: default_scheduler_tick(),
+        }
+    }
+}
+
+/// Settings given on the command line
+///
+/// They take precedence over the configuration file, both at startup and
+/// on every reload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CliOverrides {
+    /// `--mock`: use the mock executor regardless of `claude.mock_mode`
+    pub mock_mode: bool,
+}
+
+impl CliOverrides {
+    /// Applies the overrides to a configuration
+    ///
+    /// # Arguments
+    /// * `config` - Configuration loaded from file or defaults
+    pub fn apply(&self, config: &mut Config) {
+        if self.mock_mode {
+            config.claude.mock_mode = true;
+        }
+    }
+}
+
 /// Root configuration structure
 ///
 /// Aggregates all configuration sections and provides validation.
@@ -180,6 +619,12 @@ pub struct Config {
     pub claude: ClaudeConfig,
     pub limits: LimitsConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub budgets: BudgetConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 impl Config {
@@ -227,6 +672,7 @@ impl Config {
                 enable_tls: false,
                 tls_cert: None,
                 tls_key: None,
+                unix_socket: None,
             },
             auth: AuthConfig {
                 dev_token: Some("dev-token-12345".to_string()),
@@ -239,17 +685,26 @@ impl Config {
                 mock_mode: false,
                 default_timeout_seconds: 300,
                 max_concurrent_sessions: 20,
+                mock: MockConfig::default(),
+                sandbox: SandboxConfig::default(),
+                permissions: ToolPolicy::default(),
+                models: ModelConfig::default(),
             },
             limits: LimitsConfig {
                 max_request_size_mb: 50,
                 max_screenshot_count: 10,
                 max_prompt_length: 50000,
+                idempotency_window_seconds: 3600,
+                max_batch_size: 100,
             },
             logging: LoggingConfig {
                 level: "debug".to_string(),
                 pretty_print: true,
                 sanitize_sensitive_data: true,
             },
+            budgets: BudgetConfig::default(),
+            webhooks: WebhookConfig::default(),
+            scheduler: SchedulerConfig::default(),
         }
     }
 
@@ -275,6 +730,20 @@ impl Config {
             ));
         }
 
+        if let Some(ref socket) = self.server.unix_socket {
+            if socket.trim().is_empty() {
+                return Err(RobertError::Config(
+                    "server.unix_socket cannot be empty".to_string(),
+                ));
+            }
+
+            if cfg!(not(unix)) {
+                return Err(RobertError::Config(
+                    "server.unix_socket is only supported on Unix platforms".to_string(),
+                ));
+            }
+        }
+
         // If TLS is enabled, cert and key must be provided
         if self.server.enable_tls {
             if self.server.tls_cert.is_none() || self.server.tls_key.is_none() {
@@ -335,6 +804,110 @@ impl Config {
             ));
         }
 
+        let sandbox = &self.claude.sandbox;
+        if sandbox.env_allowlist.iter().any(|name| name.is_empty()) {
+            return Err(RobertError::Config(
+                "Sandbox env_allowlist cannot contain empty names".to_string(),
+            ));
+        }
+
+        if [
+            sandbox.max_cpu_seconds,
+            sandbox.max_memory_mb,
+            sandbox.max_open_files,
+        ]
+        .contains(&Some(0))
+        {
+            return Err(RobertError::Config(
+                "Sandbox resource limits must be greater than 0".to_string(),
+            ));
+        }
+
+        if sandbox.wrapper.first().is_some_and(|cmd| cmd.is_empty()) {
+            return Err(RobertError::Config(
+                "Sandbox wrapper command cannot be empty".to_string(),
+            ));
+        }
+
+        if let Some(dir) = self
+            .claude
+            .permissions
+            .allowed_dirs
+            .iter()
+            .find(|dir| !Path::new(dir).is_absolute())
+        {
+            return Err(RobertError::Config(format!(
+                "Permission allowed_dirs must be absolute paths: {}",
+                dir
+            )));
+        }
+
+        let models = &self.claude.models;
+        if models
+            .default
+            .iter()
+            .chain(models.aliases.keys())
+            .chain(models.aliases.values())
+            .chain(models.fallback.iter())
+            .any(|name| name.trim().is_empty())
+        {
+            return Err(RobertError::Config(
+                "Model names and aliases cannot be empty".to_string(),
+            ));
+        }
+
+        for limits in std::iter::once(&self.budgets.default).chain(self.budgets.keys.values()) {
+            if [limits.daily_cost_usd, limits.monthly_cost_usd]
+                .iter()
+                .flatten()
+                .any(|cost| !cost.is_finite() || *cost < 0.0)
+            {
+                return Err(RobertError::Config(
+                    "Budget costs must be non-negative numbers".to_string(),
+                ));
+            }
+        }
+
+        let webhooks = &self.webhooks;
+        if let Some(url) = webhooks.allowed_urls.iter().find(|url| {
+            !reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+        }) {
+            return Err(RobertError::Config(format!(
+                "Webhook allowed_urls must be http(s) URLs: {}",
+                url
+            )));
+        }
+
+        if !webhooks.allowed_urls.is_empty() && webhooks.secret.as_deref().is_none_or(str::is_empty)
+        {
+            return Err(RobertError::Config(
+                "Webhook secret is required when allowed_urls are configured".to_string(),
+            ));
+        }
+
+        if webhooks.max_attempts == 0 || webhooks.timeout_seconds == 0 {
+            return Err(RobertError::Config(
+                "Webhook max_attempts and timeout_seconds must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.scheduler.max_history == 0 || self.scheduler.tick_seconds == 0 {
+            return Err(RobertError::Config(
+                "Scheduler max_history and tick_seconds must be greater than 0".to_string(),
+            ));
+        }
+
+        if self
+            .scheduler
+            .jobs_file
+            .as_deref()
+            .is_some_and(|path| path.trim().is_empty())
+        {
+            return Err(RobertError::Config(
+                "Scheduler jobs_file cannot be empty".to_string(),
+            ));
+        }
+
         // Validate limits config
         if self.limits.max_request_size_mb == 0 {
             return Err(RobertError::Config(
@@ -354,6 +927,12 @@ impl Config {
             ));
         }
 
+        if self.limits.max_batch_size == 0 {
+            return Err(RobertError::Config(
+                "Max batch size must be greater than 0".to_string(),
+            ));
+        }
+
         // Validate logging config
         let valid_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_levels.contains(&self.logging.level.as_str()) {
@@ -614,5 +1193,190 @@ sanitize_sensitive_data = false
         assert_eq!(config.claude.binary_path, "claude");
         assert_eq!(config.limits.max_screenshot_count, 10);
         assert_eq!(config.logging.level, "info");
+        assert_eq!(config.claude.mock, MockConfig::default());
+    }
+
+    #[test]
+    fn test_from_file_mock_section() {
+        let toml_content = r#"
+[server]
+[auth]
+[claude]
+mock_mode = true
+
+[claude.mock]
+scenario = "failure"
+event_delay_ms = 5
+
+[limits]
+[logging]
+"#;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        let config = Config::from_file(temp_file.path()).unwrap();
+        assert_eq!(config.claude.mock.scenario, MockScenario::Failure);
+        assert_eq!(config.claude.mock.event_delay_ms, 5);
+    }
+
+    #[test]
+    fn test_from_file_sandbox_section() {
+        let toml_content = r#"
+[server]
+[auth]
+[claude]
+
+[claude.sandbox]
+env_allowlist = ["PATH"]
+max_cpu_seconds = 600
+max_open_files = 256
+wrapper = ["firejail", "--quiet"]
+
+[limits]
+[logging]
+"#;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        let config = Config::from_file(temp_file.path()).unwrap();
+        let sandbox = &config.claude.sandbox;
+        assert!(sandbox.enabled);
+        assert_eq!(sandbox.env_allowlist, vec!["PATH".to_string()]);
+        assert_eq!(sandbox.max_cpu_seconds, Some(600));
+        assert_eq!(sandbox.max_memory_mb, None);
+        assert_eq!(sandbox.max_open_files, Some(256));
+        assert_eq!(sandbox.wrapper, vec!["firejail", "--quiet"]);
+    }
+
+    #[test]
+    fn test_validate_unix_socket() {
+        let mut config = Config::dev_default();
+        config.server.unix_socket = Some(" ".to_string());
+        assert!(config.validate().is_err());
+
+        config.server.unix_socket = Some("/tmp/robert.sock".to_string());
+        assert_eq!(config.validate().is_ok(), cfg!(unix));
+    }
+
+    #[test]
+    fn test_validate_sandbox_zero_limit() {
+        let mut config = Config::dev_default();
+        config.claude.sandbox.max_open_files = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_relative_permission_dir() {
+        let mut config = Config::dev_default();
+        config.claude.permissions.allowed_dirs = vec!["data".to_string()];
+        assert!(config.validate().is_err());
+
+        config.claude.permissions.allowed_dirs = vec!["/srv/data".to_string()];
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_model_config_chain() {
+        let models = ModelConfig {
+            default: Some("smart".to_string()),
+            aliases: HashMap::from([
+                ("fast".to_string(), "claude-haiku-4-5".to_string()),
+                ("smart".to_string(), "claude-opus-4-1".to_string()),
+            ]),
+            fallback: vec!["smart".to_string(), "fast".to_string()],
+        };
+
+        assert_eq!(models.resolve("fast"), "claude-haiku-4-5");
+        assert_eq!(models.resolve("claude-sonnet-4-5"), "claude-sonnet-4-5");
+
+        // Default model first, duplicates removed
+        assert_eq!(
+            models.chain(None),
+            vec!["claude-opus-4-1", "claude-haiku-4-5"]
+        );
+        assert_eq!(
+            models.chain(Some("claude-sonnet-4-5")),
+            vec!["claude-sonnet-4-5", "claude-opus-4-1", "claude-haiku-4-5"]
+        );
+        assert!(ModelConfig::default().chain(None).is_empty());
+    }
+
+    #[test]
+    fn test_from_file_budgets() {
+        let toml_content = r#"
+[server]
+[auth]
+[claude]
+[limits]
+[logging]
+
+[budgets]
+daily_tokens = 1000
+monthly_cost_usd = 50.0
+
+[budgets.keys."team-token"]
+daily_tokens = 5000
+"#;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        let config = Config::from_file(temp_file.path()).unwrap();
+
+        let default = config.budgets.limits_for("other-token");
+        assert_eq!(default.daily_tokens, Some(1000));
+        assert_eq!(default.monthly_cost_usd, Some(50.0));
+
+        let team = config.budgets.limits_for("team-token");
+        assert_eq!(team.daily_tokens, Some(5000));
+        assert_eq!(team.monthly_cost_usd, Some(50.0));
+        assert_eq!(team.monthly_tokens, None);
+    }
+
+    #[test]
+    fn test_validate_negative_budget() {
+        let mut config = Config::dev_default();
+        config.budgets.default.daily_cost_usd = Some(-1.0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_webhooks() {
+        let mut config = Config::dev_default();
+        config.webhooks.allowed_urls = vec!["https://hooks.example.com/robert".to_string()];
+        assert!(config.validate().is_err(), "secret is required");
+
+        config.webhooks.secret = Some("secret".to_string());
+        assert!(config.validate().is_ok());
+
+        config.webhooks.allowed_urls = vec!["ftp://hooks.example.com".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_webhook_allow_list() {
+        let webhooks = WebhookConfig {
+            allowed_urls: vec![
+                "https://hooks.example.com/robert/".to_string(),
+                "http://127.0.0.1:9000".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert!(webhooks.allows("https://hooks.example.com/robert"));
+        assert!(webhooks.allows("https://hooks.example.com/robert/done?x=1"));
+        assert!(webhooks.allows("https://hooks.example.com:443/robert/done"));
+        assert!(webhooks.allows("http://127.0.0.1:9000/anything"));
+
+        assert!(!webhooks.allows("https://hooks.example.com/robertx"));
+        assert!(!webhooks.allows("https://hooks.example.com.evil.io/robert"));
+        assert!(!webhooks.allows("http://hooks.example.com/robert"));
+        assert!(!webhooks.allows("http://127.0.0.1:9001/anything"));
+        assert!(!webhooks.allows("not a url"));
     }
 }
@@ -0,0 +1,584 @@
+//! Multi-turn conversations
+//!
+//! A conversation groups execute requests into turns of one dialogue.
+//! Requests join a conversation through `options.conversation_id`. When
+//! the executor can resume claude-cli sessions, each turn resumes the
+//! claude session reported by the previous one (`--resume`); otherwise
+//! the transcript so far is replayed in front of the new prompt.
+//!
+//! Conversations are kept in memory and are only visible to the API key
+//! that created them.
+
+use crate::error::RobertError;
+use crate::models::{ClaudeEvent, Conversation, ConversationTurn, RobertRequest, SessionState};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Heading placed above a replayed transcript
+const REPLAY_HEADER: &str = "Conversation so far:";
+
+/// A conversation and the API key that owns it
+#[derive(Debug, Clone)]
+struct ConversationEntry {
+    /// API key that created the conversation (empty when auth is disabled)
+    owner: String,
+
+    /// Conversation state and transcript
+    conversation: Conversation,
+}
+
+/// Conversations and the turns currently running
+#[derive(Debug, Default)]
+struct ConversationState {
+    /// Conversations by id
+    conversations: HashMap<Uuid, ConversationEntry>,
+
+    /// Running turns: session id -> conversation id
+    active: HashMap<Uuid, Uuid>,
+}
+
+/// Thread-safe conversation store
+///
+/// Shared by the SSE and WebSocket transports. Turns are started with
+/// [`ConversationManager::begin_turn`] and filled in from the session's
+/// events by [`ConversationManager::record_event`].
+#[derive(Debug, Clone)]
+pub struct ConversationManager {
+    /// Conversations and running turns
+    state: Arc<Mutex<ConversationState>>,
+
+    /// Maximum number of conversations kept; the least recently
+    /// updated idle conversation is dropped to make room
+    max_conversations: usize,
+}
+
+impl ConversationManager {
+    /// Creates a new ConversationManager
+    ///
+    /// # Arguments
+    /// * `max_conversations` - Maximum number of conversations to keep
+    ///
+    /// # Returns
+    /// New ConversationManager instance
+    pub fn new(max_conversations: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ConversationState::default())),
+            max_conversations,
+        }
+    }
+
+    /// Creates an empty conversation
+    ///
+    /// # Arguments
+    /// * `owner` - API key creating the conversation (empty when auth is disabled)
+    ///
+    /// # Returns
+    /// The new conversation
+    ///
+    /// # Errors
+    /// Returns RobertError::Internal if the store is full of conversations
+    /// with running turns
+    pub async fn create(&self, owner: &str) -> Result<Conversation, RobertError> {
+        let mut state = self.state.lock().await;
+
+        if state.conversations.len() >= self.max_conversations && !evict_idle(&mut state) {
+            return Err(RobertError::Internal(format!(
+                "Too many conversations (max: {})",
+                self.max_conversations
+            )));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let conversation = Conversation {
+            conversation_id: Uuid::new_v4(),
+            created_at: now.clone(),
+            updated_at: now,
+            claude_session_id: None,
+            turns: vec![],
+        };
+        state.conversations.insert(
+            conversation.conversation_id,
+            ConversationEntry {
+                owner: owner.to_string(),
+                conversation: conversation.clone(),
+            },
+        );
+
+        Ok(conversation)
+    }
+
+    /// Returns a conversation with its transcript
+    ///
+    /// # Arguments
+    /// * `conversation_id` - Conversation to look up
+    /// * `owner` - API key of the caller
+    ///
+    /// # Returns
+    /// The conversation
+    ///
+    /// # Errors
+    /// Returns RobertError::ConversationNotFound if the conversation does
+    /// not exist or belongs to another key
+    pub async fn get(
+        &self,
+        conversation_id: Uuid,
+        owner: &str,
+    ) -> Result<Conversation, RobertError> {
+        let state = self.state.lock().await;
+        lookup(&state, conversation_id, owner).map(|entry| entry.conversation.clone())
+    }
+
+    /// Deletes a conversation and its transcript
+    ///
+    /// A turn still running finishes normally but is no longer recorded.
+    ///
+    /// # Arguments
+    /// * `conversation_id` - Conversation to delete
+    /// * `owner` - API key of the caller
+    ///
+    /// # Errors
+    /// Returns RobertError::ConversationNotFound if the conversation does
+    /// not exist or belongs to another key
+    pub async fn delete(&self, conversation_id: Uuid, owner: &str) -> Result<(), RobertError> {
+        let mut state = self.state.lock().await;
+        lookup(&state, conversation_id, owner)?;
+
+        state.conversations.remove(&conversation_id);
+        state.active.retain(|_, id| *id != conversation_id);
+        Ok(())
+    }
+
+    /// Starts a turn for a request that references a conversation
+    ///
+    /// Does nothing for requests without `options.conversation_id`.
+    /// Otherwise records a new running turn and prepares the request to
+    /// continue the conversation: with `resumable`, the claude session of
+    /// the previous turn is resumed; without it, earlier turns are replayed
+    /// in front of the prompt, oldest turns first to be left out when the
+    /// result would exceed `max_prompt_length`. A turn that is still
+    /// running (e.g. because its client went away) is marked cancelled.
+    ///
+    /// # Arguments
+    /// * `request` - Validated request; its prompt and options are updated in place
+    /// * `owner` - API key of the caller
+    /// * `resumable` - Whether the executor can resume claude sessions
+    /// * `max_prompt_length` - Maximum length of a replayed prompt
+    ///
+    /// # Returns
+    /// Ok(()) once the turn is recorded
+    ///
+    /// # Errors
+    /// Returns RobertError::ConversationNotFound if the conversation does
+    /// not exist or belongs to another key
+    pub async fn begin_turn(
+        &self,
+        request: &mut RobertRequest,
+        owner: &str,
+        resumable: bool,
+        max_prompt_length: usize,
+    ) -> Result<(), RobertError> {
+        let Some(conversation_id) = request.options.conversation_id else {
+            return Ok(());
+        };
+
+        let mut state = self.state.lock().await;
+        lookup(&state, conversation_id, owner)?;
+        state.active.retain(|_, id| *id != conversation_id);
+
+        let entry = state
+            .conversations
+            .get_mut(&conversation_id)
+            .expect("conversation looked up above");
+        let conversation = &mut entry.conversation;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        for turn in &mut conversation.turns {
+            if turn.status == SessionState::Running {
+                turn.status = SessionState::Cancelled;
+                turn.completed_at = Some(now.clone());
+            }
+        }
+
+        let prompt = request.prompt.clone();
+        match (&conversation.claude_session_id, resumable) {
+            (Some(claude_session_id), true) => {
+                request.options.resume_session = Some(claude_session_id.clone());
+            }
+            _ => {
+                request.prompt =
+                    replay_prompt(&conversation.turns, &request.prompt, max_prompt_length);
+            }
+        }
+
+        conversation.turns.push(ConversationTurn {
+            session_id: request.session_id,
+            prompt,
+            response: String::new(),
+            status: SessionState::Running,
+            started_at: now.clone(),
+            completed_at: None,
+        });
+        conversation.updated_at = now;
+
+        state.active.insert(request.session_id, conversation_id);
+        Ok(())
+    }
+
+    /// Updates the running turn of a session with one of its events
+    ///
+    /// Content is appended to the turn's response, the claude session id
+    /// is remembered for the next turn, and terminal events finish the
+    /// turn. Events of sessions outside any conversation are ignored.
+    ///
+    /// # Arguments
+    /// * `session_id` - Session that emitted the event
+    /// * `event` - The event
+    pub async fn record_event(&self, session_id: Uuid, event: &ClaudeEvent) {
+        let mut state = self.state.lock().await;
+        let Some(&conversation_id) = state.active.get(&session_id) else {
+            return;
+        };
+        let Some(entry) = state.conversations.get_mut(&conversation_id) else {
+            return;
+        };
+        let conversation = &mut entry.conversation;
+
+        let finished = match event {
+            ClaudeEvent::SessionStarted { claude_session_id } => {
+                conversation.claude_session_id = Some(claude_session_id.clone());
+                None
+            }
+            ClaudeEvent::Content { text } => {
+                if let Some(turn) = find_turn(conversation, session_id) {
+                    turn.response.push_str(text);
+                }
+                None
+            }
+            ClaudeEvent::Complete { status, .. } => Some(match status.as_str() {
+                "success" => SessionState::Completed,
+                "cancelled" => SessionState::Cancelled,
+                _ => SessionState::Failed,
+            }),
+            ClaudeEvent::Error { .. } => Some(SessionState::Failed),
+            _ => None,
+        };
+
+        if let Some(status) = finished {
+            let now = chrono::Utc::now().to_rfc3339();
+            if let Some(turn) = find_turn(conversation, session_id) {
+                turn.status = status;
+                turn.completed_at = Some(now.clone());
+            }
+            conversation.updated_at = now;
+            state.active.remove(&session_id);
+        }
+    }
+
+    /// Returns the number of stored conversations
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.conversations.len()
+    }
+
+    /// Returns true if no conversations are stored
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+/// Looks up a conversation owned by `owner`
+fn lookup<'a>(
+    state: &'a ConversationState,
+    conversation_id: Uuid,
+    owner: &str,
+) -> Result<&'a ConversationEntry, RobertError> {
+    // Conversations of other keys are reported as missing, not forbidden
+    state
+        .conversations
+        .get(&conversation_id)
+        .filter(|entry| entry.owner == owner)
+        .ok_or_else(|| RobertError::ConversationNotFound(conversation_id.to_string()))
+}
+
+/// Returns the turn executed by a session
+fn find_turn(conversation: &mut Conversation, session_id: Uuid) -> Option<&mut ConversationTurn> {
+    conversation
+        .turns
+        .iter_mut()
+        .rev()
+        .find(|turn| turn.session_id == session_id)
+}
+
+/// Drops the least recently updated conversation without a running turn
+///
+/// # Returns
+/// true if a conversation was dropped
+fn evict_idle(state: &mut ConversationState) -> bool {
+    let oldest = state
+        .conversations
+        .values()
+        .filter(|entry| {
+            !state
+                .active
+                .values()
+                .any(|id| *id == entry.conversation.conversation_id)
+        })
+        .min_by(|a, b| a.conversation.updated_at.cmp(&b.conversation.updated_at))
+        .map(|entry| entry.conversation.conversation_id);
+
+    match oldest {
+        Some(id) => state.conversations.remove(&id).is_some(),
+        None => false,
+    }
+}
+
+/// Builds a prompt that replays earlier turns before the new prompt
+///
+/// Only completed turns are replayed. The most recent turns are kept
+/// when not all of them fit within `max_length`.
+///
+/// # Arguments
+/// * `turns` - Earlier turns of the conversation
+/// * `prompt` - New prompt
+/// * `max_length` - Maximum length of the result
+///
+/// # Returns
+/// The prompt to execute
+fn replay_prompt(turns: &[ConversationTurn], prompt: &str, max_length: usize) -> String {
+    let current = format!("User: {}", prompt);
+    let mut budget = max_length.saturating_sub(REPLAY_HEADER.len() + current.len() + 4);
+
+    let mut replayed = Vec::new();
+    for turn in turns
+        .iter()
+        .rev()
+        .filter(|turn| turn.status == SessionState::Completed)
+    {
+        let exchange = format!("User: {}\n\nAssistant: {}", turn.prompt, turn.response);
+        if exchange.len() + 2 > budget {
+            break;
+        }
+        budget -= exchange.len() + 2;
+        replayed.push(exchange);
+    }
+
+    if replayed.is_empty() {
+        return prompt.to_string();
+    }
+
+    replayed.reverse();
+    format!(
+        "{}\n\n{}\n\n{}",
+        REPLAY_HEADER,
+        replayed.join("\n\n"),
+        current
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DomState, RequestContext, RequestOptions};
+
+    fn request_for(conversation_id: Uuid, prompt: &str) -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![],
+                dom_state: DomState {
+                    accessible_tree: String::new(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "test".to_string(),
+            },
+            prompt: prompt.to_string(),
+            options: RequestOptions {
+                conversation_id: Some(conversation_id),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn complete_turn(manager: &ConversationManager, session_id: Uuid, response: &str) {
+        let events = [
+            ClaudeEvent::Content {
+                text: response.to_string(),
+            },
+            ClaudeEvent::Complete {
+                session_id,
+                status: "success".to_string(),
+                usage: None,
+            },
+        ];
+        for event in &events {
+            manager.record_event(session_id, event).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_uses_claude_session_of_previous_turn() {
+        let manager = ConversationManager::new(10);
+        let conversation = manager.create("key").await.unwrap();
+        let id = conversation.conversation_id;
+
+        // First turn has nothing to resume
+        let mut first = request_for(id, "Open the page");
+        manager
+            .begin_turn(&mut first, "key", true, 10000)
+            .await
+            .unwrap();
+        assert_eq!(first.options.resume_session, None);
+        assert_eq!(first.prompt, "Open the page");
+
+        manager
+            .record_event(
+                first.session_id,
+                &ClaudeEvent::SessionStarted {
+                    claude_session_id: "claude-1".to_string(),
+                },
+            )
+            .await;
+        complete_turn(&manager, first.session_id, "Opened").await;
+
+        let mut second = request_for(id, "Now click login");
+        manager
+            .begin_turn(&mut second, "key", true, 10000)
+            .await
+            .unwrap();
+        assert_eq!(second.options.resume_session.as_deref(), Some("claude-1"));
+        assert_eq!(second.prompt, "Now click login");
+
+        let transcript = manager.get(id, "key").await.unwrap();
+        assert_eq!(transcript.claude_session_id.as_deref(), Some("claude-1"));
+        assert_eq!(transcript.turns.len(), 2);
+        assert_eq!(transcript.turns[0].response, "Opened");
+        assert_eq!(transcript.turns[0].status, SessionState::Completed);
+        assert_eq!(transcript.turns[1].status, SessionState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_resume() {
+        let manager = ConversationManager::new(10);
+        let id = manager.create("").await.unwrap().conversation_id;
+
+        let mut first = request_for(id, "What is on the page?");
+        manager
+            .begin_turn(&mut first, "", false, 10000)
+            .await
+            .unwrap();
+        complete_turn(&manager, first.session_id, "A login form").await;
+
+        let mut second = request_for(id, "Fill it in");
+        manager
+            .begin_turn(&mut second, "", false, 10000)
+            .await
+            .unwrap();
+        assert_eq!(second.options.resume_session, None);
+        assert_eq!(
+            second.prompt,
+            "Conversation so far:\n\nUser: What is on the page?\n\nAssistant: A login form\n\nUser: Fill it in"
+        );
+
+        // The transcript keeps the prompt as sent by the client
+        let transcript = manager.get(id, "").await.unwrap();
+        assert_eq!(transcript.turns[1].prompt, "Fill it in");
+    }
+
+    #[test]
+    fn test_replay_prompt_keeps_recent_turns_within_limit() {
+        let turn = |prompt: &str, status| ConversationTurn {
+            session_id: Uuid::new_v4(),
+            prompt: prompt.to_string(),
+            response: "ok".to_string(),
+            status,
+            started_at: String::new(),
+            completed_at: None,
+        };
+        let turns = vec![
+            turn("first", SessionState::Completed),
+            turn("failed", SessionState::Failed),
+            turn("second", SessionState::Completed),
+        ];
+
+        let prompt = replay_prompt(&turns, "next", 10000);
+        assert!(prompt.contains("User: first"));
+        assert!(!prompt.contains("User: failed"));
+        assert!(prompt.ends_with("User: second\n\nAssistant: ok\n\nUser: next"));
+
+        // Only room for the most recent turn
+        let prompt = replay_prompt(&turns, "next", 80);
+        assert!(!prompt.contains("User: first"));
+        assert!(prompt.contains("User: second"));
+        assert!(prompt.len() <= 80);
+
+        // No room at all
+        assert_eq!(replay_prompt(&turns, "next", 10), "next");
+    }
+
+    #[tokio::test]
+    async fn test_conversations_are_private_to_their_owner() {
+        let manager = ConversationManager::new(10);
+        let id = manager.create("alice").await.unwrap().conversation_id;
+
+        assert!(matches!(
+            manager.get(id, "bob").await,
+            Err(RobertError::ConversationNotFound(_))
+        ));
+        let mut request = request_for(id, "hello");
+        assert!(manager
+            .begin_turn(&mut request, "bob", true, 10000)
+            .await
+            .is_err());
+        assert!(manager.delete(id, "bob").await.is_err());
+
+        manager.delete(id, "alice").await.unwrap();
+        assert!(manager.get(id, "alice").await.is_err());
+        assert!(manager.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_new_turn_cancels_unfinished_turn() {
+        let manager = ConversationManager::new(10);
+        let id = manager.create("").await.unwrap().conversation_id;
+
+        let mut first = request_for(id, "one");
+        manager
+            .begin_turn(&mut first, "", true, 10000)
+            .await
+            .unwrap();
+        let mut second = request_for(id, "two");
+        manager
+            .begin_turn(&mut second, "", true, 10000)
+            .await
+            .unwrap();
+
+        // Late events of the abandoned turn are ignored
+        complete_turn(&manager, first.session_id, "late").await;
+
+        let transcript = manager.get(id, "").await.unwrap();
+        assert_eq!(transcript.turns[0].status, SessionState::Cancelled);
+        assert_eq!(transcript.turns[0].response, "");
+        assert_eq!(transcript.turns[1].status, SessionState::Running);
+    }
+
+    #[tokio::test]
+    async fn test_create_evicts_least_recently_updated() {
+        let manager = ConversationManager::new(2);
+        let first = manager.create("").await.unwrap().conversation_id;
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+        let second = manager.create("").await.unwrap().conversation_id;
+
+        // A running turn keeps the oldest conversation alive
+        let mut request = request_for(first, "busy");
+        manager
+            .begin_turn(&mut request, "", true, 10000)
+            .await
+            .unwrap();
+
+        manager.create("").await.unwrap();
+        assert_eq!(manager.len().await, 2);
+        assert!(manager.get(first, "").await.is_ok());
+        assert!(manager.get(second, "").await.is_err());
+    }
+}
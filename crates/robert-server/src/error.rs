@@ -4,6 +4,7 @@
 //! Each error type maps to specific HTTP status codes and provides
 //! structured error responses for clients.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use warp::http::StatusCode;
 use warp::reject::Reject;
@@ -13,7 +14,7 @@ use warp::reject::Reject;
 /// Each variant represents a specific error condition that can occur
 /// during request processing. These errors are converted to appropriate
 /// HTTP responses with structured error data.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum RobertError {
     /// Authentication failed - invalid or missing token
     #[error("Authentication failed: {0}")]
@@ -23,6 +24,10 @@ pub enum RobertError {
     #[error("Rate limit exceeded: {0}")]
     RateLimited(String),
 
+    /// Usage budget for this API key exhausted
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     /// Request validation failed
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
@@ -43,6 +48,34 @@ pub enum RobertError {
     #[error("Session not found: {0}")]
     SessionNotFound(String),
 
+    /// Conversation not found
+    #[error("Conversation not found: {0}")]
+    ConversationNotFound(String),
+
+    /// Batch not found
+    #[error("Batch not found: {0}")]
+    BatchNotFound(String),
+
+    /// Scheduled job not found
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+
+    /// No route matches the request path
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// The route exists but not for this HTTP method
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    /// Request body exceeds the configured size limit
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// Server is at capacity; the request can be retried later
+    #[error("Server busy: {0}")]
+    ServerBusy(String),
+
     /// Internal server error
     #[error("Internal error: {0}")]
     Internal(String),
@@ -59,7 +92,7 @@ impl Reject for RobertError {}
 /// This structure is serialized to JSON and sent to clients
 /// when an error occurs. It provides consistent error formatting
 /// across all endpoints.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ErrorResponse {
     /// Error code (matches RobertError variant name)
     pub code: String,
@@ -77,6 +110,10 @@ pub struct ErrorResponse {
     /// Optional retry-after hint in seconds
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_after_seconds: Option<u64>,
+
+    /// Id of the failed request (also sent as the X-Request-Id header)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl RobertError {
@@ -91,11 +128,19 @@ impl RobertError {
         match self {
             RobertError::AuthFailed(_) => StatusCode::UNAUTHORIZED,
             RobertError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            RobertError::BudgetExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
             RobertError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
             RobertError::ClaudeUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             RobertError::ExecutionError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             RobertError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
             RobertError::SessionNotFound(_) => StatusCode::NOT_FOUND,
+            RobertError::ConversationNotFound(_) => StatusCode::NOT_FOUND,
+            RobertError::BatchNotFound(_) => StatusCode::NOT_FOUND,
+            RobertError::JobNotFound(_) => StatusCode::NOT_FOUND,
+            RobertError::NotFound(_) => StatusCode::NOT_FOUND,
+            RobertError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            RobertError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            RobertError::ServerBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
             RobertError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             RobertError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -112,11 +157,19 @@ impl RobertError {
         match self {
             RobertError::AuthFailed(_) => "AUTH_FAILED",
             RobertError::RateLimited(_) => "RATE_LIMITED",
+            RobertError::BudgetExceeded(_) => "BUDGET_EXCEEDED",
             RobertError::InvalidRequest(_) => "INVALID_REQUEST",
             RobertError::ClaudeUnavailable(_) => "CLAUDE_UNAVAILABLE",
             RobertError::ExecutionError(_) => "EXECUTION_ERROR",
             RobertError::Timeout(_) => "TIMEOUT",
             RobertError::SessionNotFound(_) => "SESSION_NOT_FOUND",
+            RobertError::ConversationNotFound(_) => "CONVERSATION_NOT_FOUND",
+            RobertError::BatchNotFound(_) => "BATCH_NOT_FOUND",
+            RobertError::JobNotFound(_) => "JOB_NOT_FOUND",
+            RobertError::NotFound(_) => "NOT_FOUND",
+            RobertError::MethodNotAllowed(_) => "METHOD_NOT_ALLOWED",
+            RobertError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            RobertError::ServerBusy(_) => "SERVER_BUSY",
             RobertError::Internal(_) => "INTERNAL_ERROR",
             RobertError::Config(_) => "CONFIG_ERROR",
         }
@@ -126,7 +179,7 @@ impl RobertError {
     /// Converts error to structured error response
     ///
     /// Creates an ErrorResponse with appropriate retry-after hints
-    /// for rate-limited requests.
+    /// for rate-limited requests and a busy server.
     ///
     /// # Arguments
     /// * `session_id` - Optional session ID to include in response
@@ -136,6 +189,7 @@ impl RobertError {
     pub fn to_error_response(&self, session_id: Option<String>) -> ErrorResponse {
         let retry_after = match self {
             RobertError::RateLimited(_) => Some(60),
+            RobertError::ServerBusy(_) => Some(30),
             _ => None,
         };
 
@@ -145,6 +199,7 @@ impl RobertError {
             session_id,
             timestamp: chrono::Utc::now().to_rfc3339(),
             retry_after_seconds: retry_after,
+            request_id: None,
         }
     }
 }
@@ -167,6 +222,13 @@ mod tests {
         assert_eq!(err.error_code(), "RATE_LIMITED");
     }
 
+    #[test]
+    fn test_budget_exceeded_status_code() {
+        let err = RobertError::BudgetExceeded("daily token budget".to_string());
+        assert_eq!(err.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(err.error_code(), "BUDGET_EXCEEDED");
+    }
+
     #[test]
     fn test_invalid_request_status_code() {
         let err = RobertError::InvalidRequest("missing field".to_string());
@@ -202,6 +264,21 @@ mod tests {
         assert_eq!(err.error_code(), "SESSION_NOT_FOUND");
     }
 
+    #[test]
+    fn test_routing_error_status_codes() {
+        let err = RobertError::NotFound("No such endpoint".to_string());
+        assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(err.error_code(), "NOT_FOUND");
+
+        let err = RobertError::MethodNotAllowed("GET".to_string());
+        assert_eq!(err.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(err.error_code(), "METHOD_NOT_ALLOWED");
+
+        let err = RobertError::PayloadTooLarge("20 MB".to_string());
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(err.error_code(), "PAYLOAD_TOO_LARGE");
+    }
+
     #[test]
     fn test_error_response_without_session_id() {
         let err = RobertError::InvalidRequest("test error".to_string());
@@ -233,6 +310,16 @@ mod tests {
         assert_eq!(response.retry_after_seconds, Some(60));
     }
 
+    #[test]
+    fn test_server_busy_has_retry_after() {
+        let err = RobertError::ServerBusy("too many batches".to_string());
+        let response = err.to_error_response(None);
+
+        assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.code, "SERVER_BUSY");
+        assert_eq!(response.retry_after_seconds, Some(30));
+    }
+
     #[test]
     fn test_error_response_json_serialization() {
         let err = RobertError::AuthFailed("invalid token".to_string());
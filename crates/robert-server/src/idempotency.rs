@@ -0,0 +1,382 @@
+//! Idempotency keys for execute requests
+//!
+//! A client that retries `POST /api/v1/execute` with the same
+//! `Idempotency-Key` header is attached to the run started by the first
+//! request instead of starting a second claude process. Keyed runs are
+//! executed independently of the connection that started them; their
+//! events are kept in an [`EventLog`] so that later requests can replay
+//! them from the beginning and follow the rest as it arrives.
+//!
+//! Keys are scoped to the API key of the caller and remembered for a
+//! configurable window (`limits.idempotency_window_seconds`).
+
+use crate::error::RobertError;
+use crate::models::ClaudeEvent;
+use futures::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+use uuid::Uuid;
+
+/// Longest accepted idempotency key
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Events emitted by a keyed run
+///
+/// Events are appended by the task running the session and can be read
+/// by any number of subscribers, each of which starts at the first event.
+#[derive(Debug)]
+pub struct EventLog {
+    /// Events emitted so far
+    events: StdMutex<Vec<ClaudeEvent>>,
+
+    /// Signals new events; the value is true once the run has finished
+    updates: watch::Sender<bool>,
+}
+
+impl EventLog {
+    /// Creates an empty, unfinished log
+    pub fn new() -> Self {
+        Self {
+            events: StdMutex::new(Vec::new()),
+            updates: watch::Sender::new(false),
+        }
+    }
+
+    /// Appends an event and wakes subscribers
+    pub fn push(&self, event: ClaudeEvent) {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(event);
+        self.updates.send_modify(|_| {});
+    }
+
+    /// Marks the run as finished; subscribers end after the last event
+    pub fn finish(&self) {
+        self.updates.send_replace(true);
+    }
+
+    /// Returns true once the run has finished
+    pub fn is_finished(&self) -> bool {
+        *self.updates.borrow()
+    }
+
+    /// Returns the events emitted so far
+    pub fn snapshot(&self) -> Vec<ClaudeEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Streams all events of the run, from the first one
+    ///
+    /// # Returns
+    /// Stream that yields the events emitted so far, then follows the run
+    /// until it finishes
+    pub fn subscribe(self: Arc<Self>) -> impl Stream<Item = ClaudeEvent> + Send + 'static {
+        let mut updates = self.updates.subscribe();
+
+        async_stream::stream! {
+            let mut next = 0;
+            loop {
+                // Read the flag before the events: everything pushed before
+                // the run finished is then part of this batch
+                let finished = *updates.borrow_and_update();
+                let batch: Vec<ClaudeEvent> = self
+                    .events
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())[next..]
+                    .to_vec();
+                next += batch.len();
+
+                for event in batch {
+                    yield event;
+                }
+
+                if finished || updates.changed().await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of claiming an idempotency key
+#[derive(Debug, Clone)]
+pub enum Claim {
+    /// The key is new; the caller starts the run and fills the log
+    New(Arc<EventLog>),
+
+    /// The key belongs to a run that was already started
+    Existing(Arc<EventLog>),
+}
+
+/// A remembered idempotency key
+#[derive(Debug)]
+struct Entry {
+    /// Session started for the key
+    session_id: Uuid,
+
+    /// When the key was first used
+    created_at: Instant,
+
+    /// Events of the session
+    log: Arc<EventLog>,
+}
+
+/// Idempotency keys of recent execute requests
+///
+/// Thread-safe; keys are scoped to the API key that sent them.
+#[derive(Debug)]
+pub struct IdempotencyStore {
+    /// Entries by (owner, idempotency key)
+    entries: Mutex<HashMap<(String, String), Entry>>,
+
+    /// Maximum number of keys remembered at once
+    max_entries: usize,
+}
+
+impl IdempotencyStore {
+    /// Creates an empty store
+    ///
+    /// # Arguments
+    /// * `max_entries` - Maximum number of keys remembered at once; the
+    ///   oldest keys are forgotten first
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+        }
+    }
+
+    /// Claims a key for a session
+    ///
+    /// # Arguments
+    /// * `owner` - Bearer token of the caller (empty when auth is disabled)
+    /// * `key` - Idempotency key sent by the caller
+    /// * `session_id` - Session the request wants to start
+    /// * `window` - How long keys are remembered
+    ///
+    /// # Returns
+    /// Claim::New if the key is unknown or expired, Claim::Existing if a
+    /// run for the same session was started with it
+    ///
+    /// # Errors
+    /// Returns RobertError::InvalidRequest if the key is malformed or was
+    /// used for a different session
+    pub async fn claim(
+        &self,
+        owner: &str,
+        key: &str,
+        session_id: Uuid,
+        window: Duration,
+    ) -> Result<Claim, RobertError> {
+        validate_key(key)?;
+
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+        entries.retain(|_, entry| now.duration_since(entry.created_at) < window);
+
+        let id = (owner.to_string(), key.to_string());
+        if let Some(entry) = entries.get(&id) {
+            if entry.session_id != session_id {
+                return Err(RobertError::InvalidRequest(format!(
+                    "Idempotency-Key was already used for session {}",
+                    entry.session_id
+                )));
+            }
+            return Ok(Claim::Existing(entry.log.clone()));
+        }
+
+        if entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created_at)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        let log = Arc::new(EventLog::new());
+        entries.insert(
+            id,
+            Entry {
+                session_id,
+                created_at: now,
+                log: log.clone(),
+            },
+        );
+        Ok(Claim::New(log))
+    }
+
+    /// Forgets a key whose run could not be started
+    ///
+    /// Requests already attached to the run receive `error` and end, and
+    /// the next request with the key starts afresh.
+    ///
+    /// # Arguments
+    /// * `owner` - Bearer token of the caller
+    /// * `key` - Idempotency key
+    /// * `error` - Why the run could not be started
+    pub async fn release(&self, owner: &str, key: &str, error: &RobertError) {
+        let entry = self
+            .entries
+            .lock()
+            .await
+            .remove(&(owner.to_string(), key.to_string()));
+
+        if let Some(entry) = entry {
+            entry.log.push(ClaudeEvent::Error {
+                code: error.error_code(),
+                message: error.to_string(),
+            });
+            entry.log.finish();
+        }
+    }
+
+    /// Number of remembered keys
+    pub async fn len(&self) -> usize {
+        self.entries.lock().await.len()
+    }
+
+    /// Returns true if no keys are remembered
+    pub async fn is_empty(&self) -> bool {
+        self.entries.lock().await.is_empty()
+    }
+}
+
+/// Checks that a key is 1 to MAX_KEY_LENGTH printable ASCII characters
+fn validate_key(key: &str) -> Result<(), RobertError> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(RobertError::InvalidRequest(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_KEY_LENGTH
+        )));
+    }
+    if !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(RobertError::InvalidRequest(
+            "Idempotency-Key must be printable ASCII".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn content(text: &str) -> ClaudeEvent {
+        ClaudeEvent::Content {
+            text: text.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_replay_and_follow() {
+        let log = Arc::new(EventLog::new());
+        log.push(content("one"));
+
+        let early = tokio::spawn(log.clone().subscribe().collect::<Vec<_>>());
+        tokio::task::yield_now().await;
+        log.push(content("two"));
+        log.finish();
+        let late = log.clone().subscribe().collect::<Vec<_>>().await;
+
+        let early = early.await.unwrap();
+        assert_eq!(early.len(), 2);
+        assert_eq!(late.len(), 2);
+        assert!(log.is_finished());
+        assert_eq!(log.snapshot().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_claim_attaches_to_existing_run() {
+        let store = IdempotencyStore::new(10);
+        let session_id = Uuid::new_v4();
+
+        let first = store.claim("key", "retry-1", session_id, WINDOW).await;
+        let log = match first.unwrap() {
+            Claim::New(log) => log,
+            Claim::Existing(_) => panic!("Expected a new claim"),
+        };
+        log.push(content("hi"));
+
+        match store.claim("key", "retry-1", session_id, WINDOW).await {
+            Ok(Claim::Existing(existing)) => assert!(Arc::ptr_eq(&log, &existing)),
+            other => panic!("Expected existing claim, got {:?}", other),
+        }
+
+        // Keys are scoped to the caller
+        assert!(matches!(
+            store.claim("other", "retry-1", session_id, WINDOW).await,
+            Ok(Claim::New(_))
+        ));
+
+        // A key cannot be reused for another session
+        assert!(store
+            .claim("key", "retry-1", Uuid::new_v4(), WINDOW)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_released_keys_start_afresh() {
+        let store = IdempotencyStore::new(10);
+        let session_id = Uuid::new_v4();
+
+        store
+            .claim("key", "a", session_id, Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.claim("key", "a", session_id, Duration::ZERO).await,
+            Ok(Claim::New(_))
+        ));
+
+        let log = match store.claim("key", "b", session_id, WINDOW).await.unwrap() {
+            Claim::New(log) => log,
+            Claim::Existing(_) => panic!("Expected a new claim"),
+        };
+        store
+            .release("key", "b", &RobertError::Internal("full".to_string()))
+            .await;
+        assert!(log.is_finished());
+        assert!(matches!(log.snapshot()[0], ClaudeEvent::Error { .. }));
+        assert!(matches!(
+            store.claim("key", "b", session_id, WINDOW).await,
+            Ok(Claim::New(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_store_is_bounded_and_validates_keys() {
+        let store = IdempotencyStore::new(2);
+        for key in ["a", "b", "c"] {
+            store
+                .claim("key", key, Uuid::new_v4(), WINDOW)
+                .await
+                .unwrap();
+        }
+        assert_eq!(store.len().await, 2);
+
+        for key in ["", "has space", &"x".repeat(MAX_KEY_LENGTH + 1)] {
+            assert!(store
+                .claim("key", key, Uuid::new_v4(), WINDOW)
+                .await
+                .is_err());
+        }
+    }
+}
@@ -11,8 +11,15 @@
 //! - **HTTP/HTTPS Server**: Built on Warp with async Tokio runtime
 //! - **Authentication**: Bearer token validation with rate limiting
 //! - **Session Management**: Tracks concurrent claude-cli executions
+//! - **Conversations**: Multi-turn sessions that resume claude's own session
 //! - **Streaming**: Server-Sent Events (SSE) for real-time response streaming
+//! - **Idempotency**: Retried execute requests attach to the run they started
+//! - **Webhooks**: Signed session summaries POSTed to allow-listed callback URLs
+//! - **Batches**: Many requests run in the background within concurrency limits
+//! - **Scheduled Jobs**: Requests run on cron schedules with a run history
 //! - **Process Management**: Spawns and manages headless claude-cli processes
+//! - **Client**: Typed [`client::RobertClient`] for talking to a running server
+//! - **Hot Reload**: Re-reads the config file on SIGHUP without dropping sessions
 //!
 //! # Example
 //!
@@ -28,12 +35,19 @@
 
 pub mod api;
 pub mod auth;
+pub mod batch;
 pub mod claude;
+pub mod client;
 pub mod config;
+pub mod conversation;
 pub mod error;
+pub mod idempotency;
 pub mod models;
+pub mod reload;
+pub mod scheduler;
 pub mod server;
 pub mod session;
+pub mod webhook;
 
 // Re-export commonly used types
 pub use config::Config;
@@ -42,3 +56,4 @@ pub use models::{
     ClaudeEvent, HealthResponse, RequestContext, RobertRequest, Screenshot, SessionState,
     SessionStatus,
 };
+pub use reload::{ConfigReloader, SharedConfig};
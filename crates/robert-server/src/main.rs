@@ -2,81 +2,106 @@
 //!
 //! This binary starts the Warp web server with configured routes and middleware.
 
-use robert_server::{server, Config};
+use robert_server::{config::CliOverrides, reload::LogLevelHook, server, Config};
 use std::env;
 use std::path::PathBuf;
 use tracing::info;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
     let mut dev_mode = false;
-    let mut mock_mode = false;
+    let mut overrides = CliOverrides::default();
     let mut config_path = None;
 
     for i in 0..args.len() {
         match args[i].as_str() {
             "--dev" => dev_mode = true,
-            "--mock" => mock_mode = true,
-            "--config" => {
-                if i + 1 < args.len() {
-                    config_path = Some(PathBuf::from(&args[i + 1]));
-                }
+            "--mock" => overrides.mock_mode = true,
+            "--config" if i + 1 < args.len() => {
+                config_path = Some(PathBuf::from(&args[i + 1]));
             }
             _ => {}
         }
     }
 
-    // Load configuration
-    let mut config = if let Some(path) = config_path {
-        Config::from_file(path)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
+    // Load configuration, remembering the file it came from for reloads
+    let (mut config, loaded_from) = if let Some(path) = config_path {
+        let config = Config::from_file(&path)
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+        (config, Some(path))
     } else if dev_mode {
-        Config::dev_default()
+        (Config::dev_default(), None)
     } else {
         // Default to looking for config.toml in current directory
         match Config::from_file("config.toml") {
-            Ok(c) => c,
+            Ok(c) => (c, Some(PathBuf::from("config.toml"))),
             Err(_) => {
                 // specific fallback for dev convenience if config missing
-                Config::dev_default()
+                (Config::dev_default(), None)
             }
         }
     };
 
-    // Override mock mode if specified on command line
-    if mock_mode {
-        config.claude.mock_mode = true;
-    }
+    // Command line settings win over the file (and keep winning on reload)
+    overrides.apply(&mut config);
 
     // Initialize logging
-    init_logging(&config);
+    let log_level_hook = init_logging(&config);
 
     info!("Starting Robert Server v{}", env!("CARGO_PKG_VERSION"));
 
-    // Run server
-    server::run(config).await
+    // Run server, reloading the config file on SIGHUP when there is one
+    match loaded_from {
+        Some(path) => server::run_with_reload(config, path, log_level_hook, overrides).await,
+        None => server::run(config).await,
+    }
 }
 
 /// Initializes logging based on configuration
-fn init_logging(config: &Config) {
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&config.logging.level));
+///
+/// Returns a hook that changes the log level at runtime, unless the
+/// level is pinned by RUST_LOG.
+fn init_logging(config: &Config) -> Option<LogLevelHook> {
+    let env_filter = EnvFilter::try_from_default_env().ok();
+    let pinned = env_filter.is_some();
+    let filter = env_filter.unwrap_or_else(|| EnvFilter::new(&config.logging.level));
 
-    if config.logging.pretty_print {
-        tracing_subscriber::fmt()
+    let hook: LogLevelHook = if config.logging.pretty_print {
+        let builder = tracing_subscriber::fmt()
             .with_env_filter(filter)
             .with_target(true)
             .with_thread_ids(true)
             .with_file(true)
             .with_line_number(true)
             .pretty()
-            .init();
+            .with_filter_reloading();
+        let handle = builder.reload_handle();
+        builder.init();
+        Box::new(move |level| {
+            handle
+                .reload(EnvFilter::new(level))
+                .map_err(|e| e.to_string())
+        })
     } else {
-        tracing_subscriber::fmt()
+        let builder = tracing_subscriber::fmt()
             .with_env_filter(filter)
             .json()
-            .init();
+            .with_filter_reloading();
+        let handle = builder.reload_handle();
+        builder.init();
+        Box::new(move |level| {
+            handle
+                .reload(EnvFilter::new(level))
+                .map_err(|e| e.to_string())
+        })
+    };
+
+    if pinned {
+        None
+    } else {
+        Some(hook)
     }
 }
@@ -4,15 +4,17 @@
 //! All types are designed for efficient serialization/deserialization
 //! and include comprehensive validation logic.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use uuid::Uuid;
 
 /// Screenshot metadata containing window and viewport information
 ///
 /// Captures the context of where a screenshot was taken, including
 /// window title, current URL (for web content), and viewport dimensions.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ScreenshotMetadata {
     /// Window title or application name
     pub window_title: String,
@@ -26,7 +28,7 @@ pub struct ScreenshotMetadata {
 }
 
 /// Viewport dimensions in pixels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Viewport {
     /// Width in pixels
     pub width: u32,
@@ -39,7 +41,7 @@ pub struct Viewport {
 ///
 /// Contains base64-encoded PNG image data along with metadata
 /// about when and where the screenshot was captured.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Screenshot {
     /// ISO 8601 timestamp when screenshot was captured
     pub timestamp: String,
@@ -104,7 +106,7 @@ impl Screenshot {
 ///
 /// Contains the accessibility tree and list of interactive elements
 /// from the current page or application state.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct DomState {
     /// Serialized accessibility tree
     pub accessible_tree: String,
@@ -117,7 +119,7 @@ pub struct DomState {
 ///
 /// Aggregates screenshots, DOM state, and user intent to provide
 /// complete context for Claude to understand the automation task.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct RequestContext {
     /// List of screenshots (ordered chronologically)
     pub screenshots: Vec<Screenshot>,
@@ -185,8 +187,10 @@ impl RequestContext {
 
 /// Request options for execution
 ///
-/// Configures timeout, token limits, and streaming behavior.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Configures timeout, token limits, streaming behavior and the tool
+/// permissions requested for claude-cli. Permissions are checked against
+/// the server's policy before execution.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct RequestOptions {
     /// Timeout in seconds (overrides server default)
     #[serde(default = "default_timeout")]
@@ -199,6 +203,43 @@ pub struct RequestOptions {
     /// Enable streaming response
     #[serde(default = "default_stream")]
     pub stream: bool,
+
+    /// Tools claude may use without asking (passed as --allowed-tools)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+
+    /// Tools claude must not use (passed as --disallowed-tools)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disallowed_tools: Vec<String>,
+
+    /// Additional directories claude may access (passed as --add-dir)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_dirs: Vec<PathBuf>,
+
+    /// Skip permission prompts (passed as --dangerously-skip-permissions)
+    #[serde(default)]
+    pub skip_permissions: bool,
+
+    /// Preferred model: a concrete model name or a configured alias such as "fast"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Conversation this request continues (see POST /api/v1/conversations)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<Uuid>,
+
+    /// URL that receives a signed summary when the session ends
+    ///
+    /// Must match the server's `webhooks.allowed_urls`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+
+    /// Claude session to resume (passed as --resume)
+    ///
+    /// Set by the server from the request's conversation; never read from
+    /// or sent over the wire.
+    #[serde(skip)]
+    pub resume_session: Option<String>,
 }
 
 fn default_timeout() -> u64 {
@@ -219,6 +260,14 @@ impl Default for RequestOptions {
             timeout_seconds: default_timeout(),
             max_tokens: default_max_tokens(),
             stream: default_stream(),
+            allowed_tools: vec![],
+            disallowed_tools: vec![],
+            allowed_dirs: vec![],
+            skip_permissions: false,
+            model: None,
+            conversation_id: None,
+            callback_url: None,
+            resume_session: None,
         }
     }
 }
@@ -227,7 +276,7 @@ impl Default for RequestOptions {
 ///
 /// Contains all information needed to execute a Claude CLI session,
 /// including context, prompt, and execution options.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct RobertRequest {
     /// Unique session identifier (UUIDv4)
     pub session_id: Uuid,
@@ -284,6 +333,18 @@ impl RobertRequest {
             return Err("Timeout cannot exceed 1 hour".to_string());
         }
 
+        if let Some(model) = &self.options.model {
+            if model.trim().is_empty() {
+                return Err("Model cannot be empty".to_string());
+            }
+
+            // The model is passed to claude-cli and must not read as a flag
+            if model.starts_with('-') || model.chars().any(|c| c.is_whitespace() || c.is_control())
+            {
+                return Err(format!("Invalid model name: {:?}", model));
+            }
+        }
+
         Ok(())
     }
 
@@ -301,11 +362,69 @@ impl RobertRequest {
     }
 }
 
+/// Token usage and cost of an execution
+///
+/// Token counts follow the fields reported by claude-cli. `model` is the
+/// model that produced the usage, if known.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Usage {
+    /// Input (prompt) tokens
+    #[serde(default)]
+    pub input_tokens: u64,
+
+    /// Output (completion) tokens
+    #[serde(default)]
+    pub output_tokens: u64,
+
+    /// Tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+
+    /// Tokens read from the prompt cache
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+
+    /// Cost in US dollars
+    #[serde(default)]
+    pub cost_usd: f64,
+
+    /// Model the usage was billed against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Usage {
+    /// Returns the total number of tokens of all kinds
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    /// Adds another usage record to this one
+    ///
+    /// The model is kept if both records agree and cleared otherwise.
+    ///
+    /// # Arguments
+    /// * `other` - Usage to add
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+        if self.model != other.model {
+            self.model = None;
+        }
+    }
+}
+
 /// Event types streamed from Claude CLI
 ///
 /// Represents different types of events that can be sent
 /// via Server-Sent Events (SSE) during execution.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClaudeEvent {
     /// Text content from Claude
@@ -321,10 +440,33 @@ pub enum ClaudeEvent {
     Error { code: String, message: String },
 
     /// Execution complete
-    Complete { session_id: Uuid, status: String },
+    Complete {
+        session_id: Uuid,
+        status: String,
+        /// Tokens and cost consumed, when reported by claude-cli
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
 
     /// Progress update
     Progress { message: String, percent: u8 },
+
+    /// claude-cli reported the id of its session
+    ///
+    /// Conversations resume the session with this id on their next turn.
+    SessionStarted { claude_session_id: String },
+
+    /// Claude needs an answer before it can continue
+    ///
+    /// Only interactive sessions (WebSocket) can answer; the answer is
+    /// sent back as [`SessionInput::Answer`].
+    Clarification {
+        question: String,
+        #[serde(default)]
+        options: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+    },
 }
 
 impl ClaudeEvent {
@@ -336,24 +478,113 @@ impl ClaudeEvent {
     /// # Returns
     /// String in SSE format: "event: type\ndata: json\n\n"
     pub fn to_sse(&self) -> String {
-        let event_type = match self {
+        let data = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+
+        format!("event: {}\ndata: {}\n\n", self.event_type(), data)
+    }
+
+    /// Returns the SSE event name for this event
+    ///
+    /// # Returns
+    /// The event's `type` tag, e.g. "content" or "complete"
+    pub fn event_type(&self) -> &'static str {
+        match self {
             ClaudeEvent::Content { .. } => "content",
             ClaudeEvent::ToolUse { .. } => "tool_use",
             ClaudeEvent::Error { .. } => "error",
             ClaudeEvent::Complete { .. } => "complete",
             ClaudeEvent::Progress { .. } => "progress",
-        };
+            ClaudeEvent::SessionStarted { .. } => "session_started",
+            ClaudeEvent::Clarification { .. } => "clarification",
+        }
+    }
 
+    /// Converts event to a warp SSE event
+    ///
+    /// # Returns
+    /// Event named after the event type carrying the JSON-encoded event
+    pub fn to_sse_event(&self) -> warp::sse::Event {
         let data = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
 
-        format!("event: {}\ndata: {}\n\n", event_type, data)
+        warp::sse::Event::default()
+            .event(self.event_type())
+            .data(data)
     }
 }
 
+/// Input sent to a running interactive session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionInput {
+    /// Answer to a [`ClaudeEvent::Clarification`]
+    Answer { text: String },
+
+    /// Updated browser context (new screenshots, DOM state, intent)
+    Context { context: RequestContext },
+}
+
+/// Message sent by a client over the WebSocket transport
+///
+/// Every message carries the client's token, which is validated (and
+/// rate limited) per message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WsClientFrame {
+    /// Bearer token; may be omitted when auth is disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+
+    /// The message itself
+    #[serde(flatten)]
+    pub message: WsClientMessage,
+}
+
+/// Client-to-server WebSocket messages
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsClientMessage {
+    /// Start a new session
+    Execute { request: RobertRequest },
+
+    /// Answer a clarification question of a running session
+    Answer { session_id: Uuid, text: String },
+
+    /// Send new browser context to a running session
+    Context {
+        session_id: Uuid,
+        context: RequestContext,
+    },
+
+    /// Cancel a running session
+    Cancel { session_id: Uuid },
+}
+
+/// Server-to-client WebSocket messages
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+    /// A session was accepted and started
+    Accepted { session_id: Uuid },
+
+    /// Event emitted by a running session
+    Event {
+        session_id: Uuid,
+        event: ClaudeEvent,
+    },
+
+    /// A client message was rejected
+    Error {
+        /// Session the rejected message referred to, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<Uuid>,
+        code: String,
+        message: String,
+    },
+}
+
 /// Health check response
 ///
 /// Provides server status information including Claude CLI availability.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct HealthResponse {
     /// Overall health status
     pub status: String,
@@ -371,7 +602,7 @@ pub struct HealthResponse {
 /// Session status information
 ///
 /// Tracks the state of an execution session.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct SessionStatus {
     /// Session UUID
     pub session_id: Uuid,
@@ -389,10 +620,471 @@ pub struct SessionStatus {
     /// Error message (if failed)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+
+    /// Tokens and cost consumed so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Usage report for an API key
+///
+/// Returned by GET /api/v1/usage. Covers the caller's own key only.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct UsageReport {
+    /// Usage for the current UTC day
+    pub today: Usage,
+
+    /// Usage for the current UTC month
+    pub this_month: Usage,
+
+    /// Budget limits that apply to the key
+    pub budget: crate::config::BudgetLimits,
+
+    /// Per-day usage, most recent first
+    pub by_day: Vec<DailyUsage>,
+
+    /// Usage per model over the reported days
+    pub by_model: BTreeMap<String, Usage>,
+}
+
+/// Usage for a single UTC day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DailyUsage {
+    /// Date in YYYY-MM-DD format
+    pub date: String,
+
+    /// Total usage for the day
+    pub usage: Usage,
+
+    /// Usage per model for the day
+    pub by_model: BTreeMap<String, Usage>,
+}
+
+/// Conversation transcript
+///
+/// Returned by the /api/v1/conversations endpoints. Each execute request
+/// that references the conversation adds a turn.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Conversation {
+    /// Conversation UUID
+    pub conversation_id: Uuid,
+
+    /// When the conversation was created
+    pub created_at: String,
+
+    /// When the last turn started or finished
+    pub updated_at: String,
+
+    /// claude-cli session resumed by the next turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude_session_id: Option<String>,
+
+    /// Turns in the order they were started
+    #[serde(default)]
+    pub turns: Vec<ConversationTurn>,
+}
+
+/// A single prompt and response within a conversation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ConversationTurn {
+    /// Session that executed the turn
+    pub session_id: Uuid,
+
+    /// Prompt sent by the client
+    pub prompt: String,
+
+    /// Content produced by claude, concatenated
+    pub response: String,
+
+    /// State of the turn's session
+    pub status: SessionState,
+
+    /// When the turn started
+    pub started_at: String,
+
+    /// When the turn finished (if finished)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+}
+
+/// Summary POSTed to a session's `callback_url` when it ends
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WebhookPayload {
+    /// Session that ended
+    pub session_id: Uuid,
+
+    /// Final state of the session
+    pub status: SessionState,
+
+    /// Content produced by claude, concatenated
+    pub content: String,
+
+    /// Tools claude used, in order
+    #[serde(default)]
+    pub tool_uses: Vec<WebhookToolUse>,
+
+    /// Tokens and cost consumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+
+    /// Error message (if failed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// When the session ended
+    pub completed_at: String,
+}
+
+/// A tool use reported in a webhook summary
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WebhookToolUse {
+    /// Tool name
+    pub tool: String,
+
+    /// Tool parameters
+    pub params: serde_json::Value,
+}
+
+/// Outcome of delivering a webhook
+///
+/// Returned by GET /api/v1/webhooks/deliveries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WebhookDelivery {
+    /// Delivery UUID, also sent as the `X-Robert-Delivery` header
+    pub delivery_id: Uuid,
+
+    /// Session the summary describes
+    pub session_id: Uuid,
+
+    /// Callback URL
+    pub url: String,
+
+    /// Current state of the delivery
+    pub status: DeliveryStatus,
+
+    /// Attempts made so far
+    pub attempts: u32,
+
+    /// HTTP status of the last response, if one was received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status_code: Option<u16>,
+
+    /// Why the last attempt failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    /// When the delivery was queued
+    pub created_at: String,
+
+    /// When the delivery succeeded or was given up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// State of a webhook delivery
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Attempts are in progress
+    Pending,
+
+    /// The callback answered with a 2xx status
+    Delivered,
+
+    /// All attempts failed
+    Failed,
+}
+
+/// Request payload for POST /api/v1/batches
+///
+/// Either lists the requests to run or gives a template with a parameter
+/// table; each parameter row produces one request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchRequest {
+    /// Requests to run, each with its own session id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests: Option<Vec<RobertRequest>>,
+
+    /// Template expanded once per parameter row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<BatchTemplate>,
+
+    /// Maximum number of items running at once (default 4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
+}
+
+/// Request template with a parameter table
+///
+/// `{{name}}` placeholders in the template's prompt and user intent are
+/// replaced with the row's value for `name`. Every item gets a new session
+/// id; the template's own is ignored.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchTemplate {
+    /// Request to expand
+    pub request: RobertRequest,
+
+    /// One row of placeholder values per item
+    pub parameters: Vec<BTreeMap<String, String>>,
+}
+
+/// Batch progress and per-item status
+///
+/// Returned by the /api/v1/batches endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchStatus {
+    /// Batch UUID
+    pub batch_id: Uuid,
+
+    /// Overall state of the batch
+    pub status: BatchState,
+
+    /// When the batch was created
+    pub created_at: String,
+
+    /// When the last item finished (if finished)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+
+    /// Maximum number of items running at once
+    pub max_parallel: usize,
+
+    /// Item counts by state
+    pub progress: BatchProgress,
+
+    /// Items in submission order
+    pub items: Vec<BatchItemStatus>,
+}
+
+/// Number of batch items in each state
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchProgress {
+    /// Number of items
+    pub total: usize,
+
+    /// Items waiting for a free slot
+    pub queued: usize,
+
+    /// Items currently running
+    pub running: usize,
+
+    /// Items that completed successfully
+    pub completed: usize,
+
+    /// Items that failed
+    pub failed: usize,
+
+    /// Items cancelled before finishing
+    pub cancelled: usize,
+}
+
+/// A single item of a batch
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchItemStatus {
+    /// Position of the item in the batch
+    pub index: usize,
+
+    /// Session that runs the item
+    pub session_id: Uuid,
+
+    /// State of the item
+    pub status: BatchItemState,
+
+    /// Status of the item's session, once it has started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionStatus>,
+}
+
+/// Result line of GET /api/v1/batches/:id/results (JSONL)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchItemResult {
+    /// Position of the item in the batch
+    pub index: usize,
+
+    /// Session that ran the item
+    pub session_id: Uuid,
+
+    /// State of the item
+    pub status: BatchItemState,
+
+    /// Content produced by claude, concatenated
+    pub content: String,
+
+    /// Tokens and cost consumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+
+    /// Error message (if failed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// State of a batch
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchState {
+    /// Items are queued or running
+    Running,
+
+    /// Every item has finished
+    Completed,
+
+    /// The batch was cancelled
+    Cancelled,
+}
+
+/// State of a batch item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemState {
+    /// Waiting for a free slot
+    Queued,
+
+    /// Session is running
+    Running,
+
+    /// Session completed successfully
+    Completed,
+
+    /// Session failed or could not be started
+    Failed,
+
+    /// Cancelled before finishing
+    Cancelled,
+}
+
+/// Request payload for POST and PUT /api/v1/jobs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct JobDefinition {
+    /// Human-readable name
+    pub name: String,
+
+    /// Cron expression, evaluated in UTC
+    ///
+    /// Five fields (`minute hour day month weekday`), or six with leading
+    /// seconds.
+    pub schedule: String,
+
+    /// Request run on every occurrence; each run gets a new session id
+    pub request: RobertRequest,
+
+    /// What to do about occurrences missed while the server was stopped
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+
+    /// Whether the job is paused
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Scheduled job with its run history
+///
+/// Returned by the /api/v1/jobs endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Job {
+    /// Job UUID
+    pub job_id: Uuid,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Cron expression, evaluated in UTC
+    pub schedule: String,
+
+    /// Request run on every occurrence
+    pub request: RobertRequest,
+
+    /// What to do about occurrences missed while the server was stopped
+    pub missed_runs: MissedRunPolicy,
+
+    /// Whether the job is active or paused
+    pub state: JobState,
+
+    /// When the job was created
+    pub created_at: String,
+
+    /// When the job was last changed
+    pub updated_at: String,
+
+    /// Next scheduled run (none while paused)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+
+    /// Most recent runs, oldest first
+    #[serde(default)]
+    pub runs: Vec<JobRun>,
+}
+
+/// A single run of a scheduled job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct JobRun {
+    /// Occurrence of the schedule the run belongs to
+    pub scheduled_for: String,
+
+    /// When the run started (or was skipped)
+    pub started_at: String,
+
+    /// Session that executed the run (none if skipped)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<Uuid>,
+
+    /// State of the run
+    pub status: JobRunState,
+
+    /// When the run finished (if finished)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+
+    /// Error message or reason the run was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Handling of runs missed while the server was stopped
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Record the missed runs as skipped and wait for the next occurrence
+    #[default]
+    Skip,
+
+    /// Run once as soon as possible, however many occurrences were missed
+    RunOnce,
+}
+
+/// State of a scheduled job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    /// Runs on schedule
+    Active,
+
+    /// Does not run until resumed
+    Paused,
+}
+
+/// State of a job run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobRunState {
+    /// Session is running
+    Running,
+
+    /// Session completed successfully
+    Completed,
+
+    /// Session failed or could not be started
+    Failed,
+
+    /// Session was cancelled
+    Cancelled,
+
+    /// Occurrence was not run
+    Skipped,
 }
 
 /// Session execution state
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionState {
     /// Session is currently executing
@@ -610,12 +1302,58 @@ mod tests {
         assert!(result.unwrap_err().contains("cannot exceed 1 hour"));
     }
 
+    #[test]
+    fn test_request_empty_model() {
+        let request = RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: create_valid_context(),
+            prompt: "test".to_string(),
+            options: RequestOptions {
+                model: Some(" ".to_string()),
+                ..Default::default()
+            },
+        };
+        let result = request.validate(10, 50000, 1000);
+        assert!(result.unwrap_err().contains("Model cannot be empty"));
+    }
+
+    #[test]
+    fn test_request_model_flag_injection() {
+        for model in ["--dangerously-skip-permissions", "-p", "sonnet --verbose"] {
+            let request = RobertRequest {
+                session_id: Uuid::new_v4(),
+                context: create_valid_context(),
+                prompt: "test".to_string(),
+                options: RequestOptions {
+                    model: Some(model.to_string()),
+                    ..Default::default()
+                },
+            };
+            let result = request.validate(10, 50000, 1000);
+            assert!(result.unwrap_err().contains("Invalid model name"));
+        }
+    }
+
     #[test]
     fn test_request_options_defaults() {
         let options = RequestOptions::default();
         assert_eq!(options.timeout_seconds, 300);
         assert_eq!(options.max_tokens, 100000);
         assert!(options.stream);
+        assert!(options.allowed_tools.is_empty());
+        assert!(!options.skip_permissions);
+    }
+
+    #[test]
+    fn test_request_options_permissions_deserialize() {
+        let options: RequestOptions = serde_json::from_str(
+            r#"{"allowed_tools":["Read","Bash(git:*)"],"allowed_dirs":["/srv/data"]}"#,
+        )
+        .unwrap();
+        assert_eq!(options.allowed_tools, vec!["Read", "Bash(git:*)"]);
+        assert_eq!(options.allowed_dirs, vec![PathBuf::from("/srv/data")]);
+        assert!(options.disallowed_tools.is_empty());
+        assert_eq!(options.timeout_seconds, 300);
     }
 
     #[test]
@@ -657,10 +1395,60 @@ mod tests {
         let event = ClaudeEvent::Complete {
             session_id: Uuid::new_v4(),
             status: "success".to_string(),
+            usage: None,
         };
         let sse = event.to_sse();
         assert!(sse.contains("event: complete"));
         assert!(sse.contains("success"));
+        assert!(!sse.contains("usage"));
+    }
+
+    #[test]
+    fn test_claude_event_complete_with_usage() {
+        let event = ClaudeEvent::Complete {
+            session_id: Uuid::new_v4(),
+            status: "success".to_string(),
+            usage: Some(Usage {
+                input_tokens: 10,
+                output_tokens: 5,
+                cost_usd: 0.25,
+                ..Default::default()
+            }),
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        let parsed: ClaudeEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, event);
+
+        // Events without usage still deserialize
+        let legacy: ClaudeEvent = serde_json::from_str(
+            r#"{"type":"complete","session_id":"550e8400-e29b-41d4-a716-446655440000","status":"success"}"#,
+        )
+        .unwrap();
+        assert!(matches!(legacy, ClaudeEvent::Complete { usage: None, .. }));
+    }
+
+    #[test]
+    fn test_usage_add() {
+        let mut total = Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: 0.5,
+            model: Some("a".to_string()),
+            ..Default::default()
+        };
+        total.add(&Usage {
+            input_tokens: 1,
+            cache_read_input_tokens: 4,
+            cost_usd: 0.25,
+            model: Some("b".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(total.input_tokens, 11);
+        assert_eq!(total.total_tokens(), 20);
+        assert_eq!(total.cost_usd, 0.75);
+        assert_eq!(total.model, None);
     }
 
     #[test]
@@ -671,6 +1459,7 @@ mod tests {
             started_at: "2025-10-17T10:30:00Z".to_string(),
             completed_at: None,
             error: None,
+            usage: None,
         };
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("running"));
@@ -0,0 +1,437 @@
+//! Configuration hot reload
+//!
+//! Re-reads the configuration file at runtime (on SIGHUP) and atomically
+//! swaps in the settings that are safe to change while sessions are in
+//! flight: auth tokens and rate limits, request limits, logging level,
+//! concurrency limits, tool permissions, model settings, usage budgets,
+//! webhook settings and mock scenarios. Settings that only take effect
+//! at startup (bind address, TLS, executor, sandbox and scheduler) are
+//! left untouched and reported so the operator knows a restart is needed.
+//!
+//! An invalid configuration file is rejected as a whole and the running
+//! configuration is kept.
+
+use crate::auth::AuthState;
+use crate::config::{CliOverrides, Config};
+use crate::error::RobertError;
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Configuration shared between the server and its handlers
+///
+/// Handlers take a snapshot with `load_full()` per request, so a reload
+/// never changes the configuration underneath a running request.
+pub type SharedConfig = Arc<ArcSwap<Config>>;
+
+/// Callback used to apply a new logging level
+///
+/// Receives the new `logging.level` value. Logging setup is owned by the
+/// binary, so the server only knows how to ask for a change.
+pub type LogLevelHook = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Outcome of a successful reload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Settings that changed in the file but require a restart to apply
+    pub ignored: Vec<String>,
+}
+
+/// Reloads the configuration file into a [`SharedConfig`]
+pub struct ConfigReloader {
+    /// Path of the configuration file
+    path: PathBuf,
+
+    /// Live configuration
+    config: SharedConfig,
+
+    /// Authentication state to update with new tokens and limits
+    auth_state: Arc<AuthState>,
+
+    /// Optional hook for applying a new logging level
+    log_level_hook: Option<LogLevelHook>,
+
+    /// Command line settings re-applied to every reloaded file
+    overrides: CliOverrides,
+}
+
+impl ConfigReloader {
+    /// Creates a new reloader
+    ///
+    /// # Arguments
+    /// * `path` - Configuration file to re-read on reload
+    /// * `config` - Live configuration to update
+    /// * `auth_state` - Authentication state to keep in sync with `[auth]`
+    ///
+    /// # Returns
+    /// New ConfigReloader instance
+    pub fn new(path: impl Into<PathBuf>, config: SharedConfig, auth_state: Arc<AuthState>) -> Self {
+        Self {
+            path: path.into(),
+            config,
+            auth_state,
+            log_level_hook: None,
+            overrides: CliOverrides::default(),
+        }
+    }
+
+    /// Sets the command line overrides applied on top of the file
+    ///
+    /// Without them, a reload would see every overridden setting as
+    /// changed in the file.
+    ///
+    /// # Arguments
+    /// * `overrides` - Overrides the server was started with
+    ///
+    /// # Returns
+    /// Self with the overrides installed
+    pub fn with_overrides(mut self, overrides: CliOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Sets the hook used to apply `logging.level` changes
+    ///
+    /// # Arguments
+    /// * `hook` - Callback receiving the new level
+    ///
+    /// # Returns
+    /// Self with the hook installed
+    pub fn with_log_level_hook(mut self, hook: LogLevelHook) -> Self {
+        self.log_level_hook = Some(hook);
+        self
+    }
+
+    /// Returns the path of the configuration file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Re-reads the configuration file and applies reloadable settings
+    ///
+    /// # Returns
+    /// Report of settings that changed but were not applied
+    ///
+    /// # Errors
+    /// Returns RobertError::Config if the file cannot be read, parsed or
+    /// validated, or if the new logging level cannot be applied. The
+    /// running configuration is unchanged in that case.
+    pub fn reload(&self) -> Result<ReloadReport, RobertError> {
+        let mut incoming = Config::from_file(&self.path)?;
+        self.overrides.apply(&mut incoming);
+        let current = self.config.load_full();
+
+        let (merged, report) = merge_reloadable(&current, incoming);
+        merged.validate()?;
+
+        if merged.logging.level != current.logging.level {
+            if let Some(hook) = &self.log_level_hook {
+                hook(&merged.logging.level).map_err(|e| {
+                    RobertError::Config(format!("Failed to apply log level: {}", e))
+                })?;
+            }
+        }
+
+        self.auth_state.update(
+            merged.valid_tokens(),
+            merged.auth.require_auth,
+            merged.auth.rate_limit_per_minute,
+        );
+        self.config.store(Arc::new(merged));
+
+        Ok(report)
+    }
+
+    /// Reloads the configuration each time the process receives SIGHUP
+    ///
+    /// Failed reloads are logged and the previous configuration stays
+    /// active.
+    ///
+    /// # Returns
+    /// Handle of the spawned listener task
+    #[cfg(unix)]
+    pub fn spawn_sighup_listener(self) -> std::io::Result<tokio::task::JoinHandle<()>> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+
+        Ok(tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("SIGHUP received, reloading {}", self.path.display());
+                self.reload_and_log();
+            }
+        }))
+    }
+
+    /// Runs a reload and logs the outcome
+    fn reload_and_log(&self) {
+        match self.reload() {
+            Ok(report) => {
+                info!("Configuration reloaded");
+                for setting in report.ignored {
+                    warn!("{} changed but requires a restart to take effect", setting);
+                }
+            }
+            Err(e) => error!("Configuration reload rejected, keeping previous: {}", e),
+        }
+    }
+}
+
+/// Combines the running configuration with a newly loaded one
+///
+/// Reloadable settings are taken from `incoming`; everything else is kept
+/// from `current`.
+///
+/// # Arguments
+/// * `current` - Running configuration
+/// * `incoming` - Configuration freshly read from disk
+///
+/// # Returns
+/// The configuration to activate and a report of ignored changes
+pub fn merge_reloadable(current: &Config, incoming: Config) -> (Config, ReloadReport) {
+    let mut ignored = Vec::new();
+
+    if differs(&current.server, &incoming.server) {
+        ignored.push("server".to_string());
+    }
+    if current.claude.binary_path != incoming.claude.binary_path {
+        ignored.push("claude.binary_path".to_string());
+    }
+    if current.claude.mock_mode != incoming.claude.mock_mode {
+        ignored.push("claude.mock_mode".to_string());
+    }
+    if current.claude.default_timeout_seconds != incoming.claude.default_timeout_seconds {
+        ignored.push("claude.default_timeout_seconds".to_string());
+    }
+    if current.claude.sandbox != incoming.claude.sandbox {
+        ignored.push("claude.sandbox".to_string());
+    }
+    if current.logging.pretty_print != incoming.logging.pretty_print {
+        ignored.push("logging.pretty_print".to_string());
+    }
+    if current.scheduler != incoming.scheduler {
+        ignored.push("scheduler".to_string());
+    }
+
+    let mut merged = current.clone();
+    merged.auth = incoming.auth;
+    merged.limits = incoming.limits;
+    merged.logging.level = incoming.logging.level;
+    merged.logging.sanitize_sensitive_data = incoming.logging.sanitize_sensitive_data;
+    merged.claude.max_concurrent_sessions = incoming.claude.max_concurrent_sessions;
+    merged.claude.mock = incoming.claude.mock;
+    merged.claude.permissions = incoming.claude.permissions;
+    merged.claude.models = incoming.claude.models;
+    merged.budgets = incoming.budgets;
+    merged.webhooks = incoming.webhooks;
+
+    (merged, ReloadReport { ignored })
+}
+
+/// Compares two config sections by their serialized form
+fn differs<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() != serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MockScenario;
+    use std::io::{Seek, Write};
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    const BASE_CONFIG: &str = r#"
+[server]
+port = 9000
+
+[auth]
+tokens = ["old-token"]
+require_auth = true
+rate_limit_per_minute = 10
+
+[claude]
+max_concurrent_sessions = 5
+
+[limits]
+[logging]
+level = "info"
+"#;
+
+    fn write_config(file: &mut NamedTempFile, contents: &str) {
+        let f = file.as_file_mut();
+        f.set_len(0).unwrap();
+        f.rewind().unwrap();
+        f.write_all(contents.as_bytes()).unwrap();
+        f.flush().unwrap();
+    }
+
+    fn setup() -> (NamedTempFile, SharedConfig, Arc<AuthState>, ConfigReloader) {
+        let mut file = NamedTempFile::new().unwrap();
+        write_config(&mut file, BASE_CONFIG);
+
+        let config = Config::from_file(file.path()).unwrap();
+        let auth_state = Arc::new(AuthState::new(
+            config.valid_tokens(),
+            config.auth.require_auth,
+            config.auth.rate_limit_per_minute,
+        ));
+        let shared: SharedConfig = Arc::new(ArcSwap::from_pointee(config));
+        let reloader = ConfigReloader::new(file.path(), shared.clone(), auth_state.clone());
+
+        (file, shared, auth_state, reloader)
+    }
+
+    #[test]
+    fn test_reload_applies_tokens_and_limits() {
+        let (mut file, shared, auth_state, reloader) = setup();
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG
+                .replace("old-token", "new-token")
+                .replace("max_concurrent_sessions = 5", "max_concurrent_sessions = 7"),
+        );
+
+        let report = reloader.reload().unwrap();
+        assert!(report.ignored.is_empty());
+
+        assert_eq!(shared.load().claude.max_concurrent_sessions, 7);
+        assert!(auth_state.validate_token("new-token").is_ok());
+        assert!(auth_state.validate_token("old-token").is_err());
+    }
+
+    #[test]
+    fn test_reload_invalid_config_keeps_previous() {
+        let (mut file, shared, auth_state, reloader) = setup();
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG
+                .replace("old-token", "new-token")
+                .replace("level = \"info\"", "level = \"loud\""),
+        );
+
+        assert!(matches!(reloader.reload(), Err(RobertError::Config(_))));
+        assert_eq!(shared.load().logging.level, "info");
+        assert!(auth_state.validate_token("old-token").is_ok());
+        assert!(auth_state.validate_token("new-token").is_err());
+    }
+
+    #[test]
+    fn test_reload_unparseable_config_keeps_previous() {
+        let (mut file, shared, _auth_state, reloader) = setup();
+
+        write_config(&mut file, "[server\nport = ");
+
+        assert!(reloader.reload().is_err());
+        assert_eq!(shared.load().server.port, 9000);
+    }
+
+    #[test]
+    fn test_reload_reports_restart_only_settings() {
+        let (mut file, shared, _auth_state, reloader) = setup();
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG.replace("port = 9000", "port = 9001"),
+        );
+
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.ignored, vec!["server".to_string()]);
+        assert_eq!(shared.load().server.port, 9000);
+    }
+
+    #[test]
+    fn test_reload_calls_log_level_hook() {
+        let (mut file, shared, auth_state, _) = setup();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let sink = applied.clone();
+        let reloader = ConfigReloader::new(file.path(), shared.clone(), auth_state)
+            .with_log_level_hook(Box::new(move |level| {
+                sink.lock().unwrap().push(level.to_string());
+                Ok(())
+            }));
+
+        // Unchanged level does not call the hook
+        reloader.reload().unwrap();
+        assert!(applied.lock().unwrap().is_empty());
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG.replace("level = \"info\"", "level = \"debug\""),
+        );
+        reloader.reload().unwrap();
+
+        assert_eq!(*applied.lock().unwrap(), vec!["debug".to_string()]);
+        assert_eq!(shared.load().logging.level, "debug");
+    }
+
+    #[test]
+    fn test_reload_hook_failure_keeps_previous() {
+        let (mut file, shared, auth_state, _) = setup();
+        let reloader = ConfigReloader::new(file.path(), shared.clone(), auth_state.clone())
+            .with_log_level_hook(Box::new(|_| Err("no reload handle".to_string())));
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG
+                .replace("old-token", "new-token")
+                .replace("level = \"info\"", "level = \"debug\""),
+        );
+
+        assert!(reloader.reload().is_err());
+        assert_eq!(shared.load().logging.level, "info");
+        assert!(auth_state.validate_token("old-token").is_ok());
+    }
+
+    #[test]
+    fn test_reload_reports_sandbox_change() {
+        let (mut file, shared, _auth_state, reloader) = setup();
+
+        write_config(
+            &mut file,
+            &BASE_CONFIG.replace(
+                "[limits]",
+                "[claude.sandbox]\nmax_open_files = 64\n\n[limits]",
+            ),
+        );
+
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.ignored, vec!["claude.sandbox".to_string()]);
+        assert_ne!(shared.load().claude.sandbox.max_open_files, Some(64));
+    }
+
+    #[test]
+    fn test_reload_keeps_cli_overrides() {
+        let (file, shared, auth_state, _) = setup();
+        let overrides = CliOverrides { mock_mode: true };
+
+        let mut started = Config::from_file(file.path()).unwrap();
+        overrides.apply(&mut started);
+        shared.store(Arc::new(started));
+
+        let reloader =
+            ConfigReloader::new(file.path(), shared.clone(), auth_state).with_overrides(overrides);
+
+        let report = reloader.reload().unwrap();
+        assert!(report.ignored.is_empty());
+        assert!(shared.load().claude.mock_mode);
+    }
+
+    #[test]
+    fn test_merge_reloadable_mock_scenario() {
+        let current = Config::dev_default();
+        let mut incoming = current.clone();
+        incoming.claude.mock.scenario = MockScenario::Failure;
+        incoming.claude.mock_mode = true;
+
+        let (merged, report) = merge_reloadable(&current, incoming);
+
+        assert_eq!(merged.claude.mock.scenario, MockScenario::Failure);
+        assert!(!merged.claude.mock_mode);
+        assert_eq!(report.ignored, vec!["claude.mock_mode".to_string()]);
+    }
+}
@@ -0,0 +1,1308 @@
+//! Scheduled jobs
+//!
+//! A job runs an execute request on a cron schedule (UTC). Every run gets
+//! its own session, recorded in the job's run history, so its progress can
+//! be followed through the session endpoints. A run that is still going
+//! when the next occurrence comes up makes that occurrence skipped.
+//!
+//! Occurrences missed while the server was stopped are handled according
+//! to the job's [`MissedRunPolicy`]. Jobs are only visible to the API key
+//! that created them and are persisted to `scheduler.jobs_file`, if set.
+//! The file holds a SHA-256 fingerprint of that key, never the key itself.
+//!
+//! Before each run the owner is checked against the live authentication
+//! settings: a job whose key has been removed from the configuration (e.g.
+//! by a reload) is paused instead of run.
+//!
+//! Time is read from a [`Clock`], so tests can move it forward by hand and
+//! call [`Scheduler::tick`] instead of waiting.
+
+use crate::api::execute::{check_request, start_session, track_event, track_failure};
+use crate::claude::Executor;
+use crate::config::Config;
+use crate::conversation::ConversationManager;
+use crate::error::RobertError;
+use crate::models::{
+    ClaudeEvent, Job, JobDefinition, JobRun, JobRunState, JobState, MissedRunPolicy, RobertRequest,
+    SessionState,
+};
+use crate::reload::SharedConfig;
+use crate::session::SessionManager;
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Shortest delay after which a due occurrence counts as missed
+const MIN_MISSED_GRACE: chrono::Duration = chrono::Duration::seconds(60);
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock that reads the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Used to test schedules without waiting for them.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: std::sync::Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: std::sync::Mutex::new(now),
+        }
+    }
+
+    /// Sets the current time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("clock lock poisoned") = now;
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().expect("clock lock poisoned") += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("clock lock poisoned")
+    }
+}
+
+/// A job as written to the jobs file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredJob {
+    /// Fingerprint of the API key that created the job
+    owner_fingerprint: String,
+
+    /// The job and its history
+    job: Job,
+}
+
+/// A job and its parsed schedule
+#[derive(Debug)]
+struct JobEntry {
+    /// Fingerprint of the API key that created the job
+    owner: String,
+
+    /// The job as returned by the API
+    job: Job,
+
+    /// Parsed cron schedule
+    schedule: Cron,
+
+    /// Next occurrence to run (none while paused)
+    next_run: Option<DateTime<Utc>>,
+}
+
+impl JobEntry {
+    /// Sets the next occurrence to run
+    fn set_next_run(&mut self, next_run: Option<DateTime<Utc>>) {
+        self.next_run = next_run;
+        self.job.next_run_at = next_run.map(|at| at.to_rfc3339());
+    }
+
+    /// Schedules the first occurrence after `now`, unless paused
+    fn reschedule(&mut self, now: DateTime<Utc>) {
+        let next_run = match self.job.state {
+            JobState::Active => self.schedule.iter_after(now).next(),
+            JobState::Paused => None,
+        };
+        self.set_next_run(next_run);
+    }
+
+    /// Adds a run to the history
+    ///
+    /// Beyond `max_history` runs the oldest finished runs are dropped; a
+    /// run that is still going is kept.
+    fn push_run(&mut self, run: JobRun, max_history: usize) {
+        self.job.runs.push(run);
+        while self.job.runs.len() > max_history {
+            let Some(index) = self
+                .job
+                .runs
+                .iter()
+                .position(|run| run.status != JobRunState::Running)
+            else {
+                break;
+            };
+            self.job.runs.remove(index);
+        }
+    }
+
+    /// Returns true while a run of the job is going
+    fn is_running(&self) -> bool {
+        self.job
+            .runs
+            .iter()
+            .any(|run| run.status == JobRunState::Running)
+    }
+}
+
+/// A run to start after the state lock is released
+struct DueRun {
+    job_id: Uuid,
+    owner: String,
+    session_id: Uuid,
+    request: RobertRequest,
+}
+
+/// Thread-safe job store and runner
+#[derive(Clone)]
+pub struct Scheduler {
+    /// Jobs by id
+    state: Arc<Mutex<HashMap<Uuid, JobEntry>>>,
+
+    /// Serializes writes of the jobs file
+    save_lock: Arc<Mutex<()>>,
+
+    /// File the jobs are persisted to
+    jobs_file: Option<PathBuf>,
+
+    /// Runs kept per job
+    max_history: usize,
+
+    /// Delay after which a due occurrence counts as missed
+    missed_grace: chrono::Duration,
+
+    /// Source of the current time
+    clock: Arc<dyn Clock>,
+
+    executor: Arc<dyn Executor>,
+    session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    config: SharedConfig,
+}
+
+impl Scheduler {
+    /// Creates a scheduler and loads the persisted jobs
+    ///
+    /// Runs that were still going when the server stopped are marked
+    /// failed. Occurrences missed in the meantime are handled on the first
+    /// [`tick`](Self::tick).
+    ///
+    /// # Arguments
+    /// * `executor` - Executor that runs the jobs
+    /// * `session_manager` - Session tracking
+    /// * `conversations` - Conversation store
+    /// * `config` - Live server configuration
+    /// * `clock` - Source of the current time
+    ///
+    /// # Returns
+    /// New Scheduler instance
+    ///
+    /// # Errors
+    /// Returns RobertError::Config if the jobs file cannot be read or
+    /// contains invalid jobs
+    pub fn new(
+        executor: Arc<dyn Executor>,
+        session_manager: Arc<SessionManager>,
+        conversations: Arc<ConversationManager>,
+        config: SharedConfig,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, RobertError> {
+        let settings = config.load().scheduler.clone();
+        let jobs_file = settings.jobs_file.map(PathBuf::from);
+        let now = clock.now();
+
+        let mut state = HashMap::new();
+        if let Some(path) = &jobs_file {
+            for stored in load_jobs(path)? {
+                let entry = restore(stored, now)?;
+                state.insert(entry.job.job_id, entry);
+            }
+            info!(
+                "Loaded {} scheduled jobs from {}",
+                state.len(),
+                path.display()
+            );
+        }
+
+        let tick = chrono::Duration::seconds(settings.tick_seconds as i64);
+        Ok(Self {
+            state: Arc::new(Mutex::new(state)),
+            save_lock: Arc::new(Mutex::new(())),
+            jobs_file,
+            max_history: settings.max_history,
+            missed_grace: MIN_MISSED_GRACE.max(tick * 2),
+            clock,
+            executor,
+            session_manager,
+            conversations,
+            config,
+        })
+    }
+
+    /// Starts checking for due jobs every `scheduler.tick_seconds`
+    ///
+    /// # Returns
+    /// Handle of the background task
+    pub fn spawn(&self) -> tokio::task::JoinHandle<()> {
+        let scheduler = self.clone();
+        let period = Duration::from_secs(self.config.load().scheduler.tick_seconds);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                scheduler.tick().await;
+            }
+        })
+    }
+
+    /// Starts the runs of every job that is due
+    ///
+    /// Occurrences that are overdue by more than the grace period are
+    /// treated as missed; occurrences that come up while the previous run
+    /// is still going are skipped. Jobs whose owner's key is no longer
+    /// accepted are paused.
+    pub async fn tick(&self) {
+        let now = self.clock.now();
+        let config = self.config.load_full();
+        let mut due = Vec::new();
+        let mut changed = false;
+
+        {
+            let mut state = self.state.lock().await;
+            for entry in state.values_mut() {
+                let Some(scheduled_for) = entry.next_run.filter(|at| *at <= now) else {
+                    continue;
+                };
+                changed = true;
+
+                // Latest occurrence that has come up
+                let latest = entry
+                    .schedule
+                    .iter_after(scheduled_for)
+                    .take_while(|at| *at <= now)
+                    .last()
+                    .unwrap_or(scheduled_for);
+                entry.reschedule(now);
+                let owner = run_as(entry, &config);
+
+                let skip = if owner.is_none() {
+                    warn!(
+                        "Pausing job {}: its API key is no longer accepted",
+                        entry.job.job_id
+                    );
+                    entry.job.state = JobState::Paused;
+                    entry.job.updated_at = now.to_rfc3339();
+                    entry.reschedule(now);
+                    Some("API key of the job is no longer accepted; job paused".to_string())
+                } else if entry.is_running() {
+                    Some("Previous run still running".to_string())
+                } else if now - scheduled_for > self.missed_grace
+                    && entry.job.missed_runs == MissedRunPolicy::Skip
+                {
+                    Some("Missed while the server was not running".to_string())
+                } else {
+                    None
+                };
+
+                let mut run = JobRun {
+                    scheduled_for: latest.to_rfc3339(),
+                    started_at: now.to_rfc3339(),
+                    session_id: None,
+                    status: JobRunState::Skipped,
+                    completed_at: Some(now.to_rfc3339()),
+                    error: skip,
+                };
+
+                if let (None, Some(owner)) = (&run.error, owner) {
+                    let session_id = Uuid::new_v4();
+                    run.session_id = Some(session_id);
+                    run.status = JobRunState::Running;
+                    run.completed_at = None;
+
+                    let mut request = entry.job.request.clone();
+                    request.session_id = session_id;
+                    due.push(DueRun {
+                        job_id: entry.job.job_id,
+                        owner,
+                        session_id,
+                        request,
+                    });
+                } else {
+                    info!(
+                        "Skipping job {} ({}): {}",
+                        entry.job.job_id,
+                        scheduled_for,
+                        run.error.as_deref().unwrap_or_default()
+                    );
+                }
+                entry.push_run(run, self.max_history);
+            }
+        }
+
+        for run in due {
+            self.start_run(run).await;
+        }
+
+        if changed {
+            self.persist().await;
+        }
+    }
+
+    /// Creates a job
+    ///
+    /// # Arguments
+    /// * `owner` - API key of the caller
+    /// * `definition` - Schedule and request of the job
+    ///
+    /// # Returns
+    /// The new job
+    ///
+    /// # Errors
+    /// Returns RobertError::InvalidRequest for invalid schedules and
+    /// requests, and RobertError::Internal if the job cannot be saved
+    pub async fn create(&self, owner: &str, definition: JobDefinition) -> Result<Job, RobertError> {
+        let now = self.clock.now();
+        let (definition, schedule) = self.check_definition(definition, now)?;
+
+        let mut entry = JobEntry {
+            owner: fingerprint(owner),
+            job: Job {
+                job_id: Uuid::new_v4(),
+                name: definition.name,
+                schedule: definition.schedule,
+                request: definition.request,
+                missed_runs: definition.missed_runs,
+                state: state_of(definition.paused),
+                created_at: now.to_rfc3339(),
+                updated_at: now.to_rfc3339(),
+                next_run_at: None,
+                runs: Vec::new(),
+            },
+            schedule,
+            next_run: None,
+        };
+        entry.reschedule(now);
+
+        let job = entry.job.clone();
+        self.state.lock().await.insert(job.job_id, entry);
+        self.save().await?;
+
+        info!("Created job {} ({})", job.job_id, job.schedule);
+        Ok(job)
+    }
+
+    /// Lists the caller's jobs, oldest first
+    ///
+    /// # Arguments
+    /// * `owner` - API key of the caller
+    pub async fn list(&self, owner: &str) -> Vec<Job> {
+        let owner = fingerprint(owner);
+        let state = self.state.lock().await;
+        let mut jobs: Vec<Job> = state
+            .values()
+            .filter(|entry| entry.owner == owner)
+            .map(|entry| entry.job.clone())
+            .collect();
+        jobs.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        jobs
+    }
+
+    /// Returns a job with its run history
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to look up
+    /// * `owner` - API key of the caller
+    ///
+    /// # Errors
+    /// Returns RobertError::JobNotFound if the job does not exist or
+    /// belongs to another key
+    pub async fn get(&self, job_id: Uuid, owner: &str) -> Result<Job, RobertError> {
+        let mut state = self.state.lock().await;
+        Ok(lookup(&mut state, job_id, owner)?.job.clone())
+    }
+
+    /// Replaces the definition of a job
+    ///
+    /// The run history is kept and the next run is computed from the new
+    /// schedule.
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to update
+    /// * `owner` - API key of the caller
+    /// * `definition` - New schedule and request
+    ///
+    /// # Returns
+    /// The updated job
+    ///
+    /// # Errors
+    /// Returns RobertError::JobNotFound if the job does not exist or
+    /// belongs to another key, RobertError::InvalidRequest for invalid
+    /// schedules and requests, and RobertError::Internal if the job cannot
+    /// be saved
+    pub async fn update(
+        &self,
+        job_id: Uuid,
+        owner: &str,
+        definition: JobDefinition,
+    ) -> Result<Job, RobertError> {
+        let now = self.clock.now();
+        let (definition, schedule) = self.check_definition(definition, now)?;
+
+        let job = {
+            let mut state = self.state.lock().await;
+            let entry = lookup(&mut state, job_id, owner)?;
+            entry.job.name = definition.name;
+            entry.job.schedule = definition.schedule;
+            entry.job.request = definition.request;
+            entry.job.missed_runs = definition.missed_runs;
+            entry.job.state = state_of(definition.paused);
+            entry.job.updated_at = now.to_rfc3339();
+            entry.schedule = schedule;
+            entry.reschedule(now);
+            entry.job.clone()
+        };
+        self.save().await?;
+
+        Ok(job)
+    }
+
+    /// Deletes a job
+    ///
+    /// A run that is going keeps running; its session can still be
+    /// followed and cancelled through the session endpoints.
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to delete
+    /// * `owner` - API key of the caller
+    ///
+    /// # Errors
+    /// Returns RobertError::JobNotFound if the job does not exist or
+    /// belongs to another key, and RobertError::Internal if the change
+    /// cannot be saved
+    pub async fn delete(&self, job_id: Uuid, owner: &str) -> Result<(), RobertError> {
+        {
+            let mut state = self.state.lock().await;
+            lookup(&mut state, job_id, owner)?;
+            state.remove(&job_id);
+        }
+        self.save().await?;
+
+        info!("Deleted job {}", job_id);
+        Ok(())
+    }
+
+    /// Pauses or resumes a job
+    ///
+    /// A resumed job runs at its next occurrence; occurrences that came up
+    /// while it was paused are not run.
+    ///
+    /// # Arguments
+    /// * `job_id` - Job to change
+    /// * `owner` - API key of the caller
+    /// * `paused` - Whether the job should be paused
+    ///
+    /// # Returns
+    /// The updated job
+    ///
+    /// # Errors
+    /// Returns RobertError::JobNotFound if the job does not exist or
+    /// belongs to another key, and RobertError::Internal if the change
+    /// cannot be saved
+    pub async fn set_paused(
+        &self,
+        job_id: Uuid,
+        owner: &str,
+        paused: bool,
+    ) -> Result<Job, RobertError> {
+        let now = self.clock.now();
+        let job = {
+            let mut state = self.state.lock().await;
+            let entry = lookup(&mut state, job_id, owner)?;
+            let target = state_of(paused);
+            if entry.job.state == target {
+                return Ok(entry.job.clone());
+            }
+            entry.job.state = target;
+            entry.job.updated_at = now.to_rfc3339();
+            entry.reschedule(now);
+            entry.job.clone()
+        };
+        self.save().await?;
+
+        Ok(job)
+    }
+
+    /// Validates a job definition
+    ///
+    /// # Returns
+    /// The definition with its request narrowed to the server policy, and
+    /// its parsed schedule
+    fn check_definition(
+        &self,
+        mut definition: JobDefinition,
+        now: DateTime<Utc>,
+    ) -> Result<(JobDefinition, Cron), RobertError> {
+        if definition.name.trim().is_empty() {
+            return Err(RobertError::InvalidRequest(
+                "Job name cannot be empty".to_string(),
+            ));
+        }
+
+        let schedule = parse_schedule(&definition.schedule).map_err(RobertError::InvalidRequest)?;
+        if schedule.iter_after(now).next().is_none() {
+            return Err(RobertError::InvalidRequest(format!(
+                "Schedule has no upcoming runs: {}",
+                definition.schedule
+            )));
+        }
+
+        check_request(&mut definition.request, &self.config.load_full())?;
+        Ok((definition, schedule))
+    }
+
+    /// Starts a due run and follows it in the background
+    async fn start_run(&self, run: DueRun) {
+        let DueRun {
+            job_id,
+            owner,
+            session_id,
+            mut request,
+        } = run;
+        let config = self.config.load_full();
+
+        if let Err(e) = start_session(
+            &owner,
+            &mut request,
+            self.executor.as_ref(),
+            &self.session_manager,
+            &self.conversations,
+            &config,
+        )
+        .await
+        {
+            warn!("Job {} could not start: {}", job_id, e);
+            self.finish_run(job_id, session_id, JobRunState::Failed, Some(e.to_string()))
+                .await;
+            return;
+        }
+
+        info!("Job {} started session {}", job_id, session_id);
+        let events = self.executor.execute(request).await;
+        tokio::spawn(self.clone().follow(job_id, session_id, events));
+    }
+
+    /// Drives a run to completion and records its outcome
+    async fn follow(
+        self,
+        job_id: Uuid,
+        session_id: Uuid,
+        mut events: Box<dyn Stream<Item = Result<ClaudeEvent, RobertError>> + Send + Unpin>,
+    ) {
+        while let Some(result) = events.next().await {
+            match result {
+                Ok(event) => {
+                    track_event(
+                        &self.session_manager,
+                        &self.conversations,
+                        session_id,
+                        &event,
+                    )
+                    .await
+                }
+                Err(e) => {
+                    track_failure(&self.session_manager, &self.conversations, session_id, &e).await;
+                    break;
+                }
+            }
+        }
+
+        // Report runs that stopped without a complete event
+        self.session_manager.webhooks().finish(session_id).await;
+
+        let (status, error) = match self.session_manager.get_status(session_id).await {
+            Ok(session) => match session.status {
+                SessionState::Completed => (JobRunState::Completed, None),
+                SessionState::Cancelled => (JobRunState::Cancelled, None),
+                SessionState::Failed => (JobRunState::Failed, session.error),
+                SessionState::Running => (
+                    JobRunState::Failed,
+                    Some("Session ended without a result".to_string()),
+                ),
+            },
+            Err(e) => (JobRunState::Failed, Some(e.to_string())),
+        };
+        self.finish_run(job_id, session_id, status, error).await;
+    }
+
+    /// Records the outcome of a run and saves the jobs
+    async fn finish_run(
+        &self,
+        job_id: Uuid,
+        session_id: Uuid,
+        status: JobRunState,
+        error: Option<String>,
+    ) {
+        {
+            let mut state = self.state.lock().await;
+            let Some(run) = state.get_mut(&job_id).and_then(|entry| {
+                entry
+                    .job
+                    .runs
+                    .iter_mut()
+                    .find(|run| run.session_id == Some(session_id))
+            }) else {
+                return;
+            };
+            run.status = status;
+            run.error = error;
+            run.completed_at = Some(self.clock.now().to_rfc3339());
+        }
+        self.persist().await;
+    }
+
+    /// Saves the jobs, logging failures
+    async fn persist(&self) {
+        if let Err(e) = self.save().await {
+            error!("{}", e);
+        }
+    }
+
+    /// Writes every job to the jobs file, if one is configured
+    ///
+    /// The file is replaced atomically and is only readable by the owner.
+    /// Jobs' creators are stored as key fingerprints.
+    async fn save(&self) -> Result<(), RobertError> {
+        let Some(path) = &self.jobs_file else {
+            return Ok(());
+        };
+        let _guard = self.save_lock.lock().await;
+
+        let mut jobs: Vec<StoredJob> = self
+            .state
+            .lock()
+            .await
+            .values()
+            .map(|entry| StoredJob {
+                owner_fingerprint: entry.owner.clone(),
+                job: entry.job.clone(),
+            })
+            .collect();
+        jobs.sort_by(|a, b| a.job.created_at.cmp(&b.job.created_at));
+
+        write_jobs(path, &jobs)
+            .map_err(|e| RobertError::Internal(format!("Failed to save jobs: {}", e)))
+    }
+}
+
+/// Parses a cron expression
+///
+/// Accepts five fields, or six with leading seconds.
+///
+/// # Errors
+/// Returns a message describing the invalid expression
+fn parse_schedule(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression.trim())
+        .with_seconds_optional()
+        .parse()
+        .map_err(|e| format!("Invalid schedule {:?}: {}", expression, e))
+}
+
+/// Returns the job state for a `paused` flag
+fn state_of(paused: bool) -> JobState {
+    if paused {
+        JobState::Paused
+    } else {
+        JobState::Active
+    }
+}
+
+/// Looks up a job owned by `owner`
+fn lookup<'a>(
+    state: &'a mut HashMap<Uuid, JobEntry>,
+    job_id: Uuid,
+    owner: &str,
+) -> Result<&'a mut JobEntry, RobertError> {
+    let fingerprint = fingerprint(owner);
+    state
+        .get_mut(&job_id)
+        .filter(|entry| entry.owner == fingerprint)
+        .ok_or_else(|| RobertError::JobNotFound(job_id.to_string()))
+}
+
+/// Returns the fingerprint of an API key (hex SHA-256)
+fn fingerprint(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Returns the API key to run a job as
+///
+/// The owner must still be an accepted token of the live configuration.
+/// Only key fingerprints are kept, so with authentication disabled a job
+/// whose owner is not a configured token runs as the empty key.
+///
+/// # Returns
+/// The key, or None if the owner's key is no longer accepted
+fn run_as(entry: &JobEntry, config: &Config) -> Option<String> {
+    let configured = config
+        .valid_tokens()
+        .into_iter()
+        .find(|token| fingerprint(token) == entry.owner);
+
+    match configured {
+        Some(token) => Some(token),
+        None if !config.auth.require_auth => Some(String::new()),
+        None => None,
+    }
+}
+
+/// Rebuilds a job loaded from the jobs file
+///
+/// The next run is the first occurrence after the last one that was
+/// handled, so occurrences missed while the server was stopped are due.
+fn restore(stored: StoredJob, now: DateTime<Utc>) -> Result<JobEntry, RobertError> {
+    let StoredJob {
+        owner_fingerprint,
+        mut job,
+    } = stored;
+    let schedule = parse_schedule(&job.schedule)
+        .map_err(|e| RobertError::Config(format!("Job {}: {}", job.job_id, e)))?;
+
+    for run in &mut job.runs {
+        if run.status == JobRunState::Running {
+            run.status = JobRunState::Failed;
+            run.completed_at = Some(now.to_rfc3339());
+            run.error = Some("Server stopped during the run".to_string());
+        }
+    }
+
+    let next_run = match job.state {
+        JobState::Active => job
+            .next_run_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc))
+            .or_else(|| schedule.iter_after(now).next()),
+        JobState::Paused => None,
+    };
+
+    let mut entry = JobEntry {
+        owner: owner_fingerprint,
+        job,
+        schedule,
+        next_run: None,
+    };
+    entry.set_next_run(next_run);
+    Ok(entry)
+}
+
+/// Reads the jobs file; a missing file holds no jobs
+fn load_jobs(path: &Path) -> Result<Vec<StoredJob>, RobertError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(RobertError::Config(format!(
+                "Failed to read jobs file {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+
+    serde_json::from_str(&contents).map_err(|e| {
+        RobertError::Config(format!(
+            "Failed to parse jobs file {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Atomically replaces the jobs file
+///
+/// The temporary file is created next to the target with owner-only
+/// permissions and renamed over it.
+fn write_jobs(path: &Path, jobs: &[StoredJob]) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer_pretty(&mut file, jobs)?;
+    file.write_all(b"\n")?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::MockClaudeExecutor;
+    use crate::config::Config;
+    use crate::models::{
+        DomState, RequestContext, RequestOptions, Screenshot, ScreenshotMetadata, Viewport,
+    };
+    use arc_swap::ArcSwap;
+    use chrono::TimeZone;
+
+    fn create_test_request() -> RobertRequest {
+        RobertRequest {
+            session_id: Uuid::new_v4(),
+            context: RequestContext {
+                screenshots: vec![Screenshot {
+                    timestamp: "2025-10-17T10:30:00Z".to_string(),
+                    image_data: {
+                        use base64::{engine::general_purpose, Engine as _};
+                        general_purpose::STANDARD.encode(b"test image")
+                    },
+                    metadata: ScreenshotMetadata {
+                        window_title: "Test".to_string(),
+                        url: None,
+                        viewport: Viewport {
+                            width: 1920,
+                            height: 1080,
+                        },
+                    },
+                }],
+                dom_state: DomState {
+                    accessible_tree: "test tree".to_string(),
+                    interactive_elements: vec![],
+                },
+                user_intent: "Check competitor pricing".to_string(),
+            },
+            prompt: "Check competitor pricing".to_string(),
+            options: RequestOptions::default(),
+        }
+    }
+
+    fn definition(schedule: &str, missed_runs: MissedRunPolicy) -> JobDefinition {
+        JobDefinition {
+            name: "pricing".to_string(),
+            schedule: schedule.to_string(),
+            request: create_test_request(),
+            missed_runs,
+            paused: false,
+        }
+    }
+
+    fn start_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, 17, 8, 30, 0).unwrap()
+    }
+
+    struct Fixture {
+        scheduler: Scheduler,
+        clock: Arc<ManualClock>,
+        session_manager: Arc<SessionManager>,
+        config: SharedConfig,
+    }
+
+    impl Fixture {
+        fn new(config: Config, delay_ms: u64) -> Self {
+            let clock = Arc::new(ManualClock::new(start_time()));
+            let session_manager = Arc::new(SessionManager::new(100));
+            let config: SharedConfig = Arc::new(ArcSwap::from_pointee(config));
+            let scheduler = Scheduler::new(
+                Arc::new(MockClaudeExecutor::with_delay(delay_ms)),
+                session_manager.clone(),
+                Arc::new(ConversationManager::new(10)),
+                config.clone(),
+                clock.clone(),
+            )
+            .unwrap();
+
+            Self {
+                scheduler,
+                clock,
+                session_manager,
+                config,
+            }
+        }
+
+        async fn wait_until_idle(&self, job_id: Uuid) -> Job {
+            for _ in 0..100 {
+                let job = self.scheduler.get(job_id, "key").await.unwrap();
+                if !job
+                    .runs
+                    .iter()
+                    .any(|run| run.status == JobRunState::Running)
+                {
+                    return job;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            panic!("job run did not finish");
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let daily = parse_schedule("0 9 * * *").unwrap();
+        assert_eq!(
+            daily.iter_after(start_time()).next().unwrap(),
+            Utc.with_ymd_and_hms(2025, 10, 17, 9, 0, 0).unwrap()
+        );
+
+        // Seconds field
+        let every_ten = parse_schedule("*/10 * * * * *").unwrap();
+        assert_eq!(
+            every_ten.iter_after(start_time()).next().unwrap(),
+            start_time() + chrono::Duration::seconds(10)
+        );
+
+        assert!(parse_schedule("not a schedule").is_err());
+        assert!(parse_schedule("61 * * * *").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_validates_definition() {
+        let fixture = Fixture::new(Config::dev_default(), 1);
+
+        let mut invalid = definition("0 9 * * *", MissedRunPolicy::Skip);
+        invalid.name = " ".to_string();
+        assert!(matches!(
+            fixture.scheduler.create("key", invalid).await,
+            Err(RobertError::InvalidRequest(_))
+        ));
+
+        let invalid = definition("every day", MissedRunPolicy::Skip);
+        assert!(matches!(
+            fixture.scheduler.create("key", invalid).await,
+            Err(RobertError::InvalidRequest(_))
+        ));
+
+        // Schedules that never come up again
+        let invalid = definition("0 0 30 2 *", MissedRunPolicy::Skip);
+        assert!(matches!(
+            fixture.scheduler.create("key", invalid).await,
+            Err(RobertError::InvalidRequest(_))
+        ));
+
+        let mut invalid = definition("0 9 * * *", MissedRunPolicy::Skip);
+        invalid.request.context.screenshots.clear();
+        assert!(matches!(
+            fixture.scheduler.create("key", invalid).await,
+            Err(RobertError::InvalidRequest(_))
+        ));
+
+        assert!(fixture.scheduler.list("key").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_on_schedule() {
+        let fixture = Fixture::new(Config::dev_default(), 1);
+        let job = fixture
+            .scheduler
+            .create("key", definition("0 9 * * *", MissedRunPolicy::Skip))
+            .await
+            .unwrap();
+        assert_eq!(job.state, JobState::Active);
+        assert_eq!(
+            job.next_run_at.as_deref(),
+            Some("2025-10-17T09:00:00+00:00")
+        );
+
+        // Not due yet
+        fixture
+            .clock
+            .set(start_time() + chrono::Duration::minutes(29));
+        fixture.scheduler.tick().await;
+        assert_eq!(fixture.session_manager.total_count().await, 0);
+
+        fixture
+            .clock
+            .set(start_time() + chrono::Duration::minutes(30));
+        fixture.scheduler.tick().await;
+
+        let job = fixture.wait_until_idle(job.job_id).await;
+        assert_eq!(job.runs.len(), 1);
+        let run = &job.runs[0];
+        assert_eq!(run.status, JobRunState::Completed);
+        assert_eq!(run.scheduled_for, "2025-10-17T09:00:00+00:00");
+        assert_ne!(run.session_id, Some(job.request.session_id));
+
+        let session = fixture
+            .session_manager
+            .get_status(run.session_id.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(session.status, SessionState::Completed);
+        assert_eq!(
+            job.next_run_at.as_deref(),
+            Some("2025-10-18T09:00:00+00:00")
+        );
+
+        // Other keys cannot see the job
+        assert!(matches!(
+            fixture.scheduler.get(job.job_id, "other").await,
+            Err(RobertError::JobNotFound(_))
+        ));
+        assert!(fixture.scheduler.list("other").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_runs_are_skipped() {
+        let fixture = Fixture::new(Config::dev_default(), 5000);
+        let job = fixture
+            .scheduler
+            .create("key", definition("* * * * *", MissedRunPolicy::Skip))
+            .await
+            .unwrap();
+
+        fixture.clock.advance(chrono::Duration::minutes(1));
+        fixture.scheduler.tick().await;
+        fixture.clock.advance(chrono::Duration::minutes(1));
+        fixture.scheduler.tick().await;
+
+        let job = fixture.scheduler.get(job.job_id, "key").await.unwrap();
+        assert_eq!(job.runs.len(), 2);
+        assert_eq!(job.runs[0].status, JobRunState::Running);
+        assert_eq!(job.runs[1].status, JobRunState::Skipped);
+        assert_eq!(
+            job.runs[1].error.as_deref(),
+            Some("Previous run still running")
+        );
+        assert_eq!(fixture.session_manager.running_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_missed_run_policies() {
+        let fixture = Fixture::new(Config::dev_default(), 1);
+        let skip = fixture
+            .scheduler
+            .create("key", definition("0 * * * *", MissedRunPolicy::Skip))
+            .await
+            .unwrap();
+        let run_once = fixture
+            .scheduler
+            .create("key", definition("0 * * * *", MissedRunPolicy::RunOnce))
+            .await
+            .unwrap();
+
+        // Three occurrences pass without a tick
+        fixture.clock.advance(chrono::Duration::minutes(150));
+        fixture.scheduler.tick().await;
+
+        let skip = fixture.wait_until_idle(skip.job_id).await;
+        assert_eq!(skip.runs.len(), 1);
+        assert_eq!(skip.runs[0].status, JobRunState::Skipped);
+        assert_eq!(skip.runs[0].session_id, None);
+
+        let run_once = fixture.wait_until_idle(run_once.job_id).await;
+        assert_eq!(run_once.runs.len(), 1);
+        assert_eq!(run_once.runs[0].status, JobRunState::Completed);
+        assert_eq!(run_once.runs[0].scheduled_for, "2025-10-17T11:00:00+00:00");
+        assert_eq!(
+            run_once.next_run_at.as_deref(),
+            Some("2025-10-17T12:00:00+00:00")
+        );
+        assert_eq!(fixture.session_manager.total_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let fixture = Fixture::new(Config::dev_default(), 1);
+        let job = fixture
+            .scheduler
+            .create("key", definition("0 * * * *", MissedRunPolicy::RunOnce))
+            .await
+            .unwrap();
+
+        let paused = fixture
+            .scheduler
+            .set_paused(job.job_id, "key", true)
+            .await
+            .unwrap();
+        assert_eq!(paused.state, JobState::Paused);
+        assert_eq!(paused.next_run_at, None);
+
+        fixture.clock.advance(chrono::Duration::hours(3));
+        fixture.scheduler.tick().await;
+        assert_eq!(fixture.session_manager.total_count().await, 0);
+
+        // Occurrences while paused are not caught up
+        let resumed = fixture
+            .scheduler
+            .set_paused(job.job_id, "key", false)
+            .await
+            .unwrap();
+        assert_eq!(resumed.state, JobState::Active);
+        assert_eq!(
+            resumed.next_run_at.as_deref(),
+            Some("2025-10-17T12:00:00+00:00")
+        );
+        fixture.scheduler.tick().await;
+        assert_eq!(fixture.session_manager.total_count().await, 0);
+        assert!(resumed.runs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let fixture = Fixture::new(Config::dev_default(), 1);
+        let job = fixture
+            .scheduler
+            .create("key", definition("0 9 * * *", MissedRunPolicy::Skip))
+            .await
+            .unwrap();
+
+        let mut changed = definition("0 18 * * 1-5", MissedRunPolicy::RunOnce);
+        changed.name = "evening".to_string();
+        let updated = fixture
+            .scheduler
+            .update(job.job_id, "key", changed)
+            .await
+            .unwrap();
+        assert_eq!(updated.name, "evening");
+        assert_eq!(updated.missed_runs, MissedRunPolicy::RunOnce);
+        assert_eq!(
+            updated.next_run_at.as_deref(),
+            Some("2025-10-17T18:00:00+00:00")
+        );
+        assert_eq!(updated.created_at, job.created_at);
+
+        assert!(matches!(
+            fixture.scheduler.delete(job.job_id, "other").await,
+            Err(RobertError::JobNotFound(_))
+        ));
+        fixture.scheduler.delete(job.job_id, "key").await.unwrap();
+        assert!(matches!(
+            fixture.scheduler.get(job.job_id, "key").await,
+            Err(RobertError::JobNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let mut config = Config::dev_default();
+        config.scheduler.jobs_file = Some(path.to_string_lossy().into_owned());
+
+        let fixture = Fixture::new(config.clone(), 5000);
+        let job = fixture
+            .scheduler
+            .create("key", definition("0 * * * *", MissedRunPolicy::RunOnce))
+            .await
+            .unwrap();
+        fixture.clock.advance(chrono::Duration::minutes(30));
+        fixture.scheduler.tick().await;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Only a fingerprint of the key is written
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("\"key\""), "{}", contents);
+        assert!(contents.contains(&fingerprint("key")));
+
+        // A new scheduler picks up the job; the interrupted run failed and
+        // the occurrences missed since then run once
+        let restarted = Fixture::new(config, 1);
+        restarted
+            .clock
+            .set(start_time() + chrono::Duration::hours(5));
+        let loaded = restarted.scheduler.get(job.job_id, "key").await.unwrap();
+        assert_eq!(loaded.name, job.name);
+        assert_eq!(loaded.runs.len(), 1);
+        assert_eq!(loaded.runs[0].status, JobRunState::Failed);
+        assert_eq!(
+            loaded.next_run_at.as_deref(),
+            Some("2025-10-17T10:00:00+00:00")
+        );
+
+        restarted.scheduler.tick().await;
+        let loaded = restarted.wait_until_idle(job.job_id).await;
+        assert_eq!(loaded.runs.len(), 2);
+        assert_eq!(loaded.runs[1].status, JobRunState::Completed);
+        assert_eq!(loaded.runs[1].scheduled_for, "2025-10-17T13:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_revoked_key_pauses_job() {
+        let mut config = Config::dev_default();
+        config.auth.require_auth = true;
+        config.auth.tokens = vec!["key".to_string()];
+        let fixture = Fixture::new(config.clone(), 1);
+        let job = fixture
+            .scheduler
+            .create("key", definition("0 * * * *", MissedRunPolicy::RunOnce))
+            .await
+            .unwrap();
+
+        fixture.clock.advance(chrono::Duration::minutes(30));
+        fixture.scheduler.tick().await;
+        let job = fixture.wait_until_idle(job.job_id).await;
+        assert_eq!(job.runs[0].status, JobRunState::Completed);
+        let session = fixture
+            .session_manager
+            .get_status(job.runs[0].session_id.unwrap())
+            .await
+            .unwrap();
+        assert!(session.usage.is_some());
+
+        // A reload removes the key
+        config.auth.tokens = vec!["other".to_string()];
+        fixture.config.store(Arc::new(config));
+        fixture.clock.advance(chrono::Duration::hours(1));
+        fixture.scheduler.tick().await;
+
+        let job = fixture.scheduler.get(job.job_id, "key").await.unwrap();
+        assert_eq!(job.state, JobState::Paused);
+        assert_eq!(job.next_run_at, None);
+        assert_eq!(job.runs.len(), 2);
+        assert_eq!(job.runs[1].status, JobRunState::Skipped);
+        assert_eq!(job.runs[1].session_id, None);
+        assert_eq!(fixture.session_manager.total_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded() {
+        let mut config = Config::dev_default();
+        config.scheduler.max_history = 2;
+        let fixture = Fixture::new(config, 5000);
+        let job = fixture
+            .scheduler
+            .create("key", definition("* * * * *", MissedRunPolicy::Skip))
+            .await
+            .unwrap();
+
+        for _ in 0..4 {
+            fixture.clock.advance(chrono::Duration::minutes(1));
+            fixture.scheduler.tick().await;
+        }
+
+        // The running run is kept over older skipped ones
+        let job = fixture.scheduler.get(job.job_id, "key").await.unwrap();
+        assert_eq!(job.runs.len(), 2);
+        assert_eq!(job.runs[0].status, JobRunState::Running);
+        assert_eq!(job.runs[1].status, JobRunState::Skipped);
+        assert_eq!(fixture.session_manager.total_count().await, 1);
+    }
+}
@@ -4,19 +4,41 @@
 
 use crate::{
     api::{
-        delete_session_handler, execute_handler, get_session_handler, health::HealthState,
-        health_handler, inference_handler,
+        batch_results_handler, cancel_batch_handler, create_batch_handler,
+        create_conversation_handler, create_job_handler, delete_conversation_handler,
+        delete_job_handler, delete_session_handler, execute_handler, get_batch_handler,
+        get_conversation_handler, get_job_handler, get_session_handler,
+        health::HealthState,
+        health_handler, inference_handler, list_jobs_handler, openapi_handler, pause_job_handler,
+        recover::{error_reply, rejection_to_error},
+        resume_job_handler, update_job_handler, usage_handler, webhook_deliveries_handler,
+        ws_handler,
     },
     auth::{with_auth, AuthState},
-    claude::{ClaudeExecutor, Executor, MockClaudeExecutor},
+    batch::BatchManager,
+    claude::{ClaudeExecutor, Executor, FallbackExecutor, MockClaudeExecutor},
+    config::CliOverrides,
+    conversation::ConversationManager,
+    error::RobertError,
+    reload::{ConfigReloader, LogLevelHook, SharedConfig},
+    scheduler::{Scheduler, SystemClock},
     session::SessionManager,
     Config,
 };
+use arc_swap::ArcSwap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::info;
 use uuid::Uuid;
-use warp::Filter;
+use warp::http::HeaderValue;
+use warp::{Filter, Reply};
+
+/// Header carrying the request id
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request id that is accepted
+const MAX_REQUEST_ID_LENGTH: usize = 128;
 
 /// Runs the Robert Server with the provided configuration.
 ///
@@ -28,6 +50,38 @@ use warp::Filter;
 /// # Returns
 /// Result indicating success or failure
 pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    serve(config, None).await
+}
+
+/// Runs the Robert Server and reloads its configuration on SIGHUP.
+///
+/// Behaves like [`run`], but re-reads `config_path` whenever the process
+/// receives SIGHUP and applies the settings that can change without a
+/// restart. See [`crate::reload`] for which settings are reloadable.
+///
+/// # Arguments
+/// * `config` - Initial server configuration (usually loaded from `config_path`)
+/// * `config_path` - Configuration file to re-read on reload
+/// * `log_level_hook` - Optional callback for applying a new logging level
+/// * `overrides` - Command line settings already applied to `config`; they
+///   are re-applied to every reloaded file
+///
+/// # Returns
+/// Result indicating success or failure
+pub async fn run_with_reload(
+    config: Config,
+    config_path: PathBuf,
+    log_level_hook: Option<LogLevelHook>,
+    overrides: CliOverrides,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    serve(config, Some((config_path, log_level_hook, overrides))).await
+}
+
+/// Shared implementation of [`run`] and [`run_with_reload`]
+async fn serve(
+    config: Config,
+    reload: Option<(PathBuf, Option<LogLevelHook>, CliOverrides)>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Override mock mode if specified in config (already handled by config loading, but good to check)
     let use_mock = config.claude.mock_mode;
 
@@ -42,70 +96,141 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error + Send
     info!("Configuration:");
     info!("  Host: {}", config.server.host);
     info!("  Port: {}", config.server.port);
+    if let Some(ref socket) = config.server.unix_socket {
+        info!("  Unix socket: {}", socket);
+    }
     info!("  Dev mode: {}", config.server.dev_mode);
     info!("  TLS enabled: {}", config.server.enable_tls);
     info!("  Auth required: {}", config.auth.require_auth);
     info!("  Mock mode: {}", use_mock);
 
     // Create shared state
-    let config = Arc::new(config);
+    let config: SharedConfig = Arc::new(ArcSwap::from_pointee(config));
     let session_manager = Arc::new(SessionManager::new(1000)); // Keep 1000 completed sessions
+    let conversations = Arc::new(ConversationManager::new(1000));
+    let batches = Arc::new(BatchManager::new(1000));
+    let initial = config.load_full();
     let auth_state = Arc::new(AuthState::new(
-        config.valid_tokens(),
-        config.auth.require_auth,
-        config.auth.rate_limit_per_minute,
+        initial.valid_tokens(),
+        initial.auth.require_auth,
+        initial.auth.rate_limit_per_minute,
     ));
-    let health_state = Arc::new(HealthState::new(config.claude.binary_path.clone()));
+    let health_state = Arc::new(HealthState::new(initial.claude.binary_path.clone()));
+
+    // Reload configuration on SIGHUP
+    if let Some((path, log_level_hook, overrides)) = reload {
+        let mut reloader =
+            ConfigReloader::new(path, config.clone(), auth_state.clone()).with_overrides(overrides);
+        if let Some(hook) = log_level_hook {
+            reloader = reloader.with_log_level_hook(hook);
+        }
+
+        #[cfg(unix)]
+        {
+            info!(
+                "Send SIGHUP to reload configuration from {}",
+                reloader.path().display()
+            );
+            reloader.spawn_sighup_listener()?;
+        }
+
+        #[cfg(not(unix))]
+        tracing::warn!(
+            "Configuration reload is not supported on this platform ({})",
+            reloader.path().display()
+        );
+    }
 
     // Create executor (mock or real)
     let executor: Arc<dyn Executor> = if use_mock {
         info!("Using mock executor");
-        Arc::new(MockClaudeExecutor::new())
+        Arc::new(MockClaudeExecutor::with_config(config.clone()))
     } else {
         info!(
             "Using real Claude CLI executor: {}",
-            config.claude.binary_path
+            initial.claude.binary_path
         );
-        Arc::new(ClaudeExecutor::new(
-            config.claude.binary_path.clone(),
-            config.claude.default_timeout_seconds,
-        ))
+        if initial.claude.sandbox.enabled {
+            info!("Sandboxing claude-cli sessions");
+        }
+        Arc::new(ClaudeExecutor::from_config(&initial.claude))
     };
 
+    // Apply model aliases and the fallback chain on top of either executor
+    let executor: Arc<dyn Executor> = Arc::new(FallbackExecutor::new(executor, config.clone()));
+
+    // Run scheduled jobs
+    let scheduler = Arc::new(Scheduler::new(
+        executor.clone(),
+        session_manager.clone(),
+        conversations.clone(),
+        config.clone(),
+        Arc::new(SystemClock),
+    )?);
+    scheduler.spawn();
+
     // Build routes
     let routes = build_routes(
         config.clone(),
         executor,
         session_manager,
+        conversations,
+        batches,
+        scheduler,
         auth_state,
         health_state,
     );
 
-    // Add middleware
-    let routes = routes.with(warp::trace::request());
-
-    let cors = if config.server.dev_mode {
+    let cors = if initial.server.dev_mode {
         warp::cors()
             .allow_any_origin()
-            .allow_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
-            .allow_headers(vec!["content-type", "authorization"])
+            .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+            .allow_headers(vec![
+                "content-type",
+                "authorization",
+                "idempotency-key",
+                REQUEST_ID_HEADER,
+            ])
+            .expose_headers(vec![REQUEST_ID_HEADER, "idempotent-replayed"])
     } else {
         // Restrictive CORS for production (configure as needed)
         warp::cors()
             .allow_origin("https://yourdomain.com")
-            .allow_methods(vec!["GET", "POST", "DELETE"])
-            .allow_headers(vec!["content-type", "authorization"])
+            .allow_methods(vec!["GET", "POST", "PUT", "DELETE"])
+            .allow_headers(vec![
+                "content-type",
+                "authorization",
+                "idempotency-key",
+                REQUEST_ID_HEADER,
+            ])
+            .expose_headers(vec![REQUEST_ID_HEADER, "idempotent-replayed"])
     };
 
     let routes = routes.with(cors);
 
+    // Local mode: only the owning user can reach a Unix domain socket
+    if let Some(ref socket) = initial.server.unix_socket {
+        #[cfg(unix)]
+        {
+            let listener = bind_unix_socket(std::path::Path::new(socket))?;
+            info!("Server listening on unix socket {}", socket);
+            warp::serve(routes)
+                .run_incoming(tokio_stream::wrappers::UnixListenerStream::new(listener))
+                .await;
+            return Ok(());
+        }
+
+        #[cfg(not(unix))]
+        return Err(format!("Unix domain sockets are not supported here ({})", socket).into());
+    }
+
     // Parse bind address
-    let addr: SocketAddr = config.bind_address().parse()?;
+    let addr: SocketAddr = initial.bind_address().parse()?;
 
     info!("Server listening on {}", addr);
 
     // Start server
-    if config.server.enable_tls {
+    if initial.server.enable_tls {
         // TLS mode (production)
         info!("Starting server with TLS");
         // TODO: Implement TLS support
@@ -119,33 +244,131 @@ pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error + Send
     Ok(())
 }
 
+/// Binds a Unix domain socket that only the current user can connect to
+///
+/// Missing parent directories are created with mode 0700. The directory
+/// holding the socket must be owned by the current user and closed to
+/// everyone else (no group or other permissions), so nobody else can reach
+/// the socket between `bind` and `chmod` or replace it. A socket left
+/// behind by a previous run is replaced; any other file at `path` is an
+/// error. The socket itself gets mode 0600.
+///
+/// # Arguments
+/// * `path` - Socket path
+///
+/// # Returns
+/// Listener for the socket
+///
+/// # Errors
+/// Returns an I/O error if the socket cannot be created or secured, and
+/// ErrorKind::PermissionDenied if its directory is not private to the
+/// current user
+#[cfg(unix)]
+pub fn bind_unix_socket(path: &std::path::Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(parent)?;
+    check_private_dir(parent)?;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    Ok(listener)
+}
+
+/// Checks that a directory is owned by the current user and closed to others
+#[cfg(unix)]
+fn check_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let metadata = std::fs::metadata(dir)?;
+    let denied = |reason: String| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} {}", dir.display(), reason),
+        ))
+    };
+
+    if !metadata.is_dir() {
+        return denied("is not a directory".to_string());
+    }
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if metadata.uid() != uid {
+        return denied(format!(
+            "is owned by uid {}, not the current user (uid {})",
+            metadata.uid(),
+            uid
+        ));
+    }
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return denied(format!(
+            "has mode {:o}; the socket directory must be 0700",
+            mode
+        ));
+    }
+    Ok(())
+}
+
 /// Builds all API routes
-fn build_routes(
-    config: Arc<Config>,
+///
+/// Every rejection is recovered into an error response with the matching
+/// status code, and every response carries an `X-Request-Id` header (see
+/// [`with_request_id`]).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn build_routes(
+    config: SharedConfig,
     executor: Arc<dyn Executor>,
     session_manager: Arc<SessionManager>,
+    conversations: Arc<ConversationManager>,
+    batches: Arc<BatchManager>,
+    scheduler: Arc<Scheduler>,
     auth_state: Arc<AuthState>,
     health_state: Arc<HealthState>,
-) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone {
     // Health endpoint (no auth required)
     let health = warp::path!("api" / "v1" / "health")
         .and(warp::get())
         .and(with_health_state(health_state))
         .and_then(health_handler);
 
+    // OpenAPI document (no auth required)
+    let openapi = warp::path!("api" / "v1" / "openapi.json")
+        .and(warp::get())
+        .and_then(openapi_handler);
+
     // Execute endpoint (with auth)
     let execute = warp::path!("api" / "v1" / "execute")
         .and(warp::post())
         .and(with_auth(auth_state.clone()))
+        .and(warp::header::optional::<String>("idempotency-key"))
+        .and(with_body_limit(config.clone()))
         .and(warp::body::json())
         .and(with_executor(executor.clone()))
         .and(with_session_manager(session_manager.clone()))
+        .and(with_conversations(conversations.clone()))
         .and(with_config(config.clone()))
-        .and_then(
-            |_token: String, request, executor, session_manager, config| {
-                execute_handler(request, executor, session_manager, config)
-            },
-        );
+        .and_then(execute_handler);
 
     // Get session endpoint (with auth)
     let get_session = warp::path!("api" / "v1" / "sessions" / Uuid)
@@ -159,25 +382,267 @@ fn build_routes(
     // Delete session endpoint (with auth)
     let delete_session = warp::path!("api" / "v1" / "sessions" / Uuid)
         .and(warp::delete())
-        .and(with_auth(auth_state))
-        .and(with_session_manager(session_manager))
+        .and(with_auth(auth_state.clone()))
+        .and(with_session_manager(session_manager.clone()))
         .and_then(|session_id: Uuid, _token: String, manager| {
             delete_session_handler(session_id, manager)
         });
 
+    // Conversation endpoints (with auth)
+    let create_conversation = warp::path!("api" / "v1" / "conversations")
+        .and(warp::post())
+        .and(with_auth(auth_state.clone()))
+        .and(with_conversations(conversations.clone()))
+        .and_then(create_conversation_handler);
+
+    let get_conversation = warp::path!("api" / "v1" / "conversations" / Uuid)
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_conversations(conversations.clone()))
+        .and_then(get_conversation_handler);
+
+    let delete_conversation = warp::path!("api" / "v1" / "conversations" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(auth_state.clone()))
+        .and(with_conversations(conversations.clone()))
+        .and_then(delete_conversation_handler);
+
+    // Batch endpoints (with auth)
+    let create_batch = warp::path!("api" / "v1" / "batches")
+        .and(warp::post())
+        .and(with_auth(auth_state.clone()))
+        .and(with_body_limit(config.clone()))
+        .and(warp::body::json())
+        .and(with_batches(batches.clone()))
+        .and(with_executor(executor.clone()))
+        .and(with_session_manager(session_manager.clone()))
+        .and(with_conversations(conversations.clone()))
+        .and(with_config(config.clone()))
+        .and_then(create_batch_handler);
+
+    let get_batch = warp::path!("api" / "v1" / "batches" / Uuid)
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_batches(batches.clone()))
+        .and(with_session_manager(session_manager.clone()))
+        .and_then(get_batch_handler);
+
+    let batch_results = warp::path!("api" / "v1" / "batches" / Uuid / "results")
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_batches(batches.clone()))
+        .and_then(batch_results_handler);
+
+    let cancel_batch = warp::path!("api" / "v1" / "batches" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(auth_state.clone()))
+        .and(with_batches(batches))
+        .and_then(cancel_batch_handler);
+
+    // Boxed to keep the combined route type small enough to compile
+    let batches = create_batch
+        .or(get_batch)
+        .or(batch_results)
+        .or(cancel_batch)
+        .map(Reply::into_response)
+        .boxed();
+
+    // Scheduled job endpoints (with auth)
+    let create_job = warp::path!("api" / "v1" / "jobs")
+        .and(warp::post())
+        .and(with_auth(auth_state.clone()))
+        .and(with_body_limit(config.clone()))
+        .and(warp::body::json())
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(create_job_handler);
+
+    let list_jobs = warp::path!("api" / "v1" / "jobs")
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(list_jobs_handler);
+
+    let get_job = warp::path!("api" / "v1" / "jobs" / Uuid)
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(get_job_handler);
+
+    let update_job = warp::path!("api" / "v1" / "jobs" / Uuid)
+        .and(warp::put())
+        .and(with_auth(auth_state.clone()))
+        .and(with_body_limit(config.clone()))
+        .and(warp::body::json())
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(update_job_handler);
+
+    let delete_job = warp::path!("api" / "v1" / "jobs" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(auth_state.clone()))
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(delete_job_handler);
+
+    let pause_job = warp::path!("api" / "v1" / "jobs" / Uuid / "pause")
+        .and(warp::post())
+        .and(with_auth(auth_state.clone()))
+        .and(with_scheduler(scheduler.clone()))
+        .and_then(pause_job_handler);
+
+    let resume_job = warp::path!("api" / "v1" / "jobs" / Uuid / "resume")
+        .and(warp::post())
+        .and(with_auth(auth_state.clone()))
+        .and(with_scheduler(scheduler))
+        .and_then(resume_job_handler);
+
+    let jobs = create_job
+        .or(list_jobs)
+        .or(get_job)
+        .or(update_job)
+        .or(delete_job)
+        .or(pause_job)
+        .or(resume_job)
+        .map(Reply::into_response)
+        .boxed();
+
+    // Usage endpoint (with auth)
+    let usage = warp::path!("api" / "v1" / "usage")
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(warp::query())
+        .and(with_session_manager(session_manager.clone()))
+        .and(with_config(config.clone()))
+        .and_then(usage_handler);
+
+    // Webhook delivery log (with auth)
+    let webhook_deliveries = warp::path!("api" / "v1" / "webhooks" / "deliveries")
+        .and(warp::get())
+        .and(with_auth(auth_state.clone()))
+        .and(with_session_manager(session_manager.clone()))
+        .and_then(webhook_deliveries_handler);
+
+    // WebSocket endpoint (auth checked per message)
+    let ws = warp::path!("api" / "v1" / "ws")
+        .and(warp::ws())
+        .and(with_auth_state(auth_state.clone()))
+        .and(with_executor(executor.clone()))
+        .and(with_session_manager(session_manager))
+        .and(with_conversations(conversations))
+        .and(with_shared_config(config.clone()))
+        .and_then(ws_handler);
+
     // Inference endpoint (simple JSON)
     let inference = warp::path!("inference")
         .and(warp::post())
+        .and(with_body_limit(config.clone()))
         .and(warp::body::json())
         .and(with_executor(executor))
         .and(with_config(config))
         .and_then(inference_handler);
 
-    health
+    let routes = health
+        .or(openapi)
         .or(execute)
         .or(get_session)
         .or(delete_session)
-        .or(inference)
+        .or(create_conversation)
+        .or(get_conversation)
+        .or(delete_conversation)
+        .or(batches)
+        .or(jobs)
+        .or(usage)
+        .or(webhook_deliveries)
+        .or(ws)
+        .or(inference);
+
+    with_request_id(routes)
+}
+
+/// Recovers rejections and tags responses with a request id
+///
+/// The request id is taken from the client's `X-Request-Id` header when it
+/// is short printable ASCII, and generated otherwise. It is echoed in the
+/// `X-Request-Id` response header and in the body of error responses, and
+/// recorded on the request's tracing span so that everything logged while
+/// handling the request carries it.
+fn with_request_id<F, R>(
+    routes: F,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    R: warp::Reply,
+{
+    let outcome = routes
+        .map(|reply: R| Ok(reply.into_response()))
+        .recover(|rejection: warp::Rejection| async move {
+            Ok::<_, std::convert::Infallible>(Err(rejection_to_error(&rejection)))
+        })
+        .unify();
+
+    warp::header::optional::<String>(REQUEST_ID_HEADER)
+        .map(|id: Option<String>| {
+            id.filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LENGTH
+                    && id.bytes().all(|b| b.is_ascii_graphic())
+            })
+            .unwrap_or_else(|| Uuid::new_v4().to_string())
+        })
+        .or(warp::any().map(|| Uuid::new_v4().to_string()))
+        .unify()
+        .map(|request_id: String| {
+            tracing::Span::current().record("request_id", request_id.as_str());
+            request_id
+        })
+        .and(outcome)
+        .map(
+            |request_id: String, outcome: Result<warp::reply::Response, RobertError>| {
+                let mut response = match outcome {
+                    Ok(response) => response,
+                    Err(error) => error_reply(error, Some(request_id.clone())),
+                };
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                }
+                response
+            },
+        )
+        .with(warp::trace(|info| {
+            tracing::info_span!(
+                "request",
+                method = %info.method(),
+                path = %info.path(),
+                request_id = tracing::field::Empty,
+            )
+        }))
+        .map(warp::Reply::into_response)
+}
+
+/// Warp filter that rejects request bodies over the size limit
+///
+/// Reads `limits.max_request_size_mb` from the live configuration, so the
+/// limit follows reloads. Bodies must declare their length.
+fn with_body_limit(
+    config: SharedConfig,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and_then(move |length: Option<u64>| {
+            let limit = config.load().limits.max_request_size_mb as u64 * 1024 * 1024;
+            async move {
+                match length {
+                    Some(length) if length > limit => {
+                        Err(warp::reject::custom(RobertError::PayloadTooLarge(format!(
+                            "{} bytes (max: {} bytes)",
+                            length, limit
+                        ))))
+                    }
+                    Some(_) => Ok(()),
+                    None => Err(warp::reject::custom(RobertError::InvalidRequest(
+                        "Content-Length header is required".to_string(),
+                    ))),
+                }
+            }
+        })
+        .untuple_one()
 }
 
 /// Warp filter to inject executor
@@ -194,13 +659,53 @@ fn with_session_manager(
     warp::any().map(move || manager.clone())
 }
 
+/// Warp filter to inject the conversation store
+fn with_conversations(
+    conversations: Arc<ConversationManager>,
+) -> impl Filter<Extract = (Arc<ConversationManager>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || conversations.clone())
+}
+
+/// Warp filter to inject the batch manager
+fn with_batches(
+    batches: Arc<BatchManager>,
+) -> impl Filter<Extract = (Arc<BatchManager>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || batches.clone())
+}
+
+/// Warp filter to inject the scheduler
+fn with_scheduler(
+    scheduler: Arc<Scheduler>,
+) -> impl Filter<Extract = (Arc<Scheduler>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || scheduler.clone())
+}
+
 /// Warp filter to inject config
+///
+/// Yields a snapshot of the current configuration, so each request sees
+/// a consistent config even if a reload happens while it is running.
 fn with_config(
-    config: Arc<Config>,
+    config: SharedConfig,
 ) -> impl Filter<Extract = (Arc<Config>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || config.load_full())
+}
+
+/// Warp filter to inject the live configuration
+///
+/// For long-lived connections that should observe reloads.
+fn with_shared_config(
+    config: SharedConfig,
+) -> impl Filter<Extract = (SharedConfig,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || config.clone())
 }
 
+/// Warp filter to inject authentication state
+fn with_auth_state(
+    auth_state: Arc<AuthState>,
+) -> impl Filter<Extract = (Arc<AuthState>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || auth_state.clone())
+}
+
 /// Warp filter to inject health state
 fn with_health_state(
     state: Arc<HealthState>,
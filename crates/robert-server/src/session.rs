@@ -3,14 +3,36 @@
 //! This module provides thread-safe tracking of active sessions, including
 //! status updates, cancellation, and automatic cleanup. Uses Arc<Mutex<>>
 //! for shared state management across async tasks.
+//!
+//! Also owns the [`IdempotencyStore`] used to attach retried execute
+//! requests to the run they started, and the [`WebhookDispatcher`] that
+//! reports finished sessions to their callback URLs.
+//!
+//! Also keeps the token usage ledger: usage reported by executions is
+//! added to the session and, for sessions started with an API key, to
+//! per-day, per-model buckets for that key. Budgets are checked against
+//! these buckets.
 
+use crate::config::BudgetLimits;
 use crate::error::RobertError;
-use crate::models::{SessionState, SessionStatus};
-use std::collections::HashMap;
+use crate::idempotency::IdempotencyStore;
+use crate::models::{DailyUsage, SessionState, SessionStatus, Usage, UsageReport};
+use crate::webhook::WebhookDispatcher;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// Number of days of per-key usage retained
+const USAGE_RETENTION_DAYS: i64 = 92;
+
+/// Model name used for usage that does not report one
+const UNKNOWN_MODEL: &str = "unknown";
+
+/// Per-key usage buckets: token -> (UTC day, model) -> usage
+type UsageLedger = HashMap<String, BTreeMap<(NaiveDate, String), Usage>>;
+
 /// Session metadata tracked for each execution
 ///
 /// Contains timing information, current state, and optional error details.
@@ -30,6 +52,12 @@ struct SessionInfo {
 
     /// Error message if session failed
     error: Option<String>,
+
+    /// API key that started the session
+    owner: Option<String>,
+
+    /// Usage accumulated by the session
+    usage: Option<Usage>,
 }
 
 impl SessionInfo {
@@ -37,16 +65,19 @@ impl SessionInfo {
     ///
     /// # Arguments
     /// * `id` - Session UUID
+    /// * `owner` - API key that started the session
     ///
     /// # Returns
     /// New SessionInfo with current timestamp
-    fn new(id: Uuid) -> Self {
+    fn new(id: Uuid, owner: Option<String>) -> Self {
         Self {
             id,
             state: SessionState::Running,
             started_at: chrono::Utc::now().to_rfc3339(),
             completed_at: None,
             error: None,
+            owner,
+            usage: None,
         }
     }
 
@@ -61,6 +92,7 @@ impl SessionInfo {
             started_at: self.started_at.clone(),
             completed_at: self.completed_at.clone(),
             error: self.error.clone(),
+            usage: self.usage.clone(),
         }
     }
 }
@@ -76,6 +108,15 @@ pub struct SessionManager {
 
     /// Maximum number of sessions to keep in history
     max_history: usize,
+
+    /// Usage per API key, day and model
+    usage: Arc<Mutex<UsageLedger>>,
+
+    /// Idempotency keys of recent execute requests
+    idempotency: Arc<IdempotencyStore>,
+
+    /// Webhook summaries and delivery log
+    webhooks: Arc<WebhookDispatcher>,
 }
 
 impl SessionManager {
@@ -90,9 +131,22 @@ impl SessionManager {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             max_history,
+            usage: Arc::new(Mutex::new(HashMap::new())),
+            idempotency: Arc::new(IdempotencyStore::new(max_history)),
+            webhooks: Arc::new(WebhookDispatcher::new(max_history)),
         }
     }
 
+    /// Returns the idempotency keys of recent execute requests
+    pub fn idempotency(&self) -> &IdempotencyStore {
+        &self.idempotency
+    }
+
+    /// Returns the webhook dispatcher for sessions with a callback
+    pub fn webhooks(&self) -> &WebhookDispatcher {
+        &self.webhooks
+    }
+
     /// Registers a new session
     ///
     /// Creates a new session entry in Running state. If max concurrent
@@ -111,6 +165,31 @@ impl SessionManager {
         &self,
         session_id: Uuid,
         max_concurrent: usize,
+    ) -> Result<(), RobertError> {
+        self.register_with_owner(session_id, max_concurrent, None)
+            .await
+    }
+
+    /// Registers a new session started by an API key
+    ///
+    /// Like [`SessionManager::register`], but usage recorded for the
+    /// session is also charged to `owner`.
+    ///
+    /// # Arguments
+    /// * `session_id` - UUID for the new session
+    /// * `max_concurrent` - Maximum allowed concurrent running sessions
+    /// * `owner` - Bearer token of the caller, if any
+    ///
+    /// # Returns
+    /// Ok(()) if session registered, Err if concurrent limit exceeded
+    ///
+    /// # Errors
+    /// Returns RobertError::Internal if max concurrent sessions exceeded
+    pub async fn register_with_owner(
+        &self,
+        session_id: Uuid,
+        max_concurrent: usize,
+        owner: Option<String>,
     ) -> Result<(), RobertError> {
         let mut sessions = self.sessions.lock().await;
 
@@ -127,7 +206,7 @@ impl SessionManager {
             )));
         }
 
-        sessions.insert(session_id, SessionInfo::new(session_id));
+        sessions.insert(session_id, SessionInfo::new(session_id, owner));
         Ok(())
     }
 
@@ -258,6 +337,216 @@ impl SessionManager {
         removed_count
     }
 
+    /// Records usage reported by a session
+    ///
+    /// Adds the usage to the session and, if the session has an owner, to
+    /// the owner's bucket for the current UTC day and the usage's model.
+    ///
+    /// # Arguments
+    /// * `session_id` - Session that consumed the usage
+    /// * `usage` - Tokens and cost to record
+    ///
+    /// # Returns
+    /// Ok(()) if recorded, Err if session not found
+    pub async fn record_usage(&self, session_id: Uuid, usage: &Usage) -> Result<(), RobertError> {
+        self.record_usage_on(session_id, usage, chrono::Utc::now().date_naive())
+            .await
+    }
+
+    async fn record_usage_on(
+        &self,
+        session_id: Uuid,
+        usage: &Usage,
+        today: NaiveDate,
+    ) -> Result<(), RobertError> {
+        let owner = {
+            let mut sessions = self.sessions.lock().await;
+            let session = sessions
+                .get_mut(&session_id)
+                .ok_or_else(|| RobertError::SessionNotFound(session_id.to_string()))?;
+
+            match &mut session.usage {
+                Some(total) => total.add(usage),
+                None => session.usage = Some(usage.clone()),
+            }
+            session.owner.clone()
+        };
+
+        if let Some(owner) = owner {
+            let model = usage
+                .model
+                .clone()
+                .unwrap_or_else(|| UNKNOWN_MODEL.to_string());
+
+            let mut ledger = self.usage.lock().await;
+            let buckets = ledger.entry(owner).or_default();
+            buckets
+                .entry((today, model))
+                .or_insert_with(|| Usage {
+                    model: usage.model.clone(),
+                    ..Default::default()
+                })
+                .add(usage);
+
+            // Drop buckets past the retention window
+            let cutoff = today - chrono::Duration::days(USAGE_RETENTION_DAYS);
+            buckets.retain(|(day, _), _| *day > cutoff);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether an API key still has budget left
+    ///
+    /// # Arguments
+    /// * `token` - Bearer token of the caller
+    /// * `limits` - Budget limits that apply to the key
+    ///
+    /// # Returns
+    /// Ok(()) if all limits have headroom
+    ///
+    /// # Errors
+    /// Returns RobertError::BudgetExceeded naming the exhausted limit
+    pub async fn check_budget(
+        &self,
+        token: &str,
+        limits: &BudgetLimits,
+    ) -> Result<(), RobertError> {
+        self.check_budget_on(token, limits, chrono::Utc::now().date_naive())
+            .await
+    }
+
+    async fn check_budget_on(
+        &self,
+        token: &str,
+        limits: &BudgetLimits,
+        today: NaiveDate,
+    ) -> Result<(), RobertError> {
+        let (day, month) = self.period_totals(token, today).await;
+
+        let token_checks = [
+            (
+                "Daily token",
+                limits.daily_tokens,
+                day.total_tokens(),
+                "tomorrow",
+            ),
+            (
+                "Monthly token",
+                limits.monthly_tokens,
+                month.total_tokens(),
+                "next month",
+            ),
+        ];
+        for (name, limit, used, reset) in token_checks {
+            if let Some(limit) = limit {
+                if used >= limit {
+                    return Err(RobertError::BudgetExceeded(format!(
+                        "{} budget of {} exhausted ({} used); resets {} (UTC)",
+                        name, limit, used, reset
+                    )));
+                }
+            }
+        }
+
+        let cost_checks = [
+            (
+                "Daily cost",
+                limits.daily_cost_usd,
+                day.cost_usd,
+                "tomorrow",
+            ),
+            (
+                "Monthly cost",
+                limits.monthly_cost_usd,
+                month.cost_usd,
+                "next month",
+            ),
+        ];
+        for (name, limit, used, reset) in cost_checks {
+            if let Some(limit) = limit {
+                if used >= limit {
+                    return Err(RobertError::BudgetExceeded(format!(
+                        "{} budget of ${:.2} exhausted (${:.2} used); resets {} (UTC)",
+                        name, limit, used, reset
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds a usage report for an API key
+    ///
+    /// # Arguments
+    /// * `token` - Bearer token of the caller
+    /// * `limits` - Budget limits that apply to the key
+    /// * `days` - Number of days (including today) to break down
+    ///
+    /// # Returns
+    /// Usage totals with per-day and per-model breakdowns
+    pub async fn usage_report(&self, token: &str, limits: &BudgetLimits, days: u32) -> UsageReport {
+        self.usage_report_on(token, limits, days, chrono::Utc::now().date_naive())
+            .await
+    }
+
+    async fn usage_report_on(
+        &self,
+        token: &str,
+        limits: &BudgetLimits,
+        days: u32,
+        today: NaiveDate,
+    ) -> UsageReport {
+        let (today_usage, this_month) = self.period_totals(token, today).await;
+        let first_day = today - chrono::Duration::days(i64::from(days.max(1)) - 1);
+
+        let ledger = self.usage.lock().await;
+        let mut by_day: BTreeMap<NaiveDate, DailyUsage> = BTreeMap::new();
+        let mut by_model: BTreeMap<String, Usage> = BTreeMap::new();
+
+        for ((day, model), usage) in ledger.get(token).into_iter().flatten() {
+            if *day < first_day || *day > today {
+                continue;
+            }
+
+            let daily = by_day.entry(*day).or_insert_with(|| DailyUsage {
+                date: day.to_string(),
+                usage: Usage::default(),
+                by_model: BTreeMap::new(),
+            });
+            daily.usage.add(usage);
+            daily.by_model.insert(model.clone(), usage.clone());
+            by_model.entry(model.clone()).or_default().add(usage);
+        }
+
+        UsageReport {
+            today: today_usage,
+            this_month,
+            budget: limits.clone(),
+            by_day: by_day.into_values().rev().collect(),
+            by_model,
+        }
+    }
+
+    /// Sums a key's usage for the given day and its month
+    async fn period_totals(&self, token: &str, today: NaiveDate) -> (Usage, Usage) {
+        let ledger = self.usage.lock().await;
+        let mut day_total = Usage::default();
+        let mut month_total = Usage::default();
+
+        for ((day, _), usage) in ledger.get(token).into_iter().flatten() {
+            if day.year() == today.year() && day.month() == today.month() {
+                month_total.add(usage);
+                if *day == today {
+                    day_total.add(usage);
+                }
+            }
+        }
+
+        (day_total, month_total)
+    }
+
     /// Returns count of running sessions
     ///
     /// # Returns
@@ -468,4 +757,207 @@ mod tests {
         // At least one completed should exist
         assert_eq!(manager.total_count().await, 2);
     }
+
+    fn usage(input: u64, output: u64, cost: f64, model: &str) -> Usage {
+        Usage {
+            input_tokens: input,
+            output_tokens: output,
+            cost_usd: cost,
+            model: Some(model.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_usage_accumulates_per_session_and_owner() {
+        let manager = SessionManager::new(100);
+        let today = day("2025-10-17");
+
+        let owned = Uuid::new_v4();
+        manager
+            .register_with_owner(owned, 10, Some("key-1".to_string()))
+            .await
+            .unwrap();
+        let anonymous = Uuid::new_v4();
+        manager.register(anonymous, 10).await.unwrap();
+
+        manager
+            .record_usage_on(owned, &usage(100, 50, 0.01, "model-a"), today)
+            .await
+            .unwrap();
+        manager
+            .record_usage_on(owned, &usage(10, 5, 0.001, "model-b"), today)
+            .await
+            .unwrap();
+        manager
+            .record_usage_on(anonymous, &usage(999, 999, 1.0, "model-a"), today)
+            .await
+            .unwrap();
+
+        let status = manager.get_status(owned).await.unwrap();
+        assert_eq!(status.usage.unwrap().total_tokens(), 165);
+
+        let (day_total, month_total) = manager.period_totals("key-1", today).await;
+        assert_eq!(day_total.total_tokens(), 165);
+        assert_eq!(month_total.total_tokens(), 165);
+
+        // Sessions without an owner are not charged to any key
+        let (other, _) = manager.period_totals("", today).await;
+        assert_eq!(other.total_tokens(), 0);
+
+        assert!(manager
+            .record_usage(Uuid::new_v4(), &Usage::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_budget() {
+        let manager = SessionManager::new(100);
+        let session_id = Uuid::new_v4();
+        manager
+            .register_with_owner(session_id, 10, Some("key-1".to_string()))
+            .await
+            .unwrap();
+
+        manager
+            .record_usage_on(session_id, &usage(600, 400, 2.0, "m"), day("2025-10-01"))
+            .await
+            .unwrap();
+        manager
+            .record_usage_on(session_id, &usage(300, 200, 1.0, "m"), day("2025-10-17"))
+            .await
+            .unwrap();
+
+        let today = day("2025-10-17");
+        let check = |limits: BudgetLimits| {
+            let manager = &manager;
+            async move { manager.check_budget_on("key-1", &limits, today).await }
+        };
+
+        assert!(check(BudgetLimits::default()).await.is_ok());
+        assert!(check(BudgetLimits {
+            daily_tokens: Some(600),
+            ..Default::default()
+        })
+        .await
+        .is_ok());
+
+        let err = check(BudgetLimits {
+            daily_tokens: Some(500),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+        assert!(matches!(err, RobertError::BudgetExceeded(_)));
+        assert!(err.to_string().contains("Daily token"));
+
+        let err = check(BudgetLimits {
+            monthly_tokens: Some(1500),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Monthly token"));
+
+        let err = check(BudgetLimits {
+            monthly_cost_usd: Some(2.5),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("Monthly cost"));
+
+        // A new month starts from zero
+        assert!(manager
+            .check_budget_on(
+                "key-1",
+                &BudgetLimits {
+                    monthly_tokens: Some(1500),
+                    ..Default::default()
+                },
+                day("2025-11-01"),
+            )
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_usage_report_breakdown() {
+        let manager = SessionManager::new(100);
+        let session_id = Uuid::new_v4();
+        manager
+            .register_with_owner(session_id, 10, Some("key-1".to_string()))
+            .await
+            .unwrap();
+
+        for (date, model) in [
+            ("2025-10-15", "model-a"),
+            ("2025-10-17", "model-a"),
+            ("2025-10-17", "model-b"),
+        ] {
+            manager
+                .record_usage_on(session_id, &usage(100, 10, 0.5, model), day(date))
+                .await
+                .unwrap();
+        }
+
+        let limits = BudgetLimits {
+            daily_tokens: Some(1000),
+            ..Default::default()
+        };
+        let report = manager
+            .usage_report_on("key-1", &limits, 7, day("2025-10-17"))
+            .await;
+
+        assert_eq!(report.today.total_tokens(), 220);
+        assert_eq!(report.this_month.total_tokens(), 330);
+        assert_eq!(report.budget, limits);
+        assert_eq!(report.by_day.len(), 2);
+        assert_eq!(report.by_day[0].date, "2025-10-17");
+        assert_eq!(report.by_day[0].by_model.len(), 2);
+        assert_eq!(report.by_model["model-a"].total_tokens(), 220);
+        assert_eq!(report.by_model["model-b"].total_tokens(), 110);
+
+        // Only today when asking for a single day
+        let report = manager
+            .usage_report_on("key-1", &limits, 1, day("2025-10-17"))
+            .await;
+        assert_eq!(report.by_day.len(), 1);
+
+        // Unknown keys get an empty report
+        let report = manager
+            .usage_report_on("key-2", &limits, 7, day("2025-10-17"))
+            .await;
+        assert!(report.by_day.is_empty());
+        assert_eq!(report.today, Usage::default());
+    }
+
+    #[tokio::test]
+    async fn test_usage_retention() {
+        let manager = SessionManager::new(100);
+        let session_id = Uuid::new_v4();
+        manager
+            .register_with_owner(session_id, 10, Some("key-1".to_string()))
+            .await
+            .unwrap();
+
+        manager
+            .record_usage_on(session_id, &usage(100, 0, 0.0, "m"), day("2025-01-01"))
+            .await
+            .unwrap();
+        manager
+            .record_usage_on(session_id, &usage(100, 0, 0.0, "m"), day("2025-10-17"))
+            .await
+            .unwrap();
+
+        let ledger = manager.usage.lock().await;
+        let buckets = &ledger["key-1"];
+        assert_eq!(buckets.len(), 1);
+        assert!(buckets.keys().all(|(d, _)| *d == day("2025-10-17")));
+    }
 }
@@ -0,0 +1,640 @@
+//! Webhook callbacks on session completion
+//!
+//! Requests that set `options.callback_url` get a [`WebhookPayload`]
+//! POSTed to that URL when their session ends. The dispatcher collects the
+//! session's content, tool uses, usage and error from its events, then
+//! delivers the summary in the background, retrying with exponential
+//! backoff. Every delivery is kept in a bounded log that callers can read
+//! through GET /api/v1/webhooks/deliveries.
+//!
+//! # Signatures
+//!
+//! Each delivery carries three headers:
+//!
+//! - `X-Robert-Delivery`: delivery UUID (the same for every attempt)
+//! - `X-Robert-Timestamp`: Unix time of the attempt in seconds
+//! - `X-Robert-Signature`: `sha256=` followed by the hex HMAC-SHA256 of
+//!   `"<timestamp>.<body>"`, keyed with `webhooks.secret`
+//!
+//! Receivers can check a delivery with [`verify_signature`].
+//!
+//! Redirects are not followed; a 3xx answer fails the delivery.
+
+use crate::config::WebhookConfig;
+use crate::models::{
+    ClaudeEvent, DeliveryStatus, SessionState, Usage, WebhookDelivery, WebhookPayload,
+    WebhookToolUse,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Header carrying the delivery id
+pub const DELIVERY_HEADER: &str = "x-robert-delivery";
+
+/// Header carrying the signing timestamp
+pub const TIMESTAMP_HEADER: &str = "x-robert-timestamp";
+
+/// Header carrying the signature
+pub const SIGNATURE_HEADER: &str = "x-robert-signature";
+
+/// Summary being collected for a session with a callback
+#[derive(Debug)]
+struct PendingSummary {
+    /// Callback URL
+    url: String,
+
+    /// API key that started the session
+    owner: String,
+
+    /// Webhook settings when the session started
+    config: WebhookConfig,
+
+    /// Content produced so far
+    content: String,
+
+    /// Tools used so far
+    tool_uses: Vec<WebhookToolUse>,
+
+    /// Last error reported by the session
+    error: Option<String>,
+}
+
+/// Delivery log entry
+#[derive(Debug)]
+struct LoggedDelivery {
+    /// API key that started the session
+    owner: String,
+
+    /// Public delivery record
+    delivery: WebhookDelivery,
+}
+
+/// Collects session summaries and delivers them to callback URLs
+///
+/// Thread-safe; shared by all transports through the
+/// [`SessionManager`](crate::session::SessionManager).
+#[derive(Debug)]
+pub struct WebhookDispatcher {
+    /// Summaries of running sessions with a callback
+    pending: Mutex<HashMap<Uuid, PendingSummary>>,
+
+    /// Recent deliveries, oldest first
+    deliveries: Arc<Mutex<VecDeque<LoggedDelivery>>>,
+
+    /// Maximum number of deliveries kept in the log
+    max_deliveries: usize,
+
+    /// HTTP client used for deliveries
+    http: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    /// Creates a dispatcher
+    ///
+    /// # Arguments
+    /// * `max_deliveries` - Maximum number of deliveries kept in the log
+    pub fn new(max_deliveries: usize) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            deliveries: Arc::new(Mutex::new(VecDeque::new())),
+            max_deliveries,
+            // Redirects are not followed: an allowed callback host must not
+            // be able to forward signed deliveries to another URL
+            http: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("HTTP client without redirects builds"),
+        }
+    }
+
+    /// Starts collecting the summary of a session
+    ///
+    /// # Arguments
+    /// * `session_id` - Session with a callback
+    /// * `owner` - Bearer token of the caller (empty when auth is disabled)
+    /// * `url` - Callback URL, already checked against the allow-list
+    /// * `config` - Webhook settings to deliver with
+    pub async fn register(&self, session_id: Uuid, owner: &str, url: &str, config: &WebhookConfig) {
+        self.pending.lock().await.insert(
+            session_id,
+            PendingSummary {
+                url: url.to_string(),
+                owner: owner.to_string(),
+                config: config.clone(),
+                content: String::new(),
+                tool_uses: Vec::new(),
+                error: None,
+            },
+        );
+    }
+
+    /// Adds an event to a session's summary
+    ///
+    /// A `complete` event ends the summary and queues its delivery.
+    /// Events of sessions without a callback are ignored.
+    ///
+    /// # Arguments
+    /// * `session_id` - Session that emitted the event
+    /// * `event` - The event
+    pub async fn record_event(&self, session_id: Uuid, event: &ClaudeEvent) {
+        let mut pending = self.pending.lock().await;
+        let Some(summary) = pending.get_mut(&session_id) else {
+            return;
+        };
+
+        match event {
+            ClaudeEvent::Content { text } => summary.content.push_str(text),
+            ClaudeEvent::ToolUse { tool, params } => summary.tool_uses.push(WebhookToolUse {
+                tool: tool.clone(),
+                params: params.clone(),
+            }),
+            ClaudeEvent::Error { message, .. } => summary.error = Some(message.clone()),
+            ClaudeEvent::Complete { status, usage, .. } => {
+                let state = match status.as_str() {
+                    "success" => SessionState::Completed,
+                    "cancelled" => SessionState::Cancelled,
+                    _ => SessionState::Failed,
+                };
+                if let Some(summary) = pending.remove(&session_id) {
+                    self.dispatch(session_id, summary, state, usage.clone())
+                        .await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends a session's summary if it is still open
+    ///
+    /// Used when a session stops without a `complete` event; it is
+    /// reported as failed.
+    ///
+    /// # Arguments
+    /// * `session_id` - Session that stopped
+    pub async fn finish(&self, session_id: Uuid) {
+        let Some(mut summary) = self.pending.lock().await.remove(&session_id) else {
+            return;
+        };
+
+        if summary.error.is_none() {
+            summary.error = Some("Session ended without a result".to_string());
+        }
+        self.dispatch(session_id, summary, SessionState::Failed, None)
+            .await;
+    }
+
+    /// Returns the deliveries of a key, most recent first
+    ///
+    /// # Arguments
+    /// * `owner` - Bearer token of the caller
+    pub async fn deliveries(&self, owner: &str) -> Vec<WebhookDelivery> {
+        self.deliveries
+            .lock()
+            .await
+            .iter()
+            .rev()
+            .filter(|logged| logged.owner == owner)
+            .map(|logged| logged.delivery.clone())
+            .collect()
+    }
+
+    /// Logs a delivery and sends it on a background task
+    async fn dispatch(
+        &self,
+        session_id: Uuid,
+        summary: PendingSummary,
+        status: SessionState,
+        usage: Option<Usage>,
+    ) {
+        let payload = WebhookPayload {
+            session_id,
+            status,
+            content: summary.content,
+            tool_uses: summary.tool_uses,
+            usage,
+            error: summary.error,
+            completed_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let delivery = WebhookDelivery {
+            delivery_id: Uuid::new_v4(),
+            session_id,
+            url: summary.url,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_status_code: None,
+            last_error: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+        };
+
+        {
+            let mut deliveries = self.deliveries.lock().await;
+            while deliveries.len() >= self.max_deliveries.max(1) {
+                deliveries.pop_front();
+            }
+            deliveries.push_back(LoggedDelivery {
+                owner: summary.owner,
+                delivery: delivery.clone(),
+            });
+        }
+
+        tokio::spawn(deliver(
+            self.http.clone(),
+            self.deliveries.clone(),
+            summary.config,
+            delivery,
+            payload,
+        ));
+    }
+}
+
+/// Sends a payload until it is accepted or the attempts run out
+async fn deliver(
+    http: reqwest::Client,
+    log: Arc<Mutex<VecDeque<LoggedDelivery>>>,
+    config: WebhookConfig,
+    delivery: WebhookDelivery,
+    payload: WebhookPayload,
+) {
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Could not encode webhook payload: {}", e);
+            return;
+        }
+    };
+    let secret = config.secret.clone().unwrap_or_default();
+    let delivery_id = delivery.delivery_id;
+
+    for attempt in 1..=config.max_attempts {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let result = http
+            .post(&delivery.url)
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, sign(&secret, &timestamp, &body))
+            .body(body.clone())
+            .send()
+            .await;
+
+        let (status_code, error, retryable) = match result {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None, false)
+            }
+            Ok(response) if response.status().is_redirection() => {
+                let status = response.status();
+                (
+                    Some(status.as_u16()),
+                    Some(format!(
+                        "Callback answered {} (redirects are not followed)",
+                        status
+                    )),
+                    false,
+                )
+            }
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status.is_server_error()
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                (
+                    Some(status.as_u16()),
+                    Some(format!("Callback answered {}", status)),
+                    retryable,
+                )
+            }
+            Err(e) => (None, Some(e.to_string()), true),
+        };
+
+        let delivered = error.is_none();
+        let last = delivered || !retryable || attempt == config.max_attempts;
+        update(&log, delivery_id, |record| {
+            record.attempts = attempt;
+            record.last_status_code = status_code;
+            record.last_error = error.clone();
+            if last {
+                record.status = if delivered {
+                    DeliveryStatus::Delivered
+                } else {
+                    DeliveryStatus::Failed
+                };
+                record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            }
+        })
+        .await;
+
+        if delivered {
+            info!(
+                "Delivered webhook for session {} to {}",
+                delivery.session_id, delivery.url
+            );
+            return;
+        }
+        if last {
+            warn!(
+                "Giving up webhook for session {} after {} attempt(s): {}",
+                delivery.session_id,
+                attempt,
+                error.unwrap_or_default()
+            );
+            return;
+        }
+
+        let delay = config
+            .retry_delay_ms
+            .saturating_mul(1u64 << (attempt - 1).min(16));
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+}
+
+/// Applies a change to a logged delivery, if it is still in the log
+async fn update<F>(log: &Mutex<VecDeque<LoggedDelivery>>, delivery_id: Uuid, change: F)
+where
+    F: FnOnce(&mut WebhookDelivery),
+{
+    let mut log = log.lock().await;
+    if let Some(logged) = log
+        .iter_mut()
+        .find(|logged| logged.delivery.delivery_id == delivery_id)
+    {
+        change(&mut logged.delivery);
+    }
+}
+
+/// Computes the `X-Robert-Signature` value of a delivery
+///
+/// # Arguments
+/// * `secret` - Shared webhook secret
+/// * `timestamp` - Value of the `X-Robert-Timestamp` header
+/// * `body` - Request body
+///
+/// # Returns
+/// `sha256=` followed by the hex-encoded HMAC
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mac = mac(secret, timestamp, body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks the `X-Robert-Signature` header of a delivery
+///
+/// Compares in constant time.
+///
+/// # Arguments
+/// * `secret` - Shared webhook secret
+/// * `timestamp` - Value of the `X-Robert-Timestamp` header
+/// * `body` - Request body
+/// * `signature` - Value of the `X-Robert-Signature` header
+///
+/// # Returns
+/// True if the signature matches
+pub fn verify_signature(secret: &str, timestamp: &str, body: &str, signature: &str) -> bool {
+    let Some(hex_mac) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(hex_mac) else {
+        return false;
+    };
+    mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+/// HMAC over `"<timestamp>.<body>"`
+fn mac(secret: &str, timestamp: &str, body: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use warp::Filter;
+
+    const SECRET: &str = "webhook-secret";
+
+    /// A received delivery: headers of interest and body
+    type Received = (String, String, String);
+
+    /// Local stand-in for a callback receiver
+    ///
+    /// Answers 500 to the first `failures` requests and 200 afterwards.
+    async fn spawn_receiver(failures: usize) -> (SocketAddr, Arc<std::sync::Mutex<Vec<Received>>>) {
+        let received = Arc::new(std::sync::Mutex::new(Vec::<Received>::new()));
+        let log = received.clone();
+
+        let route = warp::post()
+            .and(warp::path!("hooks" / "done"))
+            .and(warp::header::<String>(DELIVERY_HEADER))
+            .and(warp::header::<String>(TIMESTAMP_HEADER))
+            .and(warp::header::<String>(SIGNATURE_HEADER))
+            .and(warp::body::bytes())
+            .map(
+                move |delivery: String,
+                      timestamp: String,
+                      signature: String,
+                      body: warp::hyper::body::Bytes| {
+                    let body = String::from_utf8_lossy(&body).to_string();
+                    let mut log = log.lock().unwrap();
+                    assert!(verify_signature(SECRET, &timestamp, &body, &signature));
+                    log.push((delivery, timestamp, body));
+                    let status = if log.len() <= failures {
+                        warp::http::StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        warp::http::StatusCode::OK
+                    };
+                    warp::reply::with_status("", status)
+                },
+            );
+
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (addr, received)
+    }
+
+    fn config(addr: SocketAddr) -> WebhookConfig {
+        WebhookConfig {
+            allowed_urls: vec![format!("http://{}/hooks", addr)],
+            secret: Some(SECRET.to_string()),
+            max_attempts: 3,
+            retry_delay_ms: 10,
+            timeout_seconds: 5,
+        }
+    }
+
+    async fn wait_for_delivery(dispatcher: &WebhookDispatcher, owner: &str) -> WebhookDelivery {
+        for _ in 0..200 {
+            if let Some(delivery) = dispatcher.deliveries(owner).await.into_iter().next() {
+                if delivery.status != DeliveryStatus::Pending {
+                    return delivery;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Delivery did not finish");
+    }
+
+    #[test]
+    fn test_signature_round_trip() {
+        let signature = sign(SECRET, "1700000000", "{}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(SECRET, "1700000000", "{}", &signature));
+        assert!(!verify_signature(SECRET, "1700000001", "{}", &signature));
+        assert!(!verify_signature("other", "1700000000", "{}", &signature));
+        assert!(!verify_signature(SECRET, "1700000000", "{}", "sha256=zz"));
+    }
+
+    #[tokio::test]
+    async fn test_delivers_summary_with_retries() {
+        let (addr, received) = spawn_receiver(1).await;
+        let dispatcher = WebhookDispatcher::new(10);
+        let session_id = Uuid::new_v4();
+        let url = format!("http://{}/hooks/done", addr);
+
+        dispatcher
+            .register(session_id, "key-1", &url, &config(addr))
+            .await;
+        for event in [
+            ClaudeEvent::Content {
+                text: "Logged ".to_string(),
+            },
+            ClaudeEvent::ToolUse {
+                tool: "cdp_command".to_string(),
+                params: serde_json::json!({ "command": "click" }),
+            },
+            ClaudeEvent::Content {
+                text: "in.".to_string(),
+            },
+            ClaudeEvent::Complete {
+                session_id,
+                status: "success".to_string(),
+                usage: Some(Usage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                    ..Default::default()
+                }),
+            },
+        ] {
+            dispatcher.record_event(session_id, &event).await;
+        }
+
+        let delivery = wait_for_delivery(&dispatcher, "key-1").await;
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.last_status_code, Some(200));
+        assert!(dispatcher.deliveries("key-2").await.is_empty());
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        // Retries reuse the delivery id
+        assert_eq!(received[0].0, received[1].0);
+        assert_eq!(received[1].0, delivery.delivery_id.to_string());
+
+        let payload: WebhookPayload = serde_json::from_str(&received[1].2).unwrap();
+        assert_eq!(payload.session_id, session_id);
+        assert_eq!(payload.status, SessionState::Completed);
+        assert_eq!(payload.content, "Logged in.");
+        assert_eq!(payload.tool_uses.len(), 1);
+        assert_eq!(payload.usage.unwrap().total_tokens(), 15);
+        assert!(payload.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let (addr, received) = spawn_receiver(usize::MAX).await;
+        let dispatcher = WebhookDispatcher::new(10);
+        let session_id = Uuid::new_v4();
+
+        dispatcher
+            .register(
+                session_id,
+                "key-1",
+                &format!("http://{}/hooks/done", addr),
+                &config(addr),
+            )
+            .await;
+        dispatcher
+            .record_event(
+                session_id,
+                &ClaudeEvent::Error {
+                    code: "PROCESS_FAILED".to_string(),
+                    message: "crashed".to_string(),
+                },
+            )
+            .await;
+        dispatcher.finish(session_id).await;
+
+        let delivery = wait_for_delivery(&dispatcher, "key-1").await;
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.last_status_code, Some(500));
+
+        let received = received.lock().unwrap();
+        let payload: WebhookPayload = serde_json::from_str(&received[0].2).unwrap();
+        assert_eq!(payload.status, SessionState::Failed);
+        assert_eq!(payload.error.as_deref(), Some("crashed"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_not_followed() {
+        let (target, received) = spawn_receiver(0).await;
+        let location = format!("http://{}/hooks/done", target);
+        let route = warp::post().map(move || {
+            warp::reply::with_header(
+                warp::reply::with_status("", warp::http::StatusCode::TEMPORARY_REDIRECT),
+                "location",
+                location.clone(),
+            )
+        });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let dispatcher = WebhookDispatcher::new(10);
+        let session_id = Uuid::new_v4();
+        dispatcher
+            .register(
+                session_id,
+                "key-1",
+                &format!("http://{}/hooks/done", addr),
+                &config(addr),
+            )
+            .await;
+        dispatcher.finish(session_id).await;
+
+        let delivery = wait_for_delivery(&dispatcher, "key-1").await;
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.last_status_code, Some(307));
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_without_callback_are_ignored() {
+        let dispatcher = WebhookDispatcher::new(10);
+        let session_id = Uuid::new_v4();
+
+        dispatcher
+            .record_event(
+                session_id,
+                &ClaudeEvent::Complete {
+                    session_id,
+                    status: "success".to_string(),
+                    usage: None,
+                },
+            )
+            .await;
+        dispatcher.finish(session_id).await;
+
+        assert!(dispatcher.deliveries("").await.is_empty());
+    }
+}
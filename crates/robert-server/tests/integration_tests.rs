@@ -10,9 +10,10 @@
 //! ```
 
 use robert_server::{
-    api::{execute_handler, get_session_handler, health_handler},
+    api::{execute_handler, get_session_handler, handle_rejection, health_handler},
     auth::{with_auth, AuthState},
     claude::{Executor, MockClaudeExecutor},
+    conversation::ConversationManager,
     models::{
         ClaudeEvent, DomState, RequestContext, RequestOptions, RobertRequest, Screenshot,
         ScreenshotMetadata, Viewport,
@@ -122,13 +123,9 @@ async fn test_auth_with_invalid_token() {
         .reply(&filter)
         .await;
 
-    // Should be UNAUTHORIZED (401) or INTERNAL_SERVER_ERROR (500) depending on error handling
-    assert!(
-        response.status() == StatusCode::UNAUTHORIZED
-            || response.status() == StatusCode::INTERNAL_SERVER_ERROR,
-        "Expected 401 or 500, got {}",
-        response.status()
-    );
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).expect("Valid JSON");
+    assert_eq!(body["code"], "AUTH_FAILED");
 }
 
 /// Test authentication without header
@@ -144,13 +141,9 @@ async fn test_auth_without_header() {
 
     let response = request().method("GET").path("/test").reply(&filter).await;
 
-    // Should be UNAUTHORIZED (401) or INTERNAL_SERVER_ERROR (500) depending on error handling
-    assert!(
-        response.status() == StatusCode::UNAUTHORIZED
-            || response.status() == StatusCode::INTERNAL_SERVER_ERROR,
-        "Expected 401 or 500, got {}",
-        response.status()
-    );
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).expect("Valid JSON");
+    assert_eq!(body["code"], "AUTH_FAILED");
 }
 
 /// Test session creation and retrieval
@@ -200,15 +193,10 @@ async fn test_session_not_found() {
         .reply(&filter)
         .await;
 
-    // The handler returns 200 OK with error JSON in the body (this is intentional)
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
-    // Verify the response contains an error
     let body: serde_json::Value = serde_json::from_slice(response.body()).expect("Valid JSON");
-    if let Some(code) = body.get("code") {
-        // Error response format
-        assert_eq!(code, "SESSION_NOT_FOUND");
-    }
+    assert_eq!(body["code"], "SESSION_NOT_FOUND");
 }
 
 /// Test execute endpoint with mock executor
@@ -227,7 +215,15 @@ async fn test_execute_endpoint() {
         .and(warp::any().map(move || session_manager.clone()))
         .and(warp::any().map(move || config.clone()))
         .and_then(|request, executor, manager, config| {
-            execute_handler(request, executor, manager, config)
+            execute_handler(
+                String::new(),
+                None,
+                request,
+                executor,
+                manager,
+                Arc::new(ConversationManager::new(100)),
+                config,
+            )
         });
 
     let response = request()
@@ -303,29 +299,6 @@ fn test_config_validation_tls_without_cert() {
     assert!(result.is_err());
 }
 
-/// Helper function to handle rejections for testing
-async fn handle_rejection(
-    err: warp::Rejection,
-) -> Result<impl warp::Reply, std::convert::Infallible> {
-    use robert_server::error::RobertError;
-
-    let code;
-    let message;
-
-    if err.is_not_found() {
-        code = StatusCode::NOT_FOUND;
-        message = "Not found".to_string();
-    } else if let Some(e) = err.find::<RobertError>() {
-        code = e.status_code();
-        message = e.to_string();
-    } else {
-        code = StatusCode::INTERNAL_SERVER_ERROR;
-        message = "Internal server error".to_string();
-    }
-
-    Ok(warp::reply::with_status(message, code))
-}
-
 /// Test that mock executor produces expected events
 #[tokio::test]
 async fn test_mock_executor_output() {
@@ -351,6 +324,7 @@ async fn test_mock_executor_output() {
         ClaudeEvent::Complete {
             session_id: last_id,
             status,
+            ..
         } => {
             assert_eq!(*last_id, session_id);
             assert_eq!(status, "success");
@@ -0,0 +1,1122 @@
+/// Encrypted export and import of a complete user
+///
+/// An archive is a single streamed file (see `crypto::StreamEncryptor`)
+/// encrypted with a key derived from an export passphrase. The KDF
+/// parameters and salt are in the stream header, so the archive opens on
+/// any machine with nothing but the passphrase.
+///
+/// Inside the stream, files follow one another and a manifest closes it:
+///
+/// ```text
+/// "F" || [2 bytes: Path length (BE)] || [Path] || [8 bytes: Size (BE)] || [Content]
+/// ...
+/// "M" || [4 bytes: Manifest length (BE)] || [Manifest JSON]
+/// ```
+///
+/// Paths are relative to the user directory: `user.json`, `user-profile.md`,
+/// `commands/*`, `logs/debug.log*` (as JSON lines) and
+/// `browser-profiles/**`. Encrypted user files are stored decrypted; the
+/// archive encryption protects them. The manifest lists every file with its
+/// size and SHA-256 checksum, and an archive whose files do not match it is
+/// rejected before anything is imported.
+///
+/// Importing creates a new user with its own password, data key and
+/// recovery codes, and re-encrypts the archived files under that key.
+use crate::profiles::{
+    crypto::{
+        decrypt_file, decrypt_stream, derive_key, derive_key_with, encrypt_record, is_record_log,
+        is_stream_format, read_stream_header, record_log_prefix, CryptoError, EncryptionKey,
+        EnvelopeHeader, FileKind, KdfParams, RecordReader, StreamDecryptor, StreamEncryptor,
+    },
+    manager::{ManagerError, UserManager, MAX_USERNAME_LENGTH},
+    storage::{
+        get_browser_profile_dir, get_browser_profiles_dir, get_tmp_dir, get_user_config_path,
+        get_user_dir, list_encrypted_files, read_user_file, replace_user_files, seal_user_files,
+        user_exists, validate_command_name, StorageError,
+    },
+    types::UserConfig,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Version of the archive layout inside the stream
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Tag of a file entry
+const ENTRY_FILE: u8 = b'F';
+
+/// Tag of the closing manifest
+const ENTRY_MANIFEST: u8 = b'M';
+
+/// Archive path of the user config
+const CONFIG_ENTRY: &str = "user.json";
+
+/// Archive path of the profile markdown
+const PROFILE_ENTRY: &str = "user-profile.md";
+
+/// Archive directory of commands
+const COMMANDS_ENTRY: &str = "commands";
+
+/// Archive directory of logs (JSON lines)
+const LOGS_ENTRY: &str = "logs";
+
+/// Highest number tried when renaming an imported user to a free name
+const MAX_NAME_SUFFIX: u32 = 100;
+
+/// Archive directory of browser profiles
+const BROWSER_PROFILES_ENTRY: &str = "browser-profiles";
+
+/// Largest file kept in memory while importing (everything but browser profiles)
+const MAX_MEMORY_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Largest manifest accepted
+const MAX_MANIFEST_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Buffer size for copying file contents
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Errors that can occur when exporting or importing a user
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    /// File system I/O error
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+
+    /// JSON serialization error
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// Cryptography error
+    #[error("Crypto error: {0}")]
+    CryptoError(#[from] CryptoError),
+
+    /// Storage error
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+
+    /// User creation or validation error
+    #[error("Manager error: {0}")]
+    ManagerError(#[from] ManagerError),
+
+    /// The passphrase does not open the archive
+    #[error("Wrong passphrase or damaged archive")]
+    InvalidPassphrase,
+
+    /// The archive is malformed or unsupported
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
+    /// A file does not match its manifest entry
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+}
+
+pub type Result<T> = std::result::Result<T, ArchiveError>;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// What to include in an export besides config, profile and commands
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Include browser profiles (cookies, history; can be large)
+    pub include_browser_profiles: bool,
+
+    /// Include the debug log and its rotated copies
+    pub include_logs: bool,
+}
+
+/// File stored in an archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path relative to the user directory
+    pub path: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// SHA-256 of the content (lowercase hex)
+    pub sha256: String,
+}
+
+/// Manifest closing an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Archive layout version (`ARCHIVE_FORMAT_VERSION`)
+    pub format_version: u32,
+
+    /// User the archive was exported from
+    pub username: String,
+
+    /// When the archive was written
+    pub exported_at: DateTime<Utc>,
+
+    /// Every file in the archive, in archive order
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// Result of importing an archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// Username the archive was imported as
+    pub username: String,
+
+    /// Original username if the user was renamed to avoid a collision
+    pub renamed_from: Option<String>,
+
+    /// Number of commands imported
+    pub commands: usize,
+
+    /// Number of log files imported
+    pub log_files: usize,
+
+    /// Number of browser profile files imported
+    pub browser_profile_files: usize,
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Export a user to a single encrypted archive
+///
+/// The archive is written next to `dest` first and renamed into place once
+/// complete.
+///
+/// # Parameters
+/// - `username`: User to export
+/// - `key`: The user's data encryption key
+/// - `dest`: Path of the archive to write
+/// - `passphrase`: Passphrase protecting the archive (minimum 12 characters)
+/// - `options`: Optional content to include
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+///
+/// # Returns
+/// - `ArchiveManifest`: The files written to the archive
+///
+/// # Errors
+/// - Returns `ManagerError` if the passphrase is too weak
+/// - Returns `StorageError` if a user file cannot be read or fails its integrity check
+pub fn export_user(
+    username: &str,
+    key: &EncryptionKey,
+    dest: &Path,
+    passphrase: &str,
+    options: &ExportOptions,
+    base_dir: Option<&Path>,
+) -> Result<ArchiveManifest> {
+    UserManager::validate_password(passphrase)?;
+
+    let user_dir = get_user_dir(username, base_dir)?;
+    if !user_exists(username, base_dir)? {
+        return Err(ManagerError::UserNotFound(username.to_string()).into());
+    }
+
+    let (archive_key, salt) = derive_key(passphrase, None)?;
+    let header = EnvelopeHeader::for_file(FileKind::Archive, username)
+        .with_kdf(KdfParams::password(), Some(&String::from_utf8_lossy(&salt)));
+
+    let staged = with_suffix(dest, ".partial");
+    let result = (|| {
+        let file = BufWriter::new(File::create(&staged)?);
+        let mut writer = ArchiveWriter {
+            encryptor: StreamEncryptor::new(file, &archive_key, &header)?,
+            entries: Vec::new(),
+        };
+
+        for path in list_encrypted_files(username, base_dir)? {
+            let relative = relative_path(&user_dir, &path)?;
+
+            if let Some(kind) = user_file_kind(&relative) {
+                let plaintext = read_user_file(username, &path, kind, key, base_dir)?;
+                writer.add(&relative, plaintext.len() as u64, &mut plaintext.as_slice())?;
+            } else if options.include_logs && is_log_file(&relative) {
+                let lines = log_lines(&fs::read(&path)?, key)?;
+                let entry = format!("{}/{}", LOGS_ENTRY, relative);
+                writer.add(&entry, lines.len() as u64, &mut lines.as_slice())?;
+            }
+        }
+
+        if options.include_browser_profiles {
+            let profiles_dir = get_browser_profiles_dir(username, base_dir)?;
+            for path in walk_files(&profiles_dir)? {
+                let relative = relative_path(&user_dir, &path)?;
+                let size = fs::metadata(&path)?.len();
+                writer.add(&relative, size, &mut File::open(&path)?)?;
+            }
+        }
+
+        let manifest = ArchiveManifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            username: username.to_string(),
+            exported_at: Utc::now(),
+            entries: writer.entries.clone(),
+        };
+        let file = writer.finish(&manifest)?;
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        Ok(manifest)
+    })();
+
+    match result {
+        Ok(manifest) => {
+            fs::rename(&staged, dest)?;
+            log::info!(
+                "📦 Exported user '{}' ({} files) to {}",
+                username,
+                manifest.entries.len(),
+                dest.display()
+            );
+            Ok(manifest)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&staged);
+            Err(e)
+        }
+    }
+}
+
+/// Writes file entries into the encrypted stream and tracks their checksums
+struct ArchiveWriter<W: Write> {
+    encryptor: StreamEncryptor<W>,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Add a file of `size` bytes read from `content`
+    fn add(&mut self, path: &str, size: u64, content: &mut dyn Read) -> Result<()> {
+        let path_length = u16::try_from(path.len())
+            .map_err(|_| ArchiveError::InvalidArchive(format!("Path too long: {}", path)))?;
+
+        self.encryptor.write_all(&[ENTRY_FILE])?;
+        self.encryptor.write_all(&path_length.to_be_bytes())?;
+        self.encryptor.write_all(path.as_bytes())?;
+        self.encryptor.write_all(&size.to_be_bytes())?;
+
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        let mut remaining = size;
+        while remaining > 0 {
+            let wanted = buffer.len().min(remaining as usize);
+            let read = content.read(&mut buffer[..wanted])?;
+            if read == 0 {
+                return Err(ArchiveError::InvalidArchive(format!(
+                    "{} changed while exporting",
+                    path
+                )));
+            }
+            hasher.update(&buffer[..read]);
+            self.encryptor.write_all(&buffer[..read])?;
+            remaining -= read as u64;
+        }
+
+        self.entries.push(ArchiveEntry {
+            path: path.to_string(),
+            size,
+            sha256: format!("{:x}", hasher.finalize()),
+        });
+
+        Ok(())
+    }
+
+    /// Write the manifest and the final chunk
+    fn finish(mut self, manifest: &ArchiveManifest) -> Result<W> {
+        let json = serde_json::to_vec(manifest)?;
+        let length = u32::try_from(json.len())
+            .map_err(|_| ArchiveError::InvalidArchive("Manifest too large".to_string()))?;
+
+        self.encryptor.write_all(&[ENTRY_MANIFEST])?;
+        self.encryptor.write_all(&length.to_be_bytes())?;
+        self.encryptor.write_all(&json)?;
+
+        Ok(self.encryptor.finish()?)
+    }
+}
+
+/// Decrypt a log file into JSON lines, whatever its format
+fn log_lines(encrypted: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
+    if encrypted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !is_record_log(encrypted) {
+        // Logs written before the record format hold JSON lines already
+        return Ok(if is_stream_format(encrypted) {
+            decrypt_stream(encrypted, key)?.0
+        } else {
+            decrypt_file(encrypted, key)?
+        });
+    }
+
+    let mut lines = Vec::new();
+    for record in RecordReader::new(encrypted, key)? {
+        match record {
+            Ok((plaintext, _)) => {
+                lines.extend_from_slice(&plaintext);
+                lines.push(b'\n');
+            }
+            Err(e) => log::warn!("⚠️  Skipping damaged log entry: {}", e),
+        }
+    }
+
+    Ok(lines)
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Import a user from an encrypted archive
+///
+/// The whole archive is read and checked against its manifest before the
+/// user is created. If the archive's username is taken and no `username` is
+/// given, the first free `name-2`, `name-3`, ... is used. The new user gets
+/// `password`, a new data key and new recovery codes; if anything fails
+/// after the user was created, the user is removed again.
+///
+/// # Parameters
+/// - `archive`: Path of the archive
+/// - `passphrase`: Passphrase the archive was exported with
+/// - `password`: Password of the imported user (minimum 12 characters)
+/// - `username`: Import under this username instead of the archived one
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+///
+/// # Returns
+/// - `ImportReport`: Username and number of imported files
+///
+/// # Errors
+/// - Returns `InvalidPassphrase` if the passphrase is wrong
+/// - Returns `InvalidArchive` or `ChecksumMismatch` if the archive is damaged
+/// - Returns `ManagerError` if the password is too weak or `username` is taken
+pub fn import_user(
+    archive: &Path,
+    passphrase: &str,
+    password: &str,
+    username: Option<&str>,
+    base_dir: Option<&Path>,
+) -> Result<ImportReport> {
+    UserManager::validate_password(password)?;
+
+    let staging = get_tmp_dir(base_dir)?.join(format!("import-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&staging)?;
+
+    let result = read_archive(archive, passphrase, &staging)
+        .and_then(|contents| install_user(contents, password, username, &staging, base_dir));
+
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        log::warn!("⚠️  Failed to remove import staging directory: {}", e);
+    }
+
+    result
+}
+
+/// Verified content of an archive
+struct ArchiveContents {
+    manifest: ArchiveManifest,
+
+    /// Config, profile, commands and logs
+    files: Vec<(String, Vec<u8>)>,
+
+    /// Browser profile files written to the staging directory
+    browser_profile_files: Vec<String>,
+}
+
+/// Read and verify an archive
+///
+/// Browser profile files are written below `staging`; everything else is
+/// kept in memory.
+fn read_archive(archive: &Path, passphrase: &str, staging: &Path) -> Result<ArchiveContents> {
+    let mut file = File::open(archive)?;
+    let header =
+        read_stream_header(&mut file).map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+
+    let is_archive = header
+        .associated_data
+        .as_ref()
+        .is_some_and(|data| data.kind == FileKind::Archive);
+    let (Some(kdf), Some(salt), true) = (&header.kdf, &header.salt, is_archive) else {
+        return Err(ArchiveError::InvalidArchive(
+            "Not a user archive".to_string(),
+        ));
+    };
+
+    // The header is not authenticated yet, so its KDF cost must be bounded
+    kdf.validate()
+        .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+    let (archive_key, _) = derive_key_with(passphrase, Some(salt.as_bytes()), kdf)?;
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut reader = StreamDecryptor::new(BufReader::new(file), &archive_key)?;
+
+    // The first chunk fails to authenticate if the passphrase is wrong
+    let mut tag = [0u8; 1];
+    reader
+        .read_exact(&mut tag)
+        .map_err(|_| ArchiveError::InvalidPassphrase)?;
+
+    let mut files = Vec::new();
+    let mut browser_profile_files = Vec::new();
+    let mut entries = Vec::new();
+
+    while tag[0] == ENTRY_FILE {
+        let path_length = u16::from_be_bytes(read_array(&mut reader)?) as usize;
+        let mut path = vec![0u8; path_length];
+        reader.read_exact(&mut path).map_err(damaged)?;
+        let path = String::from_utf8(path)
+            .map_err(|_| ArchiveError::InvalidArchive("Path is not UTF-8".to_string()))?;
+        validate_entry_path(&path)?;
+        let size = u64::from_be_bytes(read_array(&mut reader)?);
+
+        let mut content = (&mut reader).take(size);
+        let sha256 = if path.starts_with(BROWSER_PROFILES_ENTRY) {
+            let target = staging.join(&path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut out = HashingWriter::new(BufWriter::new(File::create(&target)?));
+            copy_exact(&mut content, &mut out, size, &path)?;
+            out.inner.flush()?;
+            browser_profile_files.push(path.clone());
+            out.sha256()
+        } else {
+            if size > MAX_MEMORY_ENTRY_SIZE {
+                return Err(ArchiveError::InvalidArchive(format!(
+                    "{} is too large",
+                    path
+                )));
+            }
+            let mut out = HashingWriter::new(Vec::with_capacity(size as usize));
+            copy_exact(&mut content, &mut out, size, &path)?;
+            let sha256 = out.sha256();
+            files.push((path.clone(), out.inner));
+            sha256
+        };
+
+        entries.push(ArchiveEntry { path, size, sha256 });
+
+        reader.read_exact(&mut tag).map_err(damaged)?;
+    }
+
+    if tag[0] != ENTRY_MANIFEST {
+        return Err(ArchiveError::InvalidArchive(format!(
+            "Unknown entry type {:#04x}",
+            tag[0]
+        )));
+    }
+
+    let length = u32::from_be_bytes(read_array(&mut reader)?);
+    if length > MAX_MANIFEST_SIZE {
+        return Err(ArchiveError::InvalidArchive(
+            "Manifest too large".to_string(),
+        ));
+    }
+    let mut json = vec![0u8; length as usize];
+    reader.read_exact(&mut json).map_err(damaged)?;
+    let manifest: ArchiveManifest = serde_json::from_slice(&json)?;
+
+    // Nothing may follow the manifest, and the stream must end properly
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).map_err(damaged)?;
+    if !rest.is_empty() {
+        return Err(ArchiveError::InvalidArchive(
+            "Data after the manifest".to_string(),
+        ));
+    }
+
+    if manifest.format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(ArchiveError::InvalidArchive(format!(
+            "Archive format {} (supported: {})",
+            manifest.format_version, ARCHIVE_FORMAT_VERSION
+        )));
+    }
+    verify_entries(&manifest.entries, &entries)?;
+
+    Ok(ArchiveContents {
+        manifest,
+        files,
+        browser_profile_files,
+    })
+}
+
+/// Check the files read from an archive against its manifest
+fn verify_entries(expected: &[ArchiveEntry], actual: &[ArchiveEntry]) -> Result<()> {
+    for (index, entry) in actual.iter().enumerate() {
+        if expected.get(index) != Some(entry) {
+            return Err(ArchiveError::ChecksumMismatch(entry.path.clone()));
+        }
+        if actual[..index].iter().any(|e| e.path == entry.path) {
+            return Err(ArchiveError::InvalidArchive(format!(
+                "Duplicate file {}",
+                entry.path
+            )));
+        }
+    }
+
+    if let Some(missing) = expected.get(actual.len()) {
+        return Err(ArchiveError::ChecksumMismatch(format!(
+            "{} is missing",
+            missing.path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Create the user and write the archived files re-encrypted under its key
+fn install_user(
+    contents: ArchiveContents,
+    password: &str,
+    username: Option<&str>,
+    staging: &Path,
+    base_dir: Option<&Path>,
+) -> Result<ImportReport> {
+    let config_json = contents
+        .files
+        .iter()
+        .find(|(path, _)| path == CONFIG_ENTRY)
+        .map(|(_, data)| data)
+        .ok_or_else(|| ArchiveError::InvalidArchive(format!("{} is missing", CONFIG_ENTRY)))?;
+    let mut config: UserConfig = serde_json::from_slice(config_json)?;
+
+    let archived_name = contents.manifest.username.clone();
+    let target = match username {
+        Some(name) => name.to_string(),
+        None => free_username(&archived_name, base_dir)?,
+    };
+
+    let (key, _) = UserManager::create_user(&target, password, base_dir)?;
+
+    let result = write_user(&target, &key, &mut config, &contents, staging, base_dir);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(get_user_dir(&target, base_dir)?);
+    }
+    let (commands, log_files) = result?;
+
+    log::info!(
+        "📥 Imported user '{}' as '{}' ({} files)",
+        archived_name,
+        target,
+        contents.manifest.entries.len()
+    );
+
+    Ok(ImportReport {
+        renamed_from: (target != archived_name).then_some(archived_name),
+        username: target,
+        commands,
+        log_files,
+        browser_profile_files: contents.browser_profile_files.len(),
+    })
+}
+
+/// Write the archived files of a freshly created user
+///
+/// # Returns
+/// - Number of commands and log files written
+fn write_user(
+    username: &str,
+    key: &EncryptionKey,
+    config: &mut UserConfig,
+    contents: &ArchiveContents,
+    staging: &Path,
+    base_dir: Option<&Path>,
+) -> Result<(usize, usize)> {
+    let user_dir = get_user_dir(username, base_dir)?;
+
+    // Browser profiles live at a new path; keep the names
+    config.username = username.to_string();
+    config.last_login = Utc::now();
+    for (name, path) in config.browser_profiles.iter_mut() {
+        let dir = get_browser_profile_dir(username, name, base_dir)?;
+        fs::create_dir_all(&dir)?;
+        *path = dir.to_string_lossy().into_owned();
+    }
+
+    let mut sealed = vec![(
+        get_user_config_path(username, base_dir)?,
+        FileKind::UserConfig,
+        serde_json::to_vec_pretty(config)?,
+    )];
+    let mut logs = Vec::new();
+    let mut commands = 0;
+
+    for (path, data) in &contents.files {
+        if path == PROFILE_ENTRY {
+            sealed.push((user_dir.join(path), FileKind::UserProfile, data.clone()));
+        } else if path.starts_with(&format!("{}/", COMMANDS_ENTRY)) {
+            sealed.push((user_dir.join(path), FileKind::Command, data.clone()));
+            commands += 1;
+        } else if let Some(name) = path.strip_prefix(&format!("{}/", LOGS_ENTRY)) {
+            let header = EnvelopeHeader::for_file(FileKind::Log, username);
+            let mut log = record_log_prefix();
+            for line in data.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+                log.extend_from_slice(&encrypt_record(line, key, &header)?);
+            }
+            logs.push((user_dir.join(name), log));
+        }
+    }
+    let log_files = logs.len();
+
+    // Logs are not in the manifest but are written in the same transaction
+    let mut files = seal_user_files(username, sealed, key, key, base_dir)?;
+    files.extend(logs);
+    replace_user_files(username, files, base_dir)?;
+
+    for path in &contents.browser_profile_files {
+        let target = user_dir.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(staging.join(path), target)?;
+    }
+
+    Ok((commands, log_files))
+}
+
+/// First username based on `name` that is not taken
+///
+/// Numbered candidates are cut to fit `MAX_USERNAME_LENGTH` bytes.
+///
+/// # Errors
+/// - Returns `ManagerError::UserExists` if `MAX_NAME_SUFFIX` numbered
+///   candidates are all taken
+fn free_username(name: &str, base_dir: Option<&Path>) -> Result<String> {
+    if !user_exists(name, base_dir)? {
+        return Ok(name.to_string());
+    }
+
+    for n in 2..=MAX_NAME_SUFFIX {
+        let suffix = format!("-{}", n);
+        let mut end = name.len().min(MAX_USERNAME_LENGTH - suffix.len());
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let candidate = format!("{}{}", &name[..end], suffix);
+        if !user_exists(&candidate, base_dir)? {
+            return Ok(candidate);
+        }
+    }
+
+    Err(ManagerError::UserExists(name.to_string()).into())
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Kind of an encrypted user file that goes into every archive
+fn user_file_kind(relative: &str) -> Option<FileKind> {
+    match relative {
+        CONFIG_ENTRY => Some(FileKind::UserConfig),
+        PROFILE_ENTRY => Some(FileKind::UserProfile),
+        _ => relative
+            .strip_prefix(&format!("{}/", COMMANDS_ENTRY))
+            .filter(|name| is_command_file(name))
+            .map(|_| FileKind::Command),
+    }
+}
+
+/// Whether a file name in the commands directory belongs to a command
+fn is_command_file(name: &str) -> bool {
+    name.strip_suffix(".md")
+        .is_some_and(|command| validate_command_name(command).is_ok())
+}
+
+/// Whether a path relative to the user directory is a debug log file
+/// (`debug.log` or a rotated `debug.log.N`)
+fn is_log_file(relative: &str) -> bool {
+    relative.strip_prefix("debug.log").is_some_and(|rest| {
+        rest.is_empty()
+            || rest
+                .strip_prefix('.')
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Check that an archive path is relative, stays inside the user directory
+/// and belongs to a known part of it
+fn validate_entry_path(path: &str) -> Result<()> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let safe = parts
+        .iter()
+        .all(|part| !part.is_empty() && *part != "." && *part != ".." && !part.contains('\\'));
+
+    let known = match parts.as_slice() {
+        [CONFIG_ENTRY] | [PROFILE_ENTRY] => true,
+        [COMMANDS_ENTRY, name] => is_command_file(name),
+        [LOGS_ENTRY, name] => is_log_file(name),
+        [BROWSER_PROFILES_ENTRY, _, _, ..] => true,
+        _ => false,
+    };
+
+    if safe && known {
+        Ok(())
+    } else {
+        Err(ArchiveError::InvalidArchive(format!(
+            "Unexpected path {}",
+            path
+        )))
+    }
+}
+
+/// Path relative to the user directory, with `/` separators
+fn relative_path(user_dir: &Path, path: &Path) -> Result<String> {
+    let relative = path.strip_prefix(user_dir).map_err(|_| {
+        ArchiveError::InvalidArchive(format!("{} is outside the user directory", path.display()))
+    })?;
+
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// All regular files below a directory (symlinks are skipped), sorted
+fn walk_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Append a suffix to a path's filename
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Read a fixed number of bytes from the archive stream
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes).map_err(damaged)?;
+    Ok(bytes)
+}
+
+/// Copy exactly `size` bytes of an entry
+fn copy_exact(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    size: u64,
+    path: &str,
+) -> Result<()> {
+    let copied = io::copy(reader, writer).map_err(damaged)?;
+    if copied != size {
+        return Err(ArchiveError::InvalidArchive(format!(
+            "{} is truncated",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Error for an archive stream that ends early or fails to authenticate
+fn damaged(e: io::Error) -> ArchiveError {
+    ArchiveError::InvalidArchive(format!("Damaged archive: {}", e))
+}
+
+/// Writer that hashes everything written through it
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// SHA-256 of everything written so far (lowercase hex)
+    fn sha256(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::storage::{
+        create_user_directory, load_command, load_user_config, load_user_profile, save_command,
+        save_user_config, save_user_profile,
+    };
+    use tempfile::TempDir;
+
+    const PASSWORD: &str = "secure_password123";
+    const PASSPHRASE: &str = "export passphrase 42";
+
+    /// Create a user with a profile, a command, a log and a browser profile
+    fn setup_user(username: &str, base_dir: Option<&Path>) -> EncryptionKey {
+        let (key, mut config) = UserManager::create_user(username, PASSWORD, base_dir).unwrap();
+
+        save_user_profile(username, "# Profile\n\nLikes tea.", &key, base_dir).unwrap();
+        save_command(username, "check-prices", "# Check prices", &key, base_dir).unwrap();
+
+        let header = EnvelopeHeader::for_file(FileKind::Log, username);
+        let mut log = record_log_prefix();
+        for line in [&b"{\"message\":\"one\"}"[..], b"{\"message\":\"two\"}"] {
+            log.extend_from_slice(&encrypt_record(line, &key, &header).unwrap());
+        }
+        let user_dir = get_user_dir(username, base_dir).unwrap();
+        fs::write(user_dir.join("debug.log"), log).unwrap();
+
+        let profile_dir = get_browser_profile_dir(username, "default", base_dir).unwrap();
+        fs::create_dir_all(profile_dir.join("Default")).unwrap();
+        fs::write(profile_dir.join("Default").join("Preferences"), b"{}").unwrap();
+        config
+            .browser_profiles
+            .insert("default".to_string(), profile_dir.display().to_string());
+        save_user_config(username, &config, &key, base_dir).unwrap();
+
+        key
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let key = setup_user("alice", base_dir);
+        let archive = temp_dir.path().join("alice.robert");
+
+        let options = ExportOptions {
+            include_browser_profiles: true,
+            include_logs: true,
+        };
+        let manifest =
+            export_user("alice", &key, &archive, PASSPHRASE, &options, base_dir).unwrap();
+        let paths: Vec<&str> = manifest.entries.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"user.json"));
+        assert!(paths.contains(&"commands/check-prices.md"));
+        assert!(paths.contains(&"logs/debug.log"));
+        assert!(paths.contains(&"browser-profiles/default/Default/Preferences"));
+        assert!(!paths.contains(&"recovery-codes.json"));
+
+        // The name is taken, so the import is renamed
+        let report =
+            import_user(&archive, PASSPHRASE, "other_password_456", None, base_dir).unwrap();
+        assert_eq!(report.username, "alice-2");
+        assert_eq!(report.renamed_from.as_deref(), Some("alice"));
+        assert_eq!(report.commands, 1);
+        assert_eq!(report.log_files, 1);
+        assert_eq!(report.browser_profile_files, 1);
+
+        let new_key = UserManager::unlock_key("alice-2", "other_password_456", base_dir).unwrap();
+        assert_ne!(new_key.as_bytes(), key.as_bytes());
+
+        let config = load_user_config("alice-2", &new_key, base_dir).unwrap();
+        assert_eq!(config.username, "alice-2");
+        let profile_dir = get_browser_profile_dir("alice-2", "default", base_dir).unwrap();
+        assert_eq!(
+            config.browser_profiles["default"],
+            profile_dir.display().to_string()
+        );
+        assert!(profile_dir.join("Default").join("Preferences").exists());
+
+        assert_eq!(
+            load_user_profile("alice-2", &new_key, base_dir).unwrap(),
+            "# Profile\n\nLikes tea."
+        );
+        assert_eq!(
+            load_command("alice-2", "check-prices", &new_key, base_dir).unwrap(),
+            "# Check prices"
+        );
+
+        let log = fs::read(get_user_dir("alice-2", base_dir).unwrap().join("debug.log")).unwrap();
+        let records: Vec<Vec<u8>> = RecordReader::new(log.as_slice(), &new_key)
+            .unwrap()
+            .map(|record| record.unwrap().0)
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], b"{\"message\":\"two\"}");
+    }
+
+    #[test]
+    fn test_import_wrong_passphrase() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let key = setup_user("bob", base_dir);
+        let archive = temp_dir.path().join("bob.robert");
+
+        export_user(
+            "bob",
+            &key,
+            &archive,
+            PASSPHRASE,
+            &ExportOptions::default(),
+            base_dir,
+        )
+        .unwrap();
+
+        let result = import_user(
+            &archive,
+            "wrong passphrase 42",
+            PASSWORD,
+            Some("bob_copy"),
+            base_dir,
+        );
+        assert!(matches!(result, Err(ArchiveError::InvalidPassphrase)));
+        assert!(!user_exists("bob_copy", base_dir).unwrap());
+    }
+
+    #[test]
+    fn test_import_rejects_checksum_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let archive = temp_dir.path().join("forged.robert");
+
+        let (archive_key, salt) = derive_key(PASSPHRASE, None).unwrap();
+        let header = EnvelopeHeader::for_file(FileKind::Archive, "mallory")
+            .with_kdf(KdfParams::password(), Some(&String::from_utf8_lossy(&salt)));
+        let mut writer = ArchiveWriter {
+            encryptor: StreamEncryptor::new(Vec::new(), &archive_key, &header).unwrap(),
+            entries: Vec::new(),
+        };
+        let config = serde_json::to_vec(&UserConfig::default()).unwrap();
+        writer
+            .add(CONFIG_ENTRY, config.len() as u64, &mut config.as_slice())
+            .unwrap();
+
+        let mut entries = writer.entries.clone();
+        entries[0].sha256 = format!("{:x}", Sha256::digest(b"something else"));
+        let manifest = ArchiveManifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            username: "mallory".to_string(),
+            exported_at: Utc::now(),
+            entries,
+        };
+        fs::write(&archive, writer.finish(&manifest).unwrap()).unwrap();
+
+        let result = import_user(&archive, PASSPHRASE, PASSWORD, None, base_dir);
+        assert!(matches!(result, Err(ArchiveError::ChecksumMismatch(_))));
+        assert!(!user_exists("mallory", base_dir).unwrap());
+    }
+
+    #[test]
+    fn test_import_rejects_costly_kdf() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let archive = temp_dir.path().join("costly.robert");
+
+        let kdf = KdfParams {
+            memory_kb: u32::MAX,
+            iterations: u32::MAX,
+            ..KdfParams::password()
+        };
+        let header =
+            EnvelopeHeader::for_file(FileKind::Archive, "mallory").with_kdf(kdf, Some("c2FsdA"));
+        let encryptor = StreamEncryptor::new(
+            Vec::new(),
+            &derive_key(PASSPHRASE, None).unwrap().0,
+            &header,
+        )
+        .unwrap();
+        fs::write(&archive, encryptor.finish().unwrap()).unwrap();
+
+        let result = import_user(&archive, PASSPHRASE, PASSWORD, None, base_dir);
+        assert!(matches!(result, Err(ArchiveError::InvalidArchive(_))));
+    }
+
+    #[test]
+    fn test_free_username() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+
+        // 32 bytes, with a multi-byte character where the suffix cuts in
+        let name = format!("a{}b", "日".repeat(10));
+        assert_eq!(name.len(), MAX_USERNAME_LENGTH);
+        assert_eq!(free_username(&name, base_dir).unwrap(), name);
+
+        create_user_directory(&name, base_dir).unwrap();
+        let candidate = free_username(&name, base_dir).unwrap();
+        assert_eq!(candidate, format!("a{}-2", "日".repeat(9)));
+        assert!(candidate.len() <= MAX_USERNAME_LENGTH);
+
+        for n in 2..=MAX_NAME_SUFFIX {
+            create_user_directory(&format!("alice-{}", n), base_dir).unwrap();
+        }
+        create_user_directory("alice", base_dir).unwrap();
+        assert!(matches!(
+            free_username("alice", base_dir),
+            Err(ArchiveError::ManagerError(ManagerError::UserExists(_)))
+        ));
+    }
+
+    #[test]
+    fn test_validate_entry_path() {
+        for path in [
+            "user.json",
+            "user-profile.md",
+            "commands/check-prices.md",
+            "logs/debug.log.1",
+            "browser-profiles/default/Default/Cookies",
+        ] {
+            assert!(validate_entry_path(path).is_ok(), "{}", path);
+        }
+
+        for path in [
+            "../user.json",
+            "/etc/passwd",
+            "commands/../.key",
+            "commands/nested/x.md",
+            "commands/check-prices.sh",
+            "commands/.hidden.md",
+            "commands/check prices.md",
+            "logs/debug.log.sh",
+            "browser-profiles/default/../../../.key",
+            "logs/notes.txt",
+            ".salt",
+            "",
+        ] {
+            assert!(validate_entry_path(path).is_err(), "{}", path);
+        }
+    }
+}
@@ -4,15 +4,33 @@
 //! - User login with password validation
 //! - User logout with session cleanup
 //! - Password verification against stored credentials
-//! - Active session tracking
+//! - Password change with data key rotation
+//! - Account recovery with a recovery code
+//! - Remembered logins through an OS key store
+//! - Active session tracking with auto-lock
+//! - Failed attempt throttling and an audit trail of login attempts
 
 use crate::profiles::{
-    crypto::{derive_key, EncryptionKey},
-    manager::UserManager,
-    storage::{load_salt, load_user_config, user_exists},
+    crypto::{
+        derive_key, generate_key, unwrap_key, wrap_key, CryptoError, EncryptionKey, EnvelopeHeader,
+        FileKind,
+    },
+    keystore::KeyStore,
+    manager::{ManagerError, UserManager},
+    recovery::codes_match,
+    storage::{
+        get_key_path, get_salt_path, get_user_dir, load_login_attempts, load_recovery_codes,
+        load_recovery_keys, load_remember_key, load_user_config, reencrypt_user_files,
+        remove_remember_key, replace_user_files, save_login_attempts, save_remember_key,
+        user_exists, DeleteReport, StorageError,
+    },
+    throttle::{Clock, LoginAttempt, LoginAttempts, LoginMethod, SystemClock},
     types::UserConfig,
 };
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 // ============================================================================
@@ -29,11 +47,31 @@ pub enum AuthError {
     #[error("Invalid password")]
     InvalidPassword,
 
+    /// Recovery code does not match any unused code
+    #[error("Invalid recovery code")]
+    InvalidRecoveryCode,
+
+    /// User has no recovery codes left
+    #[error("No recovery codes available for user: {0}")]
+    RecoveryUnavailable(String),
+
     /// No active session
     #[error("No active session")]
     #[allow(dead_code)]
     NoActiveSession,
 
+    /// Session auto-locked after inactivity; the password must be entered again
+    #[error("Session locked")]
+    SessionLocked,
+
+    /// Too many failed attempts; no attempt is accepted until the wait is over
+    #[error("Too many failed attempts, try again in {retry_after_secs} seconds")]
+    TooManyAttempts { retry_after_secs: u64 },
+
+    /// Key store error (remembered logins)
+    #[error("Key store error: {0}")]
+    KeyStoreError(#[from] crate::profiles::keystore::KeyStoreError),
+
     /// Storage error
     #[error("Storage error: {0}")]
     StorageError(#[from] crate::profiles::storage::StorageError),
@@ -57,6 +95,10 @@ pub type Result<T> = std::result::Result<T, AuthError>;
 ///
 /// This structure holds the current user's session information including
 /// their username, configuration, and encryption key.
+///
+/// The session locks itself after `preferences.auto_lock_minutes` without
+/// use of the key: the key is dropped and `get_encryption_key()` returns
+/// `SessionLocked` until `unlock()` is called with the password.
 #[derive(Clone)]
 pub struct UserSession {
     /// Username of the active user
@@ -65,24 +107,128 @@ pub struct UserSession {
     /// User configuration
     pub config: UserConfig,
 
-    /// Encryption key for accessing user's encrypted files
+    /// Encryption key for accessing user's encrypted files (None once locked)
     /// Wrapped in Arc<Mutex<>> for thread-safe access
-    pub encryption_key: Arc<Mutex<EncryptionKey>>,
+    encryption_key: Arc<Mutex<Option<EncryptionKey>>>,
+
+    /// Inactivity after which the session locks (None: never)
+    auto_lock: Option<Duration>,
+
+    /// Last time the encryption key was used
+    last_activity: Arc<Mutex<Instant>>,
+
+    /// Failed and blocked login attempts since the previous successful login
+    pub failed_attempts: Vec<LoginAttempt>,
 }
 
 impl UserSession {
     /// Create a new user session
+    ///
+    /// The auto-lock timeout comes from the user's preferences.
     pub fn new(username: String, config: UserConfig, encryption_key: EncryptionKey) -> Self {
+        let auto_lock = config
+            .preferences
+            .auto_lock_minutes
+            .map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+
         Self {
             username,
             config,
-            encryption_key: Arc::new(Mutex::new(encryption_key)),
+            encryption_key: Arc::new(Mutex::new(Some(encryption_key))),
+            auto_lock,
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            failed_attempts: Vec::new(),
         }
     }
 
+    /// Override the auto-lock timeout (None: never lock)
+    pub fn with_auto_lock(mut self, timeout: Option<Duration>) -> Self {
+        self.auto_lock = timeout;
+        self
+    }
+
     /// Get a clone of the encryption key
-    pub fn get_encryption_key(&self) -> EncryptionKey {
-        self.encryption_key.lock().unwrap().clone()
+    ///
+    /// Counts as activity for the auto-lock timeout.
+    ///
+    /// # Errors
+    /// - Returns `SessionLocked` if the session is locked
+    pub fn get_encryption_key(&self) -> Result<EncryptionKey> {
+        self.lock_if_idle();
+
+        let key = self.encryption_key.lock().unwrap().clone();
+        match key {
+            Some(key) => {
+                *self.last_activity.lock().unwrap() = Instant::now();
+                Ok(key)
+            }
+            None => Err(AuthError::SessionLocked),
+        }
+    }
+
+    /// Replace the encryption key (e.g. after a password change) and unlock
+    pub fn set_encryption_key(&self, key: EncryptionKey) {
+        *self.encryption_key.lock().unwrap() = Some(key);
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Whether the session is locked
+    pub fn is_locked(&self) -> bool {
+        self.lock_if_idle();
+        self.encryption_key.lock().unwrap().is_none()
+    }
+
+    /// Lock the session now, dropping the encryption key
+    pub fn lock(&self) {
+        // Dropping the key zeroizes it
+        self.encryption_key.lock().unwrap().take();
+    }
+
+    /// Lock the session if it has been idle longer than its auto-lock timeout
+    ///
+    /// # Returns
+    /// - `true` if this call locked the session
+    pub fn lock_if_idle(&self) -> bool {
+        let Some(timeout) = self.auto_lock else {
+            return false;
+        };
+        if self.last_activity.lock().unwrap().elapsed() < timeout {
+            return false;
+        }
+
+        let mut key = self.encryption_key.lock().unwrap();
+        if key.is_none() {
+            return false;
+        }
+        *key = None;
+
+        log::info!(
+            "🔒 Session of user '{}' locked after inactivity",
+            self.username
+        );
+        true
+    }
+
+    /// Unlock a locked session with the user's password
+    ///
+    /// Failed unlocks count towards the user's login throttling.
+    ///
+    /// # Errors
+    /// - Returns `InvalidPassword` if the password is incorrect
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
+    pub fn unlock(&self, password: &str, base_dir: Option<&std::path::Path>) -> Result<()> {
+        let (key, _) = AuthService::throttled(
+            &self.username,
+            LoginMethod::Unlock,
+            &SystemClock,
+            base_dir,
+            || AuthService::check_password(&self.username, password, base_dir),
+        )?;
+
+        self.set_encryption_key(key);
+        log::info!("🔓 Session of user '{}' unlocked", self.username);
+
+        Ok(())
     }
 }
 
@@ -96,6 +242,10 @@ pub struct AuthService;
 impl AuthService {
     /// Login a user with username and password
     ///
+    /// Failed logins are throttled: see `throttle` for the backoff and
+    /// lockout policy. The session lists the failed attempts since the
+    /// previous successful login in `failed_attempts`.
+    ///
     /// # Parameters
     /// - `username`: The username to login
     /// - `password`: The user's password
@@ -107,6 +257,7 @@ impl AuthService {
     /// # Errors
     /// - Returns `UserNotFound` if user doesn't exist
     /// - Returns `InvalidPassword` if password is incorrect
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
     /// - Returns `StorageError` if file operations fail
     /// - Returns `CryptoError` if decryption fails
     ///
@@ -124,6 +275,18 @@ impl AuthService {
         username: &str,
         password: &str,
         base_dir: Option<&std::path::Path>,
+    ) -> Result<UserSession> {
+        Self::login_with_clock(username, password, &SystemClock, base_dir)
+    }
+
+    /// Login a user, reading the time for throttling from `clock`
+    ///
+    /// Same as `login`; tests use it to step through backoff and lockout.
+    pub fn login_with_clock(
+        username: &str,
+        password: &str,
+        clock: &dyn Clock,
+        base_dir: Option<&std::path::Path>,
     ) -> Result<UserSession> {
         log::info!("🔐 Login attempt for user: {}", username);
 
@@ -133,18 +296,61 @@ impl AuthService {
             return Err(AuthError::UserNotFound(username.to_string()));
         }
 
-        // Load the salt
-        let salt = load_salt(username, base_dir)?;
+        let (mut session, failed_attempts) =
+            Self::throttled(username, LoginMethod::Password, clock, base_dir, || {
+                Self::authenticate(username, password, base_dir)
+            })?;
+
+        if !failed_attempts.is_empty() {
+            log::warn!(
+                "⚠️  {} unsuccessful login attempts for user '{}' since the last login",
+                failed_attempts.len(),
+                username
+            );
+        }
+        session.failed_attempts = failed_attempts;
 
-        // Derive encryption key from password and salt
-        let (encryption_key, _) = derive_key(password, Some(&salt))?;
+        Ok(session)
+    }
 
-        // Try to load user config with the derived key
+    /// Check a password and open a session for an existing user
+    fn authenticate(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<UserSession> {
+        // Unlock the data key with the password
+        let mut encryption_key = match Self::unlock(username, password, base_dir)? {
+            Some(key) => key,
+            None => {
+                log::warn!("❌ Login failed: Invalid password for user '{}'", username);
+                return Err(AuthError::InvalidPassword);
+            }
+        };
+
+        // Try to load user config with the key
         // If this succeeds, the password was correct
         match load_user_config(username, &encryption_key, base_dir) {
             Ok(config) => {
                 log::info!("✅ Login successful for user: {}", username);
 
+                // Users from before data keys still encrypt with the password key
+                if UserManager::uses_password_key(username, base_dir)? {
+                    match UserManager::migrate_to_data_key(username, &encryption_key, base_dir) {
+                        Ok(key) => encryption_key = key,
+                        Err(e) => {
+                            log::warn!("⚠️  Failed to migrate to a data encryption key: {}", e)
+                        }
+                    }
+                }
+
+                // Files written before the versioned format have no header yet
+                if let Err(e) =
+                    crate::profiles::storage::migrate_user(username, &encryption_key, base_dir)
+                {
+                    log::warn!("⚠️  Failed to upgrade encrypted files: {}", e);
+                }
+
                 // Update last login timestamp
                 let mut updated_config = config.clone();
                 if let Err(e) = UserManager::update_last_login(
@@ -170,6 +376,14 @@ impl AuthService {
                     encryption_key,
                 ))
             }
+            Err(StorageError::IntegrityError(e)) => {
+                log::error!(
+                    "❌ Login failed: User config of '{}' was tampered with: {}",
+                    username,
+                    e
+                );
+                Err(StorageError::IntegrityError(e).into())
+            }
             Err(_) => {
                 log::warn!("❌ Login failed: Invalid password for user '{}'", username);
                 Err(AuthError::InvalidPassword)
@@ -232,6 +446,7 @@ impl AuthService {
     ///
     /// # Errors
     /// - Returns error if user doesn't exist or storage fails
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
     #[allow(dead_code)]
     pub fn verify_password(
         username: &str,
@@ -242,15 +457,537 @@ impl AuthService {
             return Err(AuthError::UserNotFound(username.to_string()));
         }
 
-        let salt = load_salt(username, base_dir)?;
-        let (encryption_key, _) = derive_key(password, Some(&salt))?;
-
-        // Try to load config - if it succeeds, password is correct
-        match load_user_config(username, &encryption_key, base_dir) {
+        match Self::throttled(
+            username,
+            LoginMethod::Password,
+            &SystemClock,
+            base_dir,
+            || Self::check_password(username, password, base_dir),
+        ) {
             Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            Err(AuthError::InvalidPassword) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Change a user's password and rotate their data encryption key
+    ///
+    /// Every encrypted file (`user.json`, `user-profile.md`, commands and the
+    /// debug log) is re-encrypted with a new random data key, which is stored
+    /// wrapped by a key derived from the new password and a new salt. The
+    /// change is transactional: if any file fails to re-encrypt, all files
+    /// keep their old encryption and the old password keeps working.
+    ///
+    /// Users whose files are still encrypted with the password key are moved
+    /// to a data key as part of the change. The user's recovery codes stay
+    /// the same but are re-wrapped around the new data key.
+    ///
+    /// Stop anything that writes the user's encrypted files (such as the
+    /// encrypted logger) before calling this, and give it the returned key.
+    ///
+    /// # Parameters
+    /// - `username`: User whose password changes
+    /// - `current_password`: The user's current password
+    /// - `new_password`: The new password (minimum 12 characters)
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `EncryptionKey`: The new data encryption key; replace the key of any
+    ///   active `UserSession` with it
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if user doesn't exist
+    /// - Returns `InvalidPassword` if the current password is incorrect
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
+    /// - Returns `ManagerError` if the new password is too weak
+    /// - Returns `StorageError` if re-encryption fails (nothing is changed)
+    pub fn change_password(
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<EncryptionKey> {
+        log::info!("🔑 Password change for user: {}", username);
+
+        if !user_exists(username, base_dir)? {
+            return Err(AuthError::UserNotFound(username.to_string()));
+        }
+
+        UserManager::validate_password(new_password)?;
+
+        let (old_key, _) = Self::throttled(
+            username,
+            LoginMethod::PasswordChange,
+            &SystemClock,
+            base_dir,
+            || Self::check_password(username, current_password, base_dir),
+        )
+        .inspect_err(|e| {
+            if matches!(e, AuthError::InvalidPassword) {
+                log::warn!(
+                    "❌ Password change failed: Invalid password for user '{}'",
+                    username
+                );
+            }
+        })?;
+
+        let (password_key, salt) = derive_key(new_password, None)?;
+        let new_key = generate_key();
+        let wrapped = wrap_key(
+            &new_key,
+            &password_key,
+            &UserManager::data_key_header(username),
+        )?;
+
+        let mut codes = load_recovery_codes(username, &old_key, base_dir)?;
+        if codes.is_empty() {
+            codes = crate::profiles::recovery::generate_codes();
+        }
+
+        let mut key_files = vec![
+            (get_salt_path(username, base_dir)?, salt),
+            (get_key_path(username, base_dir)?, wrapped),
+        ];
+        key_files.extend(UserManager::recovery_files(
+            username, &codes, &old_key, &new_key, base_dir,
+        )?);
+
+        reencrypt_user_files(username, &old_key, &new_key, key_files, base_dir)?;
+
+        // A remembered login holds the old data key
+        if let Err(e) = remove_remember_key(username, base_dir) {
+            log::warn!("⚠️  Failed to forget remembered login: {}", e);
+        }
+
+        log::info!("✅ Password changed for user: {}", username);
+
+        Ok(new_key)
+    }
+
+    /// Recover an account with a recovery code and set a new password
+    ///
+    /// The recovery code unlocks the user's data encryption key, which is
+    /// then wrapped by a key derived from the new password and a new salt.
+    /// Files are not re-encrypted. The code is used up; the remaining codes
+    /// keep working. The salt, wrapped key and recovery files are replaced
+    /// in one transaction.
+    ///
+    /// # Parameters
+    /// - `username`: User to recover
+    /// - `recovery_code`: One of the user's unused recovery codes
+    /// - `new_password`: The new password (minimum 12 characters)
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `UserSession`: Session for the user, logged in with the new password
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if user doesn't exist
+    /// - Returns `RecoveryUnavailable` if the user has no recovery codes
+    /// - Returns `InvalidRecoveryCode` if the code does not match
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
+    /// - Returns `ManagerError` if the new password is too weak
+    pub fn recover_account(
+        username: &str,
+        recovery_code: &str,
+        new_password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<UserSession> {
+        log::info!("🛟 Account recovery for user: {}", username);
+
+        if !user_exists(username, base_dir)? {
+            return Err(AuthError::UserNotFound(username.to_string()));
+        }
+
+        UserManager::validate_password(new_password)?;
+
+        crate::profiles::storage::recover_reencryption(username, base_dir)?;
+
+        let recovery_keys = match load_recovery_keys(username, base_dir)? {
+            Some(keys) if !keys.is_empty() => keys,
+            _ => return Err(AuthError::RecoveryUnavailable(username.to_string())),
+        };
+
+        let (key, failed_attempts) = Self::throttled(
+            username,
+            LoginMethod::Recovery,
+            &SystemClock,
+            base_dir,
+            || match recovery_keys.unlock(recovery_code)? {
+                Some(key) => Ok(key),
+                None => {
+                    log::warn!(
+                        "❌ Account recovery failed: Invalid recovery code for user '{}'",
+                        username
+                    );
+                    Err(AuthError::InvalidRecoveryCode)
+                }
+            },
+        )?;
+
+        let remaining: Vec<String> = load_recovery_codes(username, &key, base_dir)?
+            .into_iter()
+            .filter(|code| !codes_match(code, recovery_code))
+            .collect();
+
+        let (password_key, salt) = derive_key(new_password, None)?;
+        let wrapped = wrap_key(&key, &password_key, &UserManager::data_key_header(username))?;
+
+        let mut files = vec![
+            (get_salt_path(username, base_dir)?, salt),
+            (get_key_path(username, base_dir)?, wrapped),
+        ];
+        files.extend(UserManager::recovery_files(
+            username, &remaining, &key, &key, base_dir,
+        )?);
+        replace_user_files(username, files, base_dir)?;
+
+        // Whoever knew the old password may also have a remembered login
+        if let Err(e) = remove_remember_key(username, base_dir) {
+            log::warn!("⚠️  Failed to forget remembered login: {}", e);
+        }
+
+        log::info!(
+            "✅ Account recovered for user: {} ({} recovery codes left)",
+            username,
+            remaining.len()
+        );
+
+        let mut session = Self::authenticate(username, new_password, base_dir)?;
+        session.failed_attempts = failed_attempts;
+        Ok(session)
+    }
+
+    /// Remember a session's login so it can be unlocked without the password
+    ///
+    /// The data key is wrapped by a new random device key and stored in the
+    /// key store; the device key is written to `.remember` in the user
+    /// directory. Remembering again replaces both.
+    ///
+    /// # Parameters
+    /// - `session`: Unlocked session to remember
+    /// - `store`: Key store holding the wrapped data key
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Errors
+    /// - Returns `SessionLocked` if the session is locked
+    /// - Returns `KeyStoreError` if the key store cannot store the secret
+    /// - Returns `StorageError` if the device key cannot be written
+    pub fn remember(
+        session: &UserSession,
+        store: &dyn KeyStore,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<()> {
+        let username = session.username.as_str();
+        let key = session.get_encryption_key()?;
+
+        let device_key = generate_key();
+        let wrapped = wrap_key(
+            &key,
+            &device_key,
+            &EnvelopeHeader::for_file(FileKind::DataKey, username),
+        )?;
+
+        store.set_secret(username, &wrapped)?;
+        save_remember_key(username, &device_key, base_dir)?;
+
+        log::info!("📌 Login remembered for user: {}", username);
+
+        Ok(())
+    }
+
+    /// Forget a remembered login
+    ///
+    /// The device key is removed even if the key store is unavailable, which
+    /// is enough to make the stored secret useless.
+    ///
+    /// # Errors
+    /// - Returns `StorageError` if the device key cannot be removed
+    /// - Returns `KeyStoreError` if the key store entry cannot be deleted
+    pub fn forget(
+        username: &str,
+        store: &dyn KeyStore,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<()> {
+        remove_remember_key(username, base_dir)?;
+        store.delete_secret(username)?;
+
+        log::info!("🧹 Remembered login forgotten for user: {}", username);
+
+        Ok(())
+    }
+
+    /// Login a user through their remembered login
+    ///
+    /// An interrupted password change is finished or rolled back first.
+    ///
+    /// # Parameters
+    /// - `username`: The username to login
+    /// - `store`: Key store holding the wrapped data key
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `Some(UserSession)`: Active session if the login was remembered
+    /// - `None`: If the login is not remembered or no longer valid (e.g. the
+    ///   password changed); a stale remembered login is forgotten
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if user doesn't exist
+    /// - Returns `KeyStoreError` if the key store cannot be read
+    /// - Returns `StorageError` if the user config was tampered with or
+    ///   cannot be read; the remembered login is kept
+    pub fn login_remembered(
+        username: &str,
+        store: &dyn KeyStore,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Option<UserSession>> {
+        if !user_exists(username, base_dir)? {
+            return Err(AuthError::UserNotFound(username.to_string()));
+        }
+
+        crate::profiles::storage::recover_reencryption(username, base_dir)?;
+
+        let Some(device_key) = load_remember_key(username, base_dir)? else {
+            return Ok(None);
+        };
+        let Some(wrapped) = store.get_secret(username)? else {
+            remove_remember_key(username, base_dir)?;
+            return Ok(None);
+        };
+
+        let (encryption_key, mut config) = match unwrap_key(&wrapped, &device_key)
+            .map_err(StorageError::from)
+            .and_then(|key| load_user_config(username, &key, base_dir).map(|c| (key, c)))
+        {
+            Ok(unlocked) => unlocked,
+            Err(StorageError::IntegrityError(e)) => {
+                log::error!(
+                    "❌ Login failed: User config of '{}' was tampered with: {}",
+                    username,
+                    e
+                );
+                return Err(StorageError::IntegrityError(e).into());
+            }
+            // Only a key that no longer decrypts makes the login stale
+            Err(StorageError::CryptoError(
+                CryptoError::DecryptionFailed(_) | CryptoError::InvalidCiphertext(_),
+            )) => {
+                log::warn!(
+                    "⚠️  Remembered login of '{}' is stale, forgetting it",
+                    username
+                );
+                Self::forget(username, store, base_dir)?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        log::info!("✅ Remembered login for user: {}", username);
+
+        if let Err(e) =
+            UserManager::update_last_login(username, &mut config, &encryption_key, base_dir)
+        {
+            log::warn!("⚠️  Failed to update last login timestamp: {}", e);
+        }
+
+        // No secret is guessed here, so a lockout does not apply, but the
+        // login still belongs in the audit trail
+        let mut attempts = Self::load_attempts(username, base_dir);
+        let failed_attempts = attempts.record_success(LoginMethod::Remembered, SystemClock.now());
+        Self::save_attempts(username, &attempts, base_dir);
+
+        let mut session = UserSession::new(username.to_string(), config, encryption_key);
+        session.failed_attempts = failed_attempts;
+        Ok(Some(session))
+    }
+
+    /// Delete a user after confirming their password
+    ///
+    /// Same as `UserManager::delete_user`, but the password confirmation is
+    /// throttled like a login. End the user's session and stop the
+    /// encrypted logger first.
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if user doesn't exist
+    /// - Returns `InvalidPassword` if the password is incorrect
+    /// - Returns `TooManyAttempts` if the user has to wait before trying again
+    /// - Returns `ManagerError` if the user directory cannot be removed
+    pub fn delete_user(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<DeleteReport> {
+        if !user_exists(username, base_dir)? {
+            return Err(AuthError::UserNotFound(username.to_string()));
+        }
+
+        let (report, _) = Self::throttled(
+            username,
+            LoginMethod::Deletion,
+            &SystemClock,
+            base_dir,
+            || {
+                UserManager::delete_user(username, password, base_dir).map_err(|e| match e {
+                    ManagerError::IncorrectPassword => AuthError::InvalidPassword,
+                    e => e.into(),
+                })
+            },
+        )?;
+
+        Ok(report)
+    }
+
+    /// Get the audit trail of a user's login attempts, oldest first
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if user doesn't exist
+    /// - Returns `StorageError` if the attempts file cannot be read
+    pub fn login_history(
+        username: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Vec<LoginAttempt>> {
+        if !user_exists(username, base_dir)? {
+            return Err(AuthError::UserNotFound(username.to_string()));
+        }
+
+        Ok(load_login_attempts(username, base_dir)?.history)
+    }
+
+    /// Run a password or recovery code check under login throttling
+    ///
+    /// The check is refused with `TooManyAttempts` while the user has to
+    /// wait. Otherwise its result is recorded: `InvalidPassword` and
+    /// `InvalidRecoveryCode` count as failures, other errors are not
+    /// attempts at all.
+    ///
+    /// Attempts for the same user run one at a time, from loading the
+    /// attempts file to saving it, so concurrent guesses cannot all pass
+    /// the check against the same failure count.
+    ///
+    /// # Returns
+    /// The result of `check` and, on success, the failed and blocked
+    /// attempts since the previous success
+    fn throttled<T>(
+        username: &str,
+        method: LoginMethod,
+        clock: &dyn Clock,
+        base_dir: Option<&std::path::Path>,
+        check: impl FnOnce() -> Result<T>,
+    ) -> Result<(T, Vec<LoginAttempt>)> {
+        let lock = attempt_lock(username, base_dir);
+        let _guard = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut attempts = Self::load_attempts(username, base_dir);
+
+        let now = clock.now();
+        if let Some(wait) = attempts.retry_after(now) {
+            attempts.record_blocked(method, now);
+            Self::save_attempts(username, &attempts, base_dir);
+
+            let retry_after_secs = (wait.num_milliseconds().max(0) as u64).div_ceil(1000);
+            log::warn!(
+                "⛔ Login attempt for user '{}' blocked for {}s after {} failures",
+                username,
+                retry_after_secs,
+                attempts.failed_count
+            );
+            return Err(AuthError::TooManyAttempts { retry_after_secs });
+        }
+
+        let result = check();
+
+        let now = clock.now();
+        let failed_attempts = match &result {
+            Ok(_) => attempts.record_success(method, now),
+            Err(AuthError::InvalidPassword | AuthError::InvalidRecoveryCode) => {
+                attempts.record_failure(method, now);
+                if attempts.is_locked_out(now) {
+                    log::warn!(
+                        "🔒 User '{}' locked out after {} failed attempts",
+                        username,
+                        attempts.failed_count
+                    );
+                }
+                Vec::new()
+            }
+            Err(_) => return result.map(|value| (value, Vec::new())),
+        };
+        Self::save_attempts(username, &attempts, base_dir);
+
+        result.map(|value| (value, failed_attempts))
+    }
+
+    /// Load a user's login attempts, starting over if the file is unreadable
+    fn load_attempts(username: &str, base_dir: Option<&std::path::Path>) -> LoginAttempts {
+        load_login_attempts(username, base_dir).unwrap_or_else(|e| {
+            log::warn!(
+                "⚠️  Failed to read login attempts of '{}', starting over: {}",
+                username,
+                e
+            );
+            LoginAttempts::default()
+        })
+    }
+
+    /// Save a user's login attempts; a failure is logged but not returned
+    fn save_attempts(username: &str, attempts: &LoginAttempts, base_dir: Option<&std::path::Path>) {
+        // Nothing to record for a user the check just deleted
+        if !user_exists(username, base_dir).unwrap_or(false) {
+            return;
+        }
+
+        if let Err(e) = save_login_attempts(username, attempts, base_dir) {
+            log::warn!("⚠️  Failed to save login attempts of '{}': {}", username, e);
+        }
+    }
+
+    /// Check a user's password, returning the data key
+    ///
+    /// # Errors
+    /// - Returns `InvalidPassword` if the password is incorrect
+    fn check_password(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<EncryptionKey> {
+        let key = match Self::unlock(username, password, base_dir)? {
+            Some(key) => key,
+            None => return Err(AuthError::InvalidPassword),
+        };
+        if load_user_config(username, &key, base_dir).is_err() {
+            return Err(AuthError::InvalidPassword);
         }
+
+        Ok(key)
     }
+
+    /// Unlock a user's data key, returning `None` for a wrong password
+    fn unlock(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Option<EncryptionKey>> {
+        match UserManager::unlock_key(username, password, base_dir) {
+            Ok(key) => Ok(Some(key)),
+            Err(ManagerError::CryptoError(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Lock serializing the login attempts of one user within this process
+///
+/// Users are told apart by their directory, so the same name under
+/// different base directories (as in tests) does not share a lock.
+fn attempt_lock(username: &str, base_dir: Option<&std::path::Path>) -> Arc<Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+
+    let user_dir = get_user_dir(username, base_dir).unwrap_or_else(|_| PathBuf::from(username));
+    let mut locks = LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    locks.entry(user_dir).or_default().clone()
 }
 
 // ============================================================================
@@ -260,7 +997,15 @@ impl AuthService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::profiles::crypto::{
+        decrypt_stream, encrypt_record, encrypt_stream, record_log_prefix, RecordReader,
+    };
+    use crate::profiles::keystore::MemoryKeyStore;
     use crate::profiles::storage::{create_user_directory, save_salt, save_user_config};
+    use crate::profiles::throttle::{
+        AttemptOutcome, ManualClock, FREE_ATTEMPTS, LOCKOUT_DURATION_SECS, LOCKOUT_THRESHOLD,
+    };
+    use std::fs;
     use tempfile::TempDir;
 
     fn setup_test_user(username: &str, password: &str) -> (TempDir, EncryptionKey) {
@@ -285,6 +1030,7 @@ mod tests {
                 default_timeout_ms: 5000,
                 inference_mode: crate::profiles::types::InferenceMode::Local,
                 language: "en".to_string(),
+                auto_lock_minutes: Some(15),
             },
             stats: crate::profiles::types::UserStats {
                 total_commands_run: 0,
@@ -340,7 +1086,7 @@ mod tests {
 
         assert_eq!(session.username, "new_user");
         // Verify encryption key is accessible
-        let key = session.encryption_key.lock().unwrap();
+        let key = session.get_encryption_key().unwrap();
         assert_eq!(key.as_bytes().len(), 32); // AES-256 key
 
         // Verify the user can login again with same password
@@ -348,7 +1094,7 @@ mod tests {
             AuthService::login("new_user", "secure_password123", Some(temp_dir.path())).unwrap();
 
         assert_eq!(login_session.username, "new_user");
-        let login_key = login_session.encryption_key.lock().unwrap();
+        let login_key = login_session.get_encryption_key().unwrap();
         assert_eq!(login_key.as_bytes().len(), 32);
     }
 
@@ -374,6 +1120,611 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_change_password() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let session =
+            AuthService::create_and_login("rotating_user", "old_password_123", base_dir).unwrap();
+        let old_key = session.get_encryption_key().unwrap();
+        crate::profiles::storage::save_command(
+            "rotating_user",
+            "check-prices",
+            "# Check Prices",
+            &old_key,
+            base_dir,
+        )
+        .unwrap();
+        let user_dir = crate::profiles::storage::get_user_dir("rotating_user", base_dir).unwrap();
+        let log_header = EnvelopeHeader::for_file(FileKind::Log, "rotating_user");
+        let mut log = record_log_prefix();
+        log.extend(encrypt_record(b"log line", &old_key, &log_header).unwrap());
+        fs::write(user_dir.join("debug.log"), log).unwrap();
+        fs::write(
+            user_dir.join("debug.log.1"),
+            encrypt_stream(b"log line\n", &old_key, &log_header).unwrap(),
+        )
+        .unwrap();
+
+        let new_key = AuthService::change_password(
+            "rotating_user",
+            "old_password_123",
+            "new_password_456",
+            base_dir,
+        )
+        .unwrap();
+        assert_ne!(new_key.as_bytes(), old_key.as_bytes());
+
+        // Files moved to the new key
+        let command = crate::profiles::storage::load_command(
+            "rotating_user",
+            "check-prices",
+            &new_key,
+            base_dir,
+        )
+        .unwrap();
+        assert_eq!(command, "# Check Prices");
+        assert!(load_user_config("rotating_user", &old_key, base_dir).is_err());
+        let log = fs::read(user_dir.join("debug.log")).unwrap();
+        let (record, header) = RecordReader::new(log.as_slice(), &new_key)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!((record.as_slice(), header), (&b"log line"[..], log_header));
+        let (log, _) =
+            decrypt_stream(&fs::read(user_dir.join("debug.log.1")).unwrap(), &new_key).unwrap();
+        assert_eq!(log, b"log line\n");
+
+        // Only the new password works
+        let session = AuthService::login("rotating_user", "new_password_456", base_dir).unwrap();
+        assert_eq!(
+            session.get_encryption_key().unwrap().as_bytes(),
+            new_key.as_bytes()
+        );
+        assert!(matches!(
+            AuthService::login("rotating_user", "old_password_123", base_dir),
+            Err(AuthError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn test_change_password_wrong_current_password() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        AuthService::create_and_login("careful_user", "old_password_123", base_dir).unwrap();
+
+        let result = AuthService::change_password(
+            "careful_user",
+            "not_the_password",
+            "new_password_456",
+            base_dir,
+        );
+        assert!(matches!(result, Err(AuthError::InvalidPassword)));
+
+        let result =
+            AuthService::change_password("careful_user", "old_password_123", "short", base_dir);
+        assert!(matches!(result, Err(AuthError::ManagerError(_))));
+    }
+
+    #[test]
+    fn test_change_password_rolls_back_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        AuthService::create_and_login("unlucky_user", "old_password_123", base_dir).unwrap();
+
+        // A command file that does not decrypt aborts the change
+        let commands_dir =
+            crate::profiles::storage::get_commands_dir("unlucky_user", base_dir).unwrap();
+        std::fs::write(commands_dir.join("broken.md"), b"not encrypted at all").unwrap();
+
+        let result = AuthService::change_password(
+            "unlucky_user",
+            "old_password_123",
+            "new_password_456",
+            base_dir,
+        );
+        assert!(matches!(result, Err(AuthError::StorageError(_))));
+
+        // The old password still unlocks everything and nothing was left behind
+        AuthService::login("unlucky_user", "old_password_123", base_dir).unwrap();
+        let user_dir = crate::profiles::storage::get_user_dir("unlucky_user", base_dir).unwrap();
+        let leftovers: Vec<_> = std::fs::read_dir(&user_dir)
+            .unwrap()
+            .chain(std::fs::read_dir(&commands_dir).unwrap())
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".rekey"))
+            .collect();
+        assert!(leftovers.is_empty(), "left behind: {:?}", leftovers);
+    }
+
+    #[test]
+    fn test_login_migrates_password_key_user() {
+        let (temp_dir, password_key) = setup_test_user("legacy_user", "password123");
+        let base_dir = Some(temp_dir.path());
+        assert!(UserManager::uses_password_key("legacy_user", base_dir).unwrap());
+
+        let session = AuthService::login("legacy_user", "password123", base_dir).unwrap();
+        let data_key = session.get_encryption_key().unwrap();
+
+        assert!(!UserManager::uses_password_key("legacy_user", base_dir).unwrap());
+        assert_ne!(data_key.as_bytes(), password_key.as_bytes());
+        assert!(load_user_config("legacy_user", &data_key, base_dir).is_ok());
+        assert!(load_user_config("legacy_user", &password_key, base_dir).is_err());
+
+        // The same password keeps working after the migration
+        let session = AuthService::login("legacy_user", "password123", base_dir).unwrap();
+        assert_eq!(
+            session.get_encryption_key().unwrap().as_bytes(),
+            data_key.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_recover_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let session =
+            AuthService::create_and_login("forgetful_user", "old_password_123", base_dir).unwrap();
+        let key = session.get_encryption_key().unwrap();
+        let codes = UserManager::recovery_codes("forgetful_user", &key, base_dir).unwrap();
+        assert_eq!(codes.len(), crate::profiles::recovery::RECOVERY_CODE_COUNT);
+
+        let result = AuthService::recover_account(
+            "forgetful_user",
+            "00000-00000-00000-00000",
+            "new_password_456",
+            base_dir,
+        );
+        assert!(matches!(result, Err(AuthError::InvalidRecoveryCode)));
+
+        let session = AuthService::recover_account(
+            "forgetful_user",
+            &codes[0].to_lowercase(),
+            "new_password_456",
+            base_dir,
+        )
+        .unwrap();
+
+        // Same data key, unlocked by the new password only
+        assert_eq!(
+            session.get_encryption_key().unwrap().as_bytes(),
+            key.as_bytes()
+        );
+        assert!(matches!(
+            AuthService::login("forgetful_user", "old_password_123", base_dir),
+            Err(AuthError::InvalidPassword)
+        ));
+
+        // The used code is gone, the others still work
+        let remaining = UserManager::recovery_codes("forgetful_user", &key, base_dir).unwrap();
+        assert_eq!(remaining, codes[1..].to_vec());
+        let result = AuthService::recover_account(
+            "forgetful_user",
+            &codes[0],
+            "other_password_789",
+            base_dir,
+        );
+        assert!(matches!(result, Err(AuthError::InvalidRecoveryCode)));
+        AuthService::recover_account("forgetful_user", &codes[1], "other_password_789", base_dir)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recovery_codes_survive_password_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let session =
+            AuthService::create_and_login("rotating_user", "old_password_123", base_dir).unwrap();
+        let codes = UserManager::recovery_codes(
+            "rotating_user",
+            &session.get_encryption_key().unwrap(),
+            base_dir,
+        )
+        .unwrap();
+
+        let new_key = AuthService::change_password(
+            "rotating_user",
+            "old_password_123",
+            "new_password_456",
+            base_dir,
+        )
+        .unwrap();
+        assert_eq!(
+            UserManager::recovery_codes("rotating_user", &new_key, base_dir).unwrap(),
+            codes
+        );
+
+        let session = AuthService::recover_account(
+            "rotating_user",
+            &codes[2],
+            "third_password_789",
+            base_dir,
+        )
+        .unwrap();
+        assert_eq!(
+            session.get_encryption_key().unwrap().as_bytes(),
+            new_key.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_regenerate_recovery_codes() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let session =
+            AuthService::create_and_login("cautious_user", "old_password_123", base_dir).unwrap();
+        let key = session.get_encryption_key().unwrap();
+        let old_codes = UserManager::recovery_codes("cautious_user", &key, base_dir).unwrap();
+
+        let new_codes =
+            UserManager::regenerate_recovery_codes("cautious_user", &key, base_dir).unwrap();
+        assert_ne!(new_codes, old_codes);
+        assert_eq!(
+            UserManager::recovery_codes("cautious_user", &key, base_dir).unwrap(),
+            new_codes
+        );
+
+        let result = AuthService::recover_account(
+            "cautious_user",
+            &old_codes[0],
+            "new_password_456",
+            base_dir,
+        );
+        assert!(matches!(result, Err(AuthError::InvalidRecoveryCode)));
+        AuthService::recover_account("cautious_user", &new_codes[0], "new_password_456", base_dir)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recover_account_without_codes() {
+        let (temp_dir, _key) = setup_test_user("legacy_user_2", "password123");
+
+        let result = AuthService::recover_account(
+            "legacy_user_2",
+            "00000-00000-00000-00000",
+            "new_password_456",
+            Some(temp_dir.path()),
+        );
+        assert!(matches!(result, Err(AuthError::RecoveryUnavailable(_))));
+    }
+
+    #[test]
+    fn test_remembered_login() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let store = MemoryKeyStore::new();
+
+        let session =
+            AuthService::create_and_login("remembered_user", "secure_password123", base_dir)
+                .unwrap();
+        assert!(
+            AuthService::login_remembered("remembered_user", &store, base_dir)
+                .unwrap()
+                .is_none()
+        );
+
+        AuthService::remember(&session, &store, base_dir).unwrap();
+
+        let remembered = AuthService::login_remembered("remembered_user", &store, base_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            remembered.get_encryption_key().unwrap().as_bytes(),
+            session.get_encryption_key().unwrap().as_bytes()
+        );
+
+        // The key store entry alone does not unlock the user
+        remove_remember_key("remembered_user", base_dir).unwrap();
+        assert!(store.get_secret("remembered_user").unwrap().is_some());
+        assert!(
+            AuthService::login_remembered("remembered_user", &store, base_dir)
+                .unwrap()
+                .is_none()
+        );
+
+        AuthService::remember(&session, &store, base_dir).unwrap();
+        AuthService::forget("remembered_user", &store, base_dir).unwrap();
+        assert!(store.get_secret("remembered_user").unwrap().is_none());
+        assert!(
+            AuthService::login_remembered("remembered_user", &store, base_dir)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_remembered_login_stale_after_password_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let store = MemoryKeyStore::new();
+
+        let session =
+            AuthService::create_and_login("stale_user", "secure_password123", base_dir).unwrap();
+        AuthService::remember(&session, &store, base_dir).unwrap();
+
+        // A device key left over from before the change cannot unlock the new data key
+        let device_key = load_remember_key("stale_user", base_dir).unwrap().unwrap();
+        AuthService::change_password(
+            "stale_user",
+            "secure_password123",
+            "new_password_456",
+            base_dir,
+        )
+        .unwrap();
+        save_remember_key("stale_user", &device_key, base_dir).unwrap();
+
+        assert!(
+            AuthService::login_remembered("stale_user", &store, base_dir)
+                .unwrap()
+                .is_none()
+        );
+        assert!(store.get_secret("stale_user").unwrap().is_none());
+        assert!(load_remember_key("stale_user", base_dir).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_remembered_login_recovers_interrupted_password_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let store = MemoryKeyStore::new();
+
+        let session =
+            AuthService::create_and_login("crashed_user", "secure_password123", base_dir).unwrap();
+        AuthService::remember(&session, &store, base_dir).unwrap();
+
+        // A password change stopped before its commit
+        let user_dir = get_user_dir("crashed_user", base_dir).unwrap();
+        fs::copy(
+            user_dir.join("user.json"),
+            user_dir.join("user.json.rekey-bak"),
+        )
+        .unwrap();
+        fs::write(user_dir.join("user.json"), b"half written").unwrap();
+        fs::write(
+            user_dir.join(".rekey-journal"),
+            r#"[{"path":"user.json","existed":true}]"#,
+        )
+        .unwrap();
+
+        assert!(
+            AuthService::login_remembered("crashed_user", &store, base_dir)
+                .unwrap()
+                .is_some()
+        );
+        assert!(!user_dir.join(".rekey-journal").exists());
+    }
+
+    #[test]
+    fn test_remembered_login_kept_on_read_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let store = MemoryKeyStore::new();
+
+        let session =
+            AuthService::create_and_login("flaky_user", "secure_password123", base_dir).unwrap();
+        AuthService::remember(&session, &store, base_dir).unwrap();
+
+        // The config cannot be read, but the key itself is still good
+        let config_path = get_user_dir("flaky_user", base_dir)
+            .unwrap()
+            .join("user.json");
+        let config = fs::read(&config_path).unwrap();
+        fs::remove_file(&config_path).unwrap();
+        fs::create_dir(&config_path).unwrap();
+
+        assert!(AuthService::login_remembered("flaky_user", &store, base_dir).is_err());
+        assert!(store.get_secret("flaky_user").unwrap().is_some());
+        assert!(load_remember_key("flaky_user", base_dir).unwrap().is_some());
+
+        fs::remove_dir(&config_path).unwrap();
+        fs::write(&config_path, config).unwrap();
+        assert!(
+            AuthService::login_remembered("flaky_user", &store, base_dir)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_session_auto_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+
+        let session =
+            AuthService::create_and_login("idle_user", "secure_password123", base_dir).unwrap();
+        assert!(!session.lock_if_idle());
+
+        let key = session.get_encryption_key().unwrap();
+        let session = session.with_auto_lock(Some(Duration::ZERO));
+
+        assert!(session.lock_if_idle());
+        assert!(!session.lock_if_idle());
+        assert!(session.is_locked());
+        assert!(matches!(
+            session.get_encryption_key(),
+            Err(AuthError::SessionLocked)
+        ));
+
+        assert!(matches!(
+            session.unlock("wrong_password_123", base_dir),
+            Err(AuthError::InvalidPassword)
+        ));
+        session.unlock("secure_password123", base_dir).unwrap();
+
+        let session = session.with_auto_lock(None);
+        assert!(!session.is_locked());
+        assert_eq!(
+            session.get_encryption_key().unwrap().as_bytes(),
+            key.as_bytes()
+        );
+        session.lock();
+        assert!(session.is_locked());
+    }
+
+    #[test]
+    fn test_login_backoff() {
+        let (temp_dir, _key) = setup_test_user("throttled_user", "password123");
+        let base_dir = Some(temp_dir.path());
+        let clock = ManualClock::new(chrono::Utc::now());
+
+        for _ in 0..=FREE_ATTEMPTS {
+            let result =
+                AuthService::login_with_clock("throttled_user", "wrongpassword", &clock, base_dir);
+            assert!(matches!(result, Err(AuthError::InvalidPassword)));
+        }
+
+        // Even the right password is refused until the wait is over
+        let result =
+            AuthService::login_with_clock("throttled_user", "password123", &clock, base_dir);
+        assert!(matches!(
+            result,
+            Err(AuthError::TooManyAttempts {
+                retry_after_secs: 1
+            })
+        ));
+
+        clock.advance(chrono::Duration::seconds(1));
+        let session =
+            AuthService::login_with_clock("throttled_user", "password123", &clock, base_dir)
+                .unwrap();
+
+        // The user sees what happened since their last login
+        let outcomes: Vec<_> = session
+            .failed_attempts
+            .iter()
+            .map(|attempt| attempt.outcome)
+            .collect();
+        let mut expected = vec![AttemptOutcome::Failure; FREE_ATTEMPTS as usize + 1];
+        expected.push(AttemptOutcome::Blocked);
+        assert_eq!(outcomes, expected);
+
+        let history = AuthService::login_history("throttled_user", base_dir).unwrap();
+        assert_eq!(history.len(), expected.len() + 1);
+        assert_eq!(history.last().unwrap().outcome, AttemptOutcome::Success);
+
+        // The next login starts from a clean slate
+        let session =
+            AuthService::login_with_clock("throttled_user", "password123", &clock, base_dir)
+                .unwrap();
+        assert!(session.failed_attempts.is_empty());
+    }
+
+    #[test]
+    fn test_login_lockout() {
+        let (temp_dir, key) = setup_test_user("locked_user", "password123");
+        let base_dir = Some(temp_dir.path());
+        let clock = ManualClock::new(chrono::Utc::now());
+
+        let mut attempts = LoginAttempts::default();
+        for _ in 0..LOCKOUT_THRESHOLD - 1 {
+            attempts.record_failure(
+                LoginMethod::Password,
+                clock.now() - chrono::Duration::hours(1),
+            );
+        }
+        save_login_attempts("locked_user", &attempts, base_dir).unwrap();
+
+        let result =
+            AuthService::login_with_clock("locked_user", "wrongpassword", &clock, base_dir);
+        assert!(matches!(result, Err(AuthError::InvalidPassword)));
+
+        let locked_out =
+            AuthService::login_with_clock("locked_user", "password123", &clock, base_dir);
+        assert!(matches!(
+            locked_out,
+            Err(AuthError::TooManyAttempts { retry_after_secs }) if retry_after_secs == LOCKOUT_DURATION_SECS as u64
+        ));
+
+        // Other ways of proving the password are locked out too
+        let config = load_user_config("locked_user", &key, base_dir).unwrap();
+        let session = UserSession::new("locked_user".to_string(), config, key);
+        session.lock();
+        assert!(matches!(
+            session.unlock("password123", base_dir),
+            Err(AuthError::TooManyAttempts { .. })
+        ));
+
+        clock.advance(chrono::Duration::seconds(LOCKOUT_DURATION_SECS));
+        AuthService::login_with_clock("locked_user", "password123", &clock, base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_attempts_are_throttled() {
+        let (temp_dir, _key) = setup_test_user("raced_user", "password123");
+        let base_dir = Some(temp_dir.path());
+        let clock = ManualClock::new(chrono::Utc::now());
+        let guesses = FREE_ATTEMPTS as usize + 6;
+        let barrier = std::sync::Barrier::new(guesses);
+
+        // Every guess starts at once; the clock does not move, so only the
+        // free attempts and the first delayed one may check the password
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..guesses)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        AuthService::login_with_clock(
+                            "raced_user",
+                            "wrongpassword",
+                            &clock,
+                            base_dir,
+                        )
+                        .map(|_| ())
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let failed = results
+            .iter()
+            .filter(|r| matches!(r, Err(AuthError::InvalidPassword)))
+            .count();
+        let blocked = results
+            .iter()
+            .filter(|r| matches!(r, Err(AuthError::TooManyAttempts { .. })))
+            .count();
+        assert_eq!(failed, FREE_ATTEMPTS as usize + 1);
+        assert_eq!(blocked, guesses - failed);
+
+        let attempts = load_login_attempts("raced_user", base_dir).unwrap();
+        assert_eq!(attempts.failed_count, FREE_ATTEMPTS + 1);
+        assert_eq!(attempts.history.len(), guesses);
+    }
+
+    #[test]
+    fn test_delete_user() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+
+        AuthService::create_and_login("leaving_user", "secure_password123", base_dir).unwrap();
+        AuthService::create_and_login("staying_user", "secure_password123", base_dir).unwrap();
+
+        assert!(matches!(
+            AuthService::delete_user("leaving_user", "wrong_password_123", base_dir),
+            Err(AuthError::InvalidPassword)
+        ));
+        assert!(user_exists("leaving_user", base_dir).unwrap());
+
+        let report =
+            AuthService::delete_user("leaving_user", "secure_password123", base_dir).unwrap();
+        assert_eq!(report.username, "leaving_user");
+        assert_eq!(report.browser_profiles, vec!["default".to_string()]);
+        assert!(report.wiped_files >= 5);
+        assert!(report.wipe_failures.is_empty());
+
+        assert!(!user_exists("leaving_user", base_dir).unwrap());
+        assert_eq!(
+            UserManager::list_users(base_dir).unwrap(),
+            vec!["staying_user".to_string()]
+        );
+        assert!(matches!(
+            AuthService::login("leaving_user", "secure_password123", base_dir),
+            Err(AuthError::UserNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_verify_password_correct() {
         let (temp_dir, _key) = setup_test_user("test_user_3", "password123");
@@ -17,8 +17,8 @@
 #![allow(unused_imports)]
 
 use crate::profiles::{
-    crypto::EncryptionKey,
-    storage::{get_commands_dir, StorageError},
+    crypto::{EncryptionKey, FileKind},
+    storage::{get_commands_dir, read_user_file, remove_user_file, write_user_file, StorageError},
     types::{CommandConfig, SimpleParameter},
 };
 use chrono::Utc;
@@ -134,12 +134,15 @@ impl CommandManager {
         // Serialize to JSON
         let json = serde_json::to_string_pretty(config)?;
 
-        // Encrypt
-        let encrypted =
-            crate::profiles::crypto::encrypt_file(json.as_bytes(), &self.encryption_key)?;
-
-        // Write to file
-        fs::write(command_path, encrypted)?;
+        // Encrypt and write
+        write_user_file(
+            &self.username,
+            &command_path,
+            FileKind::Command,
+            json.as_bytes(),
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         log::info!(
             "✅ Saved command '{}' for user '{}'",
@@ -160,11 +163,14 @@ impl CommandManager {
             return Err(CommandError::CommandNotFound(name.to_string()));
         }
 
-        // Read encrypted file
-        let encrypted = fs::read(command_path)?;
-
-        // Decrypt
-        let decrypted = crate::profiles::crypto::decrypt_file(&encrypted, &self.encryption_key)?;
+        // Read and decrypt
+        let decrypted = read_user_file(
+            &self.username,
+            &command_path,
+            FileKind::Command,
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         // Parse JSON
         let config: CommandConfig = serde_json::from_slice(&decrypted)?;
@@ -224,7 +230,12 @@ impl CommandManager {
             return Err(CommandError::CommandNotFound(name.to_string()));
         }
 
-        fs::remove_file(command_path)?;
+        remove_user_file(
+            &self.username,
+            &command_path,
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         log::info!("✅ Deleted command '{}' for user '{}'", name, self.username);
 
@@ -255,6 +266,17 @@ impl CommandExecutor {
         }
     }
 
+    /// Create a new command executor with custom base directory (for testing)
+    pub fn with_base_dir(
+        username: String,
+        encryption_key: EncryptionKey,
+        base_dir: PathBuf,
+    ) -> Self {
+        Self {
+            manager: CommandManager::with_base_dir(username, encryption_key, base_dir),
+        }
+    }
+
     /// Execute a command with parameters
     ///
     /// # Parameters
@@ -465,10 +487,14 @@ mod tests {
     fn test_parameter_substitution() {
         use tempfile::TempDir;
 
-        let _temp_dir = TempDir::new().unwrap();
+        let temp_dir = TempDir::new().unwrap();
         let (key, _) = derive_key("test_password", None).unwrap();
 
-        let executor = CommandExecutor::new("testuser".to_string(), key);
+        let executor = CommandExecutor::with_base_dir(
+            "testuser".to_string(),
+            key,
+            temp_dir.path().to_path_buf(),
+        );
 
         // Create commands directory
         let commands_dir = executor.manager.get_commands_dir().unwrap();
@@ -508,10 +534,14 @@ mod tests {
     fn test_missing_required_parameter() {
         use tempfile::TempDir;
 
-        let _temp_dir = TempDir::new().unwrap();
+        let temp_dir = TempDir::new().unwrap();
         let (key, _) = derive_key("test_password", None).unwrap();
 
-        let executor = CommandExecutor::new("testuser".to_string(), key);
+        let executor = CommandExecutor::with_base_dir(
+            "testuser".to_string(),
+            key,
+            temp_dir.path().to_path_buf(),
+        );
 
         // Create commands directory
         let commands_dir = executor.manager.get_commands_dir().unwrap();
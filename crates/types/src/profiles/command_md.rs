@@ -30,9 +30,9 @@
 //! 4. Fallback to static CDP if markdown includes it
 
 use crate::profiles::{
-    crypto::EncryptionKey,
+    crypto::{EncryptionKey, FileKind},
     markdown::{generate_command_template, parse_command_template, MarkdownParseError},
-    storage::{get_commands_dir, StorageError},
+    storage::{get_commands_dir, read_user_file, remove_user_file, write_user_file, StorageError},
     types::{Command, CommandInfo},
 };
 use std::collections::HashMap;
@@ -157,12 +157,15 @@ impl CommandManager {
         // Generate markdown template
         let markdown = generate_command_template(command)?;
 
-        // Encrypt
-        let encrypted =
-            crate::profiles::crypto::encrypt_file(markdown.as_bytes(), &self.encryption_key)?;
-
-        // Write to file
-        fs::write(command_path, encrypted)?;
+        // Encrypt and write
+        write_user_file(
+            &self.username,
+            &command_path,
+            FileKind::Command,
+            markdown.as_bytes(),
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         log::info!(
             "Saved command '{}' for user '{}'",
@@ -195,11 +198,14 @@ impl CommandManager {
             return Err(CommandError::CommandNotFound(name.to_string()));
         }
 
-        // Read encrypted file
-        let encrypted = fs::read(command_path)?;
-
-        // Decrypt
-        let decrypted = crate::profiles::crypto::decrypt_file(&encrypted, &self.encryption_key)?;
+        // Read and decrypt
+        let decrypted = read_user_file(
+            &self.username,
+            &command_path,
+            FileKind::Command,
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         // Parse markdown
         let markdown = String::from_utf8_lossy(&decrypted);
@@ -274,7 +280,12 @@ impl CommandManager {
             return Err(CommandError::CommandNotFound(name.to_string()));
         }
 
-        fs::remove_file(command_path)?;
+        remove_user_file(
+            &self.username,
+            &command_path,
+            &self.encryption_key,
+            self.base_dir.as_deref(),
+        )?;
 
         log::info!("Deleted command '{}' for user '{}'", name, self.username);
 
@@ -417,6 +428,17 @@ impl CommandExecutor {
         }
     }
 
+    /// Create a new command executor with custom base directory (for testing)
+    pub fn with_base_dir(
+        username: String,
+        encryption_key: EncryptionKey,
+        base_dir: PathBuf,
+    ) -> Self {
+        Self {
+            manager: CommandManager::with_base_dir(username, encryption_key, base_dir),
+        }
+    }
+
     /// Build execution prompt for a command
     ///
     /// This creates the AI prompt but doesn't execute it yet.
@@ -671,10 +693,14 @@ mod tests {
 
     #[test]
     fn test_get_static_cdp_script() {
-        let _temp_dir = TempDir::new().unwrap();
+        let temp_dir = TempDir::new().unwrap();
         let (key, _) = derive_key("test_password", None).unwrap();
 
-        let executor = CommandExecutor::new("testuser".to_string(), key);
+        let executor = CommandExecutor::with_base_dir(
+            "testuser".to_string(),
+            key,
+            temp_dir.path().to_path_buf(),
+        );
 
         // Create commands directory
         let commands_dir = executor.manager.get_commands_dir().unwrap();
@@ -696,10 +722,14 @@ mod tests {
 
     #[test]
     fn test_missing_required_parameter() {
-        let _temp_dir = TempDir::new().unwrap();
+        let temp_dir = TempDir::new().unwrap();
         let (key, _) = derive_key("test_password", None).unwrap();
 
-        let executor = CommandExecutor::new("testuser".to_string(), key);
+        let executor = CommandExecutor::with_base_dir(
+            "testuser".to_string(),
+            key,
+            temp_dir.path().to_path_buf(),
+        );
 
         // Create commands directory
         let commands_dir = executor.manager.get_commands_dir().unwrap();
@@ -4,6 +4,8 @@
 /// algorithms:
 /// - Argon2id for password hashing and key derivation (PHC winner)
 /// - AES-256-GCM for authenticated encryption (NIST standard)
+/// - Chunked AES-256-GCM streams for large files
+/// - Logs of independently encrypted records for append-only files
 ///
 /// Security properties:
 /// - Memory-hard password hashing resistant to GPU attacks
@@ -18,7 +20,7 @@
 /// ✗ Does NOT protect against memory dumps while key is in RAM
 /// ✗ Does NOT protect against malware with root access
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm,
 };
 use argon2::{
@@ -26,9 +28,10 @@ use argon2::{
     Algorithm, Argon2, Params, Version,
 };
 use rand::{rngs::OsRng, RngCore};
-use std::io;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use thiserror::Error;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 // ============================================================================
 // Constants and Configuration
@@ -56,6 +59,57 @@ const SALT_LENGTH: usize = 16;
 /// Nonce length for AES-GCM in bytes (12 bytes = 96 bits)
 const NONCE_LENGTH: usize = 12;
 
+/// Argon2id memory cost in KB for recovery codes (19 MB)
+/// Recovery codes are random with ~100 bits of entropy, so they do not need
+/// the password cost; a lighter setting keeps unlocking with a code fast
+const RECOVERY_ARGON2_MEM_SIZE_KB: u32 = 19456;
+
+/// Argon2id iteration count for recovery codes
+const RECOVERY_ARGON2_ITERATIONS: u32 = 2;
+
+/// Argon2id parallelism for recovery codes
+const RECOVERY_ARGON2_PARALLELISM: u32 = 1;
+
+/// How far above the current password parameters KDF parameters read from
+/// a file may go
+const KDF_MAX_FACTOR: u32 = 4;
+
+/// Magic bytes at the start of every versioned encrypted file
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"RBEF";
+
+/// Current encrypted file format version
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Length of the fixed envelope prefix (magic, version, header length)
+const ENVELOPE_PREFIX_LENGTH: usize = 7;
+
+/// AES-GCM authentication tag length in bytes
+const TAG_LENGTH: usize = 16;
+
+/// Magic bytes at the start of every streamed (chunked) encrypted file
+pub const STREAM_MAGIC: &[u8; 4] = b"RBES";
+
+/// Current streamed file format version
+pub const STREAM_VERSION: u8 = 1;
+
+/// Default plaintext bytes per stream chunk (64 KiB)
+pub const STREAM_SEGMENT_SIZE: u32 = 64 * 1024;
+
+/// Largest segment size accepted from a stream header (16 MiB)
+const MAX_STREAM_SEGMENT_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Magic bytes at the start of every encrypted record log
+pub const RECORD_LOG_MAGIC: &[u8; 4] = b"RBRL";
+
+/// Current record log format version
+pub const RECORD_LOG_VERSION: u8 = 1;
+
+/// Length of the record log prefix (magic, version)
+const RECORD_LOG_PREFIX_LENGTH: usize = 5;
+
+/// Largest record accepted when reading a record log (16 MiB)
+const MAX_RECORD_LENGTH: usize = 16 * 1024 * 1024;
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -83,6 +137,10 @@ pub enum CryptoError {
     #[error("Invalid ciphertext: {0}")]
     InvalidCiphertext(String),
 
+    /// Envelope version, cipher or KDF this build does not support
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(String),
+
     /// I/O error during file operations
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
@@ -180,6 +238,59 @@ impl std::fmt::Debug for EncryptionKey {
 /// # }
 /// ```
 pub fn derive_key(password: &str, salt: Option<&[u8]>) -> Result<(EncryptionKey, Vec<u8>)> {
+    derive_key_with(password, salt, &KdfParams::password())
+}
+
+/// Derive a key from a recovery code and salt using Argon2id
+///
+/// Works like `derive_key()` with lighter Argon2id parameters. Only use it
+/// for high-entropy secrets such as generated recovery codes, never for
+/// user-chosen passwords.
+///
+/// # Parameters
+/// - `code`: Normalized recovery code
+/// - `salt`: Optional salt bytes. If None, generates a new random salt
+///
+/// # Returns
+/// - `EncryptionKey`: Derived 256-bit key
+/// - `Vec<u8>`: Salt used for derivation
+pub fn derive_recovery_key(code: &str, salt: Option<&[u8]>) -> Result<(EncryptionKey, Vec<u8>)> {
+    derive_key_with(code, salt, &KdfParams::recovery())
+}
+
+/// Derive an encryption key with explicit KDF parameters
+///
+/// Used to re-derive keys with the parameters recorded in an envelope
+/// header, so the defaults can change without breaking existing users.
+///
+/// # Parameters
+/// - `password`: Password or recovery code
+/// - `salt`: Optional salt bytes. If None, generates a new random salt
+/// - `kdf`: Argon2id parameters
+///
+/// # Returns
+/// - `EncryptionKey`: Derived 256-bit key
+/// - `Vec<u8>`: Salt used for derivation
+///
+/// # Errors
+/// - Returns `UnsupportedFormat` if `kdf` is costlier than
+///   `KdfParams::validate()` allows
+pub fn derive_key_with(
+    password: &str,
+    salt: Option<&[u8]>,
+    kdf: &KdfParams,
+) -> Result<(EncryptionKey, Vec<u8>)> {
+    // Parameters often come from an unauthenticated header
+    kdf.validate()?;
+
+    let params = Params::new(
+        kdf.memory_kb,
+        kdf.iterations,
+        kdf.parallelism,
+        Some(KEY_LENGTH),
+    )
+    .map_err(|e| CryptoError::HashingFailed(format!("Invalid Argon2 params: {}", e)))?;
+
     // Create or validate salt
     let salt_string = match salt {
         Some(s) => {
@@ -192,15 +303,6 @@ pub fn derive_key(password: &str, salt: Option<&[u8]>) -> Result<(EncryptionKey,
         None => SaltString::generate(&mut OsRng),
     };
 
-    // Configure Argon2id parameters
-    let params = Params::new(
-        ARGON2_MEM_SIZE_KB,
-        ARGON2_ITERATIONS,
-        ARGON2_PARALLELISM,
-        Some(KEY_LENGTH),
-    )
-    .map_err(|e| CryptoError::HashingFailed(format!("Invalid Argon2 params: {}", e)))?;
-
     let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
 
     // Derive key from password
@@ -274,6 +376,328 @@ fn constant_time_compare(a: &[u8], b: &[u8]) -> bool {
     result == 0
 }
 
+// ============================================================================
+// Data Encryption Keys (Key Wrapping)
+// ============================================================================
+
+/// Generate a random 256-bit data encryption key
+///
+/// User files are encrypted with a data encryption key instead of the
+/// password-derived key. The data key is stored wrapped by the password
+/// key (see `wrap_key()`), so a password change only needs a new wrapper.
+///
+/// # Returns
+/// - `EncryptionKey`: New random key from the OS random number generator
+pub fn generate_key() -> EncryptionKey {
+    let mut key_bytes = vec![0u8; KEY_LENGTH];
+    OsRng.fill_bytes(&mut key_bytes);
+    EncryptionKey::from_bytes(key_bytes)
+}
+
+/// Encrypt a data encryption key with a key encryption key
+///
+/// The wrapped key uses the same envelope as `encrypt_envelope()`, so a
+/// wrong wrapping key or a tampered key file is detected by the auth tag
+/// when it is unwrapped. The header should record the KDF parameters that
+/// derived `wrapping_key`.
+///
+/// # Parameters
+/// - `key`: Data encryption key to protect
+/// - `wrapping_key`: Key encryption key, usually derived from the password
+/// - `header`: Envelope header for the wrapped key
+///
+/// # Returns
+/// - `Vec<u8>`: Wrapped key to store alongside the salt
+pub fn wrap_key(
+    key: &EncryptionKey,
+    wrapping_key: &EncryptionKey,
+    header: &EnvelopeHeader,
+) -> Result<Vec<u8>> {
+    encrypt_envelope(key.as_bytes(), wrapping_key, header)
+}
+
+/// Decrypt a data encryption key wrapped by `wrap_key()`
+///
+/// # Parameters
+/// - `wrapped`: Wrapped key from `wrap_key()`
+/// - `wrapping_key`: Key encryption key the data key was wrapped with
+///
+/// # Returns
+/// - `EncryptionKey`: The data encryption key
+///
+/// # Errors
+/// - Returns `DecryptionFailed` if the wrapping key is wrong (wrong password)
+///   or the wrapped key was tampered with
+/// - Returns `InvalidCiphertext` if the wrapped key is malformed
+pub fn unwrap_key(wrapped: &[u8], wrapping_key: &EncryptionKey) -> Result<EncryptionKey> {
+    let key_bytes = decrypt_file(wrapped, wrapping_key)?;
+
+    if key_bytes.len() != KEY_LENGTH {
+        return Err(CryptoError::InvalidCiphertext(format!(
+            "Expected {} byte wrapped key, got {}",
+            KEY_LENGTH,
+            key_bytes.len()
+        )));
+    }
+
+    Ok(EncryptionKey::from_bytes(key_bytes))
+}
+
+// ============================================================================
+// Encrypted File Envelope
+// ============================================================================
+
+/// Cipher used for the body of an envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherAlgorithm {
+    /// AES-256-GCM with a 96-bit random nonce
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+}
+
+/// Key derivation function recorded in an envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfAlgorithm {
+    /// Argon2id (version 0x13)
+    Argon2id,
+}
+
+/// Parameters of the KDF that derived a key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// KDF algorithm
+    pub algorithm: KdfAlgorithm,
+
+    /// Memory cost in KB
+    pub memory_kb: u32,
+
+    /// Number of iterations
+    pub iterations: u32,
+
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Current parameters for password-derived keys
+    pub fn password() -> Self {
+        Self {
+            algorithm: KdfAlgorithm::Argon2id,
+            memory_kb: ARGON2_MEM_SIZE_KB,
+            iterations: ARGON2_ITERATIONS,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+
+    /// Current parameters for recovery-code-derived keys
+    pub fn recovery() -> Self {
+        Self {
+            algorithm: KdfAlgorithm::Argon2id,
+            memory_kb: RECOVERY_ARGON2_MEM_SIZE_KB,
+            iterations: RECOVERY_ARGON2_ITERATIONS,
+            parallelism: RECOVERY_ARGON2_PARALLELISM,
+        }
+    }
+
+    /// Check the parameters against upper bounds before deriving a key
+    ///
+    /// Parameters read from a file header are not authenticated until the
+    /// key is derived, so a crafted file could otherwise make derivation
+    /// use unbounded memory or time.
+    ///
+    /// # Errors
+    /// - Returns `UnsupportedFormat` if a parameter exceeds
+    ///   `KDF_MAX_FACTOR` times the current password parameters
+    pub fn validate(&self) -> Result<()> {
+        let max = Self::password();
+        let within = |value: u32, max: u32| value <= max.saturating_mul(KDF_MAX_FACTOR);
+
+        if within(self.memory_kb, max.memory_kb)
+            && within(self.iterations, max.iterations)
+            && within(self.parallelism, max.parallelism)
+        {
+            Ok(())
+        } else {
+            Err(CryptoError::UnsupportedFormat(format!(
+                "KDF parameters exceed the supported limits: {} KB, {} iterations, {} lanes",
+                self.memory_kb, self.iterations, self.parallelism
+            )))
+        }
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::password()
+    }
+}
+
+/// Kind of content stored in an encrypted file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileKind {
+    /// `user.json`
+    UserConfig,
+    /// `user-profile.md`
+    UserProfile,
+    /// `commands/*`
+    Command,
+    /// `recovery-codes.json`
+    RecoveryCodes,
+    /// `debug.log` and its rotated copies
+    Log,
+    /// `.key` and recovery slots (a wrapped data encryption key)
+    DataKey,
+    /// `.manifest` (latest version of each user file)
+    Manifest,
+    /// Exported user archive
+    Archive,
+}
+
+/// Context stored in the envelope header and authenticated with the body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssociatedData {
+    /// What the file contains
+    pub kind: FileKind,
+
+    /// User the file belongs to
+    pub username: String,
+
+    /// Path of the file relative to the user directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Write counter, compared against the user's manifest to detect rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+}
+
+/// Header of a versioned encrypted file
+///
+/// The header is stored in clear text but authenticated as AES-GCM
+/// associated data, so it cannot be changed without failing decryption.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    /// Cipher used for the body
+    pub algorithm: CipherAlgorithm,
+
+    /// Parameters of the KDF that derived the key, if it came from a secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+
+    /// Where the KDF salt is stored (e.g. `.salt`); archives carry the salt
+    /// itself because nothing else travels with them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+
+    /// File kind and owner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub associated_data: Option<AssociatedData>,
+
+    /// Plaintext bytes per chunk (streamed files only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_size: Option<u32>,
+}
+
+impl Default for EnvelopeHeader {
+    fn default() -> Self {
+        Self {
+            algorithm: CipherAlgorithm::Aes256Gcm,
+            kdf: None,
+            salt: None,
+            associated_data: None,
+            segment_size: None,
+        }
+    }
+}
+
+impl EnvelopeHeader {
+    /// Header for a user file of the given kind
+    pub fn for_file(kind: FileKind, username: &str) -> Self {
+        Self {
+            associated_data: Some(AssociatedData {
+                kind,
+                username: username.to_string(),
+                path: None,
+                version: None,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Header binding a user file to its path and write version
+    pub fn for_path(kind: FileKind, username: &str, path: &str, version: Option<u64>) -> Self {
+        Self {
+            associated_data: Some(AssociatedData {
+                kind,
+                username: username.to_string(),
+                path: Some(path.to_string()),
+                version,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Record the KDF that derived the encryption key and where its salt is
+    pub fn with_kdf(mut self, kdf: KdfParams, salt: Option<&str>) -> Self {
+        self.kdf = Some(kdf);
+        self.salt = salt.map(str::to_string);
+        self
+    }
+}
+
+/// Read the header of an encrypted file without decrypting it
+///
+/// # Returns
+/// - `Some(EnvelopeHeader)`: For versioned files
+/// - `None`: For legacy headerless files
+///
+/// # Errors
+/// - Returns `UnsupportedFormat` for a newer envelope version
+pub fn read_envelope_header(encrypted: &[u8]) -> Result<Option<EnvelopeHeader>> {
+    match parse_envelope(encrypted) {
+        Some(Ok((header, _))) => Ok(Some(header)),
+        Some(Err(e)) => Err(e),
+        None => Ok(None),
+    }
+}
+
+/// Check whether encrypted data uses the legacy headerless format
+pub fn is_legacy_format(encrypted: &[u8]) -> bool {
+    !matches!(parse_envelope(encrypted), Some(Ok(_)))
+}
+
+/// Split an envelope into its header and body offset
+///
+/// Returns `None` if the data does not start with the envelope magic.
+fn parse_envelope(encrypted: &[u8]) -> Option<Result<(EnvelopeHeader, usize)>> {
+    if encrypted.len() < ENVELOPE_PREFIX_LENGTH || &encrypted[..4] != ENVELOPE_MAGIC {
+        return None;
+    }
+
+    let version = encrypted[4];
+    if version != ENVELOPE_VERSION {
+        return Some(Err(CryptoError::UnsupportedFormat(format!(
+            "Envelope version {} (supported: {})",
+            version, ENVELOPE_VERSION
+        ))));
+    }
+
+    let header_length = u16::from_be_bytes([encrypted[5], encrypted[6]]) as usize;
+    let body_offset = ENVELOPE_PREFIX_LENGTH + header_length;
+    if encrypted.len() < body_offset + NONCE_LENGTH + TAG_LENGTH {
+        return Some(Err(CryptoError::InvalidCiphertext(
+            "Envelope shorter than its header".to_string(),
+        )));
+    }
+
+    let header = serde_json::from_slice(&encrypted[ENVELOPE_PREFIX_LENGTH..body_offset])
+        .map_err(|e| CryptoError::UnsupportedFormat(format!("Invalid envelope header: {}", e)));
+
+    Some(header.map(|header| (header, body_offset)))
+}
+
 // ============================================================================
 // File Encryption (AES-256-GCM)
 // ============================================================================
@@ -284,18 +708,15 @@ fn constant_time_compare(a: &[u8], b: &[u8]) -> bool {
 /// which provides both confidentiality and integrity. The resulting
 /// ciphertext includes an authentication tag that prevents tampering.
 ///
-/// # File Format
-/// The encrypted output has the following structure:
-/// ```text
-/// [12 bytes: Nonce] || [N bytes: Ciphertext] || [16 bytes: Auth Tag]
-/// ```
+/// The output is a versioned envelope with a default header; use
+/// `encrypt_envelope()` to record the file kind, owner or KDF parameters.
 ///
 /// # Parameters
 /// - `plaintext`: Data to encrypt (will be securely cleared)
 /// - `key`: Encryption key from `derive_key()`
 ///
 /// # Returns
-/// - `Vec<u8>`: Encrypted data with nonce and auth tag
+/// - `Vec<u8>`: Encrypted envelope with nonce and auth tag
 ///
 /// # Security Notes
 /// - A random nonce is generated for each encryption
@@ -315,6 +736,34 @@ fn constant_time_compare(a: &[u8], b: &[u8]) -> bool {
 /// # }
 /// ```
 pub fn encrypt_file(plaintext: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
+    encrypt_envelope(plaintext, key, &EnvelopeHeader::default())
+}
+
+/// Encrypt file content into a versioned envelope
+///
+/// # File Format
+/// ```text
+/// [4 bytes: Magic "RBEF"] || [1 byte: Version] || [2 bytes: Header length (BE)]
+/// || [N bytes: JSON header] || [12 bytes: Nonce] || [Ciphertext] || [16 bytes: Auth Tag]
+/// ```
+///
+/// Everything before the nonce is authenticated as associated data.
+///
+/// # Parameters
+/// - `plaintext`: Data to encrypt
+/// - `key`: Encryption key
+/// - `header`: Header describing the cipher, KDF and file
+///
+/// # Returns
+/// - `Vec<u8>`: Encrypted envelope
+pub fn encrypt_envelope(
+    plaintext: &[u8],
+    key: &EncryptionKey,
+    header: &EnvelopeHeader,
+) -> Result<Vec<u8>> {
+    let mut result = encode_prefix(ENVELOPE_MAGIC, ENVELOPE_VERSION, header)?;
+    result.reserve(NONCE_LENGTH + plaintext.len() + TAG_LENGTH);
+
     // Create AES-256-GCM cipher
     let cipher = Aes256Gcm::new_from_slice(key.as_bytes())
         .map_err(|e| CryptoError::EncryptionFailed(format!("Invalid key: {}", e)))?;
@@ -323,14 +772,19 @@ pub fn encrypt_file(plaintext: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
     let mut nonce_bytes = [0u8; NONCE_LENGTH];
     OsRng.fill_bytes(&mut nonce_bytes);
 
-    // Encrypt plaintext (includes auth tag in output)
+    // Encrypt plaintext with the prefix and header as associated data
+    // (GCM automatically appends the 16-byte auth tag to ciphertext)
     let ciphertext = cipher
-        .encrypt(&nonce_bytes.into(), plaintext)
+        .encrypt(
+            &nonce_bytes.into(),
+            Payload {
+                msg: plaintext,
+                aad: &result,
+            },
+        )
         .map_err(|e| CryptoError::EncryptionFailed(format!("Encryption failed: {}", e)))?;
 
-    // Construct output: nonce || ciphertext || tag
-    // (GCM automatically appends the 16-byte auth tag to ciphertext)
-    let mut result = nonce_bytes.to_vec();
+    result.extend_from_slice(&nonce_bytes);
     result.extend_from_slice(&ciphertext);
 
     Ok(result)
@@ -342,7 +796,7 @@ pub fn encrypt_file(plaintext: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
 /// the authentication tag before returning plaintext, ensuring data integrity.
 ///
 /// # Parameters
-/// - `encrypted`: Encrypted data from `encrypt_file()` (nonce || ciphertext || tag)
+/// - `encrypted`: Envelope from `encrypt_file()`, or a legacy headerless file
 /// - `key`: Encryption key from `derive_key()`
 ///
 /// # Returns
@@ -370,11 +824,69 @@ pub fn encrypt_file(plaintext: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
 /// # }
 /// ```
 pub fn decrypt_file(encrypted: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
+    decrypt_envelope(encrypted, key).map(|(plaintext, _)| plaintext)
+}
+
+/// Decrypt file content and return its envelope header
+///
+/// Accepts versioned envelopes and legacy headerless files
+/// (`nonce || ciphertext || tag`). Data that starts with the envelope magic
+/// but does not parse is retried as a legacy file, since a legacy nonce can
+/// begin with the same bytes by chance.
+///
+/// # Returns
+/// - `Vec<u8>`: Decrypted plaintext
+/// - `Option<EnvelopeHeader>`: Header, or `None` for legacy files
+pub fn decrypt_envelope(
+    encrypted: &[u8],
+    key: &EncryptionKey,
+) -> Result<(Vec<u8>, Option<EnvelopeHeader>)> {
+    let (header, body_offset) = match parse_envelope(encrypted) {
+        Some(Ok(parsed)) => parsed,
+        Some(Err(e)) => {
+            return decrypt_body(encrypted, key, &[])
+                .map(|plaintext| (plaintext, None))
+                .map_err(|_| e)
+        }
+        None => return decrypt_body(encrypted, key, &[]).map(|plaintext| (plaintext, None)),
+    };
+
+    if header.algorithm != CipherAlgorithm::Aes256Gcm {
+        return Err(CryptoError::UnsupportedFormat(format!(
+            "Cipher {:?}",
+            header.algorithm
+        )));
+    }
+
+    let (aad, body) = encrypted.split_at(body_offset);
+    let plaintext = decrypt_body(body, key, aad)?;
+
+    Ok((plaintext, Some(header)))
+}
+
+/// Encode the magic, version, header length and JSON header of a file
+fn encode_prefix(magic: &[u8; 4], version: u8, header: &EnvelopeHeader) -> Result<Vec<u8>> {
+    let header_json = serde_json::to_vec(header)
+        .map_err(|e| CryptoError::EncryptionFailed(format!("Invalid header: {}", e)))?;
+    let header_length = u16::try_from(header_json.len())
+        .map_err(|_| CryptoError::EncryptionFailed("Envelope header too large".to_string()))?;
+
+    let mut prefix = Vec::with_capacity(ENVELOPE_PREFIX_LENGTH + header_json.len());
+    prefix.extend_from_slice(magic);
+    prefix.push(version);
+    prefix.extend_from_slice(&header_length.to_be_bytes());
+    prefix.extend_from_slice(&header_json);
+
+    Ok(prefix)
+}
+
+/// Decrypt `nonce || ciphertext || tag` with the given associated data
+fn decrypt_body(encrypted: &[u8], key: &EncryptionKey, aad: &[u8]) -> Result<Vec<u8>> {
     // Validate minimum length (nonce + tag)
-    if encrypted.len() < NONCE_LENGTH + 16 {
+    if encrypted.len() < NONCE_LENGTH + TAG_LENGTH {
         return Err(CryptoError::InvalidCiphertext(format!(
             "Ciphertext too short: expected at least {} bytes, got {}",
-            NONCE_LENGTH + 16,
+            NONCE_LENGTH + TAG_LENGTH,
             encrypted.len()
         )));
     }
@@ -391,7 +903,13 @@ pub fn decrypt_file(encrypted: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
 
     // Decrypt and verify auth tag
     let plaintext = cipher
-        .decrypt(&nonce_bytes.into(), ciphertext)
+        .decrypt(
+            &nonce_bytes.into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
         .map_err(|e| {
             CryptoError::DecryptionFailed(format!(
                 "Decryption failed (wrong password or corrupted data): {}",
@@ -402,6 +920,639 @@ pub fn decrypt_file(encrypted: &[u8], key: &EncryptionKey) -> Result<Vec<u8>> {
     Ok(plaintext)
 }
 
+// ============================================================================
+// Streaming Encryption (Chunked AES-256-GCM)
+// ============================================================================
+//
+// Large or growing files (logs, screenshots, recordings) are encrypted as a
+// stream of fixed-size chunks so they never have to be held in memory or
+// rewritten as a whole. This follows the STREAM construction: every chunk is
+// bound to its position and to whether it is the last chunk, so chunks
+// cannot be reordered, dropped or the stream truncated without failing
+// decryption.
+//
+// # File Format
+// ```text
+// [4 bytes: Magic "RBES"] || [1 byte: Version] || [2 bytes: Header length (BE)]
+// || [N bytes: JSON header] || chunk 0 || chunk 1 || ... || final chunk
+//
+// chunk = [12 bytes: Nonce] || [Ciphertext] || [16 bytes: Auth Tag]
+// AAD   = prefix and header || [4 bytes: Chunk index (BE)] || [1 byte: Final flag]
+// ```
+//
+// Every chunk but the last holds exactly `segment_size` plaintext bytes; the
+// final chunk holds the rest (possibly nothing). Each chunk has its own
+// random nonce, so appending can re-encrypt the final chunk as a regular one
+// without reusing a nonce.
+
+/// Check whether encrypted data is a streamed file
+pub fn is_stream_format(encrypted: &[u8]) -> bool {
+    encrypted.starts_with(STREAM_MAGIC)
+}
+
+/// Encrypt a buffer as a stream
+///
+/// Convenience wrapper around `StreamEncryptor` for data already in memory.
+pub fn encrypt_stream(
+    plaintext: &[u8],
+    key: &EncryptionKey,
+    header: &EnvelopeHeader,
+) -> Result<Vec<u8>> {
+    let mut encryptor = StreamEncryptor::new(Vec::new(), key, header)?;
+    encryptor.write_all(plaintext).map_err(stream_error)?;
+    encryptor.finish()
+}
+
+/// Decrypt a whole stream held in memory
+///
+/// # Returns
+/// - `Vec<u8>`: Decrypted plaintext
+/// - `EnvelopeHeader`: Stream header
+pub fn decrypt_stream(encrypted: &[u8], key: &EncryptionKey) -> Result<(Vec<u8>, EnvelopeHeader)> {
+    let mut decryptor = StreamDecryptor::new(encrypted, key)?;
+    let mut plaintext = Vec::new();
+    decryptor
+        .read_to_end(&mut plaintext)
+        .map_err(stream_error)?;
+
+    Ok((plaintext, decryptor.header))
+}
+
+/// Writer that encrypts everything written to it as a chunked stream
+///
+/// Plaintext is buffered until a full segment is available, then written to
+/// `inner` as one chunk. `finish()` must be called to write the final chunk;
+/// a stream dropped without it is rejected as truncated when read.
+///
+/// # Example
+/// ```no_run
+/// use robert_app_lib::profiles::crypto::{generate_key, EnvelopeHeader, FileKind, StreamEncryptor};
+/// use std::io::Write;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let key = generate_key();
+/// let file = std::fs::File::create("debug.log")?;
+/// let header = EnvelopeHeader::for_file(FileKind::Log, "alice");
+/// let mut encryptor = StreamEncryptor::new(file, &key, &header)?;
+/// encryptor.write_all(b"first log line\n")?;
+/// encryptor.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamEncryptor<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    prefix: Vec<u8>,
+    segment_size: usize,
+    counter: u32,
+    buffer: Zeroizing<Vec<u8>>,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// Start a new stream and write its header to `inner`
+    ///
+    /// # Parameters
+    /// - `inner`: Destination of the encrypted stream
+    /// - `key`: Encryption key
+    /// - `header`: Header describing the file; `segment_size` defaults to
+    ///   `STREAM_SEGMENT_SIZE`
+    ///
+    /// # Errors
+    /// - Returns `UnsupportedFormat` if the segment size is out of range
+    /// - Returns `IoError` if the header cannot be written
+    pub fn new(mut inner: W, key: &EncryptionKey, header: &EnvelopeHeader) -> Result<Self> {
+        let mut header = header.clone();
+        header.segment_size.get_or_insert(STREAM_SEGMENT_SIZE);
+        let segment_size = stream_segment_size(&header)?;
+
+        let prefix = encode_prefix(STREAM_MAGIC, STREAM_VERSION, &header)?;
+        inner.write_all(&prefix)?;
+
+        let cipher = Aes256Gcm::new_from_slice(key.as_bytes())
+            .map_err(|e| CryptoError::EncryptionFailed(format!("Invalid key: {}", e)))?;
+
+        Ok(Self {
+            inner,
+            cipher,
+            prefix,
+            segment_size,
+            counter: 0,
+            buffer: Zeroizing::new(Vec::with_capacity(segment_size * 2)),
+        })
+    }
+
+    /// Write the final chunk and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        self.write_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Encrypt the next chunk from the buffer and write it
+    fn write_chunk(&mut self, last: bool) -> Result<()> {
+        let length = if last {
+            self.buffer.len()
+        } else {
+            self.segment_size
+        };
+
+        let mut nonce_bytes = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce_bytes);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce_bytes.into(),
+                Payload {
+                    msg: &self.buffer[..length],
+                    aad: &chunk_aad(&self.prefix, self.counter, last),
+                },
+            )
+            .map_err(|e| CryptoError::EncryptionFailed(format!("Encryption failed: {}", e)))?;
+
+        self.inner.write_all(&nonce_bytes)?;
+        self.inner.write_all(&ciphertext)?;
+        self.buffer.drain(..length);
+
+        if !last {
+            self.counter = self.counter.checked_add(1).ok_or_else(|| {
+                CryptoError::EncryptionFailed("Stream has too many chunks".to_string())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Read + Write + Seek> StreamEncryptor<W> {
+    /// Reopen an existing stream to append to it
+    ///
+    /// Only the final chunk is read and decrypted; its plaintext is carried
+    /// over and written back (as a regular chunk once it is full) followed by
+    /// the new data. Earlier chunks are left untouched, so appending costs the
+    /// same regardless of the file size.
+    ///
+    /// The final chunk is overwritten in place. If the process stops before
+    /// `finish()` returns, the end of the stream is unreadable and the file
+    /// is rejected as truncated.
+    ///
+    /// # Parameters
+    /// - `inner`: Existing stream, opened for reading and writing
+    /// - `key`: Key the stream was encrypted with
+    ///
+    /// # Errors
+    /// - Returns `DecryptionFailed` if the final chunk does not decrypt with `key`
+    /// - Returns `InvalidCiphertext` if the stream has no final chunk
+    pub fn append(mut inner: W, key: &EncryptionKey) -> Result<Self> {
+        inner.seek(SeekFrom::Start(0))?;
+        let (header, prefix) = read_stream_prefix(&mut inner)?;
+        let segment_size = stream_segment_size(&header)?;
+
+        let body_start = prefix.len() as u64;
+        let body_length = inner.seek(SeekFrom::End(0))? - body_start;
+        let chunk_length = (segment_size + NONCE_LENGTH + TAG_LENGTH) as u64;
+        let chunks = body_length.div_ceil(chunk_length);
+        if chunks == 0 {
+            return Err(CryptoError::InvalidCiphertext(
+                "Stream has no final chunk".to_string(),
+            ));
+        }
+
+        let counter = u32::try_from(chunks - 1).map_err(|_| {
+            CryptoError::InvalidCiphertext("Stream has too many chunks".to_string())
+        })?;
+        let last_start = body_start + (chunks - 1) * chunk_length;
+
+        let mut last_chunk = Vec::new();
+        inner.seek(SeekFrom::Start(last_start))?;
+        inner.read_to_end(&mut last_chunk)?;
+
+        let cipher = Aes256Gcm::new_from_slice(key.as_bytes())
+            .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid key: {}", e)))?;
+        let mut buffer = Zeroizing::new(Vec::with_capacity(segment_size * 2));
+        buffer.extend_from_slice(&open_chunk(&cipher, &prefix, counter, true, &last_chunk)?);
+
+        inner.seek(SeekFrom::Start(last_start))?;
+
+        Ok(Self {
+            inner,
+            cipher,
+            prefix,
+            segment_size,
+            counter,
+            buffer,
+        })
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        // Keep at least one byte back so the final chunk is never empty
+        // unless the whole stream is
+        while self.buffer.len() > self.segment_size {
+            self.write_chunk(false).map_err(io::Error::other)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that decrypts a chunked stream
+///
+/// Each chunk is authenticated before any of its plaintext is returned.
+/// Reading fails with `io::ErrorKind::InvalidData` if a chunk was modified,
+/// moved or removed, or if the stream ends before its final chunk.
+pub struct StreamDecryptor<R: Read> {
+    inner: R,
+    header: EnvelopeHeader,
+    cipher: Aes256Gcm,
+    prefix: Vec<u8>,
+    segment_size: usize,
+    counter: u32,
+    raw: Vec<u8>,
+    plaintext: Zeroizing<Vec<u8>>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// Read the stream header from `inner`
+    ///
+    /// # Errors
+    /// - Returns `UnsupportedFormat` if `inner` is not a supported stream
+    pub fn new(mut inner: R, key: &EncryptionKey) -> Result<Self> {
+        let (header, prefix) = read_stream_prefix(&mut inner)?;
+        let segment_size = stream_segment_size(&header)?;
+
+        let cipher = Aes256Gcm::new_from_slice(key.as_bytes())
+            .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid key: {}", e)))?;
+
+        Ok(Self {
+            inner,
+            header,
+            cipher,
+            prefix,
+            segment_size,
+            counter: 0,
+            raw: Vec::new(),
+            plaintext: Zeroizing::new(Vec::new()),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    /// Stream header (authenticated once the first chunk has been read)
+    pub fn header(&self) -> &EnvelopeHeader {
+        &self.header
+    }
+
+    /// Read and decrypt the next chunk
+    fn next_chunk(&mut self) -> Result<()> {
+        let chunk_length = self.segment_size + NONCE_LENGTH + TAG_LENGTH;
+
+        // Read one byte past a full chunk to tell whether it is the last one
+        let wanted = (chunk_length + 1).saturating_sub(self.raw.len()) as u64;
+        (&mut self.inner).take(wanted).read_to_end(&mut self.raw)?;
+
+        let last = self.raw.len() <= chunk_length;
+        let length = self.raw.len().min(chunk_length);
+        let plaintext = open_chunk(
+            &self.cipher,
+            &self.prefix,
+            self.counter,
+            last,
+            &self.raw[..length],
+        )?;
+        self.raw.drain(..length);
+
+        *self.plaintext = plaintext;
+        self.position = 0;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.counter = self.counter.checked_add(1).ok_or_else(|| {
+                CryptoError::InvalidCiphertext("Stream has too many chunks".to_string())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.next_chunk()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        let available = &self.plaintext[self.position..];
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+/// Read the header of a stream without decrypting it
+///
+/// Needed to find the KDF parameters of a stream encrypted with a
+/// passphrase before its key can be derived.
+pub fn read_stream_header<R: Read>(reader: &mut R) -> Result<EnvelopeHeader> {
+    Ok(read_stream_prefix(reader)?.0)
+}
+
+/// Read the prefix and header of a stream
+///
+/// # Returns
+/// - `EnvelopeHeader`: Parsed header
+/// - `Vec<u8>`: Raw prefix and header bytes (authenticated with every chunk)
+fn read_stream_prefix<R: Read>(reader: &mut R) -> Result<(EnvelopeHeader, Vec<u8>)> {
+    let mut prefix = vec![0u8; ENVELOPE_PREFIX_LENGTH];
+    reader.read_exact(&mut prefix)?;
+
+    if &prefix[..4] != STREAM_MAGIC {
+        return Err(CryptoError::UnsupportedFormat(
+            "Not a streamed file".to_string(),
+        ));
+    }
+    if prefix[4] != STREAM_VERSION {
+        return Err(CryptoError::UnsupportedFormat(format!(
+            "Stream version {} (supported: {})",
+            prefix[4], STREAM_VERSION
+        )));
+    }
+
+    let header_length = u16::from_be_bytes([prefix[5], prefix[6]]) as usize;
+    prefix.resize(ENVELOPE_PREFIX_LENGTH + header_length, 0);
+    reader.read_exact(&mut prefix[ENVELOPE_PREFIX_LENGTH..])?;
+
+    let header: EnvelopeHeader = serde_json::from_slice(&prefix[ENVELOPE_PREFIX_LENGTH..])
+        .map_err(|e| CryptoError::UnsupportedFormat(format!("Invalid stream header: {}", e)))?;
+    if header.algorithm != CipherAlgorithm::Aes256Gcm {
+        return Err(CryptoError::UnsupportedFormat(format!(
+            "Cipher {:?}",
+            header.algorithm
+        )));
+    }
+
+    Ok((header, prefix))
+}
+
+/// Segment size of a stream, checked against the supported range
+fn stream_segment_size(header: &EnvelopeHeader) -> Result<usize> {
+    match header.segment_size {
+        Some(size) if size > 0 && size <= MAX_STREAM_SEGMENT_SIZE => Ok(size as usize),
+        size => Err(CryptoError::UnsupportedFormat(format!(
+            "Stream segment size {:?}",
+            size
+        ))),
+    }
+}
+
+/// Associated data of one stream chunk
+fn chunk_aad(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(prefix.len() + 5);
+    aad.extend_from_slice(prefix);
+    aad.extend_from_slice(&counter.to_be_bytes());
+    aad.push(last as u8);
+    aad
+}
+
+/// Decrypt one stream chunk
+fn open_chunk(
+    cipher: &Aes256Gcm,
+    prefix: &[u8],
+    counter: u32,
+    last: bool,
+    chunk: &[u8],
+) -> Result<Vec<u8>> {
+    if chunk.len() < NONCE_LENGTH + TAG_LENGTH {
+        return Err(CryptoError::InvalidCiphertext(
+            "Stream is truncated".to_string(),
+        ));
+    }
+
+    let (nonce_bytes, ciphertext) = chunk.split_at(NONCE_LENGTH);
+    let nonce_bytes: [u8; NONCE_LENGTH] = nonce_bytes
+        .try_into()
+        .map_err(|_| CryptoError::InvalidCiphertext("Invalid nonce length".to_string()))?;
+
+    cipher
+        .decrypt(
+            &nonce_bytes.into(),
+            Payload {
+                msg: ciphertext,
+                aad: &chunk_aad(prefix, counter, last),
+            },
+        )
+        .map_err(|e| {
+            CryptoError::DecryptionFailed(format!(
+                "Stream chunk {} failed to decrypt (wrong key, tampered or truncated): {}",
+                counter, e
+            ))
+        })
+}
+
+/// Recover the `CryptoError` carried by an I/O error from a stream adapter
+fn stream_error(e: io::Error) -> CryptoError {
+    if !e.get_ref().is_some_and(|inner| inner.is::<CryptoError>()) {
+        return CryptoError::IoError(e);
+    }
+
+    match e.into_inner().map(|inner| inner.downcast::<CryptoError>()) {
+        Some(Ok(inner)) => *inner,
+        _ => CryptoError::InvalidCiphertext("Stream error".to_string()),
+    }
+}
+
+// ============================================================================
+// Encrypted Record Logs
+// ============================================================================
+//
+// A record log is a file that only ever grows: each record is encrypted on
+// its own as a versioned envelope and appended with its length. Writing a
+// record never reads or rewrites earlier ones, and a write interrupted
+// halfway only loses that record.
+//
+// # File Format
+// ```text
+// [4 bytes: Magic "RBRL"] || [1 byte: Version]
+// || [4 bytes: Record length (BE)] || [Envelope] || [4 bytes: Record length (BE)] || [Envelope] || ...
+// ```
+
+/// Prefix written once at the start of a new record log
+pub fn record_log_prefix() -> Vec<u8> {
+    let mut prefix = RECORD_LOG_MAGIC.to_vec();
+    prefix.push(RECORD_LOG_VERSION);
+    prefix
+}
+
+/// Check whether encrypted data is a record log
+pub fn is_record_log(encrypted: &[u8]) -> bool {
+    encrypted.starts_with(RECORD_LOG_MAGIC)
+}
+
+/// Encrypt one record, framed with its length, ready to append to a record log
+///
+/// # Parameters
+/// - `plaintext`: Record content
+/// - `key`: Encryption key
+/// - `header`: Envelope header of the record
+///
+/// # Returns
+/// - `Vec<u8>`: Length-prefixed envelope
+pub fn encrypt_record(
+    plaintext: &[u8],
+    key: &EncryptionKey,
+    header: &EnvelopeHeader,
+) -> Result<Vec<u8>> {
+    let envelope = encrypt_envelope(plaintext, key, header)?;
+    let length = u32::try_from(envelope.len())
+        .ok()
+        .filter(|length| *length as usize <= MAX_RECORD_LENGTH)
+        .ok_or_else(|| CryptoError::EncryptionFailed("Record too large".to_string()))?;
+
+    let mut record = Vec::with_capacity(4 + envelope.len());
+    record.extend_from_slice(&length.to_be_bytes());
+    record.extend_from_slice(&envelope);
+
+    Ok(record)
+}
+
+/// Length of the complete records at the start of a record log
+///
+/// Anything after this offset is a record cut short by an interrupted
+/// write; truncating the file to this length before appending keeps new
+/// records readable.
+pub fn record_log_length(encrypted: &[u8]) -> usize {
+    if !is_record_log(encrypted) || encrypted.len() < RECORD_LOG_PREFIX_LENGTH {
+        return 0;
+    }
+
+    let mut offset = RECORD_LOG_PREFIX_LENGTH;
+    while let Some(length_bytes) = encrypted.get(offset..offset + 4) {
+        let length = u32::from_be_bytes([
+            length_bytes[0],
+            length_bytes[1],
+            length_bytes[2],
+            length_bytes[3],
+        ]) as usize;
+        if length > MAX_RECORD_LENGTH || encrypted.len() < offset + 4 + length {
+            break;
+        }
+        offset += 4 + length;
+    }
+
+    offset
+}
+
+/// Iterator over the decrypted records of a record log
+///
+/// Records are decrypted one at a time as the iterator advances. A partial
+/// record at the end (from an interrupted write) ends the iteration without
+/// an error; a complete record that fails to decrypt is returned as an error
+/// and the iteration continues with the next one.
+pub struct RecordReader<R: Read> {
+    inner: R,
+    key: EncryptionKey,
+    finished: bool,
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Check the record log prefix of `inner`
+    ///
+    /// # Errors
+    /// - Returns `UnsupportedFormat` if `inner` is not a supported record log
+    pub fn new(mut inner: R, key: &EncryptionKey) -> Result<Self> {
+        let mut prefix = [0u8; RECORD_LOG_PREFIX_LENGTH];
+        inner.read_exact(&mut prefix)?;
+
+        if !is_record_log(&prefix) {
+            return Err(CryptoError::UnsupportedFormat(
+                "Not a record log".to_string(),
+            ));
+        }
+        if prefix[4] != RECORD_LOG_VERSION {
+            return Err(CryptoError::UnsupportedFormat(format!(
+                "Record log version {} (supported: {})",
+                prefix[4], RECORD_LOG_VERSION
+            )));
+        }
+
+        Ok(Self {
+            inner,
+            key: key.clone(),
+            finished: false,
+        })
+    }
+
+    /// Read the next complete record, or `None` at the end of the log
+    fn read_record(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut length_bytes = Vec::with_capacity(4);
+        (&mut self.inner).take(4).read_to_end(&mut length_bytes)?;
+        let Ok(length_bytes) = <[u8; 4]>::try_from(length_bytes) else {
+            return Ok(None);
+        };
+
+        let length = u32::from_be_bytes(length_bytes) as usize;
+        if length > MAX_RECORD_LENGTH {
+            return Err(CryptoError::InvalidCiphertext(format!(
+                "Record of {} bytes exceeds the maximum",
+                length
+            )));
+        }
+
+        let mut record = Vec::with_capacity(length);
+        (&mut self.inner)
+            .take(length as u64)
+            .read_to_end(&mut record)?;
+
+        Ok((record.len() == length).then_some(record))
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = Result<(Vec<u8>, EnvelopeHeader)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let record = match self.read_record() {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Err(e) => {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        };
+
+        Some(match decrypt_envelope(&record, &self.key) {
+            Ok((plaintext, Some(header))) => Ok((plaintext, header)),
+            Ok((_, None)) => Err(CryptoError::InvalidCiphertext(
+                "Record is not an envelope".to_string(),
+            )),
+            Err(e) => Err(e),
+        })
+    }
+}
+
 // ============================================================================
 // Unit Tests
 // ============================================================================
@@ -450,6 +1601,47 @@ mod tests {
         assert_ne!(key1.as_bytes(), key2.as_bytes());
     }
 
+    #[test]
+    fn test_kdf_params_validate() {
+        assert!(KdfParams::password().validate().is_ok());
+        assert!(KdfParams::recovery().validate().is_ok());
+
+        let too_costly = [
+            KdfParams {
+                memory_kb: u32::MAX,
+                ..KdfParams::password()
+            },
+            KdfParams {
+                iterations: 1_000_000,
+                ..KdfParams::password()
+            },
+            KdfParams {
+                parallelism: 1024,
+                ..KdfParams::password()
+            },
+        ];
+        for kdf in too_costly {
+            assert!(matches!(
+                kdf.validate(),
+                Err(CryptoError::UnsupportedFormat(_))
+            ));
+            assert!(matches!(
+                derive_key_with("password", None, &kdf),
+                Err(CryptoError::UnsupportedFormat(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_derive_recovery_key_differs_from_password_key() {
+        let (password_key, salt) = derive_key("ABCDE12345FGHJK67890", None).unwrap();
+        let (recovery_key, _) = derive_recovery_key("ABCDE12345FGHJK67890", Some(&salt)).unwrap();
+        let (again, _) = derive_recovery_key("ABCDE12345FGHJK67890", Some(&salt)).unwrap();
+
+        assert_eq!(recovery_key.as_bytes(), again.as_bytes());
+        assert_ne!(recovery_key.as_bytes(), password_key.as_bytes());
+    }
+
     #[test]
     fn test_verify_password_correct() {
         let password = "correct_password";
@@ -539,6 +1731,105 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_envelope_header_roundtrip() {
+        let key = generate_key();
+        let header = EnvelopeHeader::for_file(FileKind::Command, "alice");
+
+        let encrypted = encrypt_envelope(b"# Command", &key, &header).unwrap();
+        assert_eq!(&encrypted[..4], ENVELOPE_MAGIC);
+        assert_eq!(encrypted[4], ENVELOPE_VERSION);
+        assert!(!is_legacy_format(&encrypted));
+
+        let (plaintext, read_header) = decrypt_envelope(&encrypted, &key).unwrap();
+        assert_eq!(plaintext, b"# Command");
+        assert_eq!(read_header, Some(header));
+    }
+
+    #[test]
+    fn test_envelope_header_is_authenticated() {
+        let key = generate_key();
+        let header = EnvelopeHeader::for_file(FileKind::Command, "alice");
+        let encrypted = encrypt_envelope(b"# Command", &key, &header).unwrap();
+
+        // Same-length edit of the clear-text header
+        let mut tampered = encrypted.clone();
+        let offset = encrypted
+            .windows(5)
+            .position(|window| window == b"alice")
+            .unwrap();
+        tampered[offset..offset + 5].copy_from_slice(b"mallo");
+
+        assert!(read_envelope_header(&tampered).unwrap().is_some());
+        assert!(matches!(
+            decrypt_file(&tampered, &key),
+            Err(CryptoError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_legacy_headerless_file() {
+        let key = generate_key();
+        let cipher = Aes256Gcm::new_from_slice(key.as_bytes()).unwrap();
+        let nonce = [7u8; NONCE_LENGTH];
+        let mut legacy = nonce.to_vec();
+        legacy.extend(cipher.encrypt(&nonce.into(), &b"old data"[..]).unwrap());
+
+        assert!(is_legacy_format(&legacy));
+        assert_eq!(read_envelope_header(&legacy).unwrap(), None);
+
+        let (plaintext, header) = decrypt_envelope(&legacy, &key).unwrap();
+        assert_eq!(plaintext, b"old data");
+        assert_eq!(header, None);
+    }
+
+    #[test]
+    fn test_unsupported_envelope_version() {
+        let key = generate_key();
+        let mut encrypted = encrypt_file(b"data", &key).unwrap();
+        encrypted[4] = ENVELOPE_VERSION + 1;
+
+        assert!(matches!(
+            read_envelope_header(&encrypted),
+            Err(CryptoError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            decrypt_file(&encrypted, &key),
+            Err(CryptoError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_key_is_random() {
+        let key1 = generate_key();
+        let key2 = generate_key();
+
+        assert_eq!(key1.len(), KEY_LENGTH);
+        assert_ne!(key1.as_bytes(), key2.as_bytes());
+    }
+
+    #[test]
+    fn test_wrap_unwrap_roundtrip() {
+        let (password_key, _) = derive_key("password", None).unwrap();
+        let data_key = generate_key();
+
+        let header = EnvelopeHeader::default().with_kdf(KdfParams::password(), Some(".salt"));
+        let wrapped = wrap_key(&data_key, &password_key, &header).unwrap();
+        let unwrapped = unwrap_key(&wrapped, &password_key).unwrap();
+
+        assert_eq!(data_key.as_bytes(), unwrapped.as_bytes());
+        assert_eq!(read_envelope_header(&wrapped).unwrap(), Some(header));
+    }
+
+    #[test]
+    fn test_unwrap_with_wrong_key_fails() {
+        let data_key = generate_key();
+        let wrapped = wrap_key(&data_key, &generate_key(), &EnvelopeHeader::default()).unwrap();
+
+        let result = unwrap_key(&wrapped, &generate_key());
+        assert!(matches!(result, Err(CryptoError::DecryptionFailed(_))));
+    }
+
     #[test]
     fn test_encryption_key_is_zeroized() {
         let (key, _) = derive_key("password", None).unwrap();
@@ -552,6 +1843,112 @@ mod tests {
         assert!(!key_bytes.is_empty()); // Original bytes are still in our copy
     }
 
+    fn small_stream_header() -> EnvelopeHeader {
+        EnvelopeHeader {
+            segment_size: Some(16),
+            ..EnvelopeHeader::for_file(FileKind::Log, "alice")
+        }
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let key = generate_key();
+        let plaintext: Vec<u8> = (0..100u8).collect();
+
+        let encrypted = encrypt_stream(&plaintext, &key, &small_stream_header()).unwrap();
+        assert!(is_stream_format(&encrypted));
+
+        let (decrypted, header) = decrypt_stream(&encrypted, &key).unwrap();
+        assert_eq!(decrypted, plaintext);
+        assert_eq!(header.segment_size, Some(16));
+
+        // Exact multiple of the segment size and empty streams
+        for length in [0, 16, 32] {
+            let encrypted =
+                encrypt_stream(&plaintext[..length], &key, &small_stream_header()).unwrap();
+            let (decrypted, _) = decrypt_stream(&encrypted, &key).unwrap();
+            assert_eq!(decrypted, &plaintext[..length]);
+        }
+    }
+
+    #[test]
+    fn test_stream_append() {
+        let key = generate_key();
+        let encrypted = encrypt_stream(b"first line\n", &key, &small_stream_header()).unwrap();
+
+        let mut file = io::Cursor::new(encrypted);
+        for line in [
+            "second line\n",
+            "",
+            "third line that spans several chunks\n",
+        ] {
+            let mut encryptor = StreamEncryptor::append(file, &key).unwrap();
+            encryptor.write_all(line.as_bytes()).unwrap();
+            file = encryptor.finish().unwrap();
+        }
+
+        let (decrypted, _) = decrypt_stream(file.get_ref(), &key).unwrap();
+        assert_eq!(
+            decrypted,
+            b"first line\nsecond line\nthird line that spans several chunks\n"
+        );
+    }
+
+    #[test]
+    fn test_stream_detects_truncation_and_reordering() {
+        let key = generate_key();
+        let plaintext = vec![7u8; 40];
+        let encrypted = encrypt_stream(&plaintext, &key, &small_stream_header()).unwrap();
+
+        let body_start = encrypted.len() - (2 * (16 + 28) + (8 + 28));
+        let chunk_length = 16 + 28;
+
+        // Dropping the final chunk leaves a full chunk that is not marked final
+        let truncated = &encrypted[..encrypted.len() - (8 + 28)];
+        assert!(decrypt_stream(truncated, &key).is_err());
+
+        // Swapping two chunks breaks their position binding
+        let mut swapped = encrypted.clone();
+        let (first, second) = swapped[body_start..].split_at_mut(chunk_length);
+        first.swap_with_slice(&mut second[..chunk_length]);
+        assert!(decrypt_stream(&swapped, &key).is_err());
+
+        // Wrong key
+        assert!(decrypt_stream(&encrypted, &generate_key()).is_err());
+    }
+
+    #[test]
+    fn test_record_log() {
+        let key = generate_key();
+        let header = EnvelopeHeader::for_file(FileKind::Log, "alice");
+
+        let mut log = record_log_prefix();
+        for line in ["first", "second", "third"] {
+            log.extend(encrypt_record(line.as_bytes(), &key, &header).unwrap());
+        }
+        let complete = log.len();
+        assert!(is_record_log(&log));
+        assert_eq!(record_log_length(&log), complete);
+
+        // An interrupted write leaves a partial record that is skipped
+        let partial = encrypt_record(b"fourth", &key, &header).unwrap();
+        log.extend(&partial[..partial.len() / 2]);
+        assert_eq!(record_log_length(&log), complete);
+
+        let records: Vec<_> = RecordReader::new(&log[..], &key)
+            .unwrap()
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].0, b"third");
+        assert_eq!(records[2].1, header);
+
+        // Records decrypt independently; a wrong key fails each one
+        assert!(RecordReader::new(&log[..], &generate_key())
+            .unwrap()
+            .all(|record| record.is_err()));
+    }
+
     #[test]
     fn test_constant_time_compare_same_length() {
         let a = b"same_length_a";
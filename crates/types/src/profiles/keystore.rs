@@ -0,0 +1,92 @@
+/// Storage for remembered logins outside the profile directory
+///
+/// A `KeyStore` holds secrets in a place the operating system protects for
+/// the logged-in desktop user, such as the Secret Service API on Linux. The
+/// profile system only defines the trait; the desktop app provides the OS
+/// implementation, and tests use `MemoryKeyStore`.
+///
+/// When a user opts into "remember me", the key store holds their data
+/// encryption key wrapped by a random device key. The device key is stored
+/// in `.remember` in the user directory, so neither the key store entry nor
+/// the profile directory alone unlocks the user's files, and deleting
+/// `.remember` forgets the login even if the key store is unreachable.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Service name under which remembered logins are stored
+pub const KEYSTORE_SERVICE: &str = "robert";
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Errors that can occur when accessing a key store
+#[derive(Error, Debug)]
+pub enum KeyStoreError {
+    /// No key store is running or it refused access (e.g. locked keyring)
+    #[error("Key store unavailable: {0}")]
+    Unavailable(String),
+
+    /// The key store failed to read or write a secret
+    #[error("Key store error: {0}")]
+    Backend(String),
+}
+
+pub type Result<T> = std::result::Result<T, KeyStoreError>;
+
+// ============================================================================
+// Key Store
+// ============================================================================
+
+/// Secret storage keyed by account name (the username)
+pub trait KeyStore: Send + Sync {
+    /// Store a secret for an account, replacing any previous one
+    fn set_secret(&self, account: &str, secret: &[u8]) -> Result<()>;
+
+    /// Get the secret of an account
+    ///
+    /// # Returns
+    /// - `Some(Vec<u8>)`: The stored secret
+    /// - `None`: If nothing is stored for the account
+    fn get_secret(&self, account: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Delete the secret of an account (no error if there is none)
+    fn delete_secret(&self, account: &str) -> Result<()>;
+}
+
+/// In-memory key store for tests and platforms without an OS key store
+#[derive(Debug, Default)]
+pub struct MemoryKeyStore {
+    secrets: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryKeyStore {
+    /// Create an empty key store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn set_secret(&self, account: &str, secret: &[u8]) -> Result<()> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(account.to_string(), secret.to_vec());
+        Ok(())
+    }
+
+    fn get_secret(&self, account: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.secrets.lock().unwrap().get(account).cloned())
+    }
+
+    fn delete_secret(&self, account: &str) -> Result<()> {
+        self.secrets.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
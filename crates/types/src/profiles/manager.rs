@@ -10,17 +10,33 @@
 // Allow dead code for Phase 1 - these will be used when Tauri commands are implemented
 #[allow(dead_code)]
 use crate::profiles::{
-    crypto::{derive_key, EncryptionKey},
+    crypto::{
+        derive_key, derive_key_with, generate_key, read_envelope_header, unwrap_key, wrap_key,
+        EncryptionKey, EnvelopeHeader, FileKind, KdfParams,
+    },
+    recovery::{generate_codes, RecoveryKeys},
     storage::{
-        create_user_directory, list_users as storage_list_users, load_salt, load_user_config,
-        save_salt, save_user_config, save_user_profile, user_exists,
+        create_user_directory, delete_user_directory, get_key_path, get_recovery_codes_path,
+        get_recovery_path, get_salt_path, list_users as storage_list_users, load_recovery_codes,
+        load_salt, load_user_config, load_wrapped_key, recover_reencryption, reencrypt_user_files,
+        replace_user_files, save_recovery_codes, save_recovery_keys, save_salt, save_user_config,
+        save_user_profile, save_wrapped_key, seal_user_files, user_exists, DeleteReport,
+        StorageError, SALT_FILE,
     },
     types::{UserConfig, UserPreferences},
 };
 use chrono::Utc;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use thiserror::Error;
 
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Longest accepted username, in bytes
+pub const MAX_USERNAME_LENGTH: usize = 32;
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -51,6 +67,10 @@ pub enum ManagerError {
     /// Password validation failed
     #[error("Invalid password: {0}")]
     InvalidPassword(String),
+
+    /// Password confirmation did not match the user's password
+    #[error("Incorrect password")]
+    IncorrectPassword,
 }
 
 pub type Result<T> = std::result::Result<T, ManagerError>;
@@ -73,7 +93,7 @@ impl UserManager {
     /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
     ///
     /// # Returns
-    /// - `EncryptionKey`: Derived encryption key (store in app state)
+    /// - `EncryptionKey`: Data encryption key for the user's files (store in app state)
     /// - `UserConfig`: Created user configuration
     ///
     /// # Errors
@@ -99,11 +119,19 @@ impl UserManager {
         // Create user directory structure
         create_user_directory(username, base_dir)?;
 
-        // Derive encryption key from password
-        let (key, salt) = derive_key(password, None)?;
+        // Derive the password key and wrap a random data key with it
+        let (password_key, salt) = derive_key(password, None)?;
+        let key = generate_key();
 
-        // Save salt
+        // Save salt and wrapped data key
         save_salt(username, &salt, base_dir)?;
+        let wrapped = wrap_key(&key, &password_key, &Self::data_key_header(username))?;
+        save_wrapped_key(username, &wrapped, base_dir)?;
+
+        // Let recovery codes unwrap the data key too
+        let codes = generate_codes();
+        save_recovery_keys(username, &RecoveryKeys::new(&codes, &key)?, base_dir)?;
+        save_recovery_codes(username, &codes, &key, base_dir)?;
 
         // Create default user config
         let config = UserConfig {
@@ -155,7 +183,7 @@ impl UserManager {
     /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
     ///
     /// # Returns
-    /// - `EncryptionKey`: Derived encryption key (store in app state)
+    /// - `EncryptionKey`: Data encryption key for the user's files (store in app state)
     /// - `UserConfig`: Loaded user configuration
     ///
     /// # Errors
@@ -166,11 +194,13 @@ impl UserManager {
         password: &str,
         base_dir: Option<&std::path::Path>,
     ) -> Result<(EncryptionKey, UserConfig)> {
-        // Load salt
-        let salt = load_salt(username, base_dir)?;
-
-        // Derive key from password
-        let (key, _) = derive_key(password, Some(&salt))?;
+        // Unlock the data key with the password
+        let key = Self::unlock_key(username, password, base_dir).map_err(|e| {
+            log::warn!("Failed to unlock key for '{}': {}", username, e);
+            ManagerError::CryptoError(crate::profiles::crypto::CryptoError::DecryptionFailed(
+                "Incorrect password or corrupted data".to_string(),
+            ))
+        })?;
 
         // Try to load and decrypt user config
         let config = load_user_config(username, &key, base_dir).map_err(|e| {
@@ -185,6 +215,226 @@ impl UserManager {
         Ok((key, config))
     }
 
+    /// Delete a user and all of their data
+    ///
+    /// The password is confirmed first. Then every file in the user
+    /// directory except browser profiles is overwritten before the directory,
+    /// including browser profiles, is removed (see
+    /// `storage::delete_user_directory`). Overwriting is best effort and a
+    /// file that cannot be overwritten is still removed.
+    ///
+    /// Stop anything that writes the user's files (such as the encrypted
+    /// logger) and end the user's session before calling this.
+    ///
+    /// # Parameters
+    /// - `username`: User to delete
+    /// - `password`: The user's password, as confirmation
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `DeleteReport`: What was removed
+    ///
+    /// # Errors
+    /// - Returns `UserNotFound` if the user doesn't exist
+    /// - Returns `IncorrectPassword` if the password is wrong (nothing is removed)
+    /// - Returns `StorageError` if the directory cannot be removed
+    pub fn delete_user(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<DeleteReport> {
+        if !user_exists(username, base_dir)? {
+            return Err(ManagerError::UserNotFound(username.to_string()));
+        }
+
+        let key = match Self::unlock_key(username, password, base_dir) {
+            Ok(key) => key,
+            Err(ManagerError::CryptoError(_)) => return Err(ManagerError::IncorrectPassword),
+            Err(e) => return Err(e),
+        };
+        match load_user_config(username, &key, base_dir) {
+            Ok(_) => {}
+            Err(StorageError::IntegrityError(e)) => {
+                return Err(StorageError::IntegrityError(e).into())
+            }
+            Err(_) => return Err(ManagerError::IncorrectPassword),
+        }
+
+        let report = delete_user_directory(username, base_dir)?;
+
+        log::info!("Deleted user: {}", username);
+
+        Ok(report)
+    }
+
+    /// Unlock the key that decrypts a user's files
+    ///
+    /// Derives the password key and unwraps the user's data encryption key
+    /// with it. Users created before data keys were introduced have no
+    /// wrapped key; their files are encrypted with the password key, which
+    /// is returned instead (see `migrate_to_data_key()`).
+    ///
+    /// An interrupted password change is finished or rolled back first.
+    ///
+    /// # Errors
+    /// - Returns `CryptoError::DecryptionFailed` if the password is wrong
+    /// - Returns `StorageError` if the salt or key file cannot be read
+    pub fn unlock_key(
+        username: &str,
+        password: &str,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<EncryptionKey> {
+        recover_reencryption(username, base_dir)?;
+
+        let salt = load_salt(username, base_dir)?;
+        let wrapped = load_wrapped_key(username, base_dir)?;
+
+        // Derive with the parameters the key was wrapped with, not the defaults
+        let kdf = match &wrapped {
+            Some(wrapped) => read_envelope_header(wrapped)?
+                .and_then(|header| header.kdf)
+                .unwrap_or_default(),
+            None => KdfParams::password(),
+        };
+        let (password_key, _) = derive_key_with(password, Some(&salt), &kdf)?;
+
+        match wrapped {
+            Some(wrapped) => Ok(unwrap_key(&wrapped, &password_key)?),
+            None => Ok(password_key),
+        }
+    }
+
+    /// Check whether a user's files are still encrypted with the password key
+    pub fn uses_password_key(username: &str, base_dir: Option<&std::path::Path>) -> Result<bool> {
+        Ok(load_wrapped_key(username, base_dir)?.is_none())
+    }
+
+    /// Move a user from password-key encryption to a wrapped data key
+    ///
+    /// Re-encrypts every file with a new random data key and stores it
+    /// wrapped by `password_key` and by a new set of recovery codes. The
+    /// salt does not change, so the same password keeps working. Nothing
+    /// changes if re-encryption fails.
+    ///
+    /// # Parameters
+    /// - `username`: User to migrate
+    /// - `password_key`: Password-derived key the files are encrypted with
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `EncryptionKey`: The new data encryption key
+    pub fn migrate_to_data_key(
+        username: &str,
+        password_key: &EncryptionKey,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<EncryptionKey> {
+        let salt = load_salt(username, base_dir)?;
+        let key = generate_key();
+        let wrapped = wrap_key(&key, password_key, &Self::data_key_header(username))?;
+
+        let mut key_files = vec![
+            (get_salt_path(username, base_dir)?, salt),
+            (get_key_path(username, base_dir)?, wrapped),
+        ];
+        key_files.extend(Self::recovery_files(
+            username,
+            &generate_codes(),
+            password_key,
+            &key,
+            base_dir,
+        )?);
+
+        reencrypt_user_files(username, password_key, &key, key_files, base_dir)?;
+
+        log::info!("Migrated user '{}' to a data encryption key", username);
+
+        Ok(key)
+    }
+
+    /// Get a user's unused recovery codes
+    ///
+    /// Returns an empty list for users without recovery codes.
+    pub fn recovery_codes(
+        username: &str,
+        key: &EncryptionKey,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Vec<String>> {
+        Ok(load_recovery_codes(username, key, base_dir)?)
+    }
+
+    /// Replace a user's recovery codes with a new set
+    ///
+    /// The old codes stop working once the new ones are written. Both
+    /// recovery files are replaced together.
+    ///
+    /// # Parameters
+    /// - `username`: User to generate codes for
+    /// - `key`: The user's data encryption key
+    /// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+    ///
+    /// # Returns
+    /// - `Vec<String>`: The new recovery codes
+    pub fn regenerate_recovery_codes(
+        username: &str,
+        key: &EncryptionKey,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Vec<String>> {
+        if !user_exists(username, base_dir)? {
+            return Err(ManagerError::UserNotFound(username.to_string()));
+        }
+
+        let codes = generate_codes();
+        let files = Self::recovery_files(username, &codes, key, key, base_dir)?;
+        replace_user_files(username, files, base_dir)?;
+
+        log::info!("Regenerated recovery codes for user '{}'", username);
+
+        Ok(codes)
+    }
+
+    /// Envelope header for a data key wrapped by a password key
+    ///
+    /// Records the KDF parameters `derive_key()` uses, so `unlock_key()`
+    /// can derive the same password key after the defaults change.
+    pub(crate) fn data_key_header(username: &str) -> EnvelopeHeader {
+        EnvelopeHeader::for_file(FileKind::DataKey, username)
+            .with_kdf(KdfParams::password(), Some(SALT_FILE))
+    }
+
+    /// Build the recovery keys and recovery codes files for a data key
+    ///
+    /// Returns paths and contents for `replace_user_files()` or
+    /// `reencrypt_user_files()`, so the recovery files change in the same
+    /// transaction as the key they unwrap. The manifest is included, read
+    /// with `manifest_key` and written with `key`.
+    pub(crate) fn recovery_files(
+        username: &str,
+        codes: &[String],
+        manifest_key: &EncryptionKey,
+        key: &EncryptionKey,
+        base_dir: Option<&std::path::Path>,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let recovery_keys = RecoveryKeys::new(codes, key)?;
+
+        let mut files = vec![(
+            get_recovery_path(username, base_dir)?,
+            serde_json::to_vec(&recovery_keys).map_err(StorageError::from)?,
+        )];
+        files.extend(seal_user_files(
+            username,
+            vec![(
+                get_recovery_codes_path(username, base_dir)?,
+                FileKind::RecoveryCodes,
+                serde_json::to_vec(codes).map_err(StorageError::from)?,
+            )],
+            manifest_key,
+            key,
+            base_dir,
+        )?);
+
+        Ok(files)
+    }
+
     /// Update user's last login timestamp
     pub fn update_last_login(
         username: &str,
@@ -205,10 +455,11 @@ impl UserManager {
             ));
         }
 
-        if username.len() > 32 {
-            return Err(ManagerError::InvalidUsername(
-                "Username must be 32 characters or less".into(),
-            ));
+        if username.len() > MAX_USERNAME_LENGTH {
+            return Err(ManagerError::InvalidUsername(format!(
+                "Username must be {} bytes or less",
+                MAX_USERNAME_LENGTH
+            )));
         }
 
         if !username
@@ -224,7 +475,7 @@ impl UserManager {
     }
 
     /// Validate password strength
-    fn validate_password(password: &str) -> Result<()> {
+    pub(crate) fn validate_password(password: &str) -> Result<()> {
         if password.len() < 12 {
             return Err(ManagerError::InvalidPassword(
                 "Password must be at least 12 characters".into(),
@@ -270,4 +521,24 @@ mod tests {
         assert!(UserManager::validate_password("short").is_err());
         assert!(UserManager::validate_password("only11chars").is_err());
     }
+
+    #[test]
+    fn test_unlock_key_rejects_costly_kdf_header() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        UserManager::create_user("alice", "secure_password123", base_dir).unwrap();
+
+        // The KDF parameters in the `.key` header are read before anything is authenticated
+        let kdf = KdfParams {
+            memory_kb: KdfParams::password().memory_kb * 16,
+            ..KdfParams::password()
+        };
+        let header =
+            EnvelopeHeader::for_file(FileKind::DataKey, "alice").with_kdf(kdf, Some(SALT_FILE));
+        let wrapped = wrap_key(&generate_key(), &generate_key(), &header).unwrap();
+        save_wrapped_key("alice", &wrapped, base_dir).unwrap();
+
+        let err = UserManager::unlock_key("alice", "secure_password123", base_dir).unwrap_err();
+        assert!(err.to_string().contains("KDF parameters exceed"));
+    }
 }
@@ -8,13 +8,17 @@
 ///
 /// The system ensures data isolation between users through password-based
 /// encryption using Argon2id for key derivation and AES-256-GCM for file encryption.
+pub mod archive;
 pub mod auth;
 pub mod command;
 pub mod command_md;
 pub mod crypto;
+pub mod keystore;
 pub mod manager;
 pub mod markdown;
+pub mod recovery;
 pub mod storage;
+pub mod throttle;
 pub mod types;
 
 pub use types::{
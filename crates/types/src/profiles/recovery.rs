@@ -0,0 +1,245 @@
+/// Account recovery codes
+///
+/// Recovery codes are a second way to unlock a user's data encryption key
+/// when the password is forgotten. Each code is random, so the data key is
+/// wrapped once per code with a key derived from that code.
+///
+/// Two files in the user directory hold the codes:
+/// - `.recovery`: The data key wrapped by every code (not encrypted, needed
+///   before the user can log in)
+/// - `recovery-codes.json`: The codes themselves, encrypted with the data key
+///   so a logged-in user can view them again
+///
+/// Codes are single use. Recovering an account wraps the data key for the
+/// new password and removes the code that was used.
+use crate::profiles::crypto::{
+    derive_key_with, derive_recovery_key, read_envelope_header, unwrap_key, wrap_key, CryptoError,
+    EncryptionKey, EnvelopeHeader, KdfParams, Result,
+};
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Number of recovery codes generated for a user
+pub const RECOVERY_CODE_COUNT: usize = 8;
+
+/// Characters in a recovery code (20 characters x 5 bits = 100 bits)
+const CODE_LENGTH: usize = 20;
+
+/// Characters per dash-separated group when a code is displayed
+const CODE_GROUP_LENGTH: usize = 5;
+
+/// Crockford base32 alphabet (no I, L, O or U to avoid misreading)
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// ============================================================================
+// Recovery Keys
+// ============================================================================
+
+/// Data key wrapped by a single recovery code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecoverySlot {
+    /// Argon2id salt used to derive the code key
+    salt: String,
+
+    /// Data key wrapped by the code key
+    wrapped_key: Vec<u8>,
+}
+
+/// Data key wrapped once per recovery code (stored in `.recovery`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryKeys {
+    slots: Vec<RecoverySlot>,
+}
+
+impl RecoveryKeys {
+    /// Wrap a data key with each recovery code
+    ///
+    /// # Parameters
+    /// - `codes`: Recovery codes, in display or normalized form
+    /// - `key`: Data encryption key to wrap
+    ///
+    /// # Errors
+    /// - `CryptoError::HashingFailed` if a code cannot be normalized or hashed
+    pub fn new(codes: &[String], key: &EncryptionKey) -> Result<Self> {
+        let slots = codes
+            .iter()
+            .map(|code| {
+                let normalized = normalize_code(code).ok_or_else(|| {
+                    CryptoError::HashingFailed("Invalid recovery code".to_string())
+                })?;
+                let (code_key, salt) = derive_recovery_key(&normalized, None)?;
+                Ok(RecoverySlot {
+                    salt: String::from_utf8_lossy(&salt).into_owned(),
+                    wrapped_key: wrap_key(
+                        key,
+                        &code_key,
+                        &EnvelopeHeader::default().with_kdf(KdfParams::recovery(), None),
+                    )?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { slots })
+    }
+
+    /// Number of codes that can unlock the data key
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no recovery codes are left
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Unlock the data key with a recovery code
+    ///
+    /// # Returns
+    /// - `Some(EncryptionKey)`: The data key if the code matches a slot
+    /// - `None`: If the code is malformed or does not match
+    pub fn unlock(&self, code: &str) -> Result<Option<EncryptionKey>> {
+        let Some(normalized) = normalize_code(code) else {
+            return Ok(None);
+        };
+
+        for slot in &self.slots {
+            let kdf = read_envelope_header(&slot.wrapped_key)?
+                .and_then(|header| header.kdf)
+                .unwrap_or_else(KdfParams::recovery);
+            let (code_key, _) = derive_key_with(&normalized, Some(slot.salt.as_bytes()), &kdf)?;
+            if let Ok(key) = unwrap_key(&slot.wrapped_key, &code_key) {
+                return Ok(Some(key));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// ============================================================================
+// Recovery Codes
+// ============================================================================
+
+/// Generate a fresh set of recovery codes in display form
+///
+/// Codes look like `7K2QF-M9XWD-4TNBH-CR1PE`.
+pub fn generate_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code: String = (0..CODE_LENGTH)
+                .map(|_| CODE_ALPHABET[OsRng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect();
+            format_code(&code)
+        })
+        .collect()
+}
+
+/// Normalize a recovery code as typed by a user
+///
+/// Dashes and whitespace are ignored, letters are uppercased, and the
+/// characters Crockford base32 treats as look-alikes are mapped (`O` to
+/// `0`, `I` and `L` to `1`).
+///
+/// # Returns
+/// - `Some(String)`: The 20-character code without dashes
+/// - `None`: If the input is not a valid recovery code
+pub fn normalize_code(code: &str) -> Option<String> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            other => other,
+        })
+        .collect();
+
+    let valid =
+        normalized.len() == CODE_LENGTH && normalized.bytes().all(|b| CODE_ALPHABET.contains(&b));
+
+    valid.then_some(normalized)
+}
+
+/// Check whether two recovery codes are the same after normalization
+pub fn codes_match(a: &str, b: &str) -> bool {
+    match (normalize_code(a), normalize_code(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Split a normalized code into dash-separated groups
+fn format_code(code: &str) -> String {
+    code.as_bytes()
+        .chunks(CODE_GROUP_LENGTH)
+        .map(|group| String::from_utf8_lossy(group).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::crypto::generate_key;
+
+    #[test]
+    fn test_generate_codes() {
+        let codes = generate_codes();
+
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 23);
+            assert_eq!(code.matches('-').count(), 3);
+            assert!(normalize_code(code).is_some());
+        }
+
+        let mut unique = codes.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), codes.len());
+    }
+
+    #[test]
+    fn test_normalize_code() {
+        assert_eq!(
+            normalize_code("7k2qf-m9xwd 4tnbh-cr1pe").as_deref(),
+            Some("7K2QFM9XWD4TNBHCR1PE")
+        );
+        assert_eq!(
+            normalize_code("OIL00-00000-00000-00000").as_deref(),
+            Some("01100000000000000000")
+        );
+        assert!(normalize_code("7K2QF-M9XWD-4TNBH").is_none());
+        assert!(normalize_code("UUUUU-UUUUU-UUUUU-UUUUU").is_none());
+        assert!(codes_match(
+            "7k2qf-m9xwd-4tnbh-cr1pe",
+            "7K2QFM9XWD4TNBHCR1PE"
+        ));
+    }
+
+    #[test]
+    fn test_recovery_keys_unlock() {
+        let key = generate_key();
+        let codes = generate_codes()[..2].to_vec();
+        let recovery = RecoveryKeys::new(&codes, &key).unwrap();
+
+        assert_eq!(recovery.len(), 2);
+
+        let unlocked = recovery.unlock(&codes[1].to_lowercase()).unwrap().unwrap();
+        assert_eq!(unlocked.as_bytes(), key.as_bytes());
+
+        assert!(recovery
+            .unlock("00000-00000-00000-00000")
+            .unwrap()
+            .is_none());
+        assert!(recovery.unlock("not a code").unwrap().is_none());
+    }
+}
@@ -9,6 +9,7 @@
 /// - Encrypted file read/write operations
 /// - Path resolution and validation
 /// - Migration from older versions
+/// - User deletion with best-effort overwriting
 ///
 /// File system structure:
 /// ```text
@@ -17,6 +18,12 @@
 /// ├── users/
 /// │   ├── alice/
 /// │   │   ├── .salt            # Argon2id salt (16 bytes)
+/// │   │   ├── .key             # Data encryption key, wrapped by the password key
+/// │   │   ├── .recovery        # Data encryption key, wrapped by each recovery code
+/// │   │   ├── recovery-codes.json  # Recovery codes (encrypted)
+/// │   │   ├── .manifest        # Latest version of each file (encrypted)
+/// │   │   ├── .remember        # Device key of a remembered login (optional)
+/// │   │   ├── .login-attempts  # Failed login count and audit trail (plain JSON)
 /// │   │   ├── user.json        # User configuration (encrypted)
 /// │   │   ├── user-profile.md  # AI context document (encrypted)
 /// │   │   ├── browser-profiles/
@@ -30,11 +37,19 @@
 ///     └── ephemeral-{uuid}/    # Temporary browser profiles
 /// ```
 use crate::profiles::{
-    crypto::{decrypt_file, encrypt_file, EncryptionKey},
+    crypto::{
+        decrypt_envelope, decrypt_file, decrypt_stream, encrypt_envelope, encrypt_record,
+        encrypt_stream, is_record_log, is_stream_format, read_envelope_header, record_log_prefix,
+        EncryptionKey, EnvelopeHeader, FileKind, RecordReader,
+    },
+    recovery::RecoveryKeys,
+    throttle::LoginAttempts,
     types::UserConfig,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -58,7 +73,40 @@ const USER_CONFIG_FILE: &str = "user.json";
 const USER_PROFILE_FILE: &str = "user-profile.md";
 
 /// Filename for salt storage
-const SALT_FILE: &str = ".salt";
+pub const SALT_FILE: &str = ".salt";
+
+/// Filename for the wrapped data encryption key
+const KEY_FILE: &str = ".key";
+
+/// Filename for the data encryption key wrapped by each recovery code
+const RECOVERY_FILE: &str = ".recovery";
+
+/// Filename for the recovery codes (encrypted)
+const RECOVERY_CODES_FILE: &str = "recovery-codes.json";
+
+/// Filename for the file version manifest (encrypted)
+const MANIFEST_FILE: &str = ".manifest";
+
+/// Filename for the device key of a remembered login
+const REMEMBER_FILE: &str = ".remember";
+
+/// Filename for the failed login count and audit trail
+const LOGIN_ATTEMPTS_FILE: &str = ".login-attempts";
+
+/// Filename of the encrypted debug log (rotated copies get a numeric suffix)
+const LOG_FILE: &str = "debug.log";
+
+/// Journal listing the files of a re-encryption that has not committed yet
+const REKEY_JOURNAL_FILE: &str = ".rekey-journal";
+
+/// Journal of a re-encryption that committed but still has backups to remove
+const REKEY_COMMITTED_FILE: &str = ".rekey-committed";
+
+/// Suffix of re-encrypted files waiting to replace the originals
+const REKEY_STAGED_SUFFIX: &str = ".rekey";
+
+/// Suffix of original files kept until a re-encryption commits
+const REKEY_BACKUP_SUFFIX: &str = ".rekey-bak";
 
 /// Directory name for browser profiles
 const BROWSER_PROFILES_DIR: &str = "browser-profiles";
@@ -103,6 +151,10 @@ pub enum StorageError {
     /// Migration error
     #[error("Migration failed: {0}")]
     MigrationError(String),
+
+    /// File was swapped, replaced by another user's file or rolled back
+    #[error("Integrity check failed: {0}")]
+    IntegrityError(String),
 }
 
 pub type Result<T> = std::result::Result<T, StorageError>;
@@ -203,6 +255,48 @@ pub fn get_salt_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf>
     Ok(get_user_dir(username, base_dir)?.join(SALT_FILE))
 }
 
+/// Get the wrapped data encryption key path for a user
+///
+/// Returns `~/.robert/users/{username}/.key`
+pub fn get_key_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(KEY_FILE))
+}
+
+/// Get the recovery keys path for a user
+///
+/// Returns `~/.robert/users/{username}/.recovery`
+pub fn get_recovery_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(RECOVERY_FILE))
+}
+
+/// Get the recovery codes file path
+///
+/// Returns `~/.robert/users/{username}/recovery-codes.json`
+pub fn get_recovery_codes_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(RECOVERY_CODES_FILE))
+}
+
+/// Get the file version manifest path
+///
+/// Returns `~/.robert/users/{username}/.manifest`
+pub fn get_manifest_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(MANIFEST_FILE))
+}
+
+/// Get the remembered login device key path
+///
+/// Returns `~/.robert/users/{username}/.remember`
+pub fn get_remember_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(REMEMBER_FILE))
+}
+
+/// Get the login attempts path
+///
+/// Returns `~/.robert/users/{username}/.login-attempts`
+pub fn get_login_attempts_path(username: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+    Ok(get_user_dir(username, base_dir)?.join(LOGIN_ATTEMPTS_FILE))
+}
+
 /// Get the user config file path
 ///
 /// Returns `~/.robert/users/{username}/user.json`
@@ -262,7 +356,7 @@ fn validate_profile_name(profile_name: &str) -> Result<()> {
 }
 
 /// Validate command name format (kebab-case)
-fn validate_command_name(command_name: &str) -> Result<()> {
+pub(crate) fn validate_command_name(command_name: &str) -> Result<()> {
     if command_name.is_empty() {
         return Err(StorageError::InvalidPath(
             "Command name cannot be empty".into(),
@@ -475,6 +569,167 @@ pub fn load_salt(username: &str, base_dir: Option<&Path>) -> Result<Vec<u8>> {
     Ok(fs::read(salt_path)?)
 }
 
+/// Save the wrapped data encryption key to file
+pub fn save_wrapped_key(username: &str, wrapped_key: &[u8], base_dir: Option<&Path>) -> Result<()> {
+    let key_path = get_key_path(username, base_dir)?;
+    fs::write(key_path, wrapped_key)?;
+    Ok(())
+}
+
+/// Load the wrapped data encryption key from file
+///
+/// Returns `None` for users created before data encryption keys were
+/// introduced; their files are encrypted with the password-derived key.
+pub fn load_wrapped_key(username: &str, base_dir: Option<&Path>) -> Result<Option<Vec<u8>>> {
+    let key_path = get_key_path(username, base_dir)?;
+
+    if !key_path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(fs::read(key_path)?))
+}
+
+/// Save the data key wrapped by each recovery code
+pub fn save_recovery_keys(
+    username: &str,
+    recovery_keys: &RecoveryKeys,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let recovery_path = get_recovery_path(username, base_dir)?;
+    fs::write(recovery_path, serde_json::to_vec(recovery_keys)?)?;
+    Ok(())
+}
+
+/// Load the data key wrapped by each recovery code
+///
+/// Returns `None` if the user has no recovery codes.
+pub fn load_recovery_keys(username: &str, base_dir: Option<&Path>) -> Result<Option<RecoveryKeys>> {
+    let recovery_path = get_recovery_path(username, base_dir)?;
+
+    if !recovery_path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(&fs::read(recovery_path)?)?))
+}
+
+/// Save the device key of a remembered login (readable by the owner only)
+pub fn save_remember_key(
+    username: &str,
+    device_key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let remember_path = get_remember_path(username, base_dir)?;
+    fs::write(&remember_path, device_key.as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&remember_path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+/// Load the device key of a remembered login
+///
+/// Returns `None` if the user has no remembered login.
+pub fn load_remember_key(username: &str, base_dir: Option<&Path>) -> Result<Option<EncryptionKey>> {
+    let remember_path = get_remember_path(username, base_dir)?;
+
+    if !remember_path.exists() {
+        return Ok(None);
+    }
+
+    Ok(Some(EncryptionKey::from_bytes(fs::read(remember_path)?)))
+}
+
+/// Remove the device key of a remembered login, if any
+pub fn remove_remember_key(username: &str, base_dir: Option<&Path>) -> Result<()> {
+    let remember_path = get_remember_path(username, base_dir)?;
+
+    if remember_path.exists() {
+        fs::remove_file(remember_path)?;
+    }
+
+    Ok(())
+}
+
+/// Save the failed login count and audit trail of a user
+///
+/// Written to a temporary file and renamed, so a crash never leaves a
+/// truncated file.
+pub fn save_login_attempts(
+    username: &str,
+    attempts: &LoginAttempts,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let attempts_path = get_login_attempts_path(username, base_dir)?;
+    let tmp_path = with_suffix(&attempts_path, ".tmp");
+
+    fs::write(&tmp_path, serde_json::to_vec_pretty(attempts)?)?;
+    fs::rename(&tmp_path, &attempts_path)?;
+
+    Ok(())
+}
+
+/// Load the failed login count and audit trail of a user
+///
+/// Returns an empty record if the user has never tried to log in.
+pub fn load_login_attempts(username: &str, base_dir: Option<&Path>) -> Result<LoginAttempts> {
+    let attempts_path = get_login_attempts_path(username, base_dir)?;
+
+    if !attempts_path.exists() {
+        return Ok(LoginAttempts::default());
+    }
+
+    Ok(serde_json::from_slice(&fs::read(attempts_path)?)?)
+}
+
+/// Save recovery codes (encrypted)
+pub fn save_recovery_codes(
+    username: &str,
+    codes: &[String],
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let codes_path = get_recovery_codes_path(username, base_dir)?;
+    let json = serde_json::to_vec(codes)?;
+    write_user_file(
+        username,
+        &codes_path,
+        FileKind::RecoveryCodes,
+        &json,
+        key,
+        base_dir,
+    )
+}
+
+/// Load recovery codes (decrypt)
+///
+/// Returns an empty list if the user has no recovery codes.
+pub fn load_recovery_codes(
+    username: &str,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<Vec<String>> {
+    let codes_path = get_recovery_codes_path(username, base_dir)?;
+
+    if !codes_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let decrypted = read_user_file(
+        username,
+        &codes_path,
+        FileKind::RecoveryCodes,
+        key,
+        base_dir,
+    )?;
+    Ok(serde_json::from_slice(&decrypted)?)
+}
+
 /// Save user configuration (encrypted)
 pub fn save_user_config(
     username: &str,
@@ -487,11 +742,15 @@ pub fn save_user_config(
     // Serialize to JSON
     let json = serde_json::to_string_pretty(config)?;
 
-    // Encrypt
-    let encrypted = encrypt_file(json.as_bytes(), key)?;
-
-    // Write to file
-    fs::write(config_path, encrypted)?;
+    // Encrypt and write
+    write_user_file(
+        username,
+        &config_path,
+        FileKind::UserConfig,
+        json.as_bytes(),
+        key,
+        base_dir,
+    )?;
 
     log::debug!("Saved user config for '{}'", username);
 
@@ -513,11 +772,8 @@ pub fn load_user_config(
         )));
     }
 
-    // Read encrypted file
-    let encrypted = fs::read(config_path)?;
-
-    // Decrypt
-    let decrypted = decrypt_file(&encrypted, key)?;
+    // Read and decrypt
+    let decrypted = read_user_file(username, &config_path, FileKind::UserConfig, key, base_dir)?;
 
     // Parse JSON
     let config: UserConfig = serde_json::from_slice(&decrypted)?;
@@ -536,11 +792,15 @@ pub fn save_user_profile(
 ) -> Result<()> {
     let profile_path = get_user_profile_path(username, base_dir)?;
 
-    // Encrypt
-    let encrypted = encrypt_file(content.as_bytes(), key)?;
-
-    // Write to file
-    fs::write(profile_path, encrypted)?;
+    // Encrypt and write
+    write_user_file(
+        username,
+        &profile_path,
+        FileKind::UserProfile,
+        content.as_bytes(),
+        key,
+        base_dir,
+    )?;
 
     log::debug!("Saved user profile for '{}'", username);
 
@@ -560,11 +820,14 @@ pub fn load_user_profile(
         return Ok(create_default_user_profile(username));
     }
 
-    // Read encrypted file
-    let encrypted = fs::read(profile_path)?;
-
-    // Decrypt
-    let decrypted = decrypt_file(&encrypted, key)?;
+    // Read and decrypt
+    let decrypted = read_user_file(
+        username,
+        &profile_path,
+        FileKind::UserProfile,
+        key,
+        base_dir,
+    )?;
 
     // Convert to string
     let content = String::from_utf8(decrypted)
@@ -610,11 +873,15 @@ pub fn save_command(
     validate_command_name(command_name)?;
     let command_path = get_command_path(username, command_name, base_dir)?;
 
-    // Encrypt
-    let encrypted = encrypt_file(content.as_bytes(), key)?;
-
-    // Write to file
-    fs::write(command_path, encrypted)?;
+    // Encrypt and write
+    write_user_file(
+        username,
+        &command_path,
+        FileKind::Command,
+        content.as_bytes(),
+        key,
+        base_dir,
+    )?;
 
     log::debug!("Saved command '{}' for user '{}'", command_name, username);
 
@@ -638,11 +905,8 @@ pub fn load_command(
         )));
     }
 
-    // Read encrypted file
-    let encrypted = fs::read(command_path)?;
-
-    // Decrypt
-    let decrypted = decrypt_file(&encrypted, key)?;
+    // Read and decrypt
+    let decrypted = read_user_file(username, &command_path, FileKind::Command, key, base_dir)?;
 
     // Convert to string
     let content = String::from_utf8(decrypted)
@@ -679,7 +943,12 @@ pub fn list_commands(username: &str, base_dir: Option<&Path>) -> Result<Vec<Stri
 }
 
 /// Delete a command
-pub fn delete_command(username: &str, command_name: &str, base_dir: Option<&Path>) -> Result<()> {
+pub fn delete_command(
+    username: &str,
+    command_name: &str,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<()> {
     validate_command_name(command_name)?;
     let command_path = get_command_path(username, command_name, base_dir)?;
 
@@ -690,13 +959,778 @@ pub fn delete_command(username: &str, command_name: &str, base_dir: Option<&Path
         )));
     }
 
-    fs::remove_file(command_path)?;
+    remove_user_file(username, &command_path, key, base_dir)?;
 
     log::info!("Deleted command '{}' for user '{}'", command_name, username);
 
     Ok(())
 }
 
+// ============================================================================
+// Re-encryption (Password Change and Key Rotation)
+// ============================================================================
+
+/// File taking part in a re-encryption, relative to the user directory
+#[derive(Debug, Serialize, Deserialize)]
+struct RekeyEntry {
+    /// Path relative to the user directory
+    path: String,
+
+    /// Whether the file existed before the re-encryption
+    existed: bool,
+}
+
+/// List every file of a user that is encrypted with the data key
+///
+/// Covers `user.json`, `user-profile.md`, `recovery-codes.json`, `.manifest`,
+/// all files in `commands/` and the debug log with its rotated copies. Empty files (such as a freshly
+/// truncated log) are skipped because they hold no ciphertext.
+pub fn list_encrypted_files(username: &str, base_dir: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let user_dir = get_user_dir(username, base_dir)?;
+    let mut files = vec![
+        get_user_config_path(username, base_dir)?,
+        get_user_profile_path(username, base_dir)?,
+        get_recovery_codes_path(username, base_dir)?,
+        get_manifest_path(username, base_dir)?,
+    ];
+
+    for entry in fs::read_dir(&user_dir)? {
+        let path = entry?.path();
+        let is_log = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| name == LOG_FILE || is_rotated_log(name));
+        if is_log {
+            files.push(path);
+        }
+    }
+
+    let commands_dir = get_commands_dir(username, base_dir)?;
+    if commands_dir.exists() {
+        for entry in fs::read_dir(commands_dir)? {
+            let path = entry?.path();
+            let is_staged = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.contains(REKEY_STAGED_SUFFIX));
+            if path.is_file() && !is_staged {
+                files.push(path);
+            }
+        }
+    }
+
+    files.retain(|path| fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() > 0));
+    files.sort();
+    Ok(files)
+}
+
+/// Whether a filename is a rotated copy of the debug log (`debug.log.1`, ...)
+fn is_rotated_log(name: &str) -> bool {
+    name.strip_prefix(LOG_FILE)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Re-encrypt all of a user's files with a new key
+///
+/// Every file from `list_encrypted_files()` is decrypted with `old_key` and
+/// encrypted with `new_key`. The key files (salt, wrapped key and recovery
+/// keys) are replaced in the same transaction by `replace_user_files()`, so
+/// the files on disk always match the key that the password unlocks. A key
+/// file that is also an encrypted file takes the place of its re-encrypted
+/// version.
+///
+/// All files are re-encrypted in memory first; a file that does not decrypt
+/// aborts the operation before anything is written.
+///
+/// # Parameters
+/// - `username`: User whose files are re-encrypted
+/// - `old_key`: Key the files are currently encrypted with
+/// - `new_key`: Key to encrypt the files with
+/// - `key_files`: Paths and contents of the key files to write alongside
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+///
+/// # Returns
+/// - `usize`: Number of data files re-encrypted (key files not counted)
+pub fn reencrypt_user_files(
+    username: &str,
+    old_key: &EncryptionKey,
+    new_key: &EncryptionKey,
+    key_files: Vec<(PathBuf, Vec<u8>)>,
+    base_dir: Option<&Path>,
+) -> Result<usize> {
+    recover_reencryption(username, base_dir)?;
+
+    let mut files = list_encrypted_files(username, base_dir)?;
+    files.retain(|path| !key_files.iter().any(|(key_path, _)| key_path == path));
+
+    let mut contents = Vec::with_capacity(files.len() + key_files.len());
+    for path in &files {
+        let encrypted = fs::read(path)?;
+        if is_stream_format(&encrypted) {
+            let (plaintext, header) = decrypt_stream(&encrypted, old_key)?;
+            contents.push((path.clone(), encrypt_stream(&plaintext, new_key, &header)?));
+            continue;
+        }
+        if is_record_log(&encrypted) {
+            let mut log = record_log_prefix();
+            for record in RecordReader::new(encrypted.as_slice(), old_key)? {
+                let (plaintext, header) = record?;
+                log.extend(encrypt_record(&plaintext, new_key, &header)?);
+            }
+            contents.push((path.clone(), log));
+            continue;
+        }
+
+        // Keep each file's header so its path and version stay bound to it
+        let (plaintext, header) = decrypt_envelope(&encrypted, old_key)?;
+        let header = match header {
+            Some(header) => header,
+            None => EnvelopeHeader::for_path(
+                file_kind(path),
+                username,
+                &logical_path(username, path, base_dir)?,
+                None,
+            ),
+        };
+        contents.push((
+            path.clone(),
+            encrypt_envelope(&plaintext, new_key, &header)?,
+        ));
+    }
+    contents.extend(key_files);
+
+    replace_user_files(username, contents, base_dir)?;
+
+    log::info!("Re-encrypted {} files for user '{}'", files.len(), username);
+
+    Ok(files.len())
+}
+
+/// Replace a set of a user's files in one transaction
+///
+/// Either every file is replaced or none are:
+///
+/// 1. A journal listing the files is written, then the new versions are
+///    staged next to the originals.
+/// 2. Each original is moved to a backup and replaced by its staged file.
+/// 3. The journal is renamed to mark the commit, and the backups are removed.
+///
+/// A failure in steps 1-2 restores the originals. If the process stops part
+/// way, `recover_reencryption()` finishes or undoes the operation on the
+/// next login.
+///
+/// # Parameters
+/// - `username`: User whose files are replaced
+/// - `contents`: Paths inside the user directory and their new contents
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+pub fn replace_user_files(
+    username: &str,
+    contents: Vec<(PathBuf, Vec<u8>)>,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    recover_reencryption(username, base_dir)?;
+
+    let user_dir = get_user_dir(username, base_dir)?;
+
+    let journal = contents
+        .iter()
+        .map(|(path, _)| {
+            let relative = path.strip_prefix(&user_dir).map_err(|_| {
+                StorageError::InvalidPath(format!(
+                    "{} is outside the user directory",
+                    path.display()
+                ))
+            })?;
+            Ok(RekeyEntry {
+                path: relative.to_string_lossy().into_owned(),
+                existed: path.exists(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Written to a temporary name first so a journal is never half written
+    let journal_path = user_dir.join(REKEY_JOURNAL_FILE);
+    let pending = with_suffix(&journal_path, REKEY_STAGED_SUFFIX);
+    fs::write(&pending, serde_json::to_vec(&journal)?)?;
+    fs::rename(&pending, &journal_path)?;
+
+    let commit = || -> Result<()> {
+        for (path, data) in &contents {
+            fs::write(with_suffix(path, REKEY_STAGED_SUFFIX), data)?;
+        }
+
+        for (path, _) in &contents {
+            if path.exists() {
+                fs::rename(path, with_suffix(path, REKEY_BACKUP_SUFFIX))?;
+            }
+            fs::rename(with_suffix(path, REKEY_STAGED_SUFFIX), path)?;
+        }
+
+        fs::rename(&journal_path, user_dir.join(REKEY_COMMITTED_FILE))?;
+        Ok(())
+    };
+
+    if let Err(e) = commit() {
+        log::error!(
+            "Replacing files failed for user '{}', restoring originals: {}",
+            username,
+            e
+        );
+        recover_reencryption(username, base_dir)?;
+        return Err(e);
+    }
+
+    recover_reencryption(username, base_dir)?;
+
+    Ok(())
+}
+
+/// Finish or undo a re-encryption that did not complete
+///
+/// A committed re-encryption only has its backups removed. One that did
+/// not commit has every original restored from its backup, files it
+/// created removed, and staged files discarded. Call this before deriving
+/// a user's key.
+///
+/// # Returns
+/// - `true` if an interrupted re-encryption was found
+pub fn recover_reencryption(username: &str, base_dir: Option<&Path>) -> Result<bool> {
+    let user_dir = get_user_dir(username, base_dir)?;
+    let committed_path = user_dir.join(REKEY_COMMITTED_FILE);
+    let journal_path = user_dir.join(REKEY_JOURNAL_FILE);
+
+    let (path, committed) = if committed_path.exists() {
+        (committed_path, true)
+    } else if journal_path.exists() {
+        (journal_path, false)
+    } else {
+        let _ = fs::remove_file(with_suffix(&journal_path, REKEY_STAGED_SUFFIX));
+        return Ok(false);
+    };
+
+    let entries: Vec<RekeyEntry> = serde_json::from_slice(&fs::read(&path)?)?;
+
+    for entry in &entries {
+        if entry.path.split(['/', '\\']).any(|part| part == "..") {
+            return Err(StorageError::InvalidPath(format!(
+                "Invalid path in re-encryption journal: {}",
+                entry.path
+            )));
+        }
+
+        let target = user_dir.join(&entry.path);
+        let backup = with_suffix(&target, REKEY_BACKUP_SUFFIX);
+        let staged = with_suffix(&target, REKEY_STAGED_SUFFIX);
+
+        if committed {
+            remove_if_exists(&backup)?;
+        } else if backup.exists() {
+            fs::rename(&backup, &target)?;
+        } else if !entry.existed {
+            remove_if_exists(&target)?;
+        }
+
+        remove_if_exists(&staged)?;
+    }
+
+    fs::remove_file(&path)?;
+
+    if committed {
+        log::debug!("Removed re-encryption backups for user '{}'", username);
+    } else {
+        log::warn!(
+            "Rolled back an interrupted re-encryption for user '{}'",
+            username
+        );
+    }
+
+    Ok(true)
+}
+
+/// Kind of a file returned by `list_encrypted_files()`
+fn file_kind(path: &Path) -> FileKind {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let parent = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str());
+
+    match name {
+        USER_CONFIG_FILE => FileKind::UserConfig,
+        USER_PROFILE_FILE => FileKind::UserProfile,
+        RECOVERY_CODES_FILE => FileKind::RecoveryCodes,
+        MANIFEST_FILE => FileKind::Manifest,
+        _ if parent == Some(COMMANDS_DIR) => FileKind::Command,
+        _ => FileKind::Log,
+    }
+}
+
+/// Append a suffix to a path's filename (`user.json` -> `user.json.rekey`)
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Remove a file, ignoring files that do not exist
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ============================================================================
+// File Integrity (Associated Data and Manifest)
+// ============================================================================
+
+/// Latest write version of each user file (stored encrypted in `.manifest`)
+///
+/// Files written by `write_user_file()` carry their path, kind, owner and a
+/// version in their authenticated envelope header. The manifest records
+/// the version last written, so a file moved to another path or an older
+/// copy put back on disk fails `read_user_file()`.
+///
+/// The manifest cannot detect a rollback of the whole user directory,
+/// manifest included, to an earlier snapshot.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    versions: BTreeMap<String, u64>,
+}
+
+impl Manifest {
+    /// Latest version of a file, `None` if it was never versioned
+    pub fn version(&self, path: &str) -> Option<u64> {
+        self.versions.get(path).copied()
+    }
+
+    /// Reserve the next version of a file
+    fn bump(&mut self, path: &str) -> u64 {
+        let version = self.versions.entry(path.to_string()).or_insert(0);
+        *version += 1;
+        *version
+    }
+}
+
+/// Load a user's manifest (decrypt)
+///
+/// Returns an empty manifest if the user has none yet.
+pub fn load_manifest(
+    username: &str,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<Manifest> {
+    let manifest_path = get_manifest_path(username, base_dir)?;
+
+    if !manifest_path.exists() {
+        return Ok(Manifest::default());
+    }
+
+    let (decrypted, header) = decrypt_envelope(&fs::read(&manifest_path)?, key)?;
+    verify_associated_data(username, MANIFEST_FILE, FileKind::Manifest, header, None)?;
+
+    Ok(serde_json::from_slice(&decrypted)?)
+}
+
+/// Encrypt user files with their next version, plus the updated manifest
+///
+/// Returns paths and contents for `replace_user_files()` or
+/// `reencrypt_user_files()`, so the files and the manifest are written in
+/// one transaction.
+///
+/// # Parameters
+/// - `username`: User the files belong to
+/// - `files`: Paths inside the user directory, their kinds and plaintexts
+/// - `manifest_key`: Key the manifest is currently encrypted with (the old
+///   key while the data key changes)
+/// - `key`: Key to encrypt the files and the manifest with
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+pub fn seal_user_files(
+    username: &str,
+    files: Vec<(PathBuf, FileKind, Vec<u8>)>,
+    manifest_key: &EncryptionKey,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut manifest = load_manifest(username, manifest_key, base_dir)?;
+
+    let mut contents = Vec::with_capacity(files.len() + 1);
+    for (path, kind, plaintext) in files {
+        let logical = logical_path(username, &path, base_dir)?;
+        let version = manifest.bump(&logical);
+        let header = EnvelopeHeader::for_path(kind, username, &logical, Some(version));
+        contents.push((path, encrypt_envelope(&plaintext, key, &header)?));
+    }
+
+    contents.push((
+        get_manifest_path(username, base_dir)?,
+        encrypt_manifest(username, &manifest, key)?,
+    ));
+
+    Ok(contents)
+}
+
+/// Encrypt a file and record its new version in the manifest
+///
+/// The file and the manifest are replaced in one transaction.
+pub fn write_user_file(
+    username: &str,
+    path: &Path,
+    kind: FileKind,
+    plaintext: &[u8],
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let contents = seal_user_files(
+        username,
+        vec![(path.to_path_buf(), kind, plaintext.to_vec())],
+        key,
+        key,
+        base_dir,
+    )?;
+    replace_user_files(username, contents, base_dir)
+}
+
+/// Decrypt a file and check that it is the latest version of this file
+///
+/// # Errors
+/// - Returns `IntegrityError` if the file belongs to another path, kind or
+///   user, or its version does not match the manifest (rolled back)
+/// - Returns `CryptoError` if decryption fails
+pub fn read_user_file(
+    username: &str,
+    path: &Path,
+    kind: FileKind,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>> {
+    let logical = logical_path(username, path, base_dir)?;
+    let manifest = load_manifest(username, key, base_dir)?;
+
+    let (decrypted, header) = decrypt_envelope(&fs::read(path)?, key)?;
+    verify_associated_data(username, &logical, kind, header, manifest.version(&logical))?;
+
+    Ok(decrypted)
+}
+
+/// Delete a file and retire its version
+///
+/// The manifest version is bumped past the deleted file's, so a copy of
+/// the file put back later is rejected. The manifest is written before
+/// the file is deleted: if deleting fails or is interrupted, the file is
+/// left unreadable rather than current.
+pub fn remove_user_file(
+    username: &str,
+    path: &Path,
+    key: &EncryptionKey,
+    base_dir: Option<&Path>,
+) -> Result<()> {
+    let logical = logical_path(username, path, base_dir)?;
+    let mut manifest = load_manifest(username, key, base_dir)?;
+
+    // Fail before retiring anything if there is no file to delete
+    fs::metadata(path)?;
+
+    if manifest.version(&logical).is_some() {
+        manifest.bump(&logical);
+        let manifest_path = get_manifest_path(username, base_dir)?;
+        let encrypted = encrypt_manifest(username, &manifest, key)?;
+        replace_user_files(username, vec![(manifest_path, encrypted)], base_dir)?;
+    }
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+/// Encrypt a manifest bound to its path
+fn encrypt_manifest(username: &str, manifest: &Manifest, key: &EncryptionKey) -> Result<Vec<u8>> {
+    let header = EnvelopeHeader::for_path(FileKind::Manifest, username, MANIFEST_FILE, None);
+    Ok(encrypt_envelope(
+        &serde_json::to_vec(manifest)?,
+        key,
+        &header,
+    )?)
+}
+
+/// Check a decrypted file's header against where it was read from
+///
+/// Files without a version (legacy files and envelopes written before the
+/// manifest) are only accepted while the manifest has no entry for them;
+/// `migrate_user()` gives them one.
+fn verify_associated_data(
+    username: &str,
+    logical: &str,
+    kind: FileKind,
+    header: Option<EnvelopeHeader>,
+    expected_version: Option<u64>,
+) -> Result<()> {
+    let associated_data = header.and_then(|header| header.associated_data);
+
+    if let Some(ad) = &associated_data {
+        if ad.kind != kind
+            || ad.username != username
+            || ad.path.as_deref().is_some_and(|path| path != logical)
+        {
+            return Err(StorageError::IntegrityError(format!(
+                "{} holds {}'s {:?} file{}",
+                logical,
+                ad.username,
+                ad.kind,
+                ad.path
+                    .as_deref()
+                    .map(|path| format!(" from {}", path))
+                    .unwrap_or_default()
+            )));
+        }
+    }
+
+    let version = associated_data.and_then(|ad| ad.version);
+    match (version, expected_version) {
+        (version, expected) if version == expected => Ok(()),
+        (Some(version), Some(expected)) if version < expected => {
+            Err(StorageError::IntegrityError(format!(
+                "{} was rolled back to version {} (expected {})",
+                logical, version, expected
+            )))
+        }
+        (version, expected) => Err(StorageError::IntegrityError(format!(
+            "{} has version {:?} but the manifest expects {:?}",
+            logical, version, expected
+        ))),
+    }
+}
+
+/// Path of a user file relative to the user directory, with `/` separators
+fn logical_path(username: &str, path: &Path, base_dir: Option<&Path>) -> Result<String> {
+    let user_dir = get_user_dir(username, base_dir)?;
+    let relative = path.strip_prefix(&user_dir).map_err(|_| {
+        StorageError::InvalidPath(format!("{} is outside the user directory", path.display()))
+    })?;
+
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+// ============================================================================
+// Format Migration
+// ============================================================================
+
+/// Upgrade a user's files to the current envelope format
+///
+/// Legacy headerless files are decrypted and written back as envelopes.
+/// Files without a version (written before the manifest existed) are bound
+/// to their path and given a version in the manifest. Log files are only
+/// given an envelope, as the logger renames them when rotating and rewrites
+/// them without the manifest. Streamed files and record logs are already
+/// current.
+/// Everything is replaced in one transaction by `replace_user_files()`.
+/// Files that are already current are left alone, so this is cheap to call
+/// on every login.
+///
+/// The wrapped key files (`.key`, `.recovery`) are not upgraded here, as
+/// they are not encrypted with the data key; headerless ones are read with
+/// the default KDF parameters and rewritten on the next password change.
+///
+/// # Parameters
+/// - `username`: User whose files are upgraded
+/// - `key`: The user's data encryption key
+/// - `base_dir`: Optional base directory for testing. If None, uses the user's home directory.
+///
+/// # Returns
+/// - `usize`: Number of files upgraded
+///
+/// # Errors
+/// - Returns `MigrationError` if a file to upgrade does not decrypt with `key`
+pub fn migrate_user(username: &str, key: &EncryptionKey, base_dir: Option<&Path>) -> Result<usize> {
+    recover_reencryption(username, base_dir)?;
+
+    let mut versioned = Vec::new();
+    let mut unversioned = Vec::new();
+    for path in list_encrypted_files(username, base_dir)? {
+        let kind = file_kind(&path);
+        let encrypted = fs::read(&path)?;
+        if is_stream_format(&encrypted) || is_record_log(&encrypted) {
+            continue;
+        }
+
+        let needs_upgrade = match read_envelope_header(&encrypted) {
+            Ok(Some(header)) => {
+                !matches!(kind, FileKind::Manifest | FileKind::Log)
+                    && header
+                        .associated_data
+                        .is_none_or(|ad| ad.path.is_none() || ad.version.is_none())
+            }
+            _ => true,
+        };
+        if !needs_upgrade {
+            continue;
+        }
+
+        let plaintext = decrypt_file(&encrypted, key)
+            .map_err(|e| StorageError::MigrationError(format!("{}: {}", path.display(), e)))?;
+        if kind == FileKind::Log {
+            let header = EnvelopeHeader::for_file(kind, username);
+            unversioned.push((path, encrypt_envelope(&plaintext, key, &header)?));
+        } else {
+            versioned.push((path, kind, plaintext));
+        }
+    }
+
+    let upgraded = versioned.len() + unversioned.len();
+    if upgraded > 0 {
+        let mut contents = unversioned;
+        if !versioned.is_empty() {
+            contents.extend(seal_user_files(username, versioned, key, key, base_dir)?);
+        }
+        replace_user_files(username, contents, base_dir)?;
+        log::info!(
+            "Upgraded {} files of user '{}' to the current format",
+            upgraded,
+            username
+        );
+    }
+
+    Ok(upgraded)
+}
+
+// ============================================================================
+// User Deletion
+// ============================================================================
+
+/// What was removed when a user was deleted
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeleteReport {
+    /// Deleted user
+    pub username: String,
+
+    /// Browser profiles removed with the user (directory names)
+    pub browser_profiles: Vec<String>,
+
+    /// Files overwritten before removal
+    pub wiped_files: usize,
+
+    /// Files that could not be overwritten but were removed anyway
+    /// (relative to the user directory)
+    pub wipe_failures: Vec<String>,
+
+    /// Files removed in total, including browser profile files
+    pub removed_files: usize,
+
+    /// Bytes removed in total
+    pub removed_bytes: u64,
+}
+
+/// Overwrite a file with zeros and flush it to disk
+///
+/// Best effort only: journaling and copy-on-write filesystems and SSD wear
+/// leveling may keep older copies of the data elsewhere on the disk.
+pub fn wipe_file(path: &Path) -> Result<()> {
+    static CHUNK: [u8; 64 * 1024] = [0; 64 * 1024];
+
+    let mut remaining = fs::metadata(path)?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+
+    while remaining > 0 {
+        let len = remaining.min(CHUNK.len() as u64) as usize;
+        file.write_all(&CHUNK[..len])?;
+        remaining -= len as u64;
+    }
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// Delete a user directory, overwriting the user's files first
+///
+/// Every regular file outside `browser-profiles/` (encrypted files, wrapped
+/// keys, salt, remembered login) is overwritten with `wipe_file` before the
+/// directory is removed. Browser profiles are removed without overwriting:
+/// Chromium encrypts its own secrets and the profiles can be large. Symbolic
+/// links are removed, never followed.
+///
+/// # Errors
+/// - Returns `UserNotFound` if the user directory does not exist
+/// - Returns `IoError` if the directory cannot be removed (a failure to
+///   overwrite a file is only reported in `wipe_failures`)
+pub fn delete_user_directory(username: &str, base_dir: Option<&Path>) -> Result<DeleteReport> {
+    let user_dir = get_user_dir(username, base_dir)?;
+    if !user_dir.is_dir() {
+        return Err(StorageError::UserNotFound(username.to_string()));
+    }
+    let browser_profiles_dir = get_browser_profiles_dir(username, base_dir)?;
+
+    let mut report = DeleteReport {
+        username: username.to_string(),
+        ..Default::default()
+    };
+
+    if browser_profiles_dir.is_dir() {
+        for entry in fs::read_dir(&browser_profiles_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                report
+                    .browser_profiles
+                    .push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        report.browser_profiles.sort();
+    }
+
+    let mut pending = vec![user_dir.clone()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+
+            report.removed_files += 1;
+            report.removed_bytes += entry.metadata()?.len();
+
+            if path.starts_with(&browser_profiles_dir) {
+                continue;
+            }
+            match wipe_file(&path) {
+                Ok(()) => report.wiped_files += 1,
+                Err(e) => {
+                    let relative = path.strip_prefix(&user_dir).unwrap_or(&path);
+                    log::warn!("Failed to overwrite {}: {}", relative.display(), e);
+                    report
+                        .wipe_failures
+                        .push(relative.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+
+    fs::remove_dir_all(&user_dir)?;
+
+    log::info!(
+        "Deleted user directory of '{}' ({} files, {} overwritten)",
+        username,
+        report.removed_files,
+        report.wiped_files
+    );
+
+    Ok(report)
+}
+
 // ============================================================================
 // Ephemeral Profile Management
 // ============================================================================
@@ -831,6 +1865,259 @@ mod tests {
         assert!(command_path.ends_with("commands/clothing-search.md"));
     }
 
+    #[test]
+    fn test_list_encrypted_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let user_dir = create_user_directory("alice", base_dir).unwrap();
+        for name in [
+            "user.json",
+            "debug.log",
+            "debug.log.2",
+            ".salt",
+            ".key",
+            "notes.txt",
+        ] {
+            fs::write(user_dir.join(name), b"data").unwrap();
+        }
+        fs::write(user_dir.join("user-profile.md"), b"").unwrap();
+        fs::write(user_dir.join("commands/check-prices.md"), b"data").unwrap();
+
+        let names: Vec<_> = list_encrypted_files("alice", base_dir)
+            .unwrap()
+            .iter()
+            .map(|p| {
+                p.strip_prefix(&user_dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "commands/check-prices.md",
+                "debug.log",
+                "debug.log.2",
+                "user.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_user() {
+        use crate::profiles::crypto::{generate_key, read_envelope_header};
+        use aes_gcm::{
+            aead::{Aead, KeyInit},
+            Aes256Gcm,
+        };
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let user_dir = create_user_directory("alice", base_dir).unwrap();
+        let key = generate_key();
+
+        // Headerless files as written before the versioned format
+        let cipher = Aes256Gcm::new_from_slice(key.as_bytes()).unwrap();
+        let legacy = |plaintext: &[u8]| {
+            let nonce = [3u8; 12];
+            let mut data = nonce.to_vec();
+            data.extend(cipher.encrypt(&nonce.into(), plaintext).unwrap());
+            data
+        };
+        fs::write(
+            user_dir.join("commands/check-prices.md"),
+            legacy(b"# Check Prices"),
+        )
+        .unwrap();
+        save_user_profile("alice", "# Profile", &key, base_dir).unwrap();
+        let log_header = EnvelopeHeader::for_file(FileKind::Log, "alice");
+        let mut log = record_log_prefix();
+        log.extend(encrypt_record(b"log line", &key, &log_header).unwrap());
+        fs::write(user_dir.join(LOG_FILE), &log).unwrap();
+        let rotated_log = encrypt_stream(b"log line\n", &key, &log_header).unwrap();
+        fs::write(user_dir.join("debug.log.1"), &rotated_log).unwrap();
+
+        assert_eq!(migrate_user("alice", &key, base_dir).unwrap(), 1);
+        assert_eq!(migrate_user("alice", &key, base_dir).unwrap(), 0);
+
+        let command = fs::read(user_dir.join("commands/check-prices.md")).unwrap();
+        let header = read_envelope_header(&command).unwrap().unwrap();
+        let associated_data = header.associated_data.unwrap();
+        assert_eq!(associated_data.kind, FileKind::Command);
+        assert_eq!(
+            associated_data.path.as_deref(),
+            Some("commands/check-prices.md")
+        );
+        assert_eq!(associated_data.version, Some(1));
+        assert_eq!(
+            load_command("alice", "check-prices", &key, base_dir).unwrap(),
+            "# Check Prices"
+        );
+        assert_eq!(fs::read(user_dir.join(LOG_FILE)).unwrap(), log);
+        assert_eq!(fs::read(user_dir.join("debug.log.1")).unwrap(), rotated_log);
+
+        // A legacy file encrypted with another key fails the migration
+        fs::write(user_dir.join("commands/other.md"), legacy(b"# Other")).unwrap();
+        assert!(matches!(
+            migrate_user("alice", &generate_key(), base_dir),
+            Err(StorageError::MigrationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_swapped_files_are_rejected() {
+        use crate::profiles::crypto::generate_key;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        create_user_directory("alice", base_dir).unwrap();
+        create_user_directory("bob", base_dir).unwrap();
+        let key = generate_key();
+
+        save_command("alice", "first", "# First", &key, base_dir).unwrap();
+        save_command("alice", "second", "# Second", &key, base_dir).unwrap();
+        save_user_profile("bob", "# Bob", &key, base_dir).unwrap();
+
+        // Another command's file under this name
+        let first = get_command_path("alice", "first", base_dir).unwrap();
+        let second = get_command_path("alice", "second", base_dir).unwrap();
+        fs::copy(&first, &second).unwrap();
+        assert!(matches!(
+            load_command("alice", "second", &key, base_dir),
+            Err(StorageError::IntegrityError(_))
+        ));
+
+        // Another user's file, even one encrypted with the same key
+        fs::copy(
+            get_user_profile_path("bob", base_dir).unwrap(),
+            get_user_profile_path("alice", base_dir).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            load_user_profile("alice", &key, base_dir),
+            Err(StorageError::IntegrityError(_))
+        ));
+
+        assert_eq!(
+            load_command("alice", "first", &key, base_dir).unwrap(),
+            "# First"
+        );
+    }
+
+    #[test]
+    fn test_rolled_back_files_are_rejected() {
+        use crate::profiles::crypto::generate_key;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        create_user_directory("alice", base_dir).unwrap();
+        let key = generate_key();
+        let path = get_command_path("alice", "prices", base_dir).unwrap();
+
+        save_command("alice", "prices", "# Old", &key, base_dir).unwrap();
+        let old_copy = fs::read(&path).unwrap();
+        save_command("alice", "prices", "# New", &key, base_dir).unwrap();
+        assert_eq!(
+            load_manifest("alice", &key, base_dir)
+                .unwrap()
+                .version("commands/prices.md"),
+            Some(2)
+        );
+
+        fs::write(&path, &old_copy).unwrap();
+        assert!(matches!(
+            load_command("alice", "prices", &key, base_dir),
+            Err(StorageError::IntegrityError(_))
+        ));
+
+        // A deleted file cannot be brought back either
+        save_command("alice", "prices", "# Newest", &key, base_dir).unwrap();
+        let deleted_copy = fs::read(&path).unwrap();
+        delete_command("alice", "prices", &key, base_dir).unwrap();
+        fs::write(&path, &deleted_copy).unwrap();
+        assert!(matches!(
+            load_command("alice", "prices", &key, base_dir),
+            Err(StorageError::IntegrityError(_))
+        ));
+
+        // Saving again continues from the retired version
+        save_command("alice", "prices", "# Again", &key, base_dir).unwrap();
+        assert_eq!(
+            load_command("alice", "prices", &key, base_dir).unwrap(),
+            "# Again"
+        );
+    }
+
+    #[test]
+    fn test_recover_interrupted_reencryption() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let user_dir = create_user_directory("alice", base_dir).unwrap();
+        let journal = r#"[{"path":"user.json","existed":true},{"path":".key","existed":false}]"#;
+
+        // Stopped part way through the commit
+        fs::write(user_dir.join("user.json"), b"new").unwrap();
+        fs::write(user_dir.join("user.json.rekey-bak"), b"old").unwrap();
+        fs::write(user_dir.join(".key"), b"new key").unwrap();
+        fs::write(user_dir.join(REKEY_JOURNAL_FILE), journal).unwrap();
+
+        assert!(recover_reencryption("alice", base_dir).unwrap());
+        assert_eq!(fs::read(user_dir.join("user.json")).unwrap(), b"old");
+        assert!(!user_dir.join(".key").exists());
+        assert!(!user_dir.join("user.json.rekey-bak").exists());
+        assert!(!user_dir.join(REKEY_JOURNAL_FILE).exists());
+
+        // Stopped after the commit, before the backups were removed
+        fs::write(user_dir.join("user.json"), b"new").unwrap();
+        fs::write(user_dir.join("user.json.rekey-bak"), b"old").unwrap();
+        fs::write(user_dir.join(REKEY_COMMITTED_FILE), journal).unwrap();
+
+        assert!(recover_reencryption("alice", base_dir).unwrap());
+        assert_eq!(fs::read(user_dir.join("user.json")).unwrap(), b"new");
+        assert!(!user_dir.join("user.json.rekey-bak").exists());
+        assert!(!recover_reencryption("alice", base_dir).unwrap());
+    }
+
+    #[test]
+    fn test_delete_user_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_dir = Some(temp_dir.path());
+        let key = crate::profiles::crypto::generate_key();
+
+        create_user_directory("doomed", base_dir).unwrap();
+        save_salt("doomed", b"salt", base_dir).unwrap();
+        save_user_profile("doomed", "# Profile", &key, base_dir).unwrap();
+        create_browser_profile("doomed", "shopping", base_dir).unwrap();
+        let cookies = get_browser_profile_dir("doomed", "shopping", base_dir)
+            .unwrap()
+            .join("Cookies");
+        fs::write(&cookies, b"cookies").unwrap();
+
+        // Overwriting keeps the size and leaves no trace of the content
+        let profile_path = get_user_profile_path("doomed", base_dir).unwrap();
+        let size = fs::metadata(&profile_path).unwrap().len();
+        wipe_file(&profile_path).unwrap();
+        let wiped = fs::read(&profile_path).unwrap();
+        assert_eq!(wiped.len() as u64, size);
+        assert!(wiped.iter().all(|&b| b == 0));
+
+        let report = delete_user_directory("doomed", base_dir).unwrap();
+        assert_eq!(report.username, "doomed");
+        assert!(report.browser_profiles.contains(&"shopping".to_string()));
+        // Everything but the browser profile file was overwritten
+        assert!(report.wiped_files >= 2);
+        assert!(report.wipe_failures.is_empty());
+        assert_eq!(report.removed_files, report.wiped_files + 1);
+        assert!(!get_user_dir("doomed", base_dir).unwrap().exists());
+        assert!(!user_exists("doomed", base_dir).unwrap());
+
+        assert!(matches!(
+            delete_user_directory("doomed", base_dir),
+            Err(StorageError::UserNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_create_default_user_profile() {
         let profile = create_default_user_profile("alice");
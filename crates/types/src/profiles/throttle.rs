@@ -0,0 +1,335 @@
+/// Login throttling and audit trail of login attempts
+///
+/// Every attempt to prove a user's identity (password login, session
+/// unlock, password change, account recovery, user deletion) is recorded in
+/// `.login-attempts` in the user directory. The file is plain JSON because
+/// it must be readable before the user's key is known.
+///
+/// After `FREE_ATTEMPTS` consecutive failures, each further failure makes
+/// the user wait before the next attempt, doubling the delay from one
+/// second. From `LOCKOUT_THRESHOLD` failures on, the user is locked out for
+/// `LOCKOUT_DURATION_SECS` after every failure. A successful attempt resets
+/// the count.
+///
+/// Throttling protects the app's login paths only. Someone who can read the
+/// user directory can also delete this file or copy the encrypted files and
+/// guess offline; Argon2id key derivation is what makes that slow.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Consecutive failures allowed before attempts are delayed
+pub const FREE_ATTEMPTS: u32 = 3;
+
+/// Consecutive failures after which the user is locked out
+pub const LOCKOUT_THRESHOLD: u32 = 10;
+
+/// How long a lockout lasts after the latest failure (seconds)
+pub const LOCKOUT_DURATION_SECS: i64 = 15 * 60;
+
+/// Number of attempts kept in the audit trail
+pub const MAX_HISTORY: usize = 50;
+
+// ============================================================================
+// Clock
+// ============================================================================
+
+/// Source of the current time, replaceable in tests
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock reading the system time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to, for tests
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+// ============================================================================
+// Login Attempts
+// ============================================================================
+
+/// How the user tried to prove their identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginMethod {
+    /// Login with the password
+    Password,
+
+    /// Login through a remembered login
+    Remembered,
+
+    /// Unlock of a locked session
+    Unlock,
+
+    /// Password check before a password change
+    PasswordChange,
+
+    /// Account recovery with a recovery code
+    Recovery,
+
+    /// Password confirmation before deleting the user
+    Deletion,
+}
+
+/// Result of a login attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    /// The password or code was correct
+    Success,
+
+    /// The password or code was wrong
+    Failure,
+
+    /// Rejected without checking because of backoff or lockout
+    Blocked,
+}
+
+/// One entry of the audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginAttempt {
+    /// When the attempt was made
+    pub at: DateTime<Utc>,
+
+    /// How the user tried to log in
+    pub method: LoginMethod,
+
+    /// Result of the attempt
+    pub outcome: AttemptOutcome,
+}
+
+/// Failed attempt count and audit trail of a user (`.login-attempts`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoginAttempts {
+    /// Failures since the last successful attempt
+    pub failed_count: u32,
+
+    /// Time of the latest failure
+    pub last_failure: Option<DateTime<Utc>>,
+
+    /// Latest attempts, oldest first (at most `MAX_HISTORY`)
+    pub history: Vec<LoginAttempt>,
+}
+
+impl LoginAttempts {
+    /// Time the user has to wait before the next attempt
+    ///
+    /// # Returns
+    /// - `Some(Duration)`: Attempts are blocked for this long
+    /// - `None`: An attempt may be made now
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let delay = Self::delay(self.failed_count)?;
+        let last_failure = self.last_failure?;
+
+        // A clock set back must not extend the wait beyond one delay
+        let waited = (now - last_failure).max(Duration::zero());
+        let remaining = delay - waited;
+        (remaining > Duration::zero()).then_some(remaining)
+    }
+
+    /// Whether the user is locked out (as opposed to a short backoff)
+    pub fn is_locked_out(&self, now: DateTime<Utc>) -> bool {
+        self.failed_count >= LOCKOUT_THRESHOLD && self.retry_after(now).is_some()
+    }
+
+    /// Record a wrong password or code
+    pub fn record_failure(&mut self, method: LoginMethod, now: DateTime<Utc>) {
+        self.failed_count = self.failed_count.saturating_add(1);
+        self.last_failure = Some(now);
+        self.push(method, AttemptOutcome::Failure, now);
+    }
+
+    /// Record an attempt rejected by backoff or lockout
+    ///
+    /// Blocked attempts appear in the audit trail but do not extend the wait.
+    pub fn record_blocked(&mut self, method: LoginMethod, now: DateTime<Utc>) {
+        self.push(method, AttemptOutcome::Blocked, now);
+    }
+
+    /// Record a successful attempt and reset the failure count
+    ///
+    /// # Returns
+    /// The failed and blocked attempts since the previous success, to show
+    /// the user after logging in
+    pub fn record_success(&mut self, method: LoginMethod, now: DateTime<Utc>) -> Vec<LoginAttempt> {
+        let since_success = self
+            .history
+            .iter()
+            .rposition(|attempt| attempt.outcome == AttemptOutcome::Success)
+            .map_or(0, |i| i + 1);
+        let unsuccessful = self.history[since_success..].to_vec();
+
+        self.failed_count = 0;
+        self.last_failure = None;
+        self.push(method, AttemptOutcome::Success, now);
+
+        unsuccessful
+    }
+
+    /// Wait imposed after `failed_count` consecutive failures
+    fn delay(failed_count: u32) -> Option<Duration> {
+        if failed_count >= LOCKOUT_THRESHOLD {
+            Some(Duration::seconds(LOCKOUT_DURATION_SECS))
+        } else if failed_count > FREE_ATTEMPTS {
+            Some(Duration::seconds(1 << (failed_count - FREE_ATTEMPTS - 1)))
+        } else {
+            None
+        }
+    }
+
+    fn push(&mut self, method: LoginMethod, outcome: AttemptOutcome, at: DateTime<Utc>) {
+        self.history.push(LoginAttempt {
+            at,
+            method,
+            outcome,
+        });
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_lockout() {
+        let clock = ManualClock::new(Utc::now());
+        let mut attempts = LoginAttempts::default();
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert_eq!(attempts.retry_after(clock.now()), None);
+            attempts.record_failure(LoginMethod::Password, clock.now());
+        }
+        assert_eq!(attempts.retry_after(clock.now()), None);
+
+        // Delays double from one second
+        let mut expected = 1;
+        for _ in FREE_ATTEMPTS..LOCKOUT_THRESHOLD - 1 {
+            attempts.record_failure(LoginMethod::Password, clock.now());
+            assert_eq!(
+                attempts.retry_after(clock.now()),
+                Some(Duration::seconds(expected))
+            );
+            assert!(!attempts.is_locked_out(clock.now()));
+
+            clock.advance(Duration::seconds(expected));
+            assert_eq!(attempts.retry_after(clock.now()), None);
+            expected *= 2;
+        }
+
+        attempts.record_failure(LoginMethod::Password, clock.now());
+        assert!(attempts.is_locked_out(clock.now()));
+
+        clock.advance(Duration::seconds(LOCKOUT_DURATION_SECS - 1));
+        assert_eq!(
+            attempts.retry_after(clock.now()),
+            Some(Duration::seconds(1))
+        );
+        clock.advance(Duration::seconds(1));
+        assert_eq!(attempts.retry_after(clock.now()), None);
+
+        // Another failure locks the user out again
+        attempts.record_failure(LoginMethod::Password, clock.now());
+        assert!(attempts.is_locked_out(clock.now()));
+    }
+
+    #[test]
+    fn test_clock_set_back_does_not_extend_wait() {
+        let clock = ManualClock::new(Utc::now());
+        let mut attempts = LoginAttempts::default();
+        for _ in 0..LOCKOUT_THRESHOLD {
+            attempts.record_failure(LoginMethod::Password, clock.now());
+        }
+
+        let earlier = clock.now() - Duration::days(1);
+        assert_eq!(
+            attempts.retry_after(earlier),
+            Some(Duration::seconds(LOCKOUT_DURATION_SECS))
+        );
+    }
+
+    #[test]
+    fn test_success_resets_and_reports_failures() {
+        let clock = ManualClock::new(Utc::now());
+        let mut attempts = LoginAttempts::default();
+
+        attempts.record_failure(LoginMethod::Password, clock.now());
+        let since_success = attempts.record_success(LoginMethod::Password, clock.now());
+        assert_eq!(since_success.len(), 1);
+
+        attempts.record_failure(LoginMethod::Unlock, clock.now());
+        attempts.record_blocked(LoginMethod::Password, clock.now());
+        let since_success = attempts.record_success(LoginMethod::Password, clock.now());
+        assert_eq!(
+            since_success
+                .iter()
+                .map(|attempt| (attempt.method, attempt.outcome))
+                .collect::<Vec<_>>(),
+            vec![
+                (LoginMethod::Unlock, AttemptOutcome::Failure),
+                (LoginMethod::Password, AttemptOutcome::Blocked),
+            ]
+        );
+        assert_eq!(attempts.failed_count, 0);
+        assert_eq!(attempts.retry_after(clock.now()), None);
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let now = Utc::now();
+        let mut attempts = LoginAttempts::default();
+        for _ in 0..MAX_HISTORY + 5 {
+            attempts.record_blocked(LoginMethod::Password, now);
+        }
+        attempts.record_success(LoginMethod::Password, now);
+
+        assert_eq!(attempts.history.len(), MAX_HISTORY);
+        assert_eq!(
+            attempts.history.last().unwrap().outcome,
+            AttemptOutcome::Success
+        );
+    }
+}
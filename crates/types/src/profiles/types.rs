@@ -62,6 +62,16 @@ pub struct UserPreferences {
 
     /// UI language as ISO 639-1 code (e.g., "en", "es", "fr")
     pub language: String,
+
+    /// Minutes of inactivity after which the session locks and the password
+    /// must be entered again (None: never lock)
+    #[serde(default = "default_auto_lock_minutes")]
+    pub auto_lock_minutes: Option<u32>,
+}
+
+/// Default auto-lock timeout for existing configs without one
+fn default_auto_lock_minutes() -> Option<u32> {
+    Some(15)
 }
 
 /// UI theme options
@@ -559,6 +569,7 @@ impl Default for UserPreferences {
             default_timeout_ms: 5000,
             inference_mode: InferenceMode::Local,
             language: "en".to_string(),
+            auto_lock_minutes: default_auto_lock_minutes(),
         }
     }
 }